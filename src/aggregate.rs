@@ -0,0 +1,467 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use crate::models::{AnalysisResult, GroupBy};
+use crate::query::Formula;
+
+/// Metrics rolled up over all structs of a module
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleAggregate {
+    pub module_path: String,
+    pub struct_count: usize,
+    pub mean_lcom: f64,
+    pub total_cbo: usize,
+    pub max_cbo: usize,
+    pub total_wmc: usize,
+    pub max_wmc: usize,
+    pub instability: f64, // Ce / (Ca + Ce) over structs outside the module
+}
+
+/// Aggregate results per module, sorted by module path
+pub fn aggregate_by_module(results: &[AnalysisResult]) -> Vec<ModuleAggregate> {
+    let mut modules: BTreeMap<&str, Vec<&AnalysisResult>> = BTreeMap::new();
+    for result in results {
+        modules.entry(result.module_path.as_str()).or_default().push(result);
+    }
+
+    // Structs of other modules each module depends on, and is depended on by
+    let mut efferent: BTreeMap<&str, BTreeSet<String>> = BTreeMap::new();
+    let mut afferent: BTreeMap<&str, BTreeSet<String>> = BTreeMap::new();
+    for result in results {
+        for dependency in &result.dependencies {
+            let target_module = dependency.target.rsplit_once("::").map_or("", |(module, _)| module);
+            if target_module != result.module_path {
+                efferent.entry(&result.module_path).or_default().insert(dependency.target.clone());
+                afferent.entry(target_module).or_default().insert(result.qualified_name());
+            }
+        }
+    }
+    let count = |set: Option<&BTreeSet<String>>| set.map_or(0, BTreeSet::len);
+
+    modules
+        .into_iter()
+        .map(|(module_path, members)| ModuleAggregate {
+            instability: crate::metrics::afferent::instability(
+                count(afferent.get(module_path)),
+                count(efferent.get(module_path)),
+            ),
+            module_path: module_path.to_string(),
+            struct_count: members.len(),
+            mean_lcom: members.iter().map(|r| r.lcom).sum::<f64>() / members.len() as f64,
+            total_cbo: members.iter().map(|r| r.cbo).sum(),
+            max_cbo: members.iter().map(|r| r.cbo).max().unwrap_or(0),
+            total_wmc: members.iter().map(|r| r.wmc).sum(),
+            max_wmc: members.iter().map(|r| r.wmc).max().unwrap_or(0),
+        })
+        .collect()
+}
+
+/// Metrics rolled up over the structs of a module, file, or crate
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct GroupAggregate {
+    pub group: String,
+    pub struct_count: usize,
+    pub mean_lcom: f64,
+    pub mean_wmc: f64,
+    pub max_wmc: usize,
+    pub total_wmc: usize,
+    pub cbo_edges: usize, // Dependencies of the group's structs on other structs
+    pub internal_edges: usize, // Of those, on structs of the same group
+    pub internal_ratio: f64, // `internal_edges / cbo_edges`, 0 without edges
+}
+
+/// Aggregate results per group, sorted by group
+///
+/// A dependency is internal when its target is in the same group. Targets
+/// not among `results` are grouped by their module path when grouping by
+/// module or crate, and count as external when grouping by file; under
+/// `crate_roots` (see `GroupBy::key`), that makes them external to every
+/// crate too.
+pub fn aggregate_groups(results: &[AnalysisResult], group_by: GroupBy, crate_roots: &[(PathBuf, String)]) -> Vec<GroupAggregate> {
+    let group_of: std::collections::HashMap<String, String> =
+        results.iter().map(|r| (r.qualified_name(), group_by.key(r, crate_roots))).collect();
+    let target_group = |target: &str| -> Option<String> {
+        if let Some(group) = group_of.get(target) {
+            return Some(group.clone());
+        }
+        let module_path = target.rsplit_once("::").map_or("", |(module, _)| module);
+        match group_by {
+            GroupBy::Module => Some(module_path.to_string()),
+            GroupBy::Crate => module_path.split("::").next().map(str::to_string),
+            GroupBy::File => None,
+        }
+    };
+
+    let mut groups: BTreeMap<String, Vec<&AnalysisResult>> = BTreeMap::new();
+    for result in results {
+        groups.entry(group_by.key(result, crate_roots)).or_default().push(result);
+    }
+
+    groups
+        .into_iter()
+        .map(|(group, members)| {
+            let edges: Vec<&str> = members
+                .iter()
+                .flat_map(|r| r.dependencies.iter().map(|d| d.target.as_str()))
+                .collect();
+            let internal_edges = edges
+                .iter()
+                .filter(|target| target_group(target).as_deref() == Some(group.as_str()))
+                .count();
+            let total_wmc: usize = members.iter().map(|r| r.wmc).sum();
+            GroupAggregate {
+                struct_count: members.len(),
+                mean_lcom: members.iter().map(|r| r.lcom).sum::<f64>() / members.len() as f64,
+                mean_wmc: total_wmc as f64 / members.len() as f64,
+                max_wmc: members.iter().map(|r| r.wmc).max().unwrap_or(0),
+                total_wmc,
+                cbo_edges: edges.len(),
+                internal_edges,
+                internal_ratio: if edges.is_empty() {
+                    0.0
+                } else {
+                    internal_edges as f64 / edges.len() as f64
+                },
+                group,
+            }
+        })
+        .collect()
+}
+
+/// Distinct error types returned across the structs of a module
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ModuleErrors {
+    pub module_path: String,
+    pub error_types: Vec<String>, // Sorted
+    pub propagations: usize, // `?` operators in the module's methods
+}
+
+/// Error types per module, sorted by module path
+pub fn error_types_by_module(results: &[AnalysisResult]) -> Vec<ModuleErrors> {
+    let mut modules: BTreeMap<&str, (std::collections::BTreeSet<&str>, usize)> = BTreeMap::new();
+    for result in results {
+        let (types, propagations) = modules.entry(result.module_path.as_str()).or_default();
+        types.extend(result.error_types.iter().map(String::as_str));
+        *propagations += result.methods.iter().map(|m| m.propagations).sum::<usize>();
+    }
+
+    modules
+        .into_iter()
+        .map(|(module_path, (types, propagations))| ModuleErrors {
+            module_path: module_path.to_string(),
+            error_types: types.into_iter().map(str::to_string).collect(),
+            propagations,
+        })
+        .collect()
+}
+
+/// Metrics summarized over all analyzed structs
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct Summary {
+    pub struct_count: usize,
+    pub mean_lcom: f64,
+    pub mean_cbo: f64,
+    pub mean_wmc: f64,
+    pub total_wmc: usize,
+    pub max_wmc: usize,
+}
+
+/// Summarize all results; means are 0 when there are none
+pub fn summarize(results: &[AnalysisResult]) -> Summary {
+    if results.is_empty() {
+        return Summary::default();
+    }
+
+    let count = results.len() as f64;
+    Summary {
+        struct_count: results.len(),
+        mean_lcom: results.iter().map(|r| r.lcom).sum::<f64>() / count,
+        mean_cbo: results.iter().map(|r| r.cbo).sum::<usize>() as f64 / count,
+        mean_wmc: results.iter().map(|r| r.wmc).sum::<usize>() as f64 / count,
+        total_wmc: results.iter().map(|r| r.wmc).sum(),
+        max_wmc: results.iter().map(|r| r.wmc).max().unwrap_or(0),
+    }
+}
+
+/// Distribution of one metric over the analyzed structs
+#[derive(Debug, Clone, PartialEq, serde::Serialize, schemars::JsonSchema)]
+pub struct MetricStats {
+    #[serde(skip)] // Key of the statistics in JSON
+    pub metric: String,
+    pub count: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub p90: f64, // Linearly interpolated, like the median
+    pub max: f64,
+}
+
+/// Count, mean, median, 90th percentile, and maximum of each of `metrics`
+/// (query field names such as `wmc`); all 0 when there are no results
+pub fn metric_stats(results: &[AnalysisResult], metrics: &[&str]) -> Vec<MetricStats> {
+    metrics
+        .iter()
+        .map(|&metric| {
+            let formula = Formula::parse(metric, &[]).expect("built-in metric");
+            let mut values: Vec<f64> = results.iter().map(|r| formula.eval(r)).collect();
+            values.sort_by(f64::total_cmp);
+            MetricStats {
+                metric: metric.to_string(),
+                count: values.len(),
+                mean: if values.is_empty() { 0.0 } else { values.iter().sum::<f64>() / values.len() as f64 },
+                median: quantile(&values, 0.5),
+                p90: quantile(&values, 0.9),
+                max: values.last().copied().unwrap_or(0.0),
+            }
+        })
+        .collect()
+}
+
+/// Linearly interpolated quantile `q` of `sorted`, 0 when it is empty
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let Some(last) = sorted.len().checked_sub(1) else {
+        return 0.0;
+    };
+    let position = last as f64 * q;
+    let (low, high) = (position.floor() as usize, position.ceil() as usize);
+    sorted[low] + (sorted[high] - sorted[low]) * (position - low as f64)
+}
+
+/// Metrics rolled up over all structs in a directory and its subdirectories
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectoryAggregate {
+    pub path: String,
+    pub depth: usize, // 0 for the common root of all analyzed files
+    pub struct_count: usize,
+    pub mean_lcom: f64,
+    pub total_cbo: usize,
+    pub total_wmc: usize,
+    pub max_wmc: usize,
+}
+
+/// Aggregate results at every directory level, like `du` for metrics
+///
+/// Each struct counts toward the directory of its file and every ancestor
+/// up to the deepest directory shared by all files. Directories are
+/// returned in tree order, parents before their children.
+pub fn aggregate_by_directory(results: &[AnalysisResult]) -> Vec<DirectoryAggregate> {
+    let located: Vec<(&Path, &AnalysisResult)> = results
+        .iter()
+        .filter(|r| !r.file_path.is_empty())
+        .map(|r| (Path::new(&r.file_path).parent().unwrap_or(Path::new("")), r))
+        .collect();
+
+    let Some(root) = common_ancestor(located.iter().map(|(dir, _)| *dir)) else {
+        return Vec::new();
+    };
+    let root_depth = root.components().count();
+
+    let mut directories: BTreeMap<PathBuf, Vec<&AnalysisResult>> = BTreeMap::new();
+    for (dir, result) in &located {
+        for ancestor in dir.ancestors().take_while(|a| a.starts_with(&root)) {
+            directories.entry(ancestor.to_path_buf()).or_default().push(result);
+        }
+    }
+
+    directories
+        .into_iter()
+        .map(|(path, members)| DirectoryAggregate {
+            depth: path.components().count() - root_depth,
+            path: path.display().to_string(),
+            struct_count: members.len(),
+            mean_lcom: members.iter().map(|r| r.lcom).sum::<f64>() / members.len() as f64,
+            total_cbo: members.iter().map(|r| r.cbo).sum(),
+            total_wmc: members.iter().map(|r| r.wmc).sum(),
+            max_wmc: members.iter().map(|r| r.wmc).max().unwrap_or(0),
+        })
+        .collect()
+}
+
+/// Deepest directory containing all `dirs`
+pub fn common_ancestor<'a>(mut dirs: impl Iterator<Item = &'a Path>) -> Option<PathBuf> {
+    let mut common = dirs.next()?.to_path_buf();
+    for dir in dirs {
+        while !dir.starts_with(&common) {
+            if !common.pop() {
+                break;
+            }
+        }
+    }
+    Some(common)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Dependency, MethodInfo};
+
+    fn result(module: &str, lcom: f64, cbo: usize, wmc: usize) -> AnalysisResult {
+        AnalysisResult {
+            module_path: module.to_string(),
+            lcom,
+            cbo,
+            wmc,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_metric_stats() {
+        let results: Vec<AnalysisResult> = [4, 1, 10, 2, 3, 6, 5, 7, 9, 8, 11].iter().map(|&wmc| result("crate", 0.5, 0, wmc)).collect();
+
+        let stats = metric_stats(&results, &["wmc", "lcom"]);
+        assert_eq!(
+            stats[0],
+            MetricStats { metric: "wmc".to_string(), count: 11, mean: 6.0, median: 6.0, p90: 10.0, max: 11.0 }
+        );
+        assert_eq!(stats[1].median, 0.5);
+        assert_eq!(metric_stats(&[], &["cbo"])[0].p90, 0.0);
+    }
+
+    #[test]
+    fn test_aggregate_by_module() {
+        let results = vec![
+            result("crate::net", 0.5, 2, 10),
+            result("crate", 0.0, 1, 3),
+            result("crate::net", 1.0, 4, 30),
+        ];
+
+        let aggregates = aggregate_by_module(&results);
+        assert_eq!(aggregates.len(), 2);
+
+        let net = &aggregates[1];
+        assert_eq!(net.module_path, "crate::net");
+        assert_eq!(net.struct_count, 2);
+        assert_eq!(net.mean_lcom, 0.75);
+        assert_eq!(net.total_cbo, 6);
+        assert_eq!(net.max_cbo, 4);
+        assert_eq!(net.total_wmc, 40);
+        assert_eq!(net.max_wmc, 30);
+    }
+
+    #[test]
+    fn test_aggregate_groups() {
+        let mut results = vec![
+            result("crate::net", 0.5, 2, 10),
+            result("crate::net", 1.0, 1, 30),
+            result("crate", 0.0, 0, 2),
+        ];
+        results[0].struct_name = "Server".to_string();
+        results[0].file_path = "src/net/server.rs".to_string();
+        results[0].dependencies = vec![
+            Dependency { target: "crate::net::Socket".to_string(), references: 2 },
+            Dependency { target: "crate::Config".to_string(), references: 1 },
+        ];
+        results[1].struct_name = "Socket".to_string();
+        results[1].file_path = "src/net/socket.rs".to_string();
+        results[1].dependencies = vec![Dependency { target: "crate::Config".to_string(), references: 1 }];
+        results[2].struct_name = "Config".to_string();
+        results[2].file_path = "src/lib.rs".to_string();
+
+        let modules = aggregate_groups(&results, GroupBy::Module, &[]);
+        let net = &modules[1];
+        assert_eq!((net.group.as_str(), net.struct_count, net.max_wmc), ("crate::net", 2, 30));
+        assert_eq!((net.mean_wmc, net.cbo_edges, net.internal_edges), (20.0, 3, 1));
+        assert_eq!(net.internal_ratio, 1.0 / 3.0);
+
+        let files = aggregate_groups(&results, GroupBy::File, &[]);
+        assert_eq!(files.len(), 3);
+        assert!(files.iter().all(|f| f.internal_edges == 0));
+
+    }
+
+    #[test]
+    fn test_aggregate_groups_by_crate_root() {
+        let temp = tempfile::tempdir().unwrap();
+        let roots: Vec<(PathBuf, String)> = ["app", "core"]
+            .iter()
+            .map(|name| {
+                let src = temp.path().join(name).join("src");
+                std::fs::create_dir_all(&src).unwrap();
+                std::fs::write(src.join("lib.rs"), "").unwrap();
+                (src.canonicalize().unwrap(), name.to_string())
+            })
+            .collect();
+
+        // Both crates name their root module `crate`
+        let mut results = vec![result("crate", 0.0, 0, 1), result("crate", 0.0, 0, 1), result("crate", 0.0, 0, 1)];
+        for (result, (name, root)) in results.iter_mut().zip([("Server", &roots[0]), ("Client", &roots[0]), ("Store", &roots[1])]) {
+            result.struct_name = name.to_string();
+            result.file_path = root.0.join("lib.rs").display().to_string();
+        }
+        results[0].dependencies = vec![
+            Dependency { target: "crate::Client".to_string(), references: 1 },
+            Dependency { target: "crate::Store".to_string(), references: 1 },
+        ];
+
+        let crates = aggregate_groups(&results, GroupBy::Crate, &roots);
+        let groups: Vec<(&str, usize)> = crates.iter().map(|c| (c.group.as_str(), c.struct_count)).collect();
+        assert_eq!(groups, vec![("app", 2), ("core", 1)]);
+        assert_eq!((crates[0].cbo_edges, crates[0].internal_edges), (2, 1));
+
+        // Without roots, the module path decides
+        assert_eq!(aggregate_groups(&results, GroupBy::Crate, &[]).len(), 1);
+    }
+
+    #[test]
+    fn test_module_instability() {
+        let depend = |result: &mut AnalysisResult, target: &str| {
+            result.dependencies.push(Dependency { target: target.to_string(), references: 1 });
+        };
+        let mut results = vec![
+            result("crate::app", 0.0, 0, 1),
+            result("crate::app", 0.0, 0, 1),
+            result("crate::core", 0.0, 0, 1),
+        ];
+        results[0].struct_name = "App".to_string();
+        results[1].struct_name = "Cli".to_string();
+        results[2].struct_name = "Config".to_string();
+        depend(&mut results[0], "crate::core::Config");
+        depend(&mut results[0], "crate::app::Cli");
+        depend(&mut results[1], "crate::core::Config");
+
+        let modules = aggregate_by_module(&results);
+        assert_eq!(modules[0].instability, 1.0);
+        assert_eq!(modules[1].instability, 0.0);
+    }
+
+    #[test]
+    fn test_error_types_by_module() {
+        let mut results = vec![
+            result("crate::db", 0.0, 0, 1),
+            result("crate::db", 0.0, 0, 1),
+            result("crate", 0.0, 0, 1),
+        ];
+        results[0].error_types = vec!["io::Error".to_string(), "DbError".to_string()];
+        results[1].error_types = vec!["DbError".to_string()];
+        results[1].methods = vec![MethodInfo { propagations: 3, ..Default::default() }];
+
+        let modules = error_types_by_module(&results);
+        assert_eq!(modules.len(), 2);
+        assert_eq!(modules[1].module_path, "crate::db");
+        assert_eq!(modules[1].error_types, vec!["DbError", "io::Error"]);
+        assert_eq!(modules[1].propagations, 3);
+        assert!(modules[0].error_types.is_empty());
+    }
+
+    #[test]
+    fn test_aggregate_by_directory() {
+        let mut results = vec![
+            result("crate", 0.0, 1, 5),
+            result("crate::metrics::cbo", 0.5, 2, 10),
+            result("crate::metrics::wmc", 1.0, 0, 20),
+        ];
+        results[0].file_path = "src/main.rs".to_string();
+        results[1].file_path = "src/metrics/cbo.rs".to_string();
+        results[2].file_path = "src/metrics/wmc.rs".to_string();
+
+        let directories = aggregate_by_directory(&results);
+        let summary: Vec<(&str, usize, usize, usize)> = directories
+            .iter()
+            .map(|d| (d.path.as_str(), d.depth, d.struct_count, d.total_wmc))
+            .collect();
+
+        assert_eq!(summary, vec![("src", 0, 3, 35), ("src/metrics", 1, 2, 30)]);
+        assert_eq!(directories[1].mean_lcom, 0.75);
+        assert_eq!(directories[1].max_wmc, 20);
+    }
+}
@@ -0,0 +1,39 @@
+use crate::models::{AnalysisResult, ApiSurface, EnumInfo};
+
+/// Breaking-change risk of a crate's public types
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ApiReport {
+    pub structs: Vec<StructApi>,           // `pub` structs, riskiest first
+    pub exhaustive_enums: Vec<EnumInfo>,   // `pub` enums without `#[non_exhaustive]`, most variants first
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StructApi {
+    pub struct_name: String, // Qualified name
+    pub file_path: String,
+    #[serde(flatten)]
+    pub surface: ApiSurface,
+}
+
+/// Rank `pub` structs by API surface and list the exhaustively matchable enums
+///
+/// Adding a variant to an exported enum without `#[non_exhaustive]` breaks
+/// every caller matching on it, so those are reported alongside the structs.
+pub fn api_report(results: &[AnalysisResult], enums: &[EnumInfo]) -> ApiReport {
+    let mut structs: Vec<StructApi> = results
+        .iter()
+        .filter_map(|r| {
+            Some(StructApi {
+                struct_name: r.qualified_name(),
+                file_path: r.file_path.clone(),
+                surface: r.api?,
+            })
+        })
+        .collect();
+    structs.sort_by_key(|s| std::cmp::Reverse(s.surface.score));
+
+    let mut exhaustive_enums: Vec<EnumInfo> = enums.iter().filter(|e| !e.non_exhaustive).cloned().collect();
+    exhaustive_enums.sort_by_key(|e| std::cmp::Reverse(e.variants));
+
+    ApiReport { structs, exhaustive_enums }
+}
@@ -0,0 +1,176 @@
+use crate::aggregate::summarize;
+use crate::models::AnalysisResult;
+
+/// Reference dataset built by `build-reference` from the crates it names
+const BUILTIN_REFERENCE: &str = include_str!("../data/reference.json");
+
+/// Number of quantile points stored per metric (0th to 100th percentile)
+const QUANTILES: usize = 101;
+
+/// Metric distributions of a set of reference crates
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Reference {
+    pub crates: Vec<String>,
+    pub structs: Distribution,  // Over all structs of all crates
+    pub projects: Distribution, // Over the per-crate means
+}
+
+/// Quantiles of each metric, from the 0th to the 100th percentile
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Distribution {
+    pub lcom: Vec<f64>,
+    pub cbo: Vec<f64>,
+    pub wmc: Vec<f64>,
+}
+
+/// Where a project and its structs fall in the reference distributions
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Benchmark {
+    pub reference_crates: usize,
+    pub project: Vec<MetricRank>,
+    pub structs: Vec<StructRank>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StructRank {
+    pub struct_name: String, // Qualified name
+    pub metrics: Vec<MetricRank>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct MetricRank {
+    pub metric: &'static str,
+    pub value: f64,
+    pub percentile: f64, // Share of the reference below this value, 0 to 100
+}
+
+impl Reference {
+    /// Reference distribution shipped with the tool
+    pub fn builtin() -> Self {
+        serde_json::from_str(BUILTIN_REFERENCE).expect("built-in reference dataset is valid")
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content =
+            std::fs::read_to_string(path).map_err(|e| format!("Failed to read reference {}: {}", path, e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Invalid reference {}: {}", path, e))
+    }
+
+    /// Build a reference from the results of each named crate
+    pub fn build(crates: &[(String, Vec<AnalysisResult>)]) -> Self {
+        let all: Vec<&AnalysisResult> = crates.iter().flat_map(|(_, results)| results).collect();
+        let summaries: Vec<_> = crates.iter().map(|(_, results)| summarize(results)).collect();
+
+        Reference {
+            crates: crates.iter().map(|(name, _)| name.clone()).collect(),
+            structs: Distribution {
+                lcom: quantiles(all.iter().map(|r| r.lcom).collect()),
+                cbo: quantiles(all.iter().map(|r| r.cbo as f64).collect()),
+                wmc: quantiles(all.iter().map(|r| r.wmc as f64).collect()),
+            },
+            projects: Distribution {
+                lcom: quantiles(summaries.iter().map(|s| s.mean_lcom).collect()),
+                cbo: quantiles(summaries.iter().map(|s| s.mean_cbo).collect()),
+                wmc: quantiles(summaries.iter().map(|s| s.mean_wmc).collect()),
+            },
+        }
+    }
+}
+
+/// Rank a project's mean metrics and each struct's metrics against `reference`
+pub fn benchmark(results: &[AnalysisResult], reference: &Reference) -> Benchmark {
+    let summary = summarize(results);
+
+    let rank = |distribution: &Distribution, lcom: f64, cbo: f64, wmc: f64| {
+        vec![
+            MetricRank {
+                metric: "lcom",
+                value: lcom,
+                percentile: percentile(&distribution.lcom, lcom),
+            },
+            MetricRank {
+                metric: "cbo",
+                value: cbo,
+                percentile: percentile(&distribution.cbo, cbo),
+            },
+            MetricRank {
+                metric: "wmc",
+                value: wmc,
+                percentile: percentile(&distribution.wmc, wmc),
+            },
+        ]
+    };
+
+    Benchmark {
+        reference_crates: reference.crates.len(),
+        project: rank(&reference.projects, summary.mean_lcom, summary.mean_cbo, summary.mean_wmc),
+        structs: results
+            .iter()
+            .map(|r| StructRank {
+                struct_name: r.qualified_name(),
+                metrics: rank(&reference.structs, r.lcom, r.cbo as f64, r.wmc as f64),
+            })
+            .collect(),
+    }
+}
+
+/// Linearly interpolated quantiles of `values`, empty when there are none
+fn quantiles(mut values: Vec<f64>) -> Vec<f64> {
+    if values.is_empty() {
+        return Vec::new();
+    }
+    values.sort_by(f64::total_cmp);
+
+    let last = (values.len() - 1) as f64;
+    (0..QUANTILES)
+        .map(|i| {
+            let position = last * i as f64 / (QUANTILES - 1) as f64;
+            let (low, high) = (position.floor() as usize, position.ceil() as usize);
+            values[low] + (values[high] - values[low]) * (position - low as f64)
+        })
+        .collect()
+}
+
+/// Percentile rank of `value` among `quantiles`
+///
+/// Ties count half, so when most of the reference is 0 a value of 0 ranks
+/// in the middle of that block rather than at its top.
+fn percentile(quantiles: &[f64], value: f64) -> f64 {
+    if quantiles.is_empty() {
+        return 0.0;
+    }
+    let below = quantiles.iter().filter(|&&q| q < value).count();
+    let at_or_below = quantiles.iter().filter(|&&q| q <= value).count();
+    100.0 * (below + at_or_below) as f64 / 2.0 / quantiles.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantiles_and_percentile() {
+        let q = quantiles((0..=100).map(f64::from).collect());
+        assert_eq!(q.len(), QUANTILES);
+        assert_eq!((q[0], q[50], q[100]), (0.0, 50.0, 100.0));
+
+        assert_eq!(percentile(&q, -1.0), 0.0);
+        assert_eq!(percentile(&q, 1000.0), 100.0);
+        assert!((percentile(&q, 87.0) - 86.6).abs() < 0.1);
+
+        // Half of the reference is 0; a 0 ranks in the middle of that half
+        let zeros = quantiles([0.0; 50].into_iter().chain((1..=50).map(f64::from)).collect());
+        assert!((percentile(&zeros, 0.0) - 25.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_builtin_reference_is_complete() {
+        let reference = Reference::builtin();
+        assert!(!reference.crates.is_empty());
+        for distribution in [&reference.structs, &reference.projects] {
+            assert_eq!(distribution.lcom.len(), QUANTILES);
+            assert_eq!(distribution.cbo.len(), QUANTILES);
+            assert_eq!(distribution.wmc.len(), QUANTILES);
+        }
+    }
+}
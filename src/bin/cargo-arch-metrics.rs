@@ -0,0 +1,5 @@
+//! `cargo arch-metrics`: the `rust-arch-metrics` CLI as a cargo subcommand
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    rust_arch_metrics::cli::run_cargo(std::env::args_os())
+}
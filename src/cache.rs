@@ -0,0 +1,329 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::models::{EnumInfo, Interner, ParsedFile, Reexport, StructInfo};
+use crate::parser::{self, Links, ParseOptions, SourceFile};
+
+/// Suffix of entry files, telling them apart from others in the directory
+const ENTRY_SUFFIX: &str = ".ram-cache.json";
+
+/// What one source file parsed into, stored as `<key>.ram-cache.json`
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Entry {
+    links: Links,
+    component: Vec<(String, String)>, // Path and key of the files it links to, directly or not
+    structs: Vec<StructInfo>,
+    enums: Vec<EnumInfo>,
+    reexports: Vec<Reexport>,
+    aliases: Vec<Reexport>,
+}
+
+/// A file to parse, read from disk
+struct Input {
+    path: String,
+    module_path: String,
+    content: String,
+    key: String, // Hash of the tool version, content, path, module path, and parse options
+}
+
+/// Parse `files`, each with its module path, as one crate, reusing the
+/// parse of unchanged files cached in `dir` and caching the rest
+///
+/// Impl blocks attach to the structs of other files, so a file's cached
+/// structs are only reused while every file of its component (the files
+/// it links to, directly or through others) is unchanged, and no file
+/// parsed anew links to them. Everything else is parsed together, as
+/// without a cache. Entries of files not in `files`, such as those of
+/// their older versions, are removed.
+pub fn parse_files(
+    files: &[(PathBuf, String)],
+    options: &ParseOptions,
+    dir: &Path,
+) -> Result<ParsedFile, Box<dyn std::error::Error>> {
+    let inputs = files
+        .iter()
+        .map(|(file_path, module_path)| {
+            let content = std::fs::read_to_string(file_path)?;
+            let path = file_path.display().to_string();
+            let module_path = module_path.clone();
+            let key = key(&path, &module_path, &content, options);
+            Ok(Input { path, module_path, content, key })
+        })
+        .collect::<Result<Vec<_>, std::io::Error>>()?;
+    let current: HashMap<&str, &str> = inputs.iter().map(|f| (f.path.as_str(), f.key.as_str())).collect();
+
+    let mut entries: Vec<Option<Entry>> = inputs
+        .iter()
+        .map(|input| read_entry(dir, &input.key))
+        .map(|entry| entry.filter(|e| e.component.iter().all(|(path, key)| current.get(path.as_str()) == Some(&key.as_str()))))
+        .collect();
+
+    // Parse the files without a usable entry, until none links to a cached one
+    let mut sources: Vec<Option<SourceFile>> = inputs.iter().map(|_| None).collect();
+    let mut links: Vec<Links> = vec![Links::default(); inputs.len()];
+    let mut parsed = vec![false; inputs.len()];
+    loop {
+        for (i, input) in inputs.iter().enumerate() {
+            if entries[i].is_some() || parsed[i] {
+                continue;
+            }
+            parsed[i] = true;
+            match SourceFile::parse(&input.content, &input.module_path, &input.path) {
+                Ok(source) => {
+                    links[i] = parser::links(&source);
+                    sources[i] = Some(source);
+                }
+                Err(e) => eprintln!("Warning: Failed to parse {}: {}", input.path, e),
+            }
+        }
+
+        let declared: BTreeSet<String> = (0..inputs.len())
+            .flat_map(|i| entries[i].as_ref().map_or(&links[i], |e| &e.links).traits.iter().cloned())
+            .collect();
+        let parsed_names: BTreeSet<&String> = (0..inputs.len())
+            .filter(|&i| parsed[i])
+            .flat_map(|i| link_names(&links[i], &declared))
+            .collect();
+        let evicted: BTreeSet<String> = entries
+            .iter()
+            .flatten()
+            .filter(|e| link_names(&e.links, &declared).any(|name| parsed_names.contains(name)))
+            .flat_map(|e| e.component.iter().map(|(path, _)| path.clone()))
+            .collect();
+        if evicted.is_empty() {
+            break;
+        }
+        for (entry, input) in entries.iter_mut().zip(&inputs) {
+            if evicted.contains(&input.path) {
+                *entry = None;
+            }
+        }
+    }
+
+    let fresh: Vec<usize> = (0..inputs.len()).filter(|&i| sources[i].is_some()).collect();
+    let fresh_sources: Vec<SourceFile> = fresh.iter().filter_map(|&i| sources[i].take()).collect();
+    let mut fresh_parse = parser::parse_sources(&fresh_sources, options);
+
+    // Cache each parsed file with the others of its component
+    let declared: BTreeSet<String> = links.iter().flat_map(|l| l.traits.iter().cloned()).collect();
+    let components = components(fresh.iter().map(|&i| link_names(&links[i], &declared)));
+    let mut structs: HashMap<String, Vec<StructInfo>> = HashMap::new();
+    for struct_info in fresh_parse.structs.drain(..) {
+        structs.entry(struct_info.file_path.clone()).or_default().push(struct_info);
+    }
+    let mut enums: HashMap<String, Vec<EnumInfo>> = HashMap::new();
+    for enum_info in fresh_parse.enums.drain(..) {
+        enums.entry(enum_info.file_path.clone()).or_default().push(enum_info);
+    }
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    for (n, (&i, source)) in fresh.iter().zip(&fresh_sources).enumerate() {
+        let input = &inputs[i];
+        let (reexports, aliases) = parser::reexports_and_aliases(source);
+        let entry = Entry {
+            links: std::mem::take(&mut links[i]),
+            component: (0..fresh.len())
+                .filter(|&m| components[m] == components[n])
+                .map(|m| (inputs[fresh[m]].path.clone(), inputs[fresh[m]].key.clone()))
+                .collect(),
+            structs: structs.remove(&input.path).unwrap_or_default(),
+            enums: enums.remove(&input.path).unwrap_or_default(),
+            reexports,
+            aliases,
+        };
+        std::fs::write(entry_path(dir, &input.key), serde_json::to_string(&entry)?)?;
+        entries[i] = Some(entry);
+    }
+    prune(dir, inputs.iter().map(|input| input.key.as_str()).collect())?;
+
+    // Entries deserialize a separate string per field name and type; share
+    // them again across every struct, cached or not, as a single parse does
+    let mut interner = Interner::default();
+    let mut parsed_file = ParsedFile::default();
+    for entry in entries.into_iter().flatten() {
+        for mut struct_info in entry.structs {
+            intern(&mut struct_info, &mut interner);
+            parsed_file.structs.push(struct_info);
+        }
+        parsed_file.enums.extend(entry.enums);
+        parsed_file.reexports.extend(entry.reexports);
+        parsed_file.aliases.extend(entry.aliases);
+        parsed_file.traits.extend(entry.links.traits);
+    }
+    parsed_file.traits.sort();
+    Ok(parsed_file)
+}
+
+/// Replace the field names and types of `struct_info` with `interner`'s
+fn intern(struct_info: &mut StructInfo, interner: &mut Interner) {
+    for field in &mut struct_info.fields {
+        field.name = interner.intern(&field.name);
+        field.ty = interner.intern(&field.ty);
+    }
+    for method in &mut struct_info.methods {
+        for name in &mut method.fields_accessed {
+            *name = interner.intern(name);
+        }
+    }
+    for ty in &mut struct_info.external_types {
+        *ty = interner.intern(ty);
+    }
+}
+
+/// Key of a file's entry, stable across builds and platforms
+fn key(path: &str, module_path: &str, content: &str, options: &ParseOptions) -> String {
+    let mut hasher = blake3::Hasher::new();
+    for part in [env!("CARGO_PKG_VERSION"), path, module_path, content, &format!("{:?}", options)] {
+        // Length-prefixed, so no two inputs hash the same concatenation
+        hasher.update(&(part.len() as u64).to_le_bytes());
+        hasher.update(part.as_bytes());
+    }
+    hasher.finalize().to_hex().to_string()
+}
+
+/// Path of the entry of `key` in `dir`
+fn entry_path(dir: &Path, key: &str) -> PathBuf {
+    dir.join(format!("{}{}", key, ENTRY_SUFFIX))
+}
+
+/// Remove the entries in `dir` whose key is not in `keys`
+///
+/// Only files named like an entry, a 64-digit hex key and the entry
+/// suffix, are removed; anything else sharing the directory is left alone.
+fn prune(dir: &Path, keys: HashSet<&str>) -> std::io::Result<()> {
+    for file in std::fs::read_dir(dir)? {
+        let path = file?.path();
+        let key = path.file_name().and_then(|name| name.to_str()).and_then(|name| name.strip_suffix(ENTRY_SUFFIX));
+        let stale = key.is_some_and(|key| key.len() == 64 && key.bytes().all(|b| b.is_ascii_hexdigit()) && !keys.contains(key));
+        if stale {
+            std::fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// The cached entry of `key`, if any; unreadable entries count as missing
+fn read_entry(dir: &Path, key: &str) -> Option<Entry> {
+    let content = std::fs::read_to_string(entry_path(dir, key)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Names a file links to others through; implemented traits only link
+/// to files declaring them
+fn link_names<'a>(links: &'a Links, declared: &'a BTreeSet<String>) -> impl Iterator<Item = &'a String> {
+    links
+        .names
+        .iter()
+        .chain(&links.traits)
+        .chain(links.impl_traits.iter().filter(|name| declared.contains(*name)))
+}
+
+/// Component of each file, files sharing a name being in the same one
+fn components<'a>(files: impl Iterator<Item = impl Iterator<Item = &'a String>>) -> Vec<usize> {
+    fn root(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+
+    let mut parents = Vec::new();
+    let mut owners: HashMap<&String, usize> = HashMap::new();
+    for (i, names) in files.enumerate() {
+        parents.push(i);
+        for name in names {
+            let owner = *owners.entry(name).or_insert(i);
+            let (a, b) = (root(&mut parents, owner), root(&mut parents, i));
+            parents[a] = b;
+        }
+    }
+    (0..parents.len()).map(|i| root(&mut parents, i)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline;
+
+    #[test]
+    fn test_reuses_files_whose_component_is_unchanged() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let cache = dir.join("cache");
+        std::fs::write(dir.join("engine.rs"), "pub struct Engine { rpm: u32 }").unwrap();
+        std::fs::write(dir.join("engine_impl.rs"), "impl Engine { fn rpm(&self) -> u32 { self.rpm } }").unwrap();
+        std::fs::write(dir.join("wheel.rs"), "pub struct Wheel;").unwrap();
+        let paths = [dir.display().to_string()];
+        let files: Vec<(PathBuf, String)> = ["engine.rs", "engine_impl.rs", "wheel.rs"]
+            .iter()
+            .map(|f| (dir.join(f), pipeline::module_path(&dir.join(f), &paths)))
+            .collect();
+        let parse = || parse_files(&files, &ParseOptions::default(), &cache).unwrap();
+        let methods = |parsed: &ParsedFile| -> Vec<(String, usize)> {
+            parsed.structs.iter().map(|s| (s.name.clone(), s.methods.len())).collect()
+        };
+
+        let first = parse();
+        assert_eq!(methods(&first), vec![("Engine".to_string(), 1), ("Wheel".to_string(), 0)]);
+
+        // Mark the cached Engine to tell a reused entry from a new parse
+        let engine_key = key(
+            &files[0].0.display().to_string(),
+            &files[0].1,
+            "pub struct Engine { rpm: u32 }",
+            &ParseOptions::default(),
+        );
+        let engine_entry = entry_path(&cache, &engine_key);
+        let marked = std::fs::read_to_string(&engine_entry).unwrap().replace("\"name\":\"Engine\"", "\"name\":\"Cached\"");
+        std::fs::write(&engine_entry, marked).unwrap();
+
+        // Wheel shares no name with Engine's files, which are reused
+        std::fs::write(dir.join("wheel.rs"), "pub struct Wheel { size: u8 }").unwrap();
+        assert_eq!(methods(&parse()), vec![("Cached".to_string(), 1), ("Wheel".to_string(), 0)]);
+
+        // A change to the impl reparses the struct it attaches to
+        std::fs::write(dir.join("engine_impl.rs"), "impl Engine { fn a(&self) {} fn b(&self) {} }").unwrap();
+        assert_eq!(methods(&parse()), vec![("Engine".to_string(), 2), ("Wheel".to_string(), 0)]);
+
+        // So does a new file linking to a cached one
+        std::fs::write(dir.join("wheel.rs"), "pub struct Wheel; impl Engine { fn c(&self) {} }").unwrap();
+        assert_eq!(methods(&parse()), vec![("Engine".to_string(), 3), ("Wheel".to_string(), 0)]);
+
+        // Entries of the older versions are gone, one per file is left
+        assert_eq!(std::fs::read_dir(&cache).unwrap().count(), files.len());
+        assert_eq!(engine_key.len(), 64);
+    }
+
+    #[test]
+    fn test_prune_keeps_files_other_than_entries() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::write(dir.join("lib.rs"), "pub struct Engine;").unwrap();
+        let stale_key = "0".repeat(64);
+        for name in ["settings.json", "notes.ram-cache.json", &format!("{}.json", stale_key), &format!("{}{}", stale_key, ENTRY_SUFFIX)] {
+            std::fs::write(dir.join(name), "{}").unwrap();
+        }
+
+        // The analyzed directory is the cache directory too
+        let files = vec![(dir.join("lib.rs"), "crate".to_string())];
+        parse_files(&files, &ParseOptions::default(), dir).unwrap();
+
+        assert!(dir.join("settings.json").is_file());
+        assert!(dir.join("notes.ram-cache.json").is_file());
+        assert!(dir.join(format!("{}.json", stale_key)).is_file());
+        assert!(!dir.join(format!("{}{}", stale_key, ENTRY_SUFFIX)).exists());
+    }
+
+    #[test]
+    fn test_cached_structs_share_field_names() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::write(dir.join("lib.rs"), "pub struct Engine { rpm: u32 } impl Engine { fn rpm(&self) -> u32 { self.rpm } }").unwrap();
+        let files = vec![(dir.join("lib.rs"), "crate".to_string())];
+        parse_files(&files, &ParseOptions::default(), &dir.join("cache")).unwrap();
+
+        let cached = parse_files(&files, &ParseOptions::default(), &dir.join("cache")).unwrap();
+        let engine = &cached.structs[0];
+        assert!(std::sync::Arc::ptr_eq(&engine.fields[0].name, &engine.methods[0].fields_accessed[0]));
+    }
+}
@@ -0,0 +1,1154 @@
+//! Command-line interface of the `rust-arch-metrics` and `cargo arch-metrics` binaries
+
+use clap::error::ErrorKind;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use std::ffi::OsString;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    api, benchmark, compare, config, debug, diff, explain, git, history, hotspots, merge, metrics, models, parser, pipeline,
+    query, report, resolve, scope, score, snapshot, thresholds, workspace,
+};
+
+use models::{AnalysisResult, ColorMode, Metric, OutputFormat};
+use parser::ParseOptions;
+use pipeline::{AnalysisContext, AnalysisPipeline};
+use resolve::StructIndex;
+
+const AFTER_HELP: &str = "\nMETRICS EXPLAINED:
+    LCOM (Lack of Cohesion in Methods) - Range: 0.0 to 1.0 (lower is better)
+        Measures how closely related methods are within a struct.
+        • 0.0    = Perfect cohesion - all methods use all fields
+        • 0.0-0.5 = Good cohesion - methods work on related field subsets
+        • 0.5-0.8 = Low cohesion - may indicate multiple responsibilities
+        • 1.0    = No cohesion - methods share no fields (consider splitting)
+
+    CBO (Coupling Between Objects) - Range: 0+ (lower is better)
+        Counts dependencies on other structs defined in the analyzed codebase.
+        Does not count external types (String, Vec, etc.) or primitives.
+        • 0-2  = Low coupling, easy to test and reuse
+        • 3-5  = Moderate coupling, acceptable
+        • 6+   = High coupling, difficult to maintain
+
+    WMC (Weighted Methods per Class) - Range: 0+ (lower is better)
+        Sum of cyclomatic complexities across all methods.
+        Complexity is 1 + number of decision points (if, while, for, loop,
+        each match arm and guard, && and ||).
+        • 0-10  = Simple, easy to understand
+        • 11-20 = Moderate complexity
+        • 21-40 = Complex, consider refactoring
+        • 40+   = God class, needs decomposition
+
+EXAMPLES:
+    # Analyze current project with table output
+    rust-arch-metrics src/
+
+    # Export metrics for further processing
+    rust-arch-metrics src/ --format json --output metrics.json
+
+    # Import into spreadsheet
+    rust-arch-metrics src/ --format csv --output metrics.csv
+
+    # Browse structs with annotated source
+    rust-arch-metrics src/ --format html --output metrics.html
+
+    # Render the dependency graph, grouped by module
+    rust-arch-metrics src/ --format dot --cluster-modules | dot -Tsvg > deps.svg
+
+    # Find the subtrees carrying the most complexity
+    rust-arch-metrics src/ --by-directory
+
+    # See how self-contained each module is
+    rust-arch-metrics src/ --group-by module
+
+    # Find the impl block that makes a large struct complex
+    rust-arch-metrics src/ --by-impl
+
+    # Find the most complex methods
+    rust-arch-metrics src/ --per-method
+
+    # Find structs and modules returning many different error types
+    rust-arch-metrics src/ --error-coupling
+
+    # See where complexity concentrates in the file tree
+    rust-arch-metrics src/ --heatmap wmc
+
+    # Compare an old and a rewritten implementation
+    rust-arch-metrics compare legacy/src/ rewrite/src/
+
+    # Show why a struct scores as it does
+    rust-arch-metrics explain src/ crate::net::Server
+
+    # Find the pub types that make breaking changes likely
+    rust-arch-metrics api src/
+
+    # Publish an architecture grade badge
+    rust-arch-metrics grade src/ --output grade.json --badge grade.svg
+
+    # Archive results for CI (writes arch-snapshots/<timestamp>/)
+    rust-arch-metrics snapshot src/
+
+    # Publish a dashboard of the snapshot history (writes arch-site/)
+    rust-arch-metrics site
+
+    # Combine results of sharded CI jobs into one workbook
+    rust-arch-metrics merge shard-*/results.json --format xlsx --output all.xlsx
+
+    # Grade against stricter thresholds
+    rust-arch-metrics grade src/ --profile strict
+
+    # PR run: only report what changed and what it affects
+    rust-arch-metrics src/ --changed-since origin/main
+
+    # Faster PR run on a large tree: also skip parsing unaffected files
+    rust-arch-metrics src/ --changed-since origin/main --narrow-parse
+
+    # Fail a CI job on complex or incohesive structs
+    rust-arch-metrics src/ --fail-on 'wmc > 40' --fail-on 'lcom > 0.8'
+
+    # Gate a PR on the structs it touches
+    rust-arch-metrics check src/ --base origin/main
+
+    # Compare against popular open-source crates
+    rust-arch-metrics benchmark src/
+
+    # Analyze several directories as one crate
+    rust-arch-metrics src/ lib/ tools/
+
+    # Analyze the files changed on a branch
+    git diff --name-only main | rust-arch-metrics --files-from -
+
+    # Exclude test files
+    rust-arch-metrics src/ --exclude test
+
+    # Exclude with globs, or analyze only some files
+    rust-arch-metrics . --exclude '**/tests/**' --exclude '*_generated.rs'
+    rust-arch-metrics . --include 'src/engine/**'
+
+    # Focus on high-complexity structs
+    rust-arch-metrics src/ --query 'wmc > 40 && lcom > 0.7'
+
+    # Only the summary statistics, for a dashboard
+    rust-arch-metrics src/ --summary-only --format json
+
+    # Order by a formula
+    rust-arch-metrics src/ --sort-by 'wmc * lcom'
+
+    # Only structs worth a look on a large code base
+    rust-arch-metrics src/ --min-wmc 20 --min-cbo 5
+
+    # The ten most complex structs
+    rust-arch-metrics src/ --sort-by wmc --desc --top 10
+
+    # Query a saved result set
+    rust-arch-metrics query arch-snapshots/2024-05-01T09-30-00Z \"module ~ 'net'\"
+
+    # Debug parsing of a specific struct
+    rust-arch-metrics src/ --debug-struct MyStruct
+
+SEE ALSO:
+    https://en.wikipedia.org/wiki/Lack_of_cohesion_in_methods
+    https://en.wikipedia.org/wiki/Coupling_(computer_programming)";
+
+#[derive(Parser)]
+#[command(name = "rust-arch-metrics")]
+#[command(about = "Calculate architectural metrics (LCOM, CBO, WMC) for Rust code")]
+#[command(
+    long_about = "Analyzes Rust source files and calculates three key object-oriented metrics:
+\n\
+  • LCOM - Lack of Cohesion in Methods (how related are the methods)\n\
+  • CBO  - Coupling Between Objects (dependencies on other structs)\n\
+  • WMC  - Weighted Methods per Class (cyclomatic complexity sum)\n\
+\n\
+These metrics help identify architectural issues like God Classes, Feature Envy, \
+and low cohesion that make code harder to maintain.",
+    after_help = AFTER_HELP,
+    version,
+    subcommand_negates_reqs = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Paths to the Rust project directories or .rs files to analyze, as one crate
+    #[arg(value_name = "PATH")]
+    paths: Vec<String>,
+
+    /// Read the files to analyze from a file
+    #[arg(long, value_name = "FILE",
+          help = "Also analyze the files listed in FILE, one per line, or on stdin with -.\n\
+                  Listed files that are not Rust sources or no longer exist are skipped\n\
+                  Example: git diff --name-only main | rust-arch-metrics --files-from -")]
+    files_from: Option<String>,
+
+    /// Output format
+    #[arg(short, long, value_name = "FORMAT", default_value = "table", global = true,
+          help = "Output format: table, json, csv, html, dot, graph-json, xlsx, sarif, mermaid,\n\
+                  markdown, junit, yaml, jsonl, sqlite, or teamcity\n\
+                  • table - Human-readable aligned columns (default)\n\
+                  • json  - Machine-readable with full precision\n\
+                  • csv   - Spreadsheet-compatible\n\
+                  • html  - Self-contained page with annotated source per struct\n\
+                  • dot   - Graphviz dependency graph colored by metrics\n\
+                  • graph-json - Dependency graph as nodes/edges JSON (D3, Cytoscape.js, Gephi)\n\
+                  • xlsx  - Excel workbook with struct, method, module, and violation sheets\n\
+                  \x20         (requires --output)\n\
+                  • sarif - SARIF 2.1.0 threshold violations for GitHub code scanning\n\
+                  • mermaid - Mermaid class diagram for markdown and docs\n\
+                  • markdown - GitHub-flavored table for pull request comments\n\
+                  • junit - Threshold checks as JUnit XML test cases for CI servers\n\
+                  • yaml  - The JSON output as YAML, for YAML-only pipelines\n\
+                  • jsonl - One JSON object per struct, written as each is analyzed\n\
+                  • sqlite - Appends a run with its structs, methods, and metrics to a\n\
+                  \x20         database (requires --output)\n\
+                  • teamcity - Build statistics and threshold inspections as TeamCity\n\
+                  \x20         service messages")]
+    format: String,
+
+    /// Comma-separated list of metrics to include
+    #[arg(short, long, value_name = "METRICS", default_value = "all",
+          help = "Metrics to report: all (default) or a comma-separated list of lcom, cbo,\n\
+                  wmc, mi, lcom4, cognitive, and sloc. all is LCOM, CBO, Ca, fan-in,\n\
+                  instability, WMC, and MI in the table and every field in JSON and\n\
+                  CSV; add lcom4 for the LCOM4 column, cognitive for cognitive\n\
+                  complexity, and sloc for lines of code\n\
+                  Example: --metrics lcom,cbo or --metrics all,lcom4")]
+    metrics: String,
+
+    /// Patterns to exclude files/directories from analysis
+    #[arg(long, value_name = "PATTERN", global = true,
+          help = "Skip files matching this glob, relative to the analyzed directory,\n\
+                  or files/directories whose name contains it if it has no glob\n\
+                  characters. Repeatable.\n\
+                  Example: --exclude test (skips files with 'test' in name)\n\
+                  Example: --exclude '**/tests/**' --exclude '*_generated.rs'")]
+    exclude: Vec<String>,
+
+    /// Patterns selecting the files to analyze
+    #[arg(long, value_name = "GLOB", global = true,
+          help = "Analyze only files matching this glob, relative to the analyzed\n\
+                  directory. Repeatable.\n\
+                  Example: --include 'src/**'")]
+    include: Vec<String>,
+
+    /// Also analyze ignored files
+    #[arg(long, global = true,
+          help = "Also analyze files excluded by .gitignore, hidden files, and the\n\
+                  target/ and vendor/ directories, which are skipped by default")]
+    no_ignore: bool,
+
+    /// Follow mod declarations from the crate roots
+    #[arg(long, global = true,
+          help = "Analyze only the files reached from lib.rs, main.rs, and bin/*.rs through\n\
+                  mod declarations (honoring #[path]), with module paths from the module\n\
+                  tree, instead of every .rs file under the path")]
+    follow_mods: bool,
+
+    /// Cache parsed files in this directory
+    #[arg(long, value_name = "DIR", global = true,
+          help = "Cache the parse of each file in DIR, keyed by its content hash, so\n\
+                  later runs only re-parse modified files and those linked to them;\n\
+                  entries of files not analyzed are removed")]
+    cache_dir: Option<String>,
+
+    /// When to color the table
+    #[arg(long, value_name = "WHEN", default_value = "auto", global = true,
+          help = "Color table cells by metric band: auto (default, on a terminal unless\n\
+                  NO_COLOR is set), always, or never")]
+    color: String,
+
+    /// Output file path (default: print to stdout)
+    #[arg(short, long, value_name = "FILE", global = true,
+          help = "Write output to file instead of stdout")]
+    output: Option<String>,
+
+    /// Debug specific structs' parsed data
+    #[arg(long, value_name = "PATTERNS",
+          help = "Print detailed parsing info for structs matching comma-separated\n\
+                  names or globs (e.g. 'Http*,crate::net::Server'); shows fields,\n\
+                  methods with spans and calls, traits, and coupling sources\n\
+                  (table or json)")]
+    debug_struct: Option<String>,
+
+    /// Only report structs matching an expression
+    #[arg(long, value_name = "EXPR", global = true, help = query_help())]
+    query: Option<String>,
+
+    /// Only analyze what changed since a git ref
+    #[arg(long, value_name = "REF",
+          help = "Only report the structs of files changed since this git ref and the\n\
+                  structs depending on them; the whole codebase is still parsed so\n\
+                  their coupling is exact")]
+    changed_since: Option<String>,
+
+    /// Also narrow parsing under --changed-since
+    #[arg(long, requires = "changed_since",
+          help = "With --changed-since, only parse the changed files, the files that\n\
+                  use them, and the files those depend on (faster PR runs; Ca and\n\
+                  fan-in of dependents may be lower than in a full analysis)")]
+    narrow_parse: bool,
+
+    /// Rank structs by churn times complexity
+    #[arg(long,
+          help = "Rank structs by how often their file changed in git times their WMC,\n\
+                  the refactorings that pay off first (table, json, or csv)")]
+    hotspots: bool,
+
+    /// Only count commits since this date for --hotspots
+    #[arg(long, value_name = "DATE", requires = "hotspots",
+          help = "Only count commits after this date for --hotspots, in any format\n\
+                  git log --since accepts, e.g. 2024-01-01 or '6 months ago'")]
+    churn_since: Option<String>,
+
+    /// Report only structs with at least this WMC
+    #[arg(long, value_name = "N", global = true,
+          help = "Report only structs with a WMC of at least N")]
+    min_wmc: Option<f64>,
+
+    /// Report only structs with at least this LCOM
+    #[arg(long, value_name = "N", global = true,
+          help = "Report only structs with an LCOM of at least N (0-1)")]
+    min_lcom: Option<f64>,
+
+    /// Report only structs with at least this CBO
+    #[arg(long, value_name = "N", global = true,
+          help = "Report only structs with a CBO of at least N")]
+    min_cbo: Option<f64>,
+
+    /// Report only the summary statistics
+    #[arg(long, global = true,
+          help = "Print only the count, mean, median, 90th percentile, and maximum of\n\
+                  each metric over the reported structs (table, json, or csv)")]
+    summary_only: bool,
+
+    /// Order results by a metric
+    #[arg(long, value_name = "METRIC", global = true, help = sort_by_help())]
+    sort_by: Option<String>,
+
+    /// Sort from the highest value
+    #[arg(long, global = true, requires = "sort_by",
+          help = "Sort by --sort-by in descending order, worst first for most metrics")]
+    desc: bool,
+
+    /// Report only the first N structs
+    #[arg(long, value_name = "N", global = true,
+          help = "Report only the first N structs after sorting\n\
+                  Example: --sort-by wmc --desc --top 10")]
+    top: Option<usize>,
+
+    /// Exit non-zero when a struct matches an expression
+    #[arg(long, value_name = "EXPR", global = true,
+          help = "Exit with --fail-exit-code after reporting when any reported struct\n\
+                  matches this expression, e.g. 'wmc > 40'; repeat for several\n\
+                  conditions. Offending structs are printed to stderr")]
+    fail_on: Vec<String>,
+
+    /// Exit status for --fail-on
+    #[arg(long, value_name = "CODE", default_value_t = 1, global = true,
+          value_parser = clap::value_parser!(u8).range(1..),
+          help = "Exit status when a --fail-on condition matches or check finds\n\
+                  violations (default: 1)")]
+    fail_exit_code: u8,
+
+    /// Cluster graph nodes by module
+    #[arg(long,
+          help = "Group structs into one cluster per module in DOT output")]
+    cluster_modules: bool,
+
+    /// Roll metrics up per directory
+    #[arg(long,
+          help = "Report totals for each directory and its subdirectories\n\
+                  instead of one row per struct (table, json, or csv)")]
+    by_directory: bool,
+
+    /// Roll metrics up per module, file, or crate
+    #[arg(long, value_name = "UNIT",
+          help = "Report metrics per module, file, or crate instead of per struct:\n\
+                  mean and max WMC, CBO edges, and the share of them staying inside\n\
+                  the group (table, json, or csv)")]
+    group_by: Option<String>,
+
+    /// Break metrics down per impl block
+    #[arg(long,
+          help = "Report LCOM and WMC of each impl block (inherent and trait impls)\n\
+                  with its location, under its struct (table, json, or csv)")]
+    by_impl: bool,
+
+    /// Report one row per method
+    #[arg(long,
+          help = "Report the complexity, fields accessed, and parameter count of\n\
+                  every method (table, json, csv, or xlsx)")]
+    per_method: bool,
+
+    /// Report error-type coupling
+    #[arg(long,
+          help = "Report the distinct error types returned by each struct's and\n\
+                  module's methods, and their `?` sites (table, json, or csv)")]
+    error_coupling: bool,
+
+    /// Report ownership cycles through Rc and Arc
+    #[arg(long,
+          help = "Report reference cycles among structs formed through Rc/Arc fields,\n\
+                  noting whether a Weak field breaks them (table or json)")]
+    ownership_cycles: bool,
+
+    /// Report dependency cycles between structs
+    #[arg(long,
+          help = "Report groups of structs that depend on each other (strongly\n\
+                  connected components of the CBO graph) with their cycle paths\n\
+                  (table or json)")]
+    detect_cycles: bool,
+
+    /// Report design smells
+    #[arg(long,
+          help = "Report design smells: God Class, complex structs using much foreign\n\
+                  data with little cohesion, Data Class, structs exposing data with next\n\
+                  to no behavior, and Feature Envy, methods using more fields of other\n\
+                  structs than of their own (table, json, or csv)")]
+    detect_smells: bool,
+
+    /// Shade the source tree by a metric
+    #[arg(long, value_name = "METRIC",
+          help = "Render the file tree with each file and struct shaded by a metric:\n\
+                  lcom, cbo, or wmc (ANSI colors for table, a page for html)")]
+    heatmap: Option<String>,
+
+    /// Analyze macro-expanded source instead of the files on disk
+    #[arg(long, global = true,
+          help = "Expand macros with `cargo expand` before analyzing\n\
+                  Makes derive- and macro-generated impls visible. Falls back to\n\
+                  `cargo rustc -- -Zunpretty=expanded` when cargo-expand is missing")]
+    expand: bool,
+
+    /// Configuration file with custom threshold profiles
+    #[arg(long, value_name = "FILE", global = true,
+          help = "Read settings from this TOML file\n\
+                  (default: arch-metrics.toml in the current directory, if present)")]
+    config: Option<String>,
+
+    /// Threshold profile
+    #[arg(long, value_name = "NAME", global = true,
+          help = "Threshold profile: strict, default, lenient, or one defined in the config\n\
+                  • strict  - LCOM 0.6, CBO 3, WMC 20\n\
+                  • default - LCOM 0.8, CBO 5, WMC 40\n\
+                  • lenient - LCOM 0.9, CBO 8, WMC 60")]
+    profile: Option<String>,
+
+    /// List only structs exceeding a threshold
+    #[arg(long,
+          help = "List only the structs exceeding a threshold of the profile (markdown)")]
+    violations_only: bool,
+
+    /// How to attribute extension trait methods
+    #[arg(long, value_name = "MODE", default_value = "merge", global = true,
+          help = "How to attribute extension trait methods: merge or off\n\
+                  • merge - Count a local trait implemented for a single struct,\n\
+                  \x20         including its default methods, as part of that struct (default)\n\
+                  • off   - Treat extension traits like any other trait")]
+    extension_traits: String,
+
+    /// How to handle `#[derive(...)]` traits
+    #[arg(long, value_name = "MODE", default_value = "off", global = true,
+          help = "How to handle #[derive(...)] traits: on, off, or list\n\
+                  • on   - Count derived traits as derived_coupling, apart from CBO\n\
+                  • off  - Ignore derives (default)\n\
+                  • list - Report derived traits without counting them")]
+    count_derives: String,
+
+    /// Leave trivial trait impls out of LCOM and WMC
+    #[arg(long, global = true,
+          help = "Leave the methods of hand-written Clone, Debug, Default, PartialEq,\n\
+                  Eq, PartialOrd, Ord, Hash, and Copy impls without branches out of\n\
+                  LCOM and WMC; the trait still counts toward CBO")]
+    skip_trivial_impls: bool,
+
+    /// Propagate field access through self-calls for LCOM
+    #[arg(long, global = true,
+          help = "Credit each method with the fields read by the methods it calls\n\
+                  through self.method() or Self::method(), transitively, for LCOM")]
+    lcom_transitive: bool,
+
+    /// Report trait coupling apart from CBO
+    #[arg(long, global = true,
+          help = "Count only other structs toward CBO, and report the traits implemented\n\
+                  or named by bounds, dyn Trait, and impl Trait as trait_coupling")]
+    separate_trait_coupling: bool,
+
+    /// Decision points each error path adds to cyclomatic complexity
+    #[arg(long, value_name = "N", default_value = "0", global = true,
+          help = "Count each ? operator and return Err(..) as N decision points of\n\
+                  cyclomatic complexity, and so of WMC")]
+    error_path_weight: usize,
+
+    /// Decision points each await point adds to cyclomatic complexity
+    #[arg(long, value_name = "N", default_value = "0", global = true,
+          help = "Count each .await as N decision points of cyclomatic complexity,\n\
+                  and so of WMC")]
+    await_weight: usize,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compare two independent source trees side by side
+    #[command(long_about = "Analyze two source trees (e.g. an old and a rewritten implementation, \
+or two services) and print their aggregates side by side, followed by the metric \
+changes of structs found in both. Structs are matched by module path, then by \
+name when the name is unique. Supports table and json output.")]
+    Compare {
+        /// First tree, the baseline
+        #[arg(value_name = "PATH_A")]
+        path_a: String,
+
+        /// Second tree, compared against the first
+        #[arg(value_name = "PATH_B")]
+        path_b: String,
+    },
+
+    /// Show how struct metrics changed between two analysis runs
+    #[command(long_about = "Compare two JSON result files (snapshot results.json files or \
+directories, or --format json output) and print the metrics that changed per struct, the \
+structs added, and the structs removed. With --against REF, OLD is instead a path to analyze, \
+both at REF, checked out into a temporary git worktree, and as it is now. Structs are matched \
+by qualified name, so a moved struct counts as removed and added. Supports table and json output.")]
+    Diff {
+        /// Baseline result file, or with --against the path to analyze
+        #[arg(value_name = "OLD")]
+        old: String,
+
+        /// Result file compared against the baseline
+        #[arg(value_name = "NEW", required_unless_present = "against", conflicts_with = "against")]
+        new: Option<String>,
+
+        /// Compare OLD as a path against its state at this git ref
+        #[arg(long, value_name = "REF")]
+        against: Option<String>,
+    },
+
+    /// Grade overall architecture health for publishing per build
+    #[command(long_about = "Compute a composite score from 0 to 100 and a letter grade (A-F) \
+from how far each struct's LCOM, CBO, and WMC are below their thresholds. Writes the \
+grade as JSON and, with --badge, an SVG badge for READMEs and dashboards.")]
+    Grade {
+        /// Path to the Rust project directory or single .rs file to grade
+        #[arg(value_name = "PATH")]
+        path: String,
+
+        /// Also write an SVG badge to this file
+        #[arg(long, value_name = "FILE")]
+        badge: Option<String>,
+    },
+
+    /// Show the evidence behind one struct's metrics
+    #[command(long_about = "Analyze PATH and explain the metrics of one struct: the fields each \
+method accesses (LCOM), each method's complexity (WMC), the response set of its methods and \
+everything they call (RFC), and every type it is coupled to (CBO) with the source lines \
+mentioning it. STRUCT is a qualified name such as crate::net::Server, or a plain name when \
+it is unique. LCOM follows --lcom-transitive. Supports table and json output.")]
+    Explain {
+        /// Path to the Rust project directory or single .rs file to analyze
+        #[arg(value_name = "PATH")]
+        path: String,
+
+        /// Struct to explain
+        #[arg(value_name = "STRUCT")]
+        name: String,
+    },
+
+    /// Rank pub types by breaking-change risk
+    #[command(long_about = "Analyze PATH and rank its pub structs by semver-relevant API surface: \
+pub fields (counting double without #[non_exhaustive], as callers may construct and \
+destructure the struct), pub inherent methods, and generic parameters. Also lists pub enums \
+without #[non_exhaustive], which callers may match exhaustively. --query and --sort-by \
+select the structs. Supports table and json output.")]
+    Api {
+        /// Path to the Rust library crate directory or single .rs file to analyze
+        #[arg(value_name = "PATH")]
+        path: String,
+    },
+
+    /// Archive a full result set in a timestamped directory
+    #[command(long_about = "Analyze PATH and write results.json (all results), summary.json \
+(aggregates and grade), and config.json (settings used) into <DIR>/<UTC timestamp>/, \
+a layout suited for CI artifacts and later comparisons.")]
+    Snapshot {
+        /// Path to the Rust project directory or single .rs file to analyze
+        #[arg(value_name = "PATH")]
+        path: String,
+
+        /// Directory holding the timestamped snapshots
+        #[arg(long, value_name = "DIR", default_value = "arch-snapshots")]
+        dir: String,
+    },
+
+    /// Render a static dashboard website from snapshot history
+    #[command(long_about = "Read every snapshot under --snapshots (as written by `snapshot`) and \
+write a static website into --out: an index with trends of the grade and mean metrics, the \
+worst offenders and modules of the latest snapshot, and one page per module. Grades and \
+violations use the current thresholds, so the whole history is judged alike. The pages link \
+relatively and need no server, e.g. for GitHub Pages.")]
+    Site {
+        /// Directory holding the timestamped snapshots
+        #[arg(long, value_name = "DIR", default_value = "arch-snapshots")]
+        snapshots: String,
+
+        /// Directory to write the website into
+        #[arg(long, value_name = "DIR", default_value = "arch-site")]
+        out: String,
+    },
+
+    /// Combine JSON result files into one report
+    #[command(long_about = "Merge JSON result files (snapshot results.json files or snapshot \
+directories, or --format json output), e.g. one per CI shard, into a single report in \
+the chosen format. Structs are deduplicated by fully qualified name, keeping the first \
+occurrence, and all aggregates are recomputed from the merged set.")]
+    Merge {
+        /// Result files to merge, in priority order
+        #[arg(value_name = "FILE", required = true)]
+        files: Vec<String>,
+    },
+
+    /// Filter a saved JSON result file with a query expression
+    #[command(long_about = "Read a JSON result file (a snapshot's results.json or directory, or \
+--format json output) and report the structs matching EXPR in the chosen format. \
+See --query for the expression syntax.")]
+    Query {
+        /// Result file or snapshot directory to read
+        #[arg(value_name = "FILE")]
+        file: String,
+
+        /// Filter expression, e.g. 'wmc > 40 && lcom > 0.7'
+        #[arg(value_name = "EXPR")]
+        expr: String,
+    },
+
+    /// Fail when structs exceed the thresholds, optionally only changed ones
+    #[command(long_about = "Apply the thresholds of the selected profile and exit with status 1 \
+if any struct exceeds them. With --base, only structs defined in files changed since \
+the current branch forked from that git ref (`git diff --name-only BASE...`, plus \
+uncommitted and untracked files) are checked, so PR gates don't fail on \
+pre-existing debt. Supports table and json output.")]
+    Check {
+        /// Path to the Rust project directory or single .rs file to analyze
+        #[arg(value_name = "PATH")]
+        path: String,
+
+        /// Only check structs in files changed since the branch forked from this git ref
+        #[arg(long, value_name = "REF")]
+        base: Option<String>,
+    },
+
+    /// Rank a project against metrics of popular open-source crates
+    #[command(long_about = "Report where the project's average LCOM, CBO, and WMC, and each \
+struct's metrics, fall among a reference dataset, as percentiles: the 87th percentile \
+means a higher value than 87% of the reference. The built-in reference covers popular \
+crates from crates.io; build your own with `build-reference`. Supports table and json output.")]
+    Benchmark {
+        /// Path to the Rust project directory or single .rs file to analyze
+        #[arg(value_name = "PATH")]
+        path: String,
+
+        /// Reference dataset written by `build-reference` (default: built-in)
+        #[arg(long, value_name = "FILE")]
+        reference: Option<String>,
+    },
+
+    /// Print the JSON Schema of --format json output
+    #[command(long_about = "Print the JSON Schema (draft 2020-12) of --format json output: the \
+envelope with the schema version, tool version, timestamp, analyzed path, and settings of \
+the run, around the results. The schema version only changes when a field is removed or \
+changes meaning. The same schema is published as schema/results.schema.json.")]
+    Schema,
+
+    /// Build a reference dataset for `benchmark` from a set of crates
+    #[command(long_about = "Analyze each crate path and write the distribution of struct \
+metrics and of per-crate averages as JSON, for use with `benchmark --reference`.")]
+    BuildReference {
+        /// Source directories of the reference crates, one per crate
+        #[arg(value_name = "PATH", required = true)]
+        paths: Vec<String>,
+    },
+}
+
+/// Run `rust-arch-metrics` with the command-line `args`, including the
+/// program name
+pub fn run(args: impl IntoIterator<Item = OsString>) -> Result<(), Box<dyn std::error::Error>> {
+    run_as(args, false)
+}
+
+/// Run as `cargo arch-metrics`
+///
+/// Without a path, analyzes the source directories of the package or
+/// workspace containing the working directory.
+pub fn run_cargo(args: impl IntoIterator<Item = OsString>) -> Result<(), Box<dyn std::error::Error>> {
+    // Cargo passes the subcommand name after the program name
+    let mut args: Vec<OsString> = args.into_iter().collect();
+    if args.get(1).is_some_and(|arg| arg == "arch-metrics") {
+        args.remove(1);
+    }
+    run_as(args, true)
+}
+
+fn run_as(args: impl IntoIterator<Item = OsString>, cargo: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut command = Cli::command();
+    if cargo {
+        command = command.bin_name("cargo arch-metrics");
+    }
+    let cli = Cli::from_arg_matches(&command.get_matches_from(args)).unwrap_or_else(|e| e.exit());
+
+    let mut paths = cli.paths.clone();
+    let mut crate_names = std::collections::HashMap::new();
+    if cli.command.is_none() && paths.is_empty() && cli.files_from.is_none() {
+        if !cargo {
+            Cli::command()
+                .error(
+                    ErrorKind::MissingRequiredArgument,
+                    "the following required arguments were not provided:\n  <PATH>...",
+                )
+                .exit();
+        }
+        let dirs = workspace::source_dirs(&std::env::current_dir()?)?;
+        if dirs.is_empty() {
+            return Err("No library or binary targets found in the workspace".into());
+        }
+        // Each member is its own crate, rooted at its package name
+        for (dir, name) in dirs {
+            let path = dir.display().to_string();
+            crate_names.insert(path.clone(), name);
+            paths.push(path);
+        }
+    }
+
+    let output_format: OutputFormat = cli.format.parse()?;
+    let config = config::Config::load(cli.config.as_deref())?;
+    let thresholds = config.thresholds(cli.profile.as_deref())?;
+    let mut context = AnalysisContext {
+        parse_options: ParseOptions {
+            extension_traits: cli.extension_traits.parse()?,
+            derives: cli.count_derives.parse()?,
+            skip_trivial_impls: cli.skip_trivial_impls,
+            error_path_weight: cli.error_path_weight,
+            await_weight: cli.await_weight,
+        },
+        include: cli.include.clone(),
+        exclude: cli.exclude.clone(),
+        no_ignore: cli.no_ignore,
+        follow_mods: cli.follow_mods,
+        expand: cli.expand,
+        cache_dir: cli.cache_dir.as_deref().map(PathBuf::from),
+        crate_names,
+        lcom_transitive: cli.lcom_transitive,
+        separate_trait_coupling: cli.separate_trait_coupling,
+        formulas: config.derived_formulas()?,
+        ..Default::default()
+    };
+    let derived_names: Vec<&str> = context.formulas.iter().map(|(name, _)| name.as_str()).collect();
+    context.query = cli
+        .query
+        .as_deref()
+        .map(|expr| query::Query::parse(expr, &derived_names))
+        .transpose()?;
+    context.sort_key = cli
+        .sort_by
+        .as_deref()
+        .map(|expr| query::Formula::parse(expr, &derived_names))
+        .transpose()?;
+    context.minimums = [(Metric::Wmc, cli.min_wmc), (Metric::Lcom, cli.min_lcom), (Metric::Cbo, cli.min_cbo)]
+        .into_iter()
+        .filter_map(|(metric, min)| Some((metric, min?)))
+        .collect();
+    context.descending = cli.desc;
+    context.top = cli.top;
+    let fail_on = cli
+        .fail_on
+        .iter()
+        .map(|expr| Ok((expr.clone(), query::Query::parse(expr, &derived_names)?)))
+        .collect::<Result<Vec<_>, String>>()?;
+    let report_options = report::ReportOptions {
+        cluster_modules: cli.cluster_modules,
+        by_directory: cli.by_directory,
+        group_by: cli.group_by.as_deref().map(str::parse).transpose()?,
+        crate_roots: paths
+            .iter()
+            .filter_map(|path| Some((Path::new(path).canonicalize().ok()?, crate_name(path))))
+            .collect(),
+        by_impl: cli.by_impl,
+        per_method: cli.per_method,
+        metrics: cli.metrics.parse()?,
+        // Escapes only make sense on a terminal
+        color: cli.color.parse::<ColorMode>()?.enabled(cli.output.is_none() && std::io::stdout().is_terminal()),
+        error_coupling: cli.error_coupling,
+        ownership_cycles: cli.ownership_cycles,
+        detect_cycles: cli.detect_cycles,
+        detect_smells: cli.detect_smells,
+        heatmap: cli.heatmap.as_deref().map(str::parse).transpose()?,
+        violations_only: cli.violations_only,
+        summary_only: cli.summary_only,
+        thresholds: thresholds.clone(),
+        smells: config.smells.clone(),
+        metadata: None, // Set per run by `report_options_for`
+    };
+
+    // Unselected metrics are skipped only when nothing else reads them
+    let formulas = context.query.is_some()
+        || !context.minimums.is_empty()
+        || context.sort_key.is_some()
+        || !fail_on.is_empty()
+        || !context.formulas.is_empty();
+    if cli.command.is_none()
+        && !cli.hotspots
+        && cli.debug_struct.is_none()
+        && !formulas
+        && matches!(output_format, OutputFormat::Table | OutputFormat::Json | OutputFormat::Csv | OutputFormat::Yaml | OutputFormat::Jsonl | OutputFormat::Sqlite)
+        && report_options.is_struct_report()
+    {
+        context.metrics = report_options.metrics;
+    }
+    let pipeline = || AnalysisPipeline::new(&context);
+
+    // Settings recorded with snapshots and around `--format json` results
+    let run_settings = || snapshot::RunSettings {
+        include: cli.include.clone(),
+        exclude: cli.exclude.clone(),
+        no_ignore: cli.no_ignore,
+        follow_mods: cli.follow_mods,
+        expand: cli.expand,
+        extension_traits: cli.extension_traits.clone(),
+        count_derives: cli.count_derives.clone(),
+        skip_trivial_impls: cli.skip_trivial_impls,
+        lcom_transitive: cli.lcom_transitive,
+        separate_trait_coupling: cli.separate_trait_coupling,
+        error_path_weight: cli.error_path_weight,
+        await_weight: cli.await_weight,
+        profile: cli.profile.clone().or(config.profile.clone()),
+        thresholds: thresholds.clone(),
+    };
+    let report_options_for = |path: &str| report::ReportOptions {
+        metadata: Some(report::Metadata::new(path.to_string(), run_settings())),
+        ..report_options.clone()
+    };
+
+    match &cli.command {
+        Some(Command::Compare { path_a, path_b }) => {
+            let results_a = analyze(path_a, &context)?;
+            let results_b = analyze(path_b, &context)?;
+            let comparison = compare::compare(path_a, &results_a, path_b, &results_b);
+            report::generate_comparison_report(&comparison, output_format, cli.output.as_deref())?;
+            return Ok(());
+        }
+        Some(Command::Diff { old, new, against }) => {
+            let diff = match (new, against) {
+                (Some(new), _) => diff::diff(
+                    old,
+                    &merge::read_results(Path::new(old))?,
+                    new,
+                    &merge::read_results(Path::new(new))?,
+                ),
+                (None, Some(rev)) => {
+                    let (_worktree, past) = git::Worktree::checkout(Path::new(old), rev)?;
+                    let results_old = analyze(&past.display().to_string(), &context)?;
+                    let results_new = analyze(old, &context)?;
+                    diff::diff(&format!("{} @ {}", old, rev), &results_old, old, &results_new)
+                }
+                (None, None) => unreachable!("clap requires NEW or --against"),
+            };
+            report::generate_diff_report(&diff, output_format, cli.output.as_deref())?;
+            return Ok(());
+        }
+        Some(Command::Grade { path, badge }) => {
+            let results = analyze(path, &context)?;
+            let grade = score::grade(&results, &thresholds);
+            report::generate_grade_report(&grade, cli.output.as_deref(), badge.as_deref())?;
+            return Ok(());
+        }
+        Some(Command::Explain { path, name }) => {
+            let structs = pipeline().parse(path)?.structs;
+            let struct_info = explain::find_struct(&structs, name)?;
+            let source = if struct_info.file_path.is_empty() {
+                None
+            } else {
+                Some(std::fs::read_to_string(&struct_info.file_path)?)
+            };
+            let explanation = explain::explain(struct_info, &structs, source.as_deref(), context.lcom_transitive);
+            report::generate_explain_report(&explanation, output_format, cli.output.as_deref())?;
+            return Ok(());
+        }
+        Some(Command::Api { path }) => {
+            let analysis = pipeline().run(path)?;
+            let report = api::api_report(&analysis.results, &analysis.parsed.enums);
+            report::generate_api_report(&report, output_format, cli.output.as_deref())?;
+            return Ok(());
+        }
+        Some(Command::Snapshot { path, dir }) => {
+            let results = analyze(path, &context)?;
+            let written = snapshot::write_snapshot(
+                Path::new(dir),
+                &results,
+                &snapshot::SnapshotConfig {
+                    path: path.to_string(),
+                    settings: run_settings(),
+                    version: env!("CARGO_PKG_VERSION"),
+                },
+            )?;
+            println!("{}", written.display());
+            return Ok(());
+        }
+        Some(Command::Site { snapshots, out }) => {
+            let history = history::load_history(Path::new(snapshots))?;
+            if history.is_empty() {
+                eprintln!("No snapshots found in {}", snapshots);
+            }
+            let pages = report::generate_site_report(&history, Path::new(out), &thresholds)?;
+            println!("Wrote {} page(s) to {}", pages, out);
+            return Ok(());
+        }
+        Some(Command::Merge { files }) => {
+            let sets = files
+                .iter()
+                .map(|file| merge::read_results(Path::new(file)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let (mut results, duplicates) = merge::merge_results(sets);
+            if duplicates > 0 {
+                eprintln!("Dropped {} duplicate struct(s) by qualified name", duplicates);
+            }
+            // Shards only see the dependents within themselves
+            metrics::afferent::apply(&mut results);
+            for result in &mut results {
+                metrics::apply_derived(result, &context.formulas);
+            }
+            pipeline().select(&mut results);
+            let options = report_options_for(&files.join(", "));
+            report::generate_report(&results, output_format, cli.output.as_deref(), &options)?;
+            enforce_fail_on(&results, &fail_on, cli.fail_exit_code);
+            return Ok(());
+        }
+        Some(Command::Query { file, expr }) => {
+            let expr_query = query::Query::parse(expr, &derived_names)?;
+            let mut results = merge::read_results(Path::new(file))?;
+            for result in &mut results {
+                metrics::apply_derived(result, &context.formulas);
+            }
+            results.retain(|r| expr_query.matches(r));
+            pipeline().select(&mut results);
+            report::generate_report(&results, output_format, cli.output.as_deref(), &report_options_for(file))?;
+            enforce_fail_on(&results, &fail_on, cli.fail_exit_code);
+            return Ok(());
+        }
+        Some(Command::Check { path, base }) => {
+            let mut results = analyze(path, &context)?;
+            let total = results.len();
+            if let Some(base) = base {
+                let changed = git::changed_files(Path::new(path), base)?;
+                results.retain(|r| git::is_changed(&r.file_path, &changed));
+            }
+            let outcome = thresholds::CheckOutcome {
+                base: base.clone(),
+                checked: results.len(),
+                skipped: total - results.len(),
+                violations: thresholds::find_violations(&results, &thresholds),
+            };
+            report::generate_check_report(&outcome, output_format, cli.output.as_deref())?;
+            if !outcome.violations.is_empty() {
+                std::process::exit(cli.fail_exit_code.into());
+            }
+            return Ok(());
+        }
+        Some(Command::Benchmark { path, reference }) => {
+            let reference = match reference {
+                Some(file) => benchmark::Reference::load(file)?,
+                None => benchmark::Reference::builtin(),
+            };
+            let results = analyze(path, &context)?;
+            let benchmark = benchmark::benchmark(&results, &reference);
+            report::generate_benchmark_report(&benchmark, output_format, cli.output.as_deref())?;
+            return Ok(());
+        }
+        Some(Command::BuildReference { paths }) => {
+            let crates = paths
+                .iter()
+                .map(|path| Ok((crate_name(path), analyze(path, &context)?)))
+                .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+            let reference = benchmark::Reference::build(&crates);
+            let content = serde_json::to_string_pretty(&reference)?;
+            match cli.output.as_deref() {
+                Some(file) => std::fs::write(file, content)?,
+                None => println!("{}", content),
+            }
+            return Ok(());
+        }
+        Some(Command::Schema) => {
+            let schema = report::json_schema();
+            match cli.output.as_deref() {
+                Some(file) => std::fs::write(file, schema)?,
+                None => print!("{}", schema),
+            }
+            return Ok(());
+        }
+        None => {}
+    }
+
+    // Parse all files and collect struct information
+    if let Some(list) = &cli.files_from {
+        paths.extend(read_file_list(list)?);
+    }
+    let mut pipeline = match &cli.changed_since {
+        Some(_) if cli.expand => return Err("--changed-since cannot be combined with --expand".into()),
+        Some(base) => pipeline().with_hook(scope::ChangedSince::new(base, cli.narrow_parse)),
+        None => pipeline(),
+    };
+    let all_structs = pipeline.parse_paths(&paths)?.structs;
+
+    if all_structs.is_empty() {
+        eprintln!("No structs found in the analyzed files.");
+        std::process::exit(0);
+    }
+
+    // Handle debug output if requested
+    if let Some(patterns) = &cli.debug_struct {
+        let selected = debug::select(&all_structs, patterns)?;
+        if selected.is_empty() {
+            return Err(format!("No struct matches {}", patterns).into());
+        }
+        let index = StructIndex::new(&all_structs);
+        let dumps: Vec<_> = selected.into_iter().map(|s| debug::dump(s, &index)).collect();
+        report::generate_debug_report(&dumps, output_format, cli.output.as_deref())?;
+        return Ok(());
+    }
+
+    // JSON Lines are written as each struct is analyzed
+    if matches!(output_format, OutputFormat::Jsonl) && !cli.hotspots {
+        if context.sort_key.is_some() {
+            return Err("--sort-by needs every result before writing any; use --format json".into());
+        }
+        if !report_options.is_struct_report() || report_options.summary_only {
+            return Err("jsonl writes one struct per line and supports no other view".into());
+        }
+        let mut lines = report::JsonLines::create(cli.output.as_deref(), report_options.metrics)?;
+        // Only the results matching a --fail-on condition are kept
+        let mut failing = Vec::new();
+        pipeline.stream(&all_structs, |result| {
+            if fail_on.iter().any(|(_, query)| query.matches(result)) {
+                failing.push(result.clone());
+            }
+            lines.write(result)
+        })?;
+        lines.finish()?;
+        enforce_fail_on(&failing, &fail_on, cli.fail_exit_code);
+        return Ok(());
+    }
+
+    // Calculate metrics for each struct
+    let mut results = pipeline.analyze(&all_structs);
+    pipeline.select(&mut results);
+
+    if cli.hotspots {
+        let mut churn = std::collections::HashMap::new();
+        for path in &paths {
+            churn.extend(git::churn(Path::new(path), cli.churn_since.as_deref())?);
+        }
+        let hotspots = hotspots::rank(&results, &churn);
+        report::generate_hotspot_report(&hotspots, output_format, cli.output.as_deref())?;
+        return Ok(());
+    }
+
+    // Generate report
+    let options = report_options_for(&paths.join(", "));
+    report::generate_report(&results, output_format, cli.output.as_deref(), &options)?;
+    enforce_fail_on(&results, &fail_on, cli.fail_exit_code);
+
+    Ok(())
+}
+
+/// Paths listed one per line in `list`, or on stdin if it is `-`
+fn read_file_list(list: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let content = if list == "-" {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        std::fs::read_to_string(list).map_err(|e| format!("Failed to read {}: {}", list, e))?
+    };
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Print the structs matching `--fail-on` conditions and exit with `code`
+/// if there are any
+fn enforce_fail_on(results: &[AnalysisResult], conditions: &[(String, query::Query)], code: u8) {
+    let failures = thresholds::find_failures(results, conditions);
+    if failures.is_empty() {
+        return;
+    }
+    for failure in &failures {
+        eprintln!("--fail-on '{}' matched {} struct(s):", failure.condition, failure.structs.len());
+        for name in &failure.structs {
+            eprintln!("  {}", name);
+        }
+    }
+    std::process::exit(code.into());
+}
+
+/// Help of `--query`, listing the fields the query engine accepts
+fn query_help() -> String {
+    let numeric = query::num_field_names().chain(["derived metrics from the config"]);
+    format!(
+        "Only report structs matching an expression, e.g. 'wmc > 40 && lcom > 0.7'\n\
+         Fields: {},\n{:8}{}\n\
+         Operators: == != < <= > >= ~ (text contains), && || ! and parentheses;\n\
+         \x20          + - * / on numbers",
+        wrap_list(numeric, 8, 8),
+        "",
+        query::text_field_names().collect::<Vec<_>>().join(", ")
+    )
+}
+
+/// Help of `--sort-by`, listing the fields the query engine accepts
+fn sort_by_help() -> String {
+    let metrics = query::num_field_names().chain(["a derived metric from the config", "or a formula such as 'wmc * lcom'"]);
+    format!("Sort results in ascending order of a metric: {}", wrap_list(metrics, 44, 0))
+}
+
+/// `items` joined by commas, wrapped at 80 columns: the first line starts
+/// after `start` columns, the others are indented by `indent`
+fn wrap_list<'a>(items: impl IntoIterator<Item = &'a str>, start: usize, indent: usize) -> String {
+    let items: Vec<&str> = items.into_iter().collect();
+    let mut output = String::new();
+    let mut column = start;
+    for (i, item) in items.iter().enumerate() {
+        let separator = if i + 1 < items.len() { "," } else { "" };
+        if i > 0 {
+            if column + 1 + item.len() + separator.len() > 80 {
+                output.push('\n');
+                output.push_str(&" ".repeat(indent));
+                column = indent;
+            } else {
+                output.push(' ');
+                column += 1;
+            }
+        }
+        output.push_str(item);
+        output.push_str(separator);
+        column += item.len() + separator.len();
+    }
+    output
+}
+
+/// Display name of the crate at `path`: its directory, or the parent of `src/`
+fn crate_name(path: &str) -> String {
+    let path = Path::new(path).canonicalize().unwrap_or_else(|_| Path::new(path).to_path_buf());
+    let dir = if path.file_name().is_some_and(|name| name == "src") {
+        path.parent().unwrap_or(&path)
+    } else {
+        &path
+    };
+    dir.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().to_string())
+}
+
+/// Parse and analyze the crate at `path`, without hooks or selection
+fn analyze(path: &str, context: &AnalysisContext) -> Result<Vec<AnalysisResult>, Box<dyn std::error::Error>> {
+    let mut pipeline = AnalysisPipeline::new(context);
+    let parsed = pipeline.parse(path)?;
+    Ok(pipeline.analyze(&parsed.structs))
+}
@@ -0,0 +1,156 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::aggregate::{summarize, Summary};
+use crate::models::AnalysisResult;
+use crate::significance::{mean_changes, Significance};
+
+/// Side-by-side comparison of two independently analyzed source trees
+#[derive(Debug, serde::Serialize)]
+pub struct Comparison {
+    pub path_a: String,
+    pub path_b: String,
+    pub summary_a: Summary,
+    pub summary_b: Summary,
+    pub significance: Vec<Significance>, // Whether each mean metric really moved
+    pub matched: Vec<StructDelta>,
+    pub only_in_a: Vec<String>,
+    pub only_in_b: Vec<String>,
+}
+
+/// Metrics of a struct found in both trees
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct StructDelta {
+    pub struct_name: String,
+    pub lcom_a: f64,
+    pub lcom_b: f64,
+    pub cbo_a: usize,
+    pub cbo_b: usize,
+    pub wmc_a: usize,
+    pub wmc_b: usize,
+}
+
+/// Compare the results of two trees, matching structs between them
+///
+/// Structs are matched by qualified name first. Remaining structs are
+/// matched by plain name when that name is unique among the unmatched
+/// structs of both trees, which pairs up structs that moved modules.
+pub fn compare(path_a: &str, a: &[AnalysisResult], path_b: &str, b: &[AnalysisResult]) -> Comparison {
+    let mut matched = Vec::new();
+    let mut used_a = HashSet::new();
+    let mut used_b = HashSet::new();
+
+    let index_b: HashMap<String, usize> = b.iter().enumerate().map(|(i, r)| (r.qualified_name(), i)).collect();
+    for (i, result) in a.iter().enumerate() {
+        if let Some(&j) = index_b.get(&result.qualified_name()) {
+            if used_b.insert(j) {
+                used_a.insert(i);
+                matched.push(delta(result.qualified_name(), result, &b[j]));
+            }
+        }
+    }
+
+    let names_a = unique_names(a, &used_a);
+    let names_b = unique_names(b, &used_b);
+    for (i, result) in a.iter().enumerate() {
+        let name = result.struct_name.as_str();
+        if let (Some(Some(_)), Some(&Some(j))) = (names_a.get(name), names_b.get(name)) {
+            used_a.insert(i);
+            used_b.insert(j);
+            matched.push(delta(
+                format!("{} -> {}", result.qualified_name(), b[j].qualified_name()),
+                result,
+                &b[j],
+            ));
+        }
+    }
+
+    let unmatched = |results: &[AnalysisResult], used: &HashSet<usize>| {
+        results
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !used.contains(i))
+            .map(|(_, r)| r.qualified_name())
+            .collect()
+    };
+
+    Comparison {
+        path_a: path_a.to_string(),
+        path_b: path_b.to_string(),
+        summary_a: summarize(a),
+        summary_b: summarize(b),
+        significance: mean_changes(a, b),
+        matched,
+        only_in_a: unmatched(a, &used_a),
+        only_in_b: unmatched(b, &used_b),
+    }
+}
+
+/// Index of each unused struct by name, `None` when the name is ambiguous
+fn unique_names<'a>(results: &'a [AnalysisResult], used: &HashSet<usize>) -> HashMap<&'a str, Option<usize>> {
+    let mut names: HashMap<&str, Option<usize>> = HashMap::new();
+    for (i, r) in results.iter().enumerate().filter(|(i, _)| !used.contains(i)) {
+        names
+            .entry(r.struct_name.as_str())
+            .and_modify(|slot| *slot = None)
+            .or_insert(Some(i));
+    }
+    names
+}
+
+fn delta(struct_name: String, a: &AnalysisResult, b: &AnalysisResult) -> StructDelta {
+    StructDelta {
+        struct_name,
+        lcom_a: a.lcom,
+        lcom_b: b.lcom,
+        cbo_a: a.cbo,
+        cbo_b: b.cbo,
+        wmc_a: a.wmc,
+        wmc_b: b.wmc,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(module_path: &str, name: &str, wmc: usize) -> AnalysisResult {
+        AnalysisResult {
+            struct_name: name.to_string(),
+            module_path: module_path.to_string(),
+            wmc,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compare_matches_by_path_then_unique_name() {
+        let a = vec![
+            result("crate", "Config", 4),
+            result("crate::old", "Engine", 30),
+            result("crate", "Legacy", 7),
+        ];
+        let b = vec![
+            result("crate", "Config", 2),
+            result("crate::engine", "Engine", 12),
+            result("crate", "Cache", 3),
+        ];
+
+        let comparison = compare("old/", &a, "new/", &b);
+        let matched: Vec<(&str, usize, usize)> = comparison
+            .matched
+            .iter()
+            .map(|d| (d.struct_name.as_str(), d.wmc_a, d.wmc_b))
+            .collect();
+
+        assert_eq!(
+            matched,
+            vec![
+                ("crate::Config", 4, 2),
+                ("crate::old::Engine -> crate::engine::Engine", 30, 12),
+            ]
+        );
+        assert_eq!(comparison.only_in_a, vec!["crate::Legacy".to_string()]);
+        assert_eq!(comparison.only_in_b, vec!["crate::Cache".to_string()]);
+        assert_eq!(comparison.summary_a.total_wmc, 41);
+    }
+}
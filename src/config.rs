@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::query::{is_builtin_field, Formula};
+use crate::smells::SmellThresholds;
+use crate::thresholds::Thresholds;
+
+/// File looked up in the current directory when `--config` is not given
+pub const DEFAULT_CONFIG_FILE: &str = "arch-metrics.toml";
+
+/// Settings read from an `arch-metrics.toml` file
+///
+/// ```toml
+/// profile = "legacy"      # used when --profile is not given
+///
+/// [profiles.legacy]
+/// cbo = 10                # unset metrics keep the `default` profile's value
+/// wmc = 80
+///
+/// [metrics.debt]
+/// formula = "wmc * lcom + cbo"
+/// threshold = 30          # optional, in every profile
+///
+/// [smells.god_class]
+/// wmc = 60                # unset limits keep their defaults
+///
+/// [smells.data_class]
+/// exposed = 4
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub profile: Option<String>,
+    pub profiles: BTreeMap<String, ProfileConfig>,
+    pub metrics: BTreeMap<String, DerivedMetric>,
+    pub smells: SmellThresholds, // Limits of `--detect-smells`
+}
+
+/// A metric computed per struct from the built-in ones
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DerivedMetric {
+    pub formula: String,
+    pub threshold: Option<f64>,
+}
+
+/// A named threshold profile; defined names override the built-in ones
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProfileConfig {
+    pub lcom: Option<f64>,
+    pub cbo: Option<usize>,
+    pub wmc: Option<usize>,
+}
+
+impl Config {
+    /// Load `path`, or `arch-metrics.toml` from the current directory if it exists
+    pub fn load(path: Option<&str>) -> Result<Self, String> {
+        let path = match path {
+            Some(path) => Path::new(path),
+            None if Path::new(DEFAULT_CONFIG_FILE).is_file() => Path::new(DEFAULT_CONFIG_FILE),
+            None => return Ok(Self::default()),
+        };
+
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config {}: {}", path.display(), e))?;
+        toml::from_str(&content).map_err(|e| format!("Invalid config {}: {}", path.display(), e))
+    }
+
+    /// Parsed formulas of the derived metrics, by name
+    pub fn derived_formulas(&self) -> Result<Vec<(String, Formula)>, String> {
+        self.metrics
+            .iter()
+            .map(|(name, metric)| {
+                let valid_name = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                    && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+                if !valid_name || is_builtin_field(name) {
+                    return Err(format!("Invalid derived metric name: {}", name));
+                }
+                let formula = metric
+                    .formula
+                    .parse()
+                    .map_err(|e| format!("Invalid formula for metric {}: {}", name, e))?;
+                Ok((name.clone(), formula))
+            })
+            .collect()
+    }
+
+    /// Thresholds of the selected profile, plus those of derived metrics
+    ///
+    /// `selected` (from `--profile`) wins over the config's `profile`, which
+    /// wins over `default`. Custom profiles start from the built-in profile
+    /// of the same name, or from `default`.
+    pub fn thresholds(&self, selected: Option<&str>) -> Result<Thresholds, String> {
+        let mut thresholds = self.profile_thresholds(selected)?;
+        thresholds.derived = self
+            .metrics
+            .iter()
+            .filter_map(|(name, metric)| Some((name.clone(), metric.threshold?)))
+            .collect();
+        Ok(thresholds)
+    }
+
+    fn profile_thresholds(&self, selected: Option<&str>) -> Result<Thresholds, String> {
+        let name = selected.or(self.profile.as_deref()).unwrap_or("default");
+        let builtin = Thresholds::profile(name);
+
+        let Some(custom) = self.profiles.get(name) else {
+            return builtin.ok_or_else(|| {
+                let available: Vec<&str> = Thresholds::PROFILES
+                    .into_iter()
+                    .chain(
+                        self.profiles
+                            .keys()
+                            .map(String::as_str)
+                            .filter(|name| Thresholds::profile(name).is_none()),
+                    )
+                    .collect();
+                format!("Unknown profile: {} (available: {})", name, available.join(", "))
+            });
+        };
+
+        let base = builtin.unwrap_or_default();
+        Ok(Thresholds {
+            lcom: custom.lcom.unwrap_or(base.lcom),
+            cbo: custom.cbo.unwrap_or(base.cbo),
+            wmc: custom.wmc.unwrap_or(base.wmc),
+            derived: BTreeMap::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_selection() {
+        let config: Config = toml::from_str(
+            r#"
+            profile = "legacy"
+
+            [profiles.legacy]
+            wmc = 80
+
+            [profiles.strict]
+            cbo = 2
+            "#,
+        )
+        .unwrap();
+
+        let legacy = config.thresholds(None).unwrap();
+        assert_eq!((legacy.lcom, legacy.cbo, legacy.wmc), (0.8, 5, 80));
+
+        let strict = config.thresholds(Some("strict")).unwrap();
+        assert_eq!((strict.lcom, strict.cbo, strict.wmc), (0.6, 2, 20));
+
+        let lenient = config.thresholds(Some("lenient")).unwrap();
+        assert_eq!(lenient, Thresholds::profile("lenient").unwrap());
+
+        let err = config.thresholds(Some("missing")).unwrap_err();
+        assert!(err.ends_with("(available: strict, default, lenient, legacy)"));
+    }
+
+    #[test]
+    fn test_derived_metrics() {
+        let config: Config = toml::from_str(
+            r#"
+            [metrics.debt]
+            formula = "wmc * lcom + cbo"
+            threshold = 30
+
+            [metrics.density]
+            formula = "wmc / methods"
+            "#,
+        )
+        .unwrap();
+
+        let formulas = config.derived_formulas().unwrap();
+        let names: Vec<&str> = formulas.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["debt", "density"]);
+
+        let thresholds = config.thresholds(None).unwrap();
+        assert_eq!(thresholds.derived, BTreeMap::from([("debt".to_string(), 30.0)]));
+
+        let shadowing: Config = toml::from_str("[metrics.wmc]\nformula = \"cbo\"").unwrap();
+        assert!(shadowing.derived_formulas().is_err());
+    }
+
+    #[test]
+    fn test_smell_limits_keep_unset_defaults() {
+        let config: Config = toml::from_str("[smells.god_class]\nwmc = 60").unwrap();
+        let god_class = &config.smells.god_class;
+        assert_eq!((god_class.wmc, god_class.atfd, god_class.tcc), (60, 2, 1.0 / 3.0));
+
+        assert!(toml::from_str::<Config>("[smells.god_class]\nloc = 1").is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use globset::{Glob, GlobSetBuilder};
+
+use crate::metrics::cbo::extract_all_types;
+use crate::models::{FieldInfo, SourceSpan, StructInfo, StructKind, Symbol};
+use crate::resolve::{self, StructIndex};
+
+/// Everything parsed for one struct, for `--debug-struct`
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StructDump {
+    pub name: String,
+    pub qualified_name: String,
+    pub kind: StructKind,
+    pub file_path: String,
+    pub span: SourceSpan,
+    pub fields: Vec<FieldInfo>,
+    pub methods: Vec<MethodDump>,
+    pub external_types: Vec<Symbol>,
+    pub traits: Vec<String>,
+    pub derives: Vec<String>,
+    pub referenced_traits: Vec<String>,
+    pub reexported_as: Vec<String>,
+    pub aliases: Vec<String>,
+    pub coupling: Vec<CouplingSource>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MethodDump {
+    pub name: String,
+    pub span: SourceSpan,
+    pub impl_block: String, // Label of the impl block, e.g. `impl #1`
+    pub fields_accessed: Vec<Symbol>,
+    pub complexity: usize,
+    pub calls: Vec<String>,
+}
+
+/// A type counted by CBO and what made it count
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct CouplingSource {
+    pub target: String, // Qualified struct name or trait path
+    pub source: String, // e.g. `field config`, `type reference`, `impl`
+}
+
+/// Structs whose name or qualified name matches one of the comma-separated
+/// glob patterns, e.g. `Server,Http*` or `crate::net::*`
+pub fn select<'a>(structs: &'a [StructInfo], patterns: &str) -> Result<Vec<&'a StructInfo>, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        builder.add(Glob::new(pattern)?);
+    }
+    let set = builder.build()?;
+
+    Ok(structs
+        .iter()
+        .filter(|s| set.is_match(&s.name) || set.is_match(s.qualified_name()))
+        .collect())
+}
+
+/// Dump the parsed data of `struct_info`, with the source of each coupling
+pub fn dump(struct_info: &StructInfo, index: &StructIndex) -> StructDump {
+    let mut coupling: Vec<CouplingSource> = Vec::new();
+    let mut add = |target: String, source: String| {
+        let entry = CouplingSource { target, source };
+        if !coupling.contains(&entry) {
+            coupling.push(entry);
+        }
+    };
+
+    let self_name = struct_info.qualified_name();
+    let resolve = |path: &str| {
+        index
+            .find_struct(path, &struct_info.module_path)
+            .map(StructInfo::qualified_name)
+            .filter(|target| *target != self_name)
+    };
+    for field in &struct_info.fields {
+        for target in extract_all_types(&field.ty).iter().filter_map(|path| resolve(path)) {
+            add(target, format!("field {}", field.name));
+        }
+    }
+    for target in struct_info.external_types.iter().filter_map(|path| resolve(path)) {
+        add(target, "type reference".to_string());
+    }
+    for method in &struct_info.methods {
+        for target in method.used_types.iter().filter_map(|path| resolve(path)) {
+            add(target, format!("used in {}", method.name));
+        }
+    }
+    for trait_path in &struct_info.traits {
+        if struct_info.crate_traits.iter().any(|name| name == resolve::trait_name(trait_path)) {
+            add(trait_path.clone(), "impl".to_string());
+        }
+    }
+    for trait_path in &struct_info.referenced_traits {
+        if struct_info.crate_traits.iter().any(|name| name == resolve::trait_name(trait_path)) {
+            add(trait_path.clone(), "impl Trait in signature".to_string());
+        }
+    }
+
+    let methods = struct_info
+        .methods
+        .iter()
+        .map(|m| {
+            let mut fields_accessed = m.fields_accessed.clone();
+            fields_accessed.sort();
+            MethodDump {
+                name: m.name.clone(),
+                span: m.span,
+                impl_block: struct_info
+                    .impl_blocks
+                    .get(m.impl_block)
+                    .map(|block| block.label.clone())
+                    .unwrap_or_default(),
+                fields_accessed,
+                complexity: m.cyclomatic_complexity,
+                calls: m.calls.clone(),
+            }
+        })
+        .collect();
+
+    StructDump {
+        name: struct_info.name.clone(),
+        qualified_name: self_name,
+        kind: struct_info.kind,
+        file_path: struct_info.file_path.clone(),
+        span: struct_info.span,
+        fields: struct_info.fields.clone(),
+        methods,
+        external_types: struct_info.external_types.clone(),
+        traits: struct_info.traits.clone(),
+        derives: struct_info.derives.clone(),
+        referenced_traits: struct_info.referenced_traits.clone(),
+        reexported_as: struct_info.reexported_as.clone(),
+        aliases: struct_info.aliases.clone(),
+        coupling,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_file, ParseOptions};
+
+    #[test]
+    fn test_select_and_dump() {
+        let source = r#"
+            mod net {
+                struct HttpServer { config: crate::Config }
+                struct HttpClient;
+                impl HttpClient {
+                    fn send(&self, on_done: impl FnOnce(&crate::Config)) {}
+                }
+            }
+            struct Config;
+        "#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+
+        let names = |patterns| -> Vec<String> {
+            select(&structs, patterns).unwrap().iter().map(|s| s.name.clone()).collect()
+        };
+        assert_eq!(names("Http*"), vec!["HttpServer", "HttpClient"]);
+        assert_eq!(names("Config, crate::net::HttpClient"), vec!["HttpClient", "Config"]);
+        assert!(select(&structs, "Http[").is_err());
+
+        let server = dump(&structs[0], &StructIndex::new(&structs));
+        assert_eq!(
+            server.coupling,
+            vec![CouplingSource { target: "crate::Config".into(), source: "field config".into() }]
+        );
+        let client = dump(&structs[1], &StructIndex::new(&structs));
+        assert_eq!(client.methods[0].impl_block, "impl #1");
+        assert_eq!(client.coupling[0].source, "type reference");
+    }
+}
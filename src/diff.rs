@@ -0,0 +1,130 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::models::AnalysisResult;
+use crate::query::Formula;
+
+/// Metrics compared per struct, by their query names
+const METRICS: [&str; 8] = ["lcom", "cbo", "wmc", "cognitive", "mi", "sloc", "atfd", "ca"];
+
+/// Changes between two analysis runs of the same code base
+#[derive(Debug, serde::Serialize)]
+pub struct Diff {
+    pub old: String, // Label of each run, e.g. a file or `src/ @ main`
+    pub new: String,
+    pub changed: Vec<StructChange>,
+    pub added: Vec<String>, // Qualified names, in the order of the new run
+    pub removed: Vec<String>, // Qualified names, in the order of the old run
+    pub unchanged: usize,
+}
+
+/// A struct of both runs with the metrics that changed
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct StructChange {
+    pub struct_name: String, // Qualified name
+    pub deltas: Vec<MetricDelta>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct MetricDelta {
+    pub metric: String,
+    pub old: f64,
+    pub new: f64,
+}
+
+impl MetricDelta {
+    pub fn delta(&self) -> f64 {
+        self.new - self.old
+    }
+}
+
+/// Compare two runs, matching structs by qualified name
+///
+/// Unlike `compare`, which pairs up structs of unrelated trees, a struct
+/// moved to another module counts as removed and added, since that is what
+/// a reviewer of the change sees.
+pub fn diff(old_label: &str, old: &[AnalysisResult], new_label: &str, new: &[AnalysisResult]) -> Diff {
+    let metrics: Vec<(&str, Formula)> = METRICS
+        .iter()
+        .map(|&metric| (metric, Formula::parse(metric, &[]).expect("built-in metric")))
+        .collect();
+    let old_index: HashMap<String, &AnalysisResult> = old.iter().map(|r| (r.qualified_name(), r)).collect();
+    let new_names: HashSet<String> = new.iter().map(AnalysisResult::qualified_name).collect();
+
+    let mut changed = Vec::new();
+    let mut added = Vec::new();
+    let mut unchanged = 0;
+    for result in new {
+        let name = result.qualified_name();
+        let Some(before) = old_index.get(&name) else {
+            added.push(name);
+            continue;
+        };
+        let deltas: Vec<MetricDelta> = metrics
+            .iter()
+            .map(|(metric, formula)| MetricDelta {
+                metric: metric.to_string(),
+                old: formula.eval(before),
+                new: formula.eval(result),
+            })
+            // Below the printed precision is noise from float arithmetic
+            .filter(|delta| delta.delta().abs() >= 1e-6)
+            .collect();
+        if deltas.is_empty() {
+            unchanged += 1;
+        } else {
+            changed.push(StructChange {
+                struct_name: name,
+                deltas,
+            });
+        }
+    }
+
+    Diff {
+        old: old_label.to_string(),
+        new: new_label.to_string(),
+        changed,
+        added,
+        removed: old
+            .iter()
+            .map(AnalysisResult::qualified_name)
+            .filter(|name| !new_names.contains(name))
+            .collect(),
+        unchanged,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(name: &str, wmc: usize, lcom: f64) -> AnalysisResult {
+        AnalysisResult {
+            struct_name: name.to_string(),
+            module_path: "crate".to_string(),
+            wmc,
+            lcom,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_reports_deltas_and_added_and_removed_structs() {
+        let old = vec![result("Config", 4, 0.5), result("Engine", 30, 0.2), result("Legacy", 7, 0.0)];
+        let new = vec![result("Config", 4, 0.5), result("Engine", 35, 0.4), result("Cache", 3, 0.0)];
+
+        let diff = diff("old.json", &old, "new.json", &new);
+        assert_eq!(
+            diff.changed,
+            vec![StructChange {
+                struct_name: "crate::Engine".to_string(),
+                deltas: vec![
+                    MetricDelta { metric: "lcom".to_string(), old: 0.2, new: 0.4 },
+                    MetricDelta { metric: "wmc".to_string(), old: 30.0, new: 35.0 },
+                ],
+            }]
+        );
+        assert_eq!(diff.added, vec!["crate::Cache".to_string()]);
+        assert_eq!(diff.removed, vec!["crate::Legacy".to_string()]);
+        assert_eq!(diff.unchanged, 1);
+    }
+}
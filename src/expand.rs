@@ -0,0 +1,172 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::workspace::NON_SOURCE_KINDS;
+
+/// Find the `Cargo.toml` of the crate containing `path`
+///
+/// Walks up from `path` (a file or directory) until a directory with a
+/// manifest is found.
+pub fn find_manifest(path: &Path) -> Option<PathBuf> {
+    let start = if path.is_file() { path.parent()? } else { path };
+    let start = start.canonicalize().ok()?;
+
+    start
+        .ancestors()
+        .map(|dir| dir.join("Cargo.toml"))
+        .find(|manifest| manifest.is_file())
+}
+
+/// A library or binary target of a package
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub args: Vec<String>, // Selection arguments, e.g. `--lib` or `--bin tool`
+    pub module_path: String, // `app` for the library of package `app`, `app::bin::tool` for its binary `tool`
+    pub src_path: PathBuf, // Root source file, e.g. `src/bin/tool.rs`
+}
+
+/// Each library and binary target of the package described by `manifest`
+///
+/// Asks `cargo metadata`; tests, benches, examples, and build scripts are
+/// left out, as for `cargo-arch-metrics`.
+pub fn targets(manifest: &Path) -> Result<Vec<Target>, String> {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let output = Command::new(&cargo)
+        .args(["metadata", "--no-deps", "--format-version", "1", "--manifest-path"])
+        .arg(manifest)
+        .output()
+        .map_err(|e| format!("Failed to run cargo: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let metadata: serde_json::Value = serde_json::from_slice(&output.stdout).map_err(|e| e.to_string())?;
+    let package = metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|package| package["manifest_path"].as_str().is_some_and(|path| Path::new(path) == manifest))
+        .ok_or_else(|| format!("No package found for {}", manifest.display()))?;
+    Ok(package_targets(package))
+}
+
+/// The source targets of a `cargo metadata` package
+fn package_targets(package: &serde_json::Value) -> Vec<Target> {
+    let package_name = package["name"].as_str().unwrap_or_default().replace('-', "_");
+    let mut targets: Vec<Target> = Vec::new();
+    for target in package["targets"].as_array().into_iter().flatten() {
+        let kinds: Vec<&str> = target["kind"].as_array().into_iter().flatten().filter_map(|kind| kind.as_str()).collect();
+        if kinds.iter().all(|kind| NON_SOURCE_KINDS.contains(kind)) {
+            continue;
+        }
+        let name = target["name"].as_str().unwrap_or_default();
+        let (args, module_path) = if kinds.contains(&"bin") {
+            (vec!["--bin".to_string(), name.to_string()], format!("{}::bin::{}", package_name, name.replace('-', "_")))
+        } else {
+            (vec!["--lib".to_string()], package_name.clone())
+        };
+        if !targets.iter().any(|known| known.args == args) {
+            let src_path = PathBuf::from(target["src_path"].as_str().unwrap_or_default());
+            targets.push(Target { args, module_path, src_path });
+        }
+    }
+    targets
+}
+
+/// Expand all macros of one target of the crate described by `manifest`,
+/// selected by `target`, e.g. `--lib`
+///
+/// Runs `cargo expand` when it is installed and falls back to
+/// `cargo rustc -- -Zunpretty=expanded` otherwise. The expanded target is
+/// returned as a single source string where derive-generated impls
+/// reference the struct they were derived for, so they attach to it when
+/// parsed like any hand-written impl.
+pub fn expand_crate(manifest: &Path, target: &[String]) -> Result<String, String> {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+
+    let expand = Command::new(&cargo)
+        .arg("expand")
+        .arg("--manifest-path")
+        .arg(manifest)
+        .args(target)
+        .output();
+
+    if let Ok(output) = expand {
+        if output.status.success() {
+            return String::from_utf8(output.stdout).map_err(|e| e.to_string());
+        }
+    }
+
+    // `-Zunpretty` is a nightly flag; RUSTC_BOOTSTRAP lets stable toolchains accept it
+    let output = Command::new(&cargo)
+        .arg("rustc")
+        .arg("--manifest-path")
+        .arg(manifest)
+        .arg("--profile=check")
+        .args(target)
+        .arg("--")
+        .arg("-Zunpretty=expanded")
+        .env("RUSTC_BOOTSTRAP", "1")
+        .output()
+        .map_err(|e| format!("Failed to run cargo: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Macro expansion failed for {}:\n{}",
+            manifest.display(),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    String::from_utf8(output.stdout).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_manifest_from_source_dir() {
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let manifest = find_manifest(&src).expect("manifest should be found");
+        assert_eq!(manifest.file_name().unwrap(), "Cargo.toml");
+        assert_eq!(
+            manifest.parent().unwrap(),
+            Path::new(env!("CARGO_MANIFEST_DIR")).canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_find_manifest_from_file() {
+        let file = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/main.rs");
+        assert!(find_manifest(&file).is_some());
+    }
+
+    #[test]
+    fn test_package_targets_select_library_and_binaries() {
+        let package = serde_json::json!({
+            "name": "app",
+            "targets": [
+                { "name": "app", "kind": ["lib"], "src_path": "/ws/app/src/lib.rs" },
+                { "name": "app", "kind": ["bin"], "src_path": "/ws/app/src/main.rs" },
+                { "name": "tool", "kind": ["bin"], "src_path": "/ws/app/src/bin/tool.rs" },
+                { "name": "cli", "kind": ["test"], "src_path": "/ws/app/tests/cli.rs" },
+                { "name": "build-script-build", "kind": ["custom-build"], "src_path": "/ws/app/build.rs" }
+            ]
+        });
+
+        let targets: Vec<(String, String, String)> = package_targets(&package)
+            .into_iter()
+            .map(|t| (t.args.join(" "), t.module_path, t.src_path.display().to_string()))
+            .collect();
+        let expected = [
+            ("--lib", "app", "/ws/app/src/lib.rs"),
+            ("--bin app", "app::bin::app", "/ws/app/src/main.rs"),
+            ("--bin tool", "app::bin::tool", "/ws/app/src/bin/tool.rs"),
+        ];
+        assert_eq!(targets, expected.map(|(a, m, s)| (a.to_string(), m.to_string(), s.to_string())));
+    }
+}
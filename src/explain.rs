@@ -0,0 +1,242 @@
+use std::collections::BTreeSet;
+
+use crate::metrics::{self, cbo};
+use crate::models::{MetricSelection, SourceSpan, StructInfo, Symbol};
+use crate::resolve::{trait_name, StructIndex};
+
+/// Evidence behind the metrics of one struct
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Explanation {
+    pub struct_name: String, // Qualified name
+    pub file_path: String,
+    pub span: SourceSpan,
+    pub lcom: f64,
+    pub cbo: usize,
+    pub wmc: usize,
+    pub rfc: usize, // Size of the response set
+    pub methods: Vec<MethodEvidence>,
+    pub response_set: Vec<String>, // Own methods, then everything they call
+    pub coupling: Vec<CouplingEdge>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MethodEvidence {
+    pub name: String,
+    pub line: usize,
+    pub complexity: usize,
+    pub fields_accessed: Vec<Symbol>,
+    pub calls: Vec<String>,
+}
+
+/// A type counted by CBO and the source lines mentioning it
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct CouplingEdge {
+    pub target: String, // Qualified struct name or trait path
+    pub kind: &'static str, // `struct` or `trait`
+    pub sites: Vec<Site>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Site {
+    pub line: usize,
+    pub code: String, // The trimmed source line
+}
+
+/// Find a struct by qualified name, or by plain name when that is unique
+pub fn find_struct<'a>(structs: &'a [StructInfo], name: &str) -> Result<&'a StructInfo, String> {
+    if let Some(found) = structs.iter().find(|s| s.qualified_name() == name) {
+        return Ok(found);
+    }
+
+    let candidates: Vec<&StructInfo> = structs.iter().filter(|s| s.name == name).collect();
+    match candidates.as_slice() {
+        [found] => Ok(found),
+        [] => Err(format!("No struct named {}", name)),
+        _ => Err(format!(
+            "Struct name {} is ambiguous, use one of: {}",
+            name,
+            candidates.iter().map(|s| s.qualified_name()).collect::<Vec<_>>().join(", ")
+        )),
+    }
+}
+
+/// Explain the metrics of `struct_info` with the evidence they were computed from
+///
+/// The response set (RFC) is the struct's own methods plus every distinct
+/// method or function they call. Each coupling edge lists the lines of the
+/// struct definition and its impl blocks that mention the target; `source`
+/// is the struct's file content, if available. LCOM is transitive under
+/// `lcom_transitive`, as with `--lcom-transitive`.
+pub fn explain(struct_info: &StructInfo, all_structs: &[StructInfo], source: Option<&str>, lcom_transitive: bool) -> Explanation {
+    let index = StructIndex::new(all_structs);
+    let mut result = metrics::analyze_selected(struct_info, &index, &MetricSelection::default());
+    if lcom_transitive {
+        result.lcom = metrics::lcom::calculate_transitive(struct_info);
+    }
+
+    let mut response_set: Vec<String> = Vec::new();
+    let own = struct_info.methods.iter().map(|m| &m.name);
+    let called = struct_info.methods.iter().flat_map(|m| &m.calls);
+    for name in own.chain(called) {
+        if !response_set.contains(name) {
+            response_set.push(name.clone());
+        }
+    }
+
+    // Every CBO target with the names it may appear under in source
+    let mut targets: Vec<(String, &'static str, BTreeSet<String>)> = Vec::new();
+    for target in cbo::struct_references(struct_info, &index) {
+        let target = &index[target];
+        let qualified = target.qualified_name();
+        if targets.iter().any(|(name, _, _)| *name == qualified) {
+            continue;
+        }
+        let names = std::iter::once(target.name.clone())
+            .chain(target.reexported_as.iter().chain(&target.aliases).map(|path| last_path_segment(path)))
+            .collect();
+        targets.push((qualified, "struct", names));
+    }
+    let traits = struct_info.traits.iter().chain(&struct_info.referenced_traits);
+    for trait_path in traits.filter(|path| struct_info.crate_traits.iter().any(|name| name == trait_name(path))) {
+        let trait_path = trait_path.replace(' ', "");
+        if !targets.iter().any(|(name, _, _)| *name == trait_path) {
+            let names = BTreeSet::from([last_path_segment(&trait_path)]);
+            targets.push((trait_path, "trait", names));
+        }
+    }
+
+    let lines = source.map(|s| s.lines().collect::<Vec<_>>()).unwrap_or_default();
+    let coupling = targets
+        .into_iter()
+        .map(|(target, kind, names)| CouplingEdge {
+            target,
+            kind,
+            sites: relevant_lines(struct_info)
+                .into_iter()
+                .filter_map(|line| Some((line, *lines.get(line.checked_sub(1)?)?)))
+                .filter(|(_, code)| identifiers(code).any(|token| names.contains(token)))
+                .map(|(line, code)| Site { line, code: code.trim().to_string() })
+                .collect(),
+        })
+        .collect();
+
+    Explanation {
+        struct_name: struct_info.qualified_name(),
+        file_path: struct_info.file_path.clone(),
+        span: struct_info.span,
+        lcom: result.lcom,
+        cbo: result.cbo,
+        wmc: result.wmc,
+        rfc: response_set.len(),
+        methods: struct_info
+            .methods
+            .iter()
+            .map(|m| {
+                let mut fields_accessed = m.fields_accessed.clone();
+                fields_accessed.sort();
+                MethodEvidence {
+                    name: m.name.clone(),
+                    line: m.span.start_line,
+                    complexity: m.cyclomatic_complexity.max(1),
+                    fields_accessed,
+                    calls: m.calls.clone(),
+                }
+            })
+            .collect(),
+        response_set,
+        coupling,
+    }
+}
+
+/// Lines of the struct definition, its impl blocks, and its methods, in order
+fn relevant_lines(struct_info: &StructInfo) -> BTreeSet<usize> {
+    let impl_spans = struct_info.impl_blocks.iter().map(|b| b.span);
+    let method_spans = struct_info.methods.iter().map(|m| m.span);
+    std::iter::once(struct_info.span)
+        .chain(impl_spans)
+        .chain(method_spans)
+        .flat_map(|span| span.start_line..=span.end_line)
+        .collect()
+}
+
+fn identifiers(code: &str) -> impl Iterator<Item = &str> {
+    code.split(|c: char| !(c.is_alphanumeric() || c == '_')).filter(|t| !t.is_empty())
+}
+
+/// `Display` for `std::fmt::Display` or `From<Config>`
+fn last_path_segment(path: &str) -> String {
+    let without_generics = path.split('<').next().unwrap_or(path);
+    without_generics.rsplit("::").next().unwrap_or(without_generics).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_file, ParseOptions};
+
+    #[test]
+    fn test_explain_lists_response_set_and_coupling_sites() {
+        let source = r#"struct Config { port: u16 }
+
+struct Server {
+    config: Config,
+    clients: Vec<String>,
+}
+
+impl Server {
+    fn start(&mut self) {
+        self.clients.clear();
+        let port = self.config.port;
+        bind(port);
+    }
+}
+
+trait Describe {
+    fn describe(&self) -> usize;
+}
+
+impl Describe for Server {
+    fn describe(&self) -> usize {
+        self.clients.len()
+    }
+}
+
+impl std::fmt::Display for Server {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.clients.len().fmt(f)
+    }
+}
+"#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let server = find_struct(&structs, "Server").unwrap();
+        let explanation = explain(server, &structs, Some(source), false);
+
+        assert_eq!(explanation.response_set, vec!["start", "describe", "fmt", "clear", "bind", "len"]);
+        assert_eq!(explanation.rfc, 6);
+
+        let edges: Vec<(&str, Vec<usize>)> = explanation
+            .coupling
+            .iter()
+            .map(|e| (e.target.as_str(), e.sites.iter().map(|s| s.line).collect()))
+            .collect();
+        assert_eq!(edges, vec![("crate::Config", vec![4]), ("Describe", vec![20])]);
+        assert_eq!(explanation.coupling[0].sites[0].code, "config: Config,");
+
+        assert!(find_struct(&structs, "Client").is_err());
+    }
+
+    #[test]
+    fn test_explain_follows_lcom_mode() {
+        let source = r#"
+            struct Person { name: String }
+            impl Person {
+                fn format_name(&self) -> String { self.name.to_uppercase() }
+                fn display(&self) -> String { self.format_name() }
+            }
+        "#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+
+        assert_eq!(explain(&structs[0], &structs, None, false).lcom, 1.0);
+        assert_eq!(explain(&structs[0], &structs, None, true).lcom, 0.0);
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Files the current branch changed since it forked from `base`, in the
+/// repository containing `path`
+///
+/// Diffs the working tree against the merge base of `base` and `HEAD`, as
+/// a pull request does, so commits that landed on `base` after the fork
+/// do not count, while committed, uncommitted, and untracked (but not
+/// ignored) changes of the branch do. Returns canonical paths so they can
+/// be matched against the files results were parsed from. Deleted files
+/// are left out since no struct can live in them anymore.
+pub fn changed_files(path: &Path, base: &str) -> Result<HashSet<PathBuf>, String> {
+    let dir = if path.is_file() { path.parent().unwrap_or(Path::new(".")) } else { path };
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+
+    let root = git(dir, &["rev-parse", "--show-toplevel"])?;
+    let root = PathBuf::from(root.trim());
+
+    let fork = git(&root, &["merge-base", base, "HEAD"])?;
+    let changed = git(&root, &["diff", "--name-only", "--diff-filter=d", fork.trim(), "--"])?;
+    let untracked = git(&root, &["ls-files", "--others", "--exclude-standard"])?;
+    Ok(changed
+        .lines()
+        .chain(untracked.lines())
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| root.join(line).canonicalize().ok())
+        .collect())
+}
+
+/// Number of commits touching each file of the repository containing `path`
+///
+/// Counts every commit of `git log`, or only those after `since` (any date
+/// `git log --since` accepts, e.g. `6 months ago`). Keys are canonical
+/// paths; files that no longer exist are left out.
+pub fn churn(path: &Path, since: Option<&str>) -> Result<HashMap<PathBuf, usize>, String> {
+    let dir = if path.is_file() { path.parent().unwrap_or(Path::new(".")) } else { path };
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+
+    let root = git(dir, &["rev-parse", "--show-toplevel"])?;
+    let root = PathBuf::from(root.trim());
+
+    let since_arg = since.map(|date| format!("--since={}", date));
+    let mut args = vec!["log", "--format=", "--name-only", "--no-renames"];
+    args.extend(since_arg.as_deref());
+    let names = git(&root, &args)?;
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for line in names.lines().filter(|line| !line.is_empty()) {
+        *counts.entry(line.to_string()).or_default() += 1;
+    }
+    Ok(counts
+        .into_iter()
+        .filter_map(|(name, commits)| Some((root.join(name).canonicalize().ok()?, commits)))
+        .collect())
+}
+
+/// Whether `file_path` is one of the canonical `files`
+pub fn is_changed(file_path: &str, files: &HashSet<PathBuf>) -> bool {
+    !file_path.is_empty()
+        && Path::new(file_path)
+            .canonicalize()
+            .is_ok_and(|path| files.contains(&path))
+}
+
+/// A temporary checkout of a past revision, removed when dropped
+pub struct Worktree {
+    repo: PathBuf,
+    dir: PathBuf,
+    _parent: tempfile::TempDir, // Unique per run, so leftovers of a crashed run never collide
+}
+
+impl Worktree {
+    /// Check out `rev` of the repository containing `path` into a temporary
+    /// worktree, returning it and where `path` lies within it
+    pub fn checkout(path: &Path, rev: &str) -> Result<(Self, PathBuf), String> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))?;
+        let dir = if canonical.is_file() { canonical.parent().unwrap_or(&canonical) } else { &canonical };
+
+        let root = PathBuf::from(git(dir, &["rev-parse", "--show-toplevel"])?.trim());
+        let relative = canonical.strip_prefix(&root).map_err(|e| e.to_string())?.to_path_buf();
+
+        let parent = tempfile::Builder::new()
+            .prefix("rust-arch-metrics-")
+            .tempdir()
+            .map_err(|e| format!("Failed to create a temporary directory: {}", e))?;
+        let checkout = parent.path().join("checkout");
+        let checkout_arg = checkout.display().to_string();
+        git(&root, &["worktree", "add", "--detach", "--quiet", &checkout_arg, rev])?;
+
+        let worktree = Self { repo: root, dir: checkout, _parent: parent };
+        let path = worktree.dir.join(relative);
+        Ok((worktree, path))
+    }
+}
+
+impl Drop for Worktree {
+    fn drop(&mut self) {
+        let dir = self.dir.display().to_string();
+        if git(&self.repo, &["worktree", "remove", "--force", &dir]).is_err() {
+            eprintln!("Warning: Failed to remove the worktree at {}", dir);
+        }
+    }
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_changed_matches_canonical_paths() {
+        let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let files = HashSet::from([manifest.join("src/git.rs").canonicalize().unwrap()]);
+
+        let relative = manifest.join("src/../src/git.rs");
+        assert!(is_changed(&relative.display().to_string(), &files));
+        assert!(!is_changed(&manifest.join("src/main.rs").display().to_string(), &files));
+        assert!(!is_changed("", &files));
+    }
+
+    #[test]
+    fn test_changed_files_since_merge_base() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let run = |args: &[&str]| {
+            git(dir, &[&["-c", "user.name=test", "-c", "user.email=test@example.com"], args].concat()).unwrap();
+        };
+        let write = |name: &str| std::fs::write(dir.join(name), name).unwrap();
+        write("shared.rs");
+        run(&["init", "--quiet", "--initial-branch", "main"]);
+        run(&["add", "."]);
+        run(&["commit", "--quiet", "-m", "initial"]);
+        run(&["checkout", "--quiet", "-b", "feature"]);
+        write("feature.rs");
+        run(&["add", "."]);
+        run(&["commit", "--quiet", "-m", "feature"]);
+
+        // `main` moves on after the fork
+        run(&["checkout", "--quiet", "main"]);
+        write("upstream.rs");
+        run(&["add", "."]);
+        run(&["commit", "--quiet", "-m", "upstream"]);
+        run(&["checkout", "--quiet", "feature"]);
+        write("untracked.rs");
+
+        let mut names: Vec<String> = changed_files(dir, "main")
+            .unwrap()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["feature.rs", "untracked.rs"]);
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::models::AnalysisResult;
+
+/// Struct-to-struct dependency graph built from analysis results
+///
+/// Nodes index into the results the graph was built from; edges only
+/// connect structs present in those results.
+#[derive(Debug)]
+pub struct DependencyGraph<'a> {
+    pub nodes: Vec<&'a AnalysisResult>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// A directed dependency `from -> to`, weighted by its number of references
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphEdge {
+    pub from: usize,
+    pub to: usize,
+    pub references: usize,
+}
+
+/// Structs that all depend on each other, directly or transitively
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct DependencyCycle {
+    pub structs: Vec<String>, // Qualified names of the strongly connected component
+    pub cycles: Vec<Vec<String>>, // Shortest cycle through each struct, in dependency order
+}
+
+/// Dependency cycles among `results`, from the CBO dependencies
+///
+/// Every strongly connected component of more than one struct is a cycle:
+/// none of its structs can be understood, tested, or moved without the
+/// others. Larger components are listed first.
+pub fn find_cycles(results: &[AnalysisResult]) -> Vec<DependencyCycle> {
+    let graph = DependencyGraph::build(results);
+    let name = |i: usize| results[i].qualified_name();
+
+    let mut cycles: Vec<DependencyCycle> = graph
+        .cyclic_components()
+        .into_iter()
+        .map(|component| DependencyCycle {
+            cycles: graph
+                .cycles_in(&component)
+                .into_iter()
+                .map(|cycle| cycle.into_iter().map(name).collect())
+                .collect(),
+            structs: component.into_iter().map(name).collect(),
+        })
+        .collect();
+    cycles.sort_by_key(|c| std::cmp::Reverse(c.structs.len()));
+    cycles
+}
+
+impl<'a> DependencyGraph<'a> {
+    pub fn build(results: &'a [AnalysisResult]) -> Self {
+        let index: HashMap<String, usize> = results
+            .iter()
+            .enumerate()
+            .map(|(i, r)| (r.qualified_name(), i))
+            .collect();
+
+        let mut edges = Vec::new();
+        for (from, result) in results.iter().enumerate() {
+            for dependency in &result.dependencies {
+                if let Some(&to) = index.get(&dependency.target) {
+                    edges.push(GraphEdge {
+                        from,
+                        to,
+                        references: dependency.references,
+                    });
+                }
+            }
+        }
+
+        Self {
+            nodes: results.iter().collect(),
+            edges,
+        }
+    }
+
+    /// Successors of every node, in edge order
+    fn successors(&self) -> Vec<Vec<usize>> {
+        let mut successors = vec![Vec::new(); self.nodes.len()];
+        for edge in &self.edges {
+            successors[edge.from].push(edge.to);
+        }
+        successors
+    }
+
+    /// Strongly connected components of more than one node, each sorted,
+    /// in order of their smallest node
+    ///
+    /// Iterative Tarjan, so deep dependency chains cannot overflow the stack.
+    pub fn cyclic_components(&self) -> Vec<Vec<usize>> {
+        let successors = self.successors();
+        let n = self.nodes.len();
+        let mut index = vec![usize::MAX; n];
+        let mut lowlink = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack = Vec::new();
+        let mut next_index = 0;
+        let mut components = Vec::new();
+
+        for root in 0..n {
+            if index[root] != usize::MAX {
+                continue;
+            }
+            // (node, position of the next successor to visit)
+            let mut work = vec![(root, 0)];
+            while let Some(&mut (node, ref mut position)) = work.last_mut() {
+                if *position == 0 && index[node] == usize::MAX {
+                    index[node] = next_index;
+                    lowlink[node] = next_index;
+                    next_index += 1;
+                    stack.push(node);
+                    on_stack[node] = true;
+                }
+                if let Some(&next) = successors[node].get(*position) {
+                    *position += 1;
+                    if index[next] == usize::MAX {
+                        work.push((next, 0));
+                    } else if on_stack[next] {
+                        lowlink[node] = lowlink[node].min(index[next]);
+                    }
+                    continue;
+                }
+
+                work.pop();
+                if let Some(&(parent, _)) = work.last() {
+                    lowlink[parent] = lowlink[parent].min(lowlink[node]);
+                }
+                if lowlink[node] == index[node] {
+                    let mut component = Vec::new();
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        component.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    if component.len() > 1 {
+                        component.sort_unstable();
+                        components.push(component);
+                    }
+                }
+            }
+        }
+
+        components.sort();
+        components
+    }
+
+    /// The shortest cycle through each node of `component`, without
+    /// repeats, each starting at its smallest node
+    pub fn cycles_in(&self, component: &[usize]) -> Vec<Vec<usize>> {
+        let successors = self.successors();
+        let members: HashSet<usize> = component.iter().copied().collect();
+        let mut cycles: Vec<Vec<usize>> = Vec::new();
+
+        for &start in component {
+            // Breadth-first search back to `start`, within the component
+            let mut previous: HashMap<usize, usize> = HashMap::new();
+            let mut queue = VecDeque::from([start]);
+            let mut closing = None;
+            while let Some(node) = queue.pop_front() {
+                if successors[node].contains(&start) {
+                    closing = Some(node);
+                    break;
+                }
+                for &next in &successors[node] {
+                    if members.contains(&next) && next != start && !previous.contains_key(&next) {
+                        previous.insert(next, node);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            let Some(mut node) = closing else {
+                continue;
+            };
+
+            let mut cycle = vec![node];
+            while node != start {
+                node = previous[&node];
+                cycle.push(node);
+            }
+            cycle.reverse();
+
+            let smallest = (0..cycle.len()).min_by_key(|&i| cycle[i]).unwrap_or(0);
+            cycle.rotate_left(smallest);
+            if !cycles.contains(&cycle) {
+                cycles.push(cycle);
+            }
+        }
+
+        cycles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Dependency;
+
+    #[test]
+    fn test_build_graph_skips_unknown_targets() {
+        let results = vec![
+            AnalysisResult {
+                struct_name: "Order".to_string(),
+                module_path: "crate".to_string(),
+                dependencies: vec![
+                    Dependency {
+                        target: "crate::User".to_string(),
+                        references: 2,
+                    },
+                    Dependency {
+                        target: "crate::Filtered".to_string(),
+                        references: 1,
+                    },
+                ],
+                ..Default::default()
+            },
+            AnalysisResult {
+                struct_name: "User".to_string(),
+                module_path: "crate".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let graph = DependencyGraph::build(&results);
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(
+            graph.edges,
+            vec![GraphEdge {
+                from: 0,
+                to: 1,
+                references: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn test_find_cycles_reports_components_with_paths() {
+        let result = |name: &str, targets: &[&str]| AnalysisResult {
+            struct_name: name.to_string(),
+            module_path: "crate".to_string(),
+            dependencies: targets
+                .iter()
+                .map(|t| Dependency {
+                    target: format!("crate::{}", t),
+                    references: 1,
+                })
+                .collect(),
+            ..Default::default()
+        };
+        // A -> B -> C -> A and B -> A; D <-> E; F depends on A outside any cycle
+        let results = vec![
+            result("A", &["B"]),
+            result("B", &["C", "A"]),
+            result("C", &["A"]),
+            result("D", &["E"]),
+            result("E", &["D"]),
+            result("F", &["A"]),
+        ];
+
+        let cycles = find_cycles(&results);
+        assert_eq!(cycles.len(), 2);
+        assert_eq!(cycles[0].structs, vec!["crate::A", "crate::B", "crate::C"]);
+        assert_eq!(
+            cycles[0].cycles,
+            vec![vec!["crate::A", "crate::B"], vec!["crate::A", "crate::B", "crate::C"]]
+        );
+        assert_eq!(cycles[1].structs, vec!["crate::D", "crate::E"]);
+        assert_eq!(cycles[1].cycles, vec![vec!["crate::D", "crate::E"]]);
+    }
+}
@@ -0,0 +1,68 @@
+use std::path::Path;
+
+use crate::merge::read_results;
+use crate::models::AnalysisResult;
+
+/// Result set of one snapshot
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub created_at: String, // Snapshot directory name, a UTC timestamp
+    pub results: Vec<AnalysisResult>,
+}
+
+/// Read every snapshot under `root`, oldest first
+///
+/// Snapshot directory names are timestamps that sort chronologically.
+/// Entries without a `results.json` are skipped.
+pub fn load_history(root: &Path) -> Result<Vec<HistoryEntry>, Box<dyn std::error::Error>> {
+    let entries = std::fs::read_dir(root)
+        .map_err(|e| format!("Failed to read snapshots in {}: {}", root.display(), e))?;
+
+    let mut dirs = Vec::new();
+    for entry in entries {
+        let dir = entry?.path();
+        if dir.join("results.json").is_file() {
+            dirs.push(dir);
+        }
+    }
+    dirs.sort();
+
+    dirs.into_iter()
+        .map(|dir| {
+            Ok(HistoryEntry {
+                created_at: dir.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                results: read_results(&dir)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_history_is_chronological() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        for (name, results) in [
+            ("2024-05-02T00-00-00Z", r#"[{"struct_name": "B"}]"#),
+            ("2024-05-01T00-00-00Z", r#"[{"struct_name": "A"}]"#),
+        ] {
+            std::fs::create_dir_all(root.join(name)).unwrap();
+            std::fs::write(root.join(name).join("results.json"), results).unwrap();
+        }
+        std::fs::create_dir_all(root.join("not-a-snapshot")).unwrap();
+
+        let history = load_history(root).unwrap();
+
+        let names: Vec<(&str, &str)> = history
+            .iter()
+            .map(|e| (e.created_at.as_str(), e.results[0].struct_name.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("2024-05-01T00-00-00Z", "A"), ("2024-05-02T00-00-00Z", "B")]
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::models::{AnalysisResult, SourceSpan};
+
+/// A struct that is both complex and often changed
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Hotspot {
+    pub struct_name: String, // Qualified name
+    pub file_path: String,
+    pub span: SourceSpan,
+    pub commits: usize, // Commits touching the struct's file
+    pub wmc: usize,
+    pub score: f64, // 0-1, higher needs attention first
+}
+
+/// Rank structs by change frequency times complexity
+///
+/// The score multiplies the commits touching a struct's file and its WMC,
+/// each relative to the largest among the structs, so 1 is the most
+/// changed and most complex struct. Code that is complex but stable, or
+/// changed often but simple, ranks low: refactoring pays off where both
+/// meet. Structs without commits or without complexity are left out.
+pub fn rank(results: &[AnalysisResult], churn: &HashMap<PathBuf, usize>) -> Vec<Hotspot> {
+    let commits = |result: &AnalysisResult| {
+        Path::new(&result.file_path)
+            .canonicalize()
+            .ok()
+            .and_then(|path| churn.get(&path).copied())
+            .unwrap_or(0)
+    };
+    let candidates: Vec<(&AnalysisResult, usize)> = results
+        .iter()
+        .map(|result| (result, commits(result)))
+        .filter(|(result, commits)| *commits > 0 && result.wmc > 0)
+        .collect();
+
+    let max_commits = candidates.iter().map(|(_, commits)| *commits).max().unwrap_or(1);
+    let max_wmc = candidates.iter().map(|(result, _)| result.wmc).max().unwrap_or(1);
+
+    let mut hotspots: Vec<Hotspot> = candidates
+        .into_iter()
+        .map(|(result, commits)| Hotspot {
+            struct_name: result.qualified_name(),
+            file_path: result.file_path.clone(),
+            span: result.span,
+            commits,
+            wmc: result.wmc,
+            score: (commits as f64 / max_commits as f64) * (result.wmc as f64 / max_wmc as f64),
+        })
+        .collect();
+    hotspots.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hotspots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_multiplies_relative_churn_and_complexity() {
+        let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let file = |name: &str| manifest.join(name).display().to_string();
+        let result = |name: &str, file_path: String, wmc| AnalysisResult {
+            struct_name: name.to_string(),
+            module_path: "crate".to_string(),
+            file_path,
+            wmc,
+            ..Default::default()
+        };
+        let results = vec![
+            result("Stable", file("src/lib.rs"), 40),
+            result("Busy", file("src/main.rs"), 20),
+            result("Simple", file("src/main.rs"), 0),
+            result("Untracked", String::new(), 30),
+        ];
+        let churn = HashMap::from([
+            (manifest.join("src/lib.rs").canonicalize().unwrap(), 2),
+            (manifest.join("src/main.rs").canonicalize().unwrap(), 10),
+        ]);
+
+        let ranked: Vec<(String, usize, f64)> = rank(&results, &churn)
+            .into_iter()
+            .map(|h| (h.struct_name, h.commits, h.score))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![("crate::Busy".to_string(), 10, 0.5), ("crate::Stable".to_string(), 2, 0.2)]
+        );
+    }
+}
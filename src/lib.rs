@@ -0,0 +1,63 @@
+//! Architectural metrics (LCOM, CBO, WMC) for Rust code
+//!
+//! The `rust-arch-metrics` and `cargo-arch-metrics` binaries are thin
+//! wrappers around [`cli`]. Tools that embed the analysis, such as
+//! editors, CI bots, or dashboards, can run the whole pipeline with [`analyze_path`], or parse sources with
+//! [`parse_file`] and compute the metrics of each struct with
+//! [`analyze_struct`].
+//!
+//! ```no_run
+//! use rust_arch_metrics::{analyze_path, AnalysisContext};
+//!
+//! let results = analyze_path("src/", &AnalysisContext::default()).unwrap();
+//! for result in results.iter().filter(|r| r.wmc > 40) {
+//!     println!("{} has WMC {}", result.qualified_name(), result.wmc);
+//! }
+//! ```
+
+mod aggregate;
+pub mod api;
+pub mod benchmark;
+mod cache;
+pub mod cli;
+pub mod compare;
+pub mod config;
+pub mod debug;
+pub mod diff;
+mod expand;
+pub mod explain;
+pub mod git;
+mod graph;
+pub mod history;
+pub mod hotspots;
+pub mod merge;
+pub mod metrics;
+pub mod models;
+mod modules;
+mod ownership;
+pub mod parser;
+pub mod pipeline;
+pub mod query;
+pub mod report;
+mod resolve;
+pub mod scope;
+pub mod score;
+pub mod smells;
+mod significance;
+pub mod snapshot;
+pub mod thresholds;
+mod workspace;
+
+pub use metrics::analyze_struct;
+pub use models::{AnalysisResult, MethodInfo, ParsedFile, StructInfo};
+pub use parser::{parse_file, ParseOptions};
+pub use pipeline::{AnalysisContext, AnalysisPipeline};
+
+/// Analyze every struct of the crate at `path`, a directory or a single
+/// file, with the settings of `context`
+///
+/// Results are filtered by `context.query` and ordered by
+/// `context.sort_key` when set, as on the command line.
+pub fn analyze_path(path: &str, context: &AnalysisContext) -> Result<Vec<AnalysisResult>, Box<dyn std::error::Error>> {
+    Ok(AnalysisPipeline::new(context).run(path)?.results)
+}
@@ -0,0 +1,92 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::models::AnalysisResult;
+
+/// Read a JSON result file: a `snapshot`'s `results.json` or `--format json` output
+///
+/// A snapshot directory may be given in place of its `results.json`. Both
+/// a bare array of results and the envelope of `--format json` are read.
+pub fn read_results(path: &Path) -> Result<Vec<AnalysisResult>, Box<dyn std::error::Error>> {
+    #[derive(serde::Deserialize)]
+    struct Envelope {
+        results: Vec<AnalysisResult>,
+    }
+
+    let file = if path.is_dir() { path.join("results.json") } else { path.to_path_buf() };
+    let content = std::fs::read_to_string(&file)
+        .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+    let results = if content.trim_start().starts_with('{') {
+        serde_json::from_str::<Envelope>(&content).map(|envelope| envelope.results)
+    } else {
+        serde_json::from_str(&content)
+    };
+    results.map_err(|e| format!("Failed to parse {}: {}", file.display(), e).into())
+}
+
+/// Combine result sets, keeping the first result for each qualified name
+///
+/// Returns the merged results in input order and the number of duplicates dropped.
+pub fn merge_results(sets: Vec<Vec<AnalysisResult>>) -> (Vec<AnalysisResult>, usize) {
+    let mut seen = HashSet::new();
+    let mut merged = Vec::new();
+    let mut duplicates = 0;
+
+    for result in sets.into_iter().flatten() {
+        if seen.insert(result.qualified_name()) {
+            merged.push(result);
+        } else {
+            duplicates += 1;
+        }
+    }
+
+    (merged, duplicates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_deduplicates_by_qualified_name() {
+        let shard_a: Vec<AnalysisResult> = serde_json::from_str(
+            r#"[{"struct_name": "Config", "module_path": "crate", "wmc": 3},
+                {"struct_name": "Server", "module_path": "crate::net", "wmc": 8}]"#,
+        )
+        .unwrap();
+        let shard_b: Vec<AnalysisResult> = serde_json::from_str(
+            r#"[{"struct_name": "Config", "module_path": "crate", "wmc": 5},
+                {"struct_name": "Config", "module_path": "crate::db", "wmc": 1}]"#,
+        )
+        .unwrap();
+
+        let (merged, duplicates) = merge_results(vec![shard_a, shard_b]);
+        let names: Vec<(String, usize)> = merged.iter().map(|r| (r.qualified_name(), r.wmc)).collect();
+
+        assert_eq!(
+            names,
+            vec![
+                ("crate::Config".to_string(), 3),
+                ("crate::net::Server".to_string(), 8),
+                ("crate::db::Config".to_string(), 1),
+            ]
+        );
+        assert_eq!(duplicates, 1);
+    }
+
+    #[test]
+    fn test_read_results_from_envelope() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("envelope.json");
+        std::fs::write(
+            &file,
+            r#"{"schema_version": 1, "tool": "rust-arch-metrics", "path": "src/",
+                "results": [{"struct_name": "Config", "module_path": "crate", "wmc": 3}]}"#,
+        )
+        .unwrap();
+        let results = read_results(&file).unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!((results[0].qualified_name(), results[0].wmc), ("crate::Config".to_string(), 3));
+    }
+}
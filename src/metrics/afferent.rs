@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use crate::models::{AnalysisResult, Dependency};
+
+/// Set the afferent coupling (Ca), fan-in, and instability of every result
+///
+/// Ca counts the other structs depending on a struct, through fields,
+/// method bodies, or signatures; fan-in counts their references to it.
+/// Both are read from the outgoing `dependencies` of the other results,
+/// so they only see the structs in `results`. Instability is Martin's
+/// `Ce / (Ca + Ce)`, with Ce the structs depended on.
+pub fn apply(results: &mut [AnalysisResult]) {
+    let incoming = Incoming::new(results.iter().map(|r| r.dependencies.as_slice()));
+    for result in results.iter_mut() {
+        incoming.apply(result);
+    }
+}
+
+/// Dependents of every struct, by qualified name: Ca and fan-in
+pub struct Incoming(HashMap<String, (usize, usize)>);
+
+impl Incoming {
+    /// Count the dependents from the outgoing dependencies of every struct
+    pub fn new<'a>(dependencies: impl IntoIterator<Item = &'a [Dependency]>) -> Self {
+        let mut incoming: HashMap<String, (usize, usize)> = HashMap::new();
+        for dependency in dependencies.into_iter().flatten() {
+            let (ca, fan_in) = incoming.entry(dependency.target.clone()).or_default();
+            *ca += 1;
+            *fan_in += dependency.references;
+        }
+        Self(incoming)
+    }
+
+    /// Set the Ca, fan-in, and instability of `result`
+    pub fn apply(&self, result: &mut AnalysisResult) {
+        (result.ca, result.fan_in) = self.0.get(&result.qualified_name()).copied().unwrap_or_default();
+        result.instability = instability(result.ca, result.dependencies.len());
+    }
+}
+
+/// `Ce / (Ca + Ce)`: 0 for a struct only depended upon, 1 for one only
+/// depending on others, and 0 for an isolated struct
+pub fn instability(ca: usize, ce: usize) -> f64 {
+    if ca + ce == 0 {
+        0.0
+    } else {
+        ce as f64 / (ca + ce) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::analyze_struct;
+    use crate::parser::{parse_file, ParseOptions};
+
+    #[test]
+    fn test_afferent_coupling() {
+        let source = r#"
+            struct Config { name: String }
+            struct Server { config: Config, fallback: Option<Config> }
+            struct Client { config: Config, server: Box<Server> }
+        "#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let mut results: Vec<AnalysisResult> = structs.iter().map(|s| analyze_struct(s, &structs)).collect();
+        apply(&mut results);
+
+        let afferent: Vec<(usize, usize, f64)> = results.iter().map(|r| (r.ca, r.fan_in, r.instability)).collect();
+        assert_eq!(afferent, vec![(2, 3, 0.0), (1, 1, 0.5), (0, 0, 1.0)]);
+    }
+}
@@ -0,0 +1,78 @@
+use crate::models::{ApiSurface, StructInfo};
+
+/// Calculate the semver-relevant API surface of a `pub` struct
+///
+/// Every item callers can depend on is something a release could break:
+/// - each `pub` inherent method counts 1
+/// - each `pub` field counts 2, or 1 under `#[non_exhaustive]`; without it
+///   callers may construct and destructure the struct, so adding any field
+///   is breaking too
+/// - each type or const generic parameter counts 2, since changing bounds
+///   or defaults breaks every instantiation
+///
+/// # Returns
+/// `None` for structs that are not declared `pub`
+pub fn calculate(struct_info: &StructInfo) -> Option<ApiSurface> {
+    if !struct_info.is_pub {
+        return None;
+    }
+
+    let pub_methods = struct_info.methods.iter().filter(|m| m.is_pub).count();
+    let field_weight = if struct_info.non_exhaustive { 1 } else { 2 };
+
+    Some(ApiSurface {
+        pub_fields: struct_info.pub_fields,
+        pub_methods,
+        generic_params: struct_info.generic_params,
+        non_exhaustive: struct_info.non_exhaustive,
+        score: pub_methods + struct_info.pub_fields * field_weight + 2 * struct_info.generic_params,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_file, ParseOptions};
+
+    #[test]
+    fn test_api_surface() {
+        let source = r#"
+            pub struct Options<T> {
+                pub name: String,
+                pub value: T,
+                cache: Vec<u8>,
+            }
+
+            impl<T> Options<T> {
+                pub fn new(value: T) -> Self { todo!() }
+                pub(crate) fn reset(&mut self) {}
+                fn internal(&self) {}
+            }
+
+            impl<T> Clone for Options<T> {
+                fn clone(&self) -> Self { todo!() }
+            }
+
+            #[non_exhaustive]
+            pub struct Settings { pub verbose: bool }
+
+            struct Private { pub field: u8 }
+        "#;
+
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let surfaces: Vec<Option<ApiSurface>> = structs.iter().map(calculate).collect();
+
+        assert_eq!(
+            surfaces[0],
+            Some(ApiSurface {
+                pub_fields: 2,
+                pub_methods: 1,
+                generic_params: 1,
+                non_exhaustive: false,
+                score: 1 + 2 * 2 + 2,
+            })
+        );
+        assert_eq!(surfaces[1].map(|s| s.score), Some(1));
+        assert_eq!(surfaces[2], None);
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::BTreeSet;
+
+use crate::models::{MethodInfo, StructInfo};
+use crate::resolve::StructIndex;
+
+/// Resolve the foreign accesses of a method of `struct_info`
+///
+/// An access counts when its type resolves to another analyzed struct and
+/// its member is a field of that struct, or a getter: a method without
+/// parameters reading exactly one field.
+///
+/// # Returns
+/// The fields and getters accessed, e.g. `crate::Order::total`, sorted
+pub fn foreign_data(method: &MethodInfo, struct_info: &StructInfo, index: &StructIndex) -> Vec<String> {
+    let mut data = BTreeSet::new();
+    for access in &method.foreign_accesses {
+        let Some(target) = index.find_struct(&access.ty, &struct_info.module_path) else {
+            continue;
+        };
+        if target.qualified_name() == struct_info.qualified_name() {
+            continue;
+        }
+
+        let is_data = if access.call {
+            target
+                .methods
+                .iter()
+                .any(|m| m.name == access.member && m.params == 0 && m.fields_accessed.len() == 1)
+        } else {
+            target.fields.iter().any(|f| *f.name == *access.member)
+        };
+        if is_data {
+            data.insert(format!("{}::{}", target.qualified_name(), access.member));
+        }
+    }
+    data.into_iter().collect()
+}
+
+/// Calculate Access To Foreign Data (ATFD) of a struct
+///
+/// The number of distinct fields and getters of other analyzed structs its
+/// methods use, from their resolved `foreign_data`.
+pub fn calculate(methods: &[MethodInfo]) -> usize {
+    methods
+        .iter()
+        .flat_map(|m| &m.foreign_data)
+        .collect::<BTreeSet<_>>()
+        .len()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::analyze_struct;
+    use crate::parser::{parse_file, ParseOptions};
+
+    #[test]
+    fn test_foreign_data_of_fields_and_getters() {
+        let source = r#"
+            struct Order { price: u32, quantity: u32, customer: Customer }
+            impl Order {
+                fn quantity(&self) -> u32 { self.quantity }
+                fn discounted(&self, by: u32) -> u32 { self.price - by }
+            }
+            struct Customer { name: String }
+            struct Invoice { order: Box<Order>, total: u32 }
+            impl Invoice {
+                fn compute(&mut self, other: &Order) {
+                    let copy: Order = other.clone();
+                    self.total = self.order.price * self.order.quantity()
+                        + other.discounted(1) + copy.customer.name.len() as u32;
+                }
+            }
+        "#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let invoice = analyze_struct(&structs[2], &structs);
+
+        // `discounted` takes a parameter and `len` is not a member of Customer
+        assert_eq!(
+            invoice.methods[0].foreign_data,
+            vec!["crate::Order::customer", "crate::Order::price", "crate::Order::quantity"]
+        );
+        assert_eq!(invoice.atfd, 3);
+    }
+}
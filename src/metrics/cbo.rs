@@ -1,4 +1,9 @@
+use std::collections::HashSet;
+
+use syn::visit::Visit;
+
 use crate::models::StructInfo;
+use crate::resolve::{StructId, StructIndex};
 
 /// Calculate Coupling Between Objects (CBO)
 ///
@@ -8,80 +13,134 @@ use crate::models::StructInfo;
 ///
 /// Coupling includes:
 /// - Field types that are other structs
-/// - Trait implementations
-/// - Generic type parameters with trait bounds
+/// - Implementations of traits declared in the analyzed code; std traits
+///   such as `Display` or `Clone` don't couple structs to each other
+/// - Traits declared in the analyzed code and named by generic bounds
+///   (`T: Validator`), `dyn Trait`, and `impl Trait`, in the struct, its
+///   impl blocks, fields, and method signatures
+/// - Structs named by method parameter and return types
+/// - Structs whose associated functions are called (`Config::new()`), that
+///   are constructed with a struct literal, or whose methods are called on
+///   a parameter, local, or field of that type
 ///
 /// # Arguments
 /// * `struct_info` - The struct to analyze
-/// * `all_structs` - All structs in the codebase for reference
+/// * `index` - All structs in the codebase for reference
 ///
 /// # Returns
 /// The number of distinct external types this struct depends on
-pub fn calculate(struct_info: &StructInfo, all_structs: &[StructInfo]) -> usize {
-    let mut coupled_types: std::collections::HashSet<String> = std::collections::HashSet::new();
+pub fn calculate(struct_info: &StructInfo, index: &StructIndex) -> usize {
+    struct_coupling(struct_info, index) + trait_coupling(struct_info)
+}
+
+/// The number of distinct other analyzed structs `struct_info` depends on
+pub fn struct_coupling(struct_info: &StructInfo, index: &StructIndex) -> usize {
+    struct_references(struct_info, index)
+        .into_iter()
+        .map(|id| index[id].qualified_name())
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// The number of distinct crate traits `struct_info` implements or refers to
+pub fn trait_coupling(struct_info: &StructInfo) -> usize {
+    struct_info.crate_traits.len()
+}
+
+/// Every reference from `struct_info` to another analyzed struct
+///
+/// Returns one entry per occurrence in field types, external type
+/// references, and types used by each method body, so a struct referenced
+/// twice appears twice. Only types that
+/// resolve to another struct in our codebase are returned; paths like
+/// `super::Config` are resolved relative to the struct's module.
+pub fn struct_references(struct_info: &StructInfo, index: &StructIndex) -> Vec<StructId> {
+    let self_name = struct_info.qualified_name();
 
     // Collect all external types from the struct
-    for ext_type in &struct_info.external_types {
-        // Only count if it's another struct in our codebase
-        if all_structs.iter().any(|s| s.name == *ext_type) {
-            coupled_types.insert(ext_type.clone());
-        }
-    }
+    let external_types = struct_info.external_types.iter().filter_map(|type_path| index.find(type_path, &struct_info.module_path));
 
-    // Collect types from field types
-    for field in &struct_info.fields {
-        // Check the base type and all generic type parameters
-        let type_names = extract_all_types(&field.ty);
-        for type_name in type_names {
-            if all_structs.iter().any(|s| s.name == type_name) && type_name != struct_info.name {
-                coupled_types.insert(type_name);
-            }
-        }
-    }
+    // Collect types from field types, checking the base type and all generic type parameters
+    let field_types = struct_info
+        .fields
+        .iter()
+        .flat_map(|field| extract_all_types(&field.ty))
+        .filter_map(|type_path| index.find(&type_path, &struct_info.module_path));
 
-    // Count trait implementations as coupling
-    for trait_name in &struct_info.traits {
-        coupled_types.insert(trait_name.clone());
-    }
+    // Collect types whose functions and methods are called, or that are constructed, in method bodies
+    let used_types = struct_info
+        .methods
+        .iter()
+        .flat_map(|method| &method.used_types)
+        .filter_map(|type_path| index.find(type_path, &struct_info.module_path));
 
-    coupled_types.len()
+    external_types
+        .chain(field_types)
+        .chain(used_types)
+        .filter(|&id| index[id].qualified_name() != self_name)
+        .collect()
 }
 
-/// Extract all type names from a type string
-/// e.g., ["String"] from "String", ["Vec", "Item"] from "Vec < Item >"
-fn extract_all_types(ty: &str) -> Vec<String> {
-    let mut types = Vec::new();
-    let ty = ty.trim();
-
-    // Handle reference types like &T, &mut T
-    if ty.starts_with('&') {
-        let inner = ty[1..].trim();
-        if inner.starts_with("mut ") {
-            return extract_all_types(&inner[4..]);
+/// Extract all type paths from a type string
+/// e.g. ["String"] from "String", ["Vec", "Item"] from "Vec < Item >",
+/// ["Tile"] from "[Tile ; N]", ["A", "B"] from "(A , B)"
+///
+/// The string is parsed into a `syn::Type` so arrays, slices, tuples,
+/// references, and const generic arguments are handled structurally.
+/// Strings that are not valid types fall back to a token scan.
+pub fn extract_all_types(ty: &str) -> Vec<String> {
+    match syn::parse_str::<syn::Type>(ty) {
+        Ok(parsed) => {
+            let mut collector = TypePathCollector::default();
+            collector.visit_type(&parsed);
+            collector.paths
         }
-        return extract_all_types(inner);
+        Err(_) => extract_path_tokens(ty),
     }
+}
 
-    // Handle generic types like Vec<T>, Option<T>, HashMap<K, V>, etc.
-    if let Some(start) = ty.find('<') {
-        let base = ty[..start].trim().to_string();
-        types.push(base);
+/// Collects every type path in a type, outermost first, without generic arguments
+#[derive(Default)]
+struct TypePathCollector {
+    paths: Vec<String>,
+}
 
-        // Extract inner types from the generic parameters
-        let end = ty.rfind('>').unwrap_or(ty.len());
-        let inner = &ty[start + 1..end];
+impl<'ast> Visit<'ast> for TypePathCollector {
+    fn visit_type_path(&mut self, node: &'ast syn::TypePath) {
+        let path = node
+            .path
+            .segments
+            .iter()
+            .map(|seg| seg.ident.to_string())
+            .collect::<Vec<_>>()
+            .join("::");
+        self.paths.push(path);
 
-        // Split by comma to handle multiple type parameters like HashMap<K, V>
-        for part in inner.split(',') {
-            let inner_types = extract_all_types(part.trim());
-            types.extend(inner_types);
-        }
-    } else {
-        // Simple type
-        types.push(ty.to_string());
+        // Generic arguments, including types nested in const generic expressions
+        syn::visit::visit_type_path(self, node);
     }
+}
 
-    types
+/// Fallback extraction scanning for identifier paths in arbitrary type-like text
+/// e.g. ["Matrix", "T", "R", "usize"] from "Matrix < T , const R : usize >"
+fn extract_path_tokens(ty: &str) -> Vec<String> {
+    // Join path separators so `crate :: map :: Tile` stays a single token
+    let mut normalized = ty.to_string();
+    while normalized.contains(" ::") || normalized.contains(":: ") {
+        normalized = normalized.replace(" ::", "::").replace(":: ", "::");
+    }
+    let normalized = normalized.replace("::", "\0");
+
+    normalized
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '\'' || c == '\0'))
+        .map(|token| token.trim_matches('\0').replace('\0', "::"))
+        .filter(|token| {
+            !token.is_empty()
+                && !token.starts_with('\'') // Lifetimes
+                && !token.starts_with(|c: char| c.is_ascii_digit())
+                && !matches!(token.as_str(), "const" | "mut" | "dyn" | "impl" | "for" | "where" | "as")
+        })
+        .collect()
 }
 
 /// Extract the base type name from a type string (deprecated, use extract_all_types)
@@ -101,18 +160,20 @@ mod tests {
             name: "User".to_string(),
             fields: vec![
                 FieldInfo {
-                    name: "name".to_string(),
-                    ty: "String".to_string(),
+                    name: "name".into(),
+                    ty: "String".into(),
+                    ..Default::default()
                 },
             ],
             methods: vec![],
             external_types: vec![],
             traits: vec![],
+            ..Default::default()
         };
 
         let all_structs = vec![struct_a.clone()];
 
-        assert_eq!(calculate(&struct_a, &all_structs), 0);
+        assert_eq!(calculate(&struct_a, &StructIndex::new(&all_structs)), 0);
     }
 
     #[test]
@@ -121,36 +182,41 @@ mod tests {
             name: "User".to_string(),
             fields: vec![
                 FieldInfo {
-                    name: "name".to_string(),
-                    ty: "String".to_string(),
+                    name: "name".into(),
+                    ty: "String".into(),
+                    ..Default::default()
                 },
                 FieldInfo {
-                    name: "address".to_string(),
-                    ty: "Address".to_string(),
+                    name: "address".into(),
+                    ty: "Address".into(),
+                    ..Default::default()
                 },
             ],
             methods: vec![],
             external_types: vec![],
             traits: vec![],
+            ..Default::default()
         };
 
         let struct_b = StructInfo {
             name: "Address".to_string(),
             fields: vec![
                 FieldInfo {
-                    name: "street".to_string(),
-                    ty: "String".to_string(),
+                    name: "street".into(),
+                    ty: "String".into(),
+                    ..Default::default()
                 },
             ],
             methods: vec![],
             external_types: vec![],
             traits: vec![],
+            ..Default::default()
         };
 
         let all_structs = vec![struct_a.clone(), struct_b];
 
         // User is coupled to Address
-        assert_eq!(calculate(&struct_a, &all_structs), 1);
+        assert_eq!(calculate(&struct_a, &StructIndex::new(&all_structs)), 1);
     }
 
     #[test]
@@ -159,17 +225,20 @@ mod tests {
             name: "Order".to_string(),
             fields: vec![
                 FieldInfo {
-                    name: "user".to_string(),
-                    ty: "User".to_string(),
+                    name: "user".into(),
+                    ty: "User".into(),
+                    ..Default::default()
                 },
                 FieldInfo {
-                    name: "product".to_string(),
-                    ty: "Product".to_string(),
+                    name: "product".into(),
+                    ty: "Product".into(),
+                    ..Default::default()
                 },
             ],
             methods: vec![],
             external_types: vec![],
             traits: vec![],
+            ..Default::default()
         };
 
         let struct_b = StructInfo {
@@ -178,6 +247,7 @@ mod tests {
             methods: vec![],
             external_types: vec![],
             traits: vec![],
+            ..Default::default()
         };
 
         let struct_c = StructInfo {
@@ -186,12 +256,117 @@ mod tests {
             methods: vec![],
             external_types: vec![],
             traits: vec![],
+            ..Default::default()
         };
 
         let all_structs = vec![struct_a.clone(), struct_b, struct_c];
 
         // Order is coupled to both User and Product
-        assert_eq!(calculate(&struct_a, &all_structs), 2);
+        assert_eq!(calculate(&struct_a, &StructIndex::new(&all_structs)), 2);
+    }
+
+    #[test]
+    fn test_cbo_impl_trait_references() {
+        let server = StructInfo {
+            name: "Server".to_string(),
+            external_types: vec!["Config".into(), "Server".into()],
+            referenced_traits: vec!["Responder".to_string()],
+            crate_traits: vec!["Responder".to_string()],
+            ..Default::default()
+        };
+        let config = StructInfo {
+            name: "Config".to_string(),
+            ..Default::default()
+        };
+
+        let all_structs = vec![server.clone(), config];
+
+        // Config (via impl FnOnce(&Config)) and Responder, but not Server itself
+        assert_eq!(calculate(&server, &StructIndex::new(&all_structs)), 2);
+    }
+
+    #[test]
+    fn test_cbo_counts_only_crate_trait_impls() {
+        let source = r#"
+            trait Render {}
+            struct Page;
+
+            impl Render for Page {}
+            impl Clone for Page { fn clone(&self) -> Self { Page } }
+            impl std::fmt::Display for Page { fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result { Ok(()) } }
+            impl From<u8> for Page { fn from(_: u8) -> Self { Page } }
+        "#;
+        let structs = crate::parser::parse_file(source, "crate", &crate::parser::ParseOptions::default()).unwrap().structs;
+        let page = &structs[0];
+
+        assert_eq!(page.traits.len(), 4);
+        assert_eq!(page.crate_traits, vec!["Render"]);
+        assert_eq!(trait_coupling(page), 1);
+        assert_eq!(calculate(page, &StructIndex::new(&structs)), 1);
+    }
+
+    #[test]
+    fn test_cbo_resolves_module_prefixes() {
+        let server = StructInfo {
+            name: "Server".to_string(),
+            module_path: "crate::net::server".to_string(),
+            fields: vec![
+                FieldInfo {
+                    name: "config".into(),
+                    ty: "super :: Config".into(),
+                    ..Default::default()
+                },
+                FieldInfo {
+                    name: "users".into(),
+                    ty: "Vec < crate :: models :: User >".into(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let config = StructInfo {
+            name: "Config".to_string(),
+            module_path: "crate::net".to_string(),
+            ..Default::default()
+        };
+        let user = StructInfo {
+            name: "User".to_string(),
+            module_path: "crate::models".to_string(),
+            ..Default::default()
+        };
+
+        let all_structs = vec![server.clone(), config, user];
+
+        assert_eq!(calculate(&server, &StructIndex::new(&all_structs)), 2);
+    }
+
+    #[test]
+    fn test_struct_references_counts_occurrences() {
+        let order = StructInfo {
+            name: "Order".to_string(),
+            fields: vec![
+                FieldInfo {
+                    name: "buyer".into(),
+                    ty: "User".into(),
+                    ..Default::default()
+                },
+                FieldInfo {
+                    name: "seller".into(),
+                    ty: "User".into(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let user = StructInfo {
+            name: "User".to_string(),
+            ..Default::default()
+        };
+
+        let all_structs = vec![order.clone(), user];
+
+        assert_eq!(struct_references(&order, &StructIndex::new(&all_structs)).len(), 2);
+        assert_eq!(calculate(&order, &StructIndex::new(&all_structs)), 1);
     }
 
     #[test]
@@ -201,4 +376,133 @@ mod tests {
         assert_eq!(extract_type_name("&str"), Some("str".to_string()));
         assert_eq!(extract_type_name("&mut String"), Some("String".to_string()));
     }
+
+    #[test]
+    fn test_extract_all_types_structured() {
+        assert_eq!(extract_all_types("[Tile ; N]"), vec!["Tile"]);
+        assert_eq!(extract_all_types("& [Tile]"), vec!["Tile"]);
+        assert_eq!(extract_all_types("(Player , Option < Score >)"), vec!["Player", "Option", "Score"]);
+        assert_eq!(extract_all_types("Matrix < Cell , 3 , { N * 2 } >"), vec!["Matrix", "Cell"]);
+        assert_eq!(extract_all_types("[[Tile ; W] ; H]"), vec!["Tile"]);
+        assert_eq!(extract_all_types("& 'a super :: Config"), vec!["super::Config"]);
+    }
+
+    #[test]
+    fn test_extract_path_tokens_fallback() {
+        assert_eq!(
+            extract_all_types("Matrix < T , const R : usize , const C : usize >"),
+            vec!["Matrix", "T", "R", "usize", "C", "usize"]
+        );
+        assert_eq!(extract_path_tokens("Grid < crate :: map :: Tile >"), vec!["Grid", "crate::map::Tile"]);
+    }
+
+    #[test]
+    fn test_cbo_array_and_tuple_fields() {
+        let board = StructInfo {
+            name: "Board".to_string(),
+            fields: vec![
+                FieldInfo {
+                    name: "tiles".into(),
+                    ty: "[Tile ; N]".into(),
+                    ..Default::default()
+                },
+                FieldInfo {
+                    name: "players".into(),
+                    ty: "(Player , Player)".into(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let tile = StructInfo {
+            name: "Tile".to_string(),
+            ..Default::default()
+        };
+        let player = StructInfo {
+            name: "Player".to_string(),
+            ..Default::default()
+        };
+
+        let all_structs = vec![board.clone(), tile, player];
+
+        assert_eq!(calculate(&board, &StructIndex::new(&all_structs)), 2);
+    }
+
+    #[test]
+    fn test_cbo_counts_calls_and_construction_in_method_bodies() {
+        let source = r#"
+            struct Config { port: u16 }
+            struct Logger;
+            struct Cache;
+            struct Metrics;
+            struct Unused;
+
+            struct Service { port: u16 }
+
+            impl Service {
+                fn new() -> Self { Service { port: Config::load().port } }
+                fn log(&self, logger: &Logger) { logger.write(self.port); }
+                fn warm(&self) { let cache: Cache = make(); cache.fill(); }
+                fn report(&self) -> Metrics { Metrics {} }
+                fn local(&self) { let unused = build(); unused.run(); }
+            }
+        "#;
+        let structs = crate::parser::parse_file(source, "crate", &crate::parser::ParseOptions::default()).unwrap().structs;
+        let service = structs.iter().find(|s| s.name == "Service").unwrap();
+
+        // Config, Logger, Cache, and Metrics; not Unused, whose local has no type, nor Service itself
+        assert_eq!(calculate(service, &StructIndex::new(&structs)), 4);
+    }
+
+    #[test]
+    fn test_cbo_counts_parameter_and_return_types() {
+        let source = r#"
+            struct Customer; struct Order; struct Invoice; struct Payment; struct Address;
+            struct Error;
+
+            struct Billing;
+
+            impl Billing {
+                fn bill(&self, customer: &Customer, orders: Vec<Order>) -> Result<Invoice, String> { todo!() }
+                fn pay(&mut self, payment: Option<Box<Payment>>) -> impl Iterator<Item = (Address, Self)> { todo!() }
+                fn export(&self, out: &mut dyn std::io::Write) -> Result<(), io::Error> { todo!() }
+                fn render(&self) -> fmt::Result { todo!() }
+            }
+        "#;
+        let structs = crate::parser::parse_file(source, "crate", &crate::parser::ParseOptions::default()).unwrap().structs;
+        let billing = structs.iter().find(|s| s.name == "Billing").unwrap();
+
+        // The five domain types; `Iterator` is a std trait, and `io::Error`
+        // is not the crate's `Error`
+        assert_eq!(struct_references(billing, &StructIndex::new(&structs)).len(), 5);
+        assert_eq!(calculate(billing, &StructIndex::new(&structs)), 5);
+    }
+
+    #[test]
+    fn test_cbo_counts_bounds_dyn_and_impl_trait() {
+        let source = r#"
+            trait Repository {} trait Sink {} trait Validator {}
+            struct Order;
+
+            struct Service<R: Repository> where R: Clone {
+                repo: R,
+                sink: Box<dyn Sink + Send>,
+            }
+
+            impl<R: Repository + ?Sized> Service<R> {
+                fn run<V>(&self, validator: V) -> impl Iterator<Item = Order> where V: Validator { todo!() }
+            }
+        "#;
+        let structs = crate::parser::parse_file(source, "crate", &crate::parser::ParseOptions::default()).unwrap().structs;
+        let service = structs.iter().find(|s| s.name == "Service").unwrap();
+
+        // `Send` and `?Sized` are plumbing; `Order` comes through the associated type
+        assert_eq!(service.referenced_traits, vec!["Clone", "Repository", "Sink", "Iterator", "Validator"]);
+        // `Clone` and `Iterator` are std traits
+        assert_eq!(service.crate_traits, vec!["Repository", "Sink", "Validator"]);
+        let index = StructIndex::new(&structs);
+        assert_eq!(struct_coupling(service, &index), 1);
+        assert_eq!(trait_coupling(service), 3);
+        assert_eq!(calculate(service, &index), 4);
+    }
 }
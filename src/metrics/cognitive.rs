@@ -0,0 +1,12 @@
+use crate::models::StructInfo;
+
+/// Calculate the cognitive complexity of a struct
+///
+/// The sum of the cognitive complexities of its methods. Unlike WMC, which
+/// counts every decision point, cognitive complexity weighs control flow
+/// by how deeply it is nested and scores a `match` once however many arms
+/// it has, so flat dispatch over an enum stays cheap while nested loops
+/// and conditions add up quickly.
+pub fn calculate(struct_info: &StructInfo) -> usize {
+    struct_info.methods.iter().map(|m| m.cognitive_complexity).sum()
+}
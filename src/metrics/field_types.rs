@@ -0,0 +1,72 @@
+use std::collections::BTreeSet;
+
+use crate::models::StructInfo;
+
+use super::cbo::extract_all_types;
+
+/// Primitive types, including `str` and `String`
+const PRIMITIVES: &[&str] = &[
+    "bool", "char", "str", "String", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64",
+    "i128", "isize", "f32", "f64",
+];
+
+/// Collections and wrappers of the standard library
+const COLLECTIONS: &[&str] = &[
+    "Vec", "VecDeque", "LinkedList", "HashMap", "BTreeMap", "HashSet", "BTreeSet", "BinaryHeap", "Option",
+    "Result", "Box", "Rc", "Arc", "Weak", "Cell", "RefCell", "Mutex", "RwLock", "Cow", "PhantomData",
+];
+
+/// Calculate the field type diversity of a struct
+///
+/// Every type named in a field type counts once, by its last path
+/// segment, so `Vec<User>` names `Vec` and `User`. The struct's own type
+/// parameters are not counted. Domain types are those that are neither
+/// primitives nor standard collections; a struct aggregating many of them
+/// is usually doing too much even when its LCOM looks fine.
+///
+/// # Returns
+/// The number of distinct types, and the share of them that are domain
+/// types (0 for a struct without fields)
+pub fn calculate(struct_info: &StructInfo) -> (usize, f64) {
+    let types: BTreeSet<String> = struct_info
+        .fields
+        .iter()
+        .flat_map(|field| extract_all_types(&field.ty))
+        .map(|path| path.rsplit("::").next().unwrap_or(&path).to_string())
+        .filter(|name| !struct_info.type_params.contains(name) && name != "Self")
+        .collect();
+
+    if types.is_empty() {
+        return (0, 0.0);
+    }
+
+    let domain = types
+        .iter()
+        .filter(|name| !PRIMITIVES.contains(&name.as_str()) && !COLLECTIONS.contains(&name.as_str()))
+        .count();
+    (types.len(), domain as f64 / types.len() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_file, ParseOptions};
+
+    #[test]
+    fn test_field_type_diversity() {
+        let source = r#"
+            struct Cache<K> {
+                entries: std::collections::HashMap<K, Entry>,
+                owner: User,
+                admins: Vec<User>,
+                name: String,
+                size: usize,
+            }
+        "#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+
+        // HashMap, Entry, User, Vec, String, usize
+        assert_eq!(calculate(&structs[0]), (6, 2.0 / 6.0));
+        assert_eq!(calculate(&StructInfo::default()), (0, 0.0));
+    }
+}
@@ -0,0 +1,54 @@
+use crate::models::StructInfo;
+
+/// Calculate the Halstead metrics of a struct from those of its methods
+///
+/// Volume and effort add up over the methods. Difficulty is their ratio,
+/// i.e. the mean difficulty of the methods weighted by their volume, so a
+/// large tricky method outweighs many small accessors.
+///
+/// # Returns
+/// Volume, difficulty, and effort (all 0 for a struct without methods)
+pub fn calculate(struct_info: &StructInfo) -> (f64, f64, f64) {
+    // Folded from 0.0: an empty `sum()` of f64 is -0.0, which reports print as `-0`
+    let volume = struct_info.methods.iter().fold(0.0, |total, m| total + m.halstead.volume());
+    let effort = struct_info.methods.iter().fold(0.0, |total, m| total + m.halstead.effort());
+    let difficulty = if volume > 0.0 { effort / volume } else { 0.0 };
+    (volume, difficulty, effort)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{HalsteadCounts, MethodInfo};
+
+    #[test]
+    fn test_struct_halstead_weights_difficulty_by_volume() {
+        let method = |halstead| MethodInfo {
+            halstead,
+            ..Default::default()
+        };
+        // n = 8, N = 16: volume 48, difficulty 2 * 8 / 4 = 4
+        let large = HalsteadCounts {
+            distinct_operators: 4,
+            distinct_operands: 4,
+            operators: 8,
+            operands: 8,
+        };
+        // n = 4, N = 4: volume 8, difficulty 1 * 2 / 2 = 1
+        let small = HalsteadCounts {
+            distinct_operators: 2,
+            distinct_operands: 2,
+            operators: 2,
+            operands: 2,
+        };
+        assert_eq!((large.volume(), large.difficulty(), large.effort()), (48.0, 4.0, 192.0));
+
+        let struct_info = StructInfo {
+            methods: vec![method(large), method(small)],
+            ..Default::default()
+        };
+        assert_eq!(calculate(&struct_info), (56.0, 200.0 / 56.0, 200.0));
+        let (volume, difficulty, effort) = calculate(&StructInfo::default());
+        assert_eq!(format!("{} {} {}", volume, difficulty, effort), "0 0 0");
+    }
+}
@@ -1,4 +1,6 @@
-use crate::models::StructInfo;
+use crate::models::{FieldInfo, StructInfo, Symbol};
+
+use super::lcom4;
 
 /// Calculate Lack of Cohesion in Methods (LCOM)
 ///
@@ -18,8 +20,51 @@ use crate::models::StructInfo;
 /// # Returns
 /// LCOM value between 0 and 1 (higher = less cohesive)
 pub fn calculate(struct_info: &StructInfo) -> f64 {
-    let method_count = struct_info.methods.len();
-    let field_count = struct_info.fields.len();
+    let accesses: Vec<&[Symbol]> = struct_info.methods.iter().map(|m| m.fields_accessed.as_slice()).collect();
+    henderson_sellers(&struct_info.fields, &accesses)
+}
+
+/// Like `calculate`, crediting each method with the fields of the methods
+/// it calls through `self.method()` or `Self::method()`, directly or
+/// through other methods (`--lcom-transitive`)
+///
+/// A `display` that reads `name` only through `self.format_name()` then
+/// shares `name` with `format_name`, as LCOM4-style tools count it.
+pub fn calculate_transitive(struct_info: &StructInfo) -> f64 {
+    henderson_sellers(&struct_info.fields, &transitive_accesses(struct_info))
+}
+
+/// The fields each method accesses itself or through the methods it calls
+fn transitive_accesses(struct_info: &StructInfo) -> Vec<Vec<Symbol>> {
+    let methods = &struct_info.methods;
+    (0..methods.len())
+        .map(|start| {
+            let mut reached = vec![false; methods.len()];
+            let mut pending = vec![start];
+            reached[start] = true;
+            let mut fields: Vec<Symbol> = Vec::new();
+            while let Some(i) = pending.pop() {
+                for field in &methods[i].fields_accessed {
+                    if !fields.contains(field) {
+                        fields.push(field.clone());
+                    }
+                }
+                for (j, callee) in methods.iter().enumerate() {
+                    if !reached[j] && lcom4::calls(&methods[i], &callee.name) {
+                        reached[j] = true;
+                        pending.push(j);
+                    }
+                }
+            }
+            fields
+        })
+        .collect()
+}
+
+/// Henderson-Sellers LCOM of `fields` given the fields each method accesses
+fn henderson_sellers(fields: &[FieldInfo], accesses: &[impl AsRef<[Symbol]>]) -> f64 {
+    let method_count = accesses.len();
+    let field_count = fields.len();
 
     // Handle edge cases
     if method_count <= 1 || field_count == 0 {
@@ -29,9 +74,9 @@ pub fn calculate(struct_info: &StructInfo) -> f64 {
     // Count how many methods access each field
     let mut field_access_counts: Vec<usize> = vec![0; field_count];
 
-    for method in &struct_info.methods {
-        for (idx, field) in struct_info.fields.iter().enumerate() {
-            if method.fields_accessed.contains(&field.name) {
+    for accessed in accesses {
+        for (idx, field) in fields.iter().enumerate() {
+            if accessed.as_ref().contains(&field.name) {
                 field_access_counts[idx] += 1;
             }
         }
@@ -57,6 +102,7 @@ pub fn calculate(struct_info: &StructInfo) -> f64 {
 mod tests {
     use super::*;
     use crate::models::{FieldInfo, MethodInfo};
+    use crate::parser::{parse_file, ParseOptions};
 
     #[test]
     fn test_lcom_perfectly_cohesive() {
@@ -65,22 +111,26 @@ mod tests {
             name: "User".to_string(),
             fields: vec![
                 FieldInfo {
-                    name: "name".to_string(),
-                    ty: "String".to_string(),
+                    name: "name".into(),
+                    ty: "String".into(),
+                    ..Default::default()
                 },
             ],
             methods: vec![
                 MethodInfo {
-                    fields_accessed: vec!["name".to_string()],
+                    fields_accessed: vec!["name".into()],
                     cyclomatic_complexity: 1,
+                    ..Default::default()
                 },
                 MethodInfo {
-                    fields_accessed: vec!["name".to_string()],
+                    fields_accessed: vec!["name".into()],
                     cyclomatic_complexity: 1,
+                    ..Default::default()
                 },
             ],
             external_types: vec![],
             traits: vec![],
+            ..Default::default()
         };
 
         // Should be close to 0 (perfectly cohesive)
@@ -95,26 +145,31 @@ mod tests {
             name: "User".to_string(),
             fields: vec![
                 FieldInfo {
-                    name: "name".to_string(),
-                    ty: "String".to_string(),
+                    name: "name".into(),
+                    ty: "String".into(),
+                    ..Default::default()
                 },
                 FieldInfo {
-                    name: "email".to_string(),
-                    ty: "String".to_string(),
+                    name: "email".into(),
+                    ty: "String".into(),
+                    ..Default::default()
                 },
             ],
             methods: vec![
                 MethodInfo {
-                    fields_accessed: vec!["name".to_string()],
+                    fields_accessed: vec!["name".into()],
                     cyclomatic_complexity: 1,
+                    ..Default::default()
                 },
                 MethodInfo {
-                    fields_accessed: vec!["email".to_string()],
+                    fields_accessed: vec!["email".into()],
                     cyclomatic_complexity: 1,
+                    ..Default::default()
                 },
             ],
             external_types: vec![],
             traits: vec![],
+            ..Default::default()
         };
 
         // Should be higher (less cohesive)
@@ -122,6 +177,26 @@ mod tests {
         assert!(lcom > 0.5, "Expected high LCOM for low cohesion struct, got {}", lcom);
     }
 
+    #[test]
+    fn test_lcom_transitive_follows_self_calls() {
+        let source = r#"
+            struct Person { name: String, email: String }
+
+            impl Person {
+                fn format_name(&self) -> String { self.name.to_uppercase() }
+                fn display(&self) -> String { self.format_name() }
+                fn greet(&self) -> String { Self::display(self) }
+                fn contact(&self) -> &str { &self.email }
+            }
+        "#;
+        let person = &parse_file(source, "crate", &ParseOptions::default()).unwrap().structs[0];
+
+        // Only `format_name` reads `name` directly: 4 - (1 + 1) / 2 over 3
+        assert!((calculate(person) - 1.0).abs() < 1e-9);
+        // `display` and `greet` read it through `format_name`: 4 - (3 + 1) / 2 over 3
+        assert!((calculate_transitive(person) - 2.0 / 3.0).abs() < 1e-9);
+    }
+
     #[test]
     fn test_lcom_empty_struct() {
         let struct_info = StructInfo {
@@ -130,6 +205,7 @@ mod tests {
             methods: vec![],
             external_types: vec![],
             traits: vec![],
+            ..Default::default()
         };
 
         assert_eq!(calculate(&struct_info), 0.0);
@@ -0,0 +1,77 @@
+use crate::models::{MethodInfo, StructInfo};
+
+/// Calculate LCOM4, the number of connected components among methods
+///
+/// Two methods are connected when they access a common field or one calls
+/// the other, through `self.method()` or `Self::method()`. A cohesive
+/// struct has one component; each further component is a cluster of
+/// methods and fields that could be split off, which Henderson-Sellers
+/// LCOM averages away.
+///
+/// # Returns
+/// The number of components, 0 for a struct without methods
+pub fn calculate(struct_info: &StructInfo) -> usize {
+    let methods = &struct_info.methods;
+    let mut parent: Vec<usize> = (0..methods.len()).collect();
+
+    for (i, a) in methods.iter().enumerate() {
+        for (j, b) in methods.iter().enumerate().skip(i + 1) {
+            let shares_field = a.fields_accessed.iter().any(|f| b.fields_accessed.contains(f));
+            if shares_field || calls(a, &b.name) || calls(b, &a.name) {
+                let (root_a, root_b) = (find(&mut parent, i), find(&mut parent, j));
+                parent[root_a] = root_b;
+            }
+        }
+    }
+
+    (0..methods.len()).filter(|&i| find(&mut parent, i) == i).count()
+}
+
+/// Whether `method` calls the method `name`, bare or as `Self::name`
+pub(super) fn calls(method: &MethodInfo, name: &str) -> bool {
+    method
+        .calls
+        .iter()
+        .any(|call| call == name || call.strip_prefix("Self::") == Some(name))
+}
+
+/// Root of `node` in the union-find forest, compressing the path to it
+pub(super) fn find(parent: &mut [usize], node: usize) -> usize {
+    let mut root = node;
+    while parent[root] != root {
+        root = parent[root];
+    }
+    let mut node = node;
+    while parent[node] != root {
+        let next = parent[node];
+        parent[node] = root;
+        node = next;
+    }
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_file, ParseOptions};
+
+    #[test]
+    fn test_lcom4_counts_independent_clusters() {
+        let source = r#"
+            struct Service { cache: Vec<u8>, hits: usize, log: String }
+            impl Service {
+                fn get(&mut self) -> usize { self.hits += 1; self.lookup() }
+                fn lookup(&self) -> usize { self.cache.len() }
+                fn clear(&mut self) { self.cache.clear(); }
+                fn write(&mut self, line: &str) { self.log.push_str(line); }
+                fn flush(&mut self) { Self::write(self, "\n"); }
+                fn version() -> u32 { 1 }
+            }
+        "#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+
+        // {get, lookup, clear}, {write, flush}, {version}
+        assert_eq!(calculate(&structs[0]), 3);
+        assert_eq!(calculate(&StructInfo::default()), 0);
+    }
+}
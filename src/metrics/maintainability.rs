@@ -0,0 +1,66 @@
+use crate::models::StructInfo;
+
+/// Maintainability Index on the 0-100 scale of Visual Studio
+///
+/// `171 - 5.2 ln(volume) - 0.23 complexity - 16.2 ln(sloc)`, rescaled from
+/// 0-171 to 0-100 and floored at 0. Higher is more maintainable: 20 and
+/// above is good, 10 to 19 moderate, below 10 poor.
+pub fn index(volume: f64, complexity: f64, sloc: f64) -> f64 {
+    // ln of 0 or less would be undefined; an empty body costs nothing
+    let ln = |x: f64| if x > 1.0 { x.ln() } else { 0.0 };
+    let raw = 171.0 - 5.2 * ln(volume) - 0.23 * complexity - 16.2 * ln(sloc);
+    (raw * 100.0 / 171.0).clamp(0.0, 100.0)
+}
+
+/// Calculate the Maintainability Index of a struct
+///
+/// Applies the formula to the mean Halstead volume, cyclomatic complexity,
+/// and SLOC of its methods, as the original definition does for a module.
+/// A struct without methods scores 100.
+pub fn calculate(struct_info: &StructInfo) -> f64 {
+    let methods = &struct_info.methods;
+    if methods.is_empty() {
+        return 100.0;
+    }
+
+    let count = methods.len() as f64;
+    let volume: f64 = methods.iter().map(|m| m.halstead.volume()).sum();
+    let complexity: usize = methods.iter().map(|m| m.cyclomatic_complexity.max(1)).sum();
+    let sloc: usize = methods.iter().map(|m| m.sloc).sum();
+    index(volume / count, complexity as f64 / count, sloc as f64 / count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::analyze_struct;
+    use crate::parser::{parse_file, ParseOptions};
+
+    #[test]
+    fn test_maintainability_index() {
+        assert_eq!(index(0.0, 1.0, 0.0), (171.0 - 0.23) * 100.0 / 171.0);
+        assert_eq!(index(1e12, 500.0, 1e6), 0.0);
+
+        let source = r#"
+            struct Counter { count: usize }
+            impl Counter {
+                fn get(&self) -> usize { self.count }
+
+                fn bump(&mut self, by: usize) {
+                    // Comments and blank lines are not code
+
+                    if by > 0 {
+                        self.count += by;
+                    }
+                }
+            }
+        "#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let sloc: Vec<usize> = structs[0].methods.iter().map(|m| m.sloc).collect();
+        assert_eq!(sloc, vec![1, 5]);
+
+        let result = analyze_struct(&structs[0], &structs);
+        assert!(result.maintainability > 60.0 && result.maintainability < 100.0);
+        assert_eq!(calculate(&StructInfo::default()), 100.0);
+    }
+}
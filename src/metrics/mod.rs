@@ -1,14 +1,189 @@
+pub mod afferent;
+pub mod api;
+pub mod atfd;
 pub mod cbo;
+pub mod cognitive;
+pub mod field_types;
+pub mod halstead;
 pub mod lcom;
+pub mod lcom4;
+pub mod maintainability;
+pub mod sloc;
+pub mod tcc;
 pub mod wmc;
 
-use crate::models::{AnalysisResult, StructInfo};
+use std::collections::BTreeMap;
 
+use crate::models::{AnalysisResult, Dependency, ImplBlockMetrics, MethodInfo, MethodResult, MetricSelection, StructInfo};
+use crate::ownership;
+use crate::query::Formula;
+use crate::resolve::StructIndex;
+
+/// Calculate every metric of `struct_info`, resolving references among `all_structs`
+///
+/// Indexes `all_structs` on each call; to analyze many structs, build one
+/// `StructIndex` and use `analyze_selected`.
 pub fn analyze_struct(struct_info: &StructInfo, all_structs: &[StructInfo]) -> AnalysisResult {
+    analyze_selected(struct_info, &StructIndex::new(all_structs), &MetricSelection::default())
+}
+
+/// Like `analyze_struct`, skipping the passes of the metrics `selection`
+/// leaves out, which stay zero
+///
+/// Without `all`, the metrics that `--metrics` cannot select (TCC/LCC,
+/// Halstead, ATFD, field types) are skipped as well.
+pub fn analyze_selected(struct_info: &StructInfo, index: &StructIndex, selection: &MetricSelection) -> AnalysisResult {
+    let pass = |selected: bool| selection.all || selected;
+    let (field_types, domain_ratio) = if selection.all { field_types::calculate(struct_info) } else { (0, 0.0) };
+    let (tcc, lcc) = if selection.all { tcc::calculate(struct_info) } else { (0.0, 0.0) };
+    let (volume, difficulty, effort) = if selection.all { halstead::calculate(struct_info) } else { (0.0, 0.0, 0.0) };
+    let (sloc, mean_method_sloc) = if pass(selection.sloc) { sloc::calculate(struct_info) } else { (0, 0.0) };
+    let methods: Vec<MethodInfo> = if selection.all {
+        struct_info
+            .methods
+            .iter()
+            .map(|method| MethodInfo {
+                foreign_data: atfd::foreign_data(method, struct_info, index),
+                ..method.clone()
+            })
+            .collect()
+    } else {
+        struct_info.methods.clone()
+    };
     AnalysisResult {
         struct_name: struct_info.name.clone(),
-        lcom: lcom::calculate(struct_info),
-        cbo: cbo::calculate(struct_info, all_structs),
-        wmc: wmc::calculate(struct_info),
+        module_path: struct_info.module_path.clone(),
+        kind: struct_info.kind,
+        file_path: struct_info.file_path.clone(),
+        span: struct_info.span,
+        fields: struct_info.fields.clone(),
+        atfd: atfd::calculate(&methods),
+        methods,
+        lcom: if pass(selection.lcom) { lcom::calculate(struct_info) } else { 0.0 },
+        lcom4: if pass(selection.lcom4) { lcom4::calculate(struct_info) } else { 0 },
+        tcc,
+        lcc,
+        cbo: if pass(selection.cbo) { cbo::calculate(struct_info, index) } else { 0 },
+        trait_coupling: None,
+        derived_coupling: if pass(selection.cbo) { struct_info.derives.iter().collect::<std::collections::HashSet<_>>().len() } else { 0 },
+        ca: 0, // Needs every result; see `afferent::apply`
+        fan_in: 0,
+        instability: 0.0,
+        wmc: if pass(selection.wmc) { wmc::calculate(struct_info) } else { 0 },
+        cognitive: if pass(selection.cognitive) { cognitive::calculate(struct_info) } else { 0 },
+        volume,
+        difficulty,
+        effort,
+        maintainability: if pass(selection.mi) { maintainability::calculate(struct_info) } else { 0.0 },
+        sloc,
+        mean_method_sloc,
+        dyn_dispatch: struct_info.dyn_dispatch,
+        field_types,
+        domain_ratio,
+        error_types: error_types(struct_info),
+        error_density: error_density(struct_info),
+        async_methods: struct_info.methods.iter().filter(|m| m.is_async).count(),
+        awaits: struct_info.methods.iter().map(|m| m.awaits).sum(),
+        api: api::calculate(struct_info),
+        derives: struct_info.derives.clone(),
+        dependencies: dependencies(struct_info, index),
+        ownership: ownership::edges(struct_info, index),
+        impl_blocks: impl_blocks(struct_info),
+        derived: BTreeMap::new(),
+    }
+}
+
+/// Evaluate config-defined metrics on a result from its built-in metrics
+pub fn apply_derived(result: &mut AnalysisResult, formulas: &[(String, Formula)]) {
+    for (name, formula) in formulas {
+        let value = formula.eval(result);
+        result.derived.insert(name.clone(), value);
     }
 }
+
+/// One row per method of every result, in source order within a struct
+pub fn method_results(results: &[AnalysisResult]) -> Vec<MethodResult> {
+    results
+        .iter()
+        .flat_map(|result| {
+            result.methods.iter().map(|method| MethodResult {
+                struct_name: result.struct_name.clone(),
+                module_path: result.module_path.clone(),
+                file_path: result.file_path.clone(),
+                method: method.name.clone(),
+                span: method.span,
+                complexity: method.cyclomatic_complexity,
+                cognitive: method.cognitive_complexity,
+                volume: method.halstead.volume(),
+                difficulty: method.halstead.difficulty(),
+                effort: method.halstead.effort(),
+                maintainability: maintainability::index(
+                    method.halstead.volume(),
+                    method.cyclomatic_complexity.max(1) as f64,
+                    method.sloc as f64,
+                ),
+                sloc: method.sloc,
+                fields_accessed: method.fields_accessed.len(),
+                params: method.params,
+                demeter: method.demeter_violations.len(),
+                demeter_lines: method.demeter_violations.clone(),
+                is_async: method.is_async,
+                awaits: method.awaits,
+            })
+        })
+        .collect()
+}
+
+/// LCOM and WMC of each impl block, as if its methods were the whole struct
+fn impl_blocks(struct_info: &StructInfo) -> Vec<ImplBlockMetrics> {
+    struct_info
+        .impl_blocks
+        .iter()
+        .enumerate()
+        .map(|(index, block)| {
+            let block_struct = StructInfo {
+                fields: struct_info.fields.clone(),
+                methods: struct_info.methods.iter().filter(|m| m.impl_block == index).cloned().collect(),
+                ..Default::default()
+            };
+            ImplBlockMetrics {
+                label: block.label.clone(),
+                span: block.span,
+                method_count: block_struct.methods.len(),
+                lcom: lcom::calculate(&block_struct),
+                wmc: wmc::calculate(&block_struct),
+            }
+        })
+        .collect()
+}
+
+/// Distinct error types returned by the struct's methods, sorted
+fn error_types(struct_info: &StructInfo) -> Vec<String> {
+    let types: std::collections::BTreeSet<&String> =
+        struct_info.methods.iter().filter_map(|m| m.error_type.as_ref()).collect();
+    types.into_iter().cloned().collect()
+}
+
+/// Error-path sites, `?` operators and `return Err(..)`, per line of
+/// method code; 0 for a struct without methods
+fn error_density(struct_info: &StructInfo) -> f64 {
+    let lines: usize = struct_info.methods.iter().map(|m| m.sloc).sum();
+    if lines == 0 {
+        return 0.0;
+    }
+    let sites: usize = struct_info.methods.iter().map(|m| m.propagations + m.error_returns).sum();
+    sites as f64 / lines as f64
+}
+
+/// Outgoing struct-to-struct dependencies with their reference counts
+pub fn dependencies(struct_info: &StructInfo, index: &StructIndex) -> Vec<Dependency> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for target in cbo::struct_references(struct_info, index) {
+        *counts.entry(index[target].qualified_name()).or_default() += 1;
+    }
+
+    counts
+        .into_iter()
+        .map(|(target, references)| Dependency { target, references })
+        .collect()
+}
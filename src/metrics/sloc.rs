@@ -0,0 +1,50 @@
+use crate::models::StructInfo;
+
+/// Calculate the source lines of code of a struct
+///
+/// Lines count when they hold code: comments, blank lines, and attributes
+/// such as derives and doc comments do not. The struct total adds the
+/// definition to its methods, wherever their impl blocks are.
+///
+/// # Returns
+/// Total lines of code, and the mean length of the methods (0 for a
+/// struct without methods)
+pub fn calculate(struct_info: &StructInfo) -> (usize, f64) {
+    let methods: usize = struct_info.methods.iter().map(|m| m.sloc).sum();
+    let mean = if struct_info.methods.is_empty() {
+        0.0
+    } else {
+        methods as f64 / struct_info.methods.len() as f64
+    };
+    (struct_info.sloc + methods, mean)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_file, ParseOptions};
+
+    #[test]
+    fn test_sloc_of_definition_and_methods() {
+        let source = r#"
+            /// A counter
+            #[derive(Debug)]
+            struct Counter {
+                // Current value
+                count: usize,
+            }
+
+            impl Counter {
+                fn get(&self) -> usize { self.count }
+
+                fn reset(&mut self) {
+                    self.count = 0;
+                }
+            }
+        "#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        assert_eq!(structs[0].sloc, 3);
+        assert_eq!(calculate(&structs[0]), (3 + 1 + 3, 2.0));
+        assert_eq!(calculate(&StructInfo::default()), (0, 0.0));
+    }
+}
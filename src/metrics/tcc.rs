@@ -0,0 +1,67 @@
+use crate::models::StructInfo;
+
+use super::lcom4::find;
+
+/// Calculate Tight and Loose Class Cohesion (Bieman and Kang)
+///
+/// Two methods are directly connected when they access a common field.
+/// TCC is the share of method pairs that are directly connected; LCC the
+/// share connected directly or through a chain of other methods. Both are
+/// higher for more cohesive structs, and LCC is never below TCC. A struct
+/// with a high LCC but a low TCC hangs together only through a few
+/// methods, a likely seam for a split.
+///
+/// # Returns
+/// TCC and LCC between 0 and 1, both 1 for fewer than two methods
+pub fn calculate(struct_info: &StructInfo) -> (f64, f64) {
+    let methods = &struct_info.methods;
+    let n = methods.len();
+    if n < 2 {
+        return (1.0, 1.0);
+    }
+
+    let mut parent: Vec<usize> = (0..n).collect();
+    let mut direct = 0;
+    for (i, a) in methods.iter().enumerate() {
+        for (j, b) in methods.iter().enumerate().skip(i + 1) {
+            if a.fields_accessed.iter().any(|f| b.fields_accessed.contains(f)) {
+                direct += 1;
+                let (root_a, root_b) = (find(&mut parent, i), find(&mut parent, j));
+                parent[root_a] = root_b;
+            }
+        }
+    }
+
+    let mut component_sizes = vec![0usize; n];
+    for i in 0..n {
+        component_sizes[find(&mut parent, i)] += 1;
+    }
+    let indirect: usize = component_sizes.iter().map(|&size| size * size.saturating_sub(1) / 2).sum();
+
+    let pairs = (n * (n - 1) / 2) as f64;
+    (direct as f64 / pairs, indirect as f64 / pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_file, ParseOptions};
+
+    #[test]
+    fn test_tcc_and_lcc() {
+        let source = r#"
+            struct Chain { a: u8, b: u8, c: u8 }
+            impl Chain {
+                fn ab(&self) -> u8 { self.a + self.b }
+                fn bc(&self) -> u8 { self.b + self.c }
+                fn c(&self) -> u8 { self.c }
+                fn none(&self) -> u8 { 0 }
+            }
+        "#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+
+        // Direct: ab-bc, bc-c; indirect also ab-c; `none` connects to nothing
+        assert_eq!(calculate(&structs[0]), (2.0 / 6.0, 3.0 / 6.0));
+        assert_eq!(calculate(&StructInfo::default()), (1.0, 1.0));
+    }
+}
@@ -31,6 +31,7 @@ mod tests {
             methods: vec![],
             external_types: vec![],
             traits: vec![],
+            ..Default::default()
         };
 
         assert_eq!(calculate(&struct_info), 0);
@@ -42,26 +43,31 @@ mod tests {
             name: "User".to_string(),
             fields: vec![
                 FieldInfo {
-                    name: "name".to_string(),
-                    ty: "String".to_string(),
+                    name: "name".into(),
+                    ty: "String".into(),
+                    ..Default::default()
                 },
             ],
             methods: vec![
                 MethodInfo {
-                    fields_accessed: vec!["name".to_string()],
+                    fields_accessed: vec!["name".into()],
                     cyclomatic_complexity: 1,
+                    ..Default::default()
                 },
                 MethodInfo {
-                    fields_accessed: vec!["name".to_string()],
+                    fields_accessed: vec!["name".into()],
                     cyclomatic_complexity: 1,
+                    ..Default::default()
                 },
                 MethodInfo {
                     fields_accessed: vec![],
                     cyclomatic_complexity: 3,
+                    ..Default::default()
                 },
             ],
             external_types: vec![],
             traits: vec![],
+            ..Default::default()
         };
 
         assert_eq!(calculate(&struct_info), 5); // 1 + 1 + 3
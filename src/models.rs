@@ -1,34 +1,374 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Shared text of a name that recurs across structs and methods, such as
+/// a field name or a field type; clones are reference count bumps
+pub type Symbol = Arc<str>;
+
+/// Hands out a single shared `Symbol` per distinct string
+#[derive(Debug, Default)]
+pub struct Interner {
+    symbols: HashSet<Symbol>,
+}
+
+impl Interner {
+    pub fn intern(&mut self, text: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(text) {
+            return symbol.clone();
+        }
+        let symbol: Symbol = Arc::from(text);
+        self.symbols.insert(symbol.clone());
+        symbol
+    }
+}
+
 /// Represents information about a struct field
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct FieldInfo {
-    pub name: String,
-    pub ty: String,
+    pub name: Symbol,
+    pub ty: Symbol,
+    #[serde(default)]
+    pub is_pub: bool,
+}
+
+/// Line range of an item in its source file (1-based, inclusive)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct SourceSpan {
+    pub start_line: usize,
+    pub end_line: usize,
 }
 
 /// Represents information about a method
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct MethodInfo {
-    pub fields_accessed: Vec<String>,
+    pub name: String,
+    pub span: SourceSpan,
+    pub fields_accessed: Vec<Symbol>,
     pub cyclomatic_complexity: usize,
+    #[serde(default)]
+    pub cognitive_complexity: usize, // Nesting-weighted breaks in linear flow
+    #[serde(default)]
+    pub halstead: HalsteadCounts,
+    #[serde(default)]
+    pub sloc: usize, // Lines holding code of the signature and body
+    #[serde(default)]
+    pub impl_block: usize, // Index into the struct's impl blocks
+    #[serde(default)]
+    pub calls: Vec<String>, // Methods and functions called, e.g. `push` or `Config::new`
+    #[serde(default)]
+    pub error_type: Option<String>, // `E` of a `Result<_, E>` return type
+    #[serde(default)]
+    pub propagations: usize, // `?` operators propagating errors; none in methods returning `Option`
+    #[serde(default)]
+    pub error_returns: usize, // `return Err(..)` sites
+    #[serde(default)]
+    pub is_async: bool, // An `async fn`
+    #[serde(default)]
+    pub awaits: usize, // `.await` expressions
+    #[serde(default)]
+    pub is_pub: bool, // A `pub` inherent method
+    #[serde(default)]
+    pub params: usize, // Parameters, not counting `self`
+    #[serde(default)]
+    pub role: MethodRole,
+    #[serde(default)]
+    pub demeter_violations: Vec<usize>, // Lines of member chains reaching through a stranger
+    #[serde(default)]
+    pub foreign_accesses: Vec<ForeignAccess>, // Members of other types used, as written
+    #[serde(default)]
+    pub used_types: Vec<String>, // Types called or constructed, as written, e.g. `Config` of `Config::new()`
+    #[serde(default)]
+    pub foreign_data: Vec<String>, // Fields and getters of other analyzed structs, set by analysis
+}
+
+/// What a method does for its struct, judged from its signature and body
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MethodRole {
+    #[default]
+    Behavior,
+    Getter,      // Returns a field, e.g. `&self.name` or `self.name.clone()`
+    Setter,      // Assigns a parameter to a field
+    Constructor, // An associated function returning `Self`
+}
+
+impl MethodRole {
+    pub fn is_accessor(self) -> bool {
+        matches!(self, MethodRole::Getter | MethodRole::Setter)
+    }
+}
+
+/// A member of another type used in a method body, e.g. `order.total` or
+/// `self.customer.name()`, with the type as written in the method
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ForeignAccess {
+    pub ty: String, // Type path of the parameter, local, or field accessed through
+    pub member: String,
+    pub call: bool, // A call without arguments, i.e. a possible getter
+}
+
+/// Operator and operand counts of a method body, for Halstead metrics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct HalsteadCounts {
+    pub distinct_operators: usize, // n1
+    pub distinct_operands: usize,  // n2
+    pub operators: usize,          // N1
+    pub operands: usize,           // N2
+}
+
+impl HalsteadCounts {
+    /// Program length times the bits needed to pick each token: N * log2(n)
+    pub fn volume(&self) -> f64 {
+        let vocabulary = self.distinct_operators + self.distinct_operands;
+        if vocabulary == 0 {
+            return 0.0;
+        }
+        (self.operators + self.operands) as f64 * (vocabulary as f64).log2()
+    }
+
+    /// How error-prone the code is to write: n1 / 2 * N2 / n2
+    pub fn difficulty(&self) -> f64 {
+        if self.distinct_operands == 0 {
+            return 0.0;
+        }
+        self.distinct_operators as f64 / 2.0 * self.operands as f64 / self.distinct_operands as f64
+    }
+
+    /// Mental effort to write or understand the code: difficulty * volume
+    pub fn effort(&self) -> f64 {
+        self.difficulty() * self.volume()
+    }
+}
+
+/// Metrics of one method, for locating the complex methods of a struct
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct MethodResult {
+    pub struct_name: String,
+    pub module_path: String,
+    pub file_path: String,
+    pub method: String,
+    pub span: SourceSpan,
+    pub complexity: usize,
+    pub cognitive: usize,
+    pub volume: f64, // Halstead
+    pub difficulty: f64,
+    pub effort: f64,
+    pub maintainability: f64, // Maintainability Index, 0-100
+    pub sloc: usize,
+    pub fields_accessed: usize,
+    pub params: usize,
+    pub demeter: usize, // Law of Demeter violations
+    pub demeter_lines: Vec<usize>,
+    pub is_async: bool,
+    pub awaits: usize, // `.await` expressions
+}
+
+/// Semver-relevant public surface of a `pub` struct
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ApiSurface {
+    pub pub_fields: usize,
+    pub pub_methods: usize,
+    pub generic_params: usize, // Type and const parameters
+    pub non_exhaustive: bool,
+    pub score: usize, // Weighted breaking-change risk, higher is riskier
+}
+
+/// A `pub` enum, which callers may match exhaustively unless `#[non_exhaustive]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EnumInfo {
+    pub name: String,
+    pub module_path: String,
+    pub file_path: String,
+    pub variants: usize,
+    pub non_exhaustive: bool,
+}
+
+/// An `impl` block of a struct, e.g. `impl #1` or `impl Display`
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ImplBlock {
+    pub label: String, // `impl #N` for the Nth inherent impl, `impl Trait` otherwise
+    pub span: SourceSpan,
+}
+
+/// Metrics of the methods of one impl block
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ImplBlockMetrics {
+    pub label: String,
+    pub span: SourceSpan, // In the struct's file
+    pub method_count: usize,
+    pub lcom: f64, // Cohesion of the block's methods over all fields
+    pub wmc: usize,
+}
+
+/// Kind of type definition a `StructInfo` was parsed from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StructKind {
+    #[default]
+    Struct,
+    Union,
+}
+
+impl std::fmt::Display for StructKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StructKind::Struct => write!(f, "struct"),
+            StructKind::Union => write!(f, "union"),
+        }
+    }
 }
 
 /// Represents information about a struct and its methods
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct StructInfo {
     pub name: String,
+    pub kind: StructKind,
+    pub module_path: String, // e.g. `crate::net::server`
+    pub file_path: String, // Under `--expand`, the target's root file, spans being lines of the expanded source
+    pub span: SourceSpan, // Struct definition
+    pub sloc: usize, // Lines holding code of the definition, without attributes
+    pub is_pub: bool,
+    pub non_exhaustive: bool,
+    pub generic_params: usize, // Type and const parameters
+    pub type_params: Vec<String>, // Names of the type parameters, e.g. `T`
+    pub pub_fields: usize,
     pub fields: Vec<FieldInfo>,
     pub methods: Vec<MethodInfo>,
-    pub external_types: Vec<String>,
+    pub impl_blocks: Vec<ImplBlock>,
+    pub external_types: Vec<Symbol>, // Type paths named by method parameter and return types
     pub traits: Vec<String>, // Traits this struct implements
+    pub derives: Vec<String>, // Traits from `#[derive(...)]`, recorded per `DeriveMode`
+    pub referenced_traits: Vec<String>, // Traits of `impl Trait`, `dyn Trait`, and generic bounds
+    pub crate_traits: Vec<String>, // Names of the traits in `traits` and `referenced_traits` declared in the analyzed code
+    pub dyn_dispatch: usize, // `dyn Trait` types in fields and method signatures
+    pub reexported_as: Vec<String>, // Paths of `pub use` re-exports, e.g. `crate::Engine`
+    pub aliases: Vec<String>, // Paths of type aliases and `use ... as` renames, e.g. `crate::billing::Bill`
+    pub imports: Vec<Reexport>, // `use` items of its module, as `crate::db::Config` -> `crate::net::Config`
+}
+
+impl StructInfo {
+    /// Crate-absolute path of the struct, e.g. `crate::net::Server`
+    pub fn qualified_name(&self) -> String {
+        if self.module_path.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}::{}", self.module_path, self.name)
+        }
+    }
+}
+
+/// A `pub use` re-export: `path` makes the item at `target` available
+///
+/// Globs are recorded with a trailing `*` on both sides, e.g.
+/// `crate::*` -> `crate::internal::*` for `pub use internal::*;` in the crate root
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Reexport {
+    pub path: String,
+    pub target: String,
+}
+
+/// Everything extracted from a single source file
+#[derive(Debug, Clone, Default)]
+pub struct ParsedFile {
+    pub structs: Vec<StructInfo>,
+    pub enums: Vec<EnumInfo>, // `pub` enums only
+    pub reexports: Vec<Reexport>,
+    pub aliases: Vec<Reexport>, // Type aliases and private `use ... as` renames, as `path` -> `target`
+    pub traits: Vec<String>, // Names of the traits declared
+}
+
+/// A dependency of one struct on another analyzed struct
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Dependency {
+    pub target: String,    // Qualified name of the struct depended on
+    pub references: usize, // Number of references (field types, type mentions)
+}
+
+/// How a field holds another analyzed struct
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ownership {
+    Owned,  // By value or through `Box`, `Vec`, and other containers
+    Shared, // Through `Rc` or `Arc`
+    Weak,   // Through `rc::Weak` or `sync::Weak`
+}
+
+impl std::fmt::Display for Ownership {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ownership::Owned => write!(f, "owned"),
+            Ownership::Shared => write!(f, "shared"),
+            Ownership::Weak => write!(f, "weak"),
+        }
+    }
+}
+
+/// A field holding another analyzed struct, e.g. `parent: Weak<Node>`
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct OwnershipEdge {
+    pub field: String,
+    pub target: String, // Qualified name of the struct held
+    pub kind: Ownership,
 }
 
 /// Represents the analysis result for a struct
-#[derive(Debug, Clone)]
+///
+/// Serialized in full by `snapshot`; missing fields default when reading
+/// result files written by older versions.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct AnalysisResult {
     pub struct_name: String,
+    pub module_path: String,
+    pub kind: StructKind,
+    pub file_path: String,
+    pub span: SourceSpan,
+    pub fields: Vec<FieldInfo>,
+    pub methods: Vec<MethodInfo>,
     pub lcom: f64,
+    pub lcom4: usize, // Connected components of methods
+    pub tcc: f64, // Share of method pairs sharing a field
+    pub lcc: f64, // Share of method pairs connected through shared fields
     pub cbo: usize,
+    pub trait_coupling: Option<usize>, // Traits coupled to, left out of `cbo` under `--separate-trait-coupling`
+    pub derived_coupling: usize, // Distinct derived traits, recorded unless derives are off
+    pub atfd: usize, // Fields and getters of other analyzed structs used by the methods
+    pub ca: usize, // Other structs depending on this one
+    pub fan_in: usize, // References from other structs
+    pub instability: f64, // Ce / (Ca + Ce)
     pub wmc: usize,
+    pub cognitive: usize, // Summed cognitive complexity of the methods
+    pub volume: f64, // Summed Halstead volume of the methods
+    pub difficulty: f64, // Halstead difficulty of the methods, weighted by volume
+    pub effort: f64, // Summed Halstead effort of the methods
+    pub maintainability: f64, // Maintainability Index, 0-100
+    pub sloc: usize, // Lines of code of the definition and the methods
+    pub mean_method_sloc: f64, // Average method length in lines of code
+    pub dyn_dispatch: usize, // `dyn Trait` types in fields and method signatures
+    pub field_types: usize, // Distinct types named in field types
+    pub domain_ratio: f64, // Share of `field_types` that are neither primitives nor std collections
+    pub error_types: Vec<String>, // Distinct error types returned by the methods
+    pub error_density: f64, // `?` and `return Err(..)` sites per line of method code
+    pub async_methods: usize, // `async fn` methods
+    pub awaits: usize, // `.await` expressions across the methods
+    pub api: Option<ApiSurface>, // For `pub` structs
+    pub derives: Vec<String>, // Empty unless derives are counted or listed
+    pub dependencies: Vec<Dependency>,
+    pub ownership: Vec<OwnershipEdge>, // Fields holding other analyzed structs
+    pub impl_blocks: Vec<ImplBlockMetrics>,
+    pub derived: BTreeMap<String, f64>, // Config-defined metrics by name
+}
+
+impl AnalysisResult {
+    /// Crate-absolute path of the struct, e.g. `crate::net::Server`
+    pub fn qualified_name(&self) -> String {
+        if self.module_path.is_empty() {
+            self.struct_name.clone()
+        } else {
+            format!("{}::{}", self.module_path, self.struct_name)
+        }
+    }
 }
 
 /// Output format options
@@ -38,6 +378,18 @@ pub enum OutputFormat {
     Table,
     Json,
     Csv,
+    Html,
+    Dot,
+    GraphJson,
+    Xlsx,
+    Sarif,
+    Mermaid,
+    Markdown,
+    Junit,
+    Yaml,
+    Jsonl,
+    Sqlite,
+    Teamcity,
 }
 
 impl std::str::FromStr for OutputFormat {
@@ -48,7 +400,316 @@ impl std::str::FromStr for OutputFormat {
             "table" => Ok(OutputFormat::Table),
             "json" => Ok(OutputFormat::Json),
             "csv" => Ok(OutputFormat::Csv),
+            "html" => Ok(OutputFormat::Html),
+            "dot" => Ok(OutputFormat::Dot),
+            "graph-json" => Ok(OutputFormat::GraphJson),
+            "xlsx" => Ok(OutputFormat::Xlsx),
+            "sarif" => Ok(OutputFormat::Sarif),
+            "mermaid" => Ok(OutputFormat::Mermaid),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "junit" => Ok(OutputFormat::Junit),
+            "yaml" | "yml" => Ok(OutputFormat::Yaml),
+            "jsonl" | "ndjson" => Ok(OutputFormat::Jsonl),
+            "sqlite" => Ok(OutputFormat::Sqlite),
+            "teamcity" => Ok(OutputFormat::Teamcity),
             _ => Err(format!("Unknown format: {}", s)),
         }
     }
 }
+
+/// How methods of extension traits are attributed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExtensionTraitMode {
+    /// Attribute an extension trait's methods, including its default
+    /// methods, to the struct it extends
+    #[default]
+    Merge,
+    /// Treat extension traits like any other trait
+    Off,
+}
+
+impl std::str::FromStr for ExtensionTraitMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "merge" => Ok(ExtensionTraitMode::Merge),
+            "off" => Ok(ExtensionTraitMode::Off),
+            _ => Err(format!("Unknown extension trait mode: {}", s)),
+        }
+    }
+}
+
+/// How `#[derive(...)]` attributes are recorded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DeriveMode {
+    /// Count derived traits as `derived_coupling`, apart from CBO
+    On,
+    /// Ignore derives
+    #[default]
+    Off,
+    /// Record derived traits for reporting without counting them
+    List,
+}
+
+impl std::str::FromStr for DeriveMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "on" => Ok(DeriveMode::On),
+            "off" => Ok(DeriveMode::Off),
+            "list" => Ok(DeriveMode::List),
+            _ => Err(format!("Unknown derive mode: {}", s)),
+        }
+    }
+}
+
+/// A per-struct metric that can be selected on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Lcom,
+    Cbo,
+    Wmc,
+}
+
+impl Metric {
+    /// Value of this metric for an analyzed struct
+    pub fn value(&self, result: &AnalysisResult) -> f64 {
+        match self {
+            Metric::Lcom => result.lcom,
+            Metric::Cbo => result.cbo as f64,
+            Metric::Wmc => result.wmc as f64,
+        }
+    }
+}
+
+impl std::fmt::Display for Metric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Metric::Lcom => write!(f, "LCOM"),
+            Metric::Cbo => write!(f, "CBO"),
+            Metric::Wmc => write!(f, "WMC"),
+        }
+    }
+}
+
+impl std::str::FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "lcom" => Ok(Metric::Lcom),
+            "cbo" => Ok(Metric::Cbo),
+            "wmc" => Ok(Metric::Wmc),
+            _ => Err(format!("Unknown metric: {}", s)),
+        }
+    }
+}
+
+/// When to color table output, per `--color`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    #[default]
+    Auto, // On a terminal, unless NO_COLOR is set
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    /// Whether to color output going to a terminal if `terminal`
+    pub fn enabled(self, terminal: bool) -> bool {
+        self.enabled_with(terminal, std::env::var_os("NO_COLOR").as_deref())
+    }
+
+    /// Like `enabled`, with `no_color` the value of `NO_COLOR`, if set
+    pub(crate) fn enabled_with(self, terminal: bool, no_color: Option<&std::ffi::OsStr>) -> bool {
+        match self {
+            ColorMode::Auto => terminal && no_color.is_none_or(|value| value.is_empty()),
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+        }
+    }
+}
+
+impl std::str::FromStr for ColorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            _ => Err(format!("Unknown color mode: {} (expected auto, always, or never)", s)),
+        }
+    }
+}
+
+/// Metrics chosen with `--metrics`, e.g. `lcom,cbo` or `all,lcom4`
+///
+/// `all` stands for the LCOM, CBO, Ca, fan-in, instability, WMC, and MI
+/// table columns and every field of JSON and CSV output. A list without
+/// `all` reports only the listed metrics, and the passes computing the
+/// others can be skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MetricSelection {
+    pub all: bool,
+    pub lcom: bool,
+    pub cbo: bool,
+    pub wmc: bool,
+    pub mi: bool,
+    pub lcom4: bool,
+    pub cognitive: bool,
+    pub sloc: bool, // SLOC and mean method length
+}
+
+impl Default for MetricSelection {
+    fn default() -> Self {
+        Self {
+            all: true,
+            lcom: true,
+            cbo: true,
+            wmc: true,
+            mi: true,
+            lcom4: false,
+            cognitive: false,
+            sloc: false,
+        }
+    }
+}
+
+impl MetricSelection {
+    /// Query names of the selected metrics, in table column order
+    pub fn names(&self) -> Vec<&'static str> {
+        [
+            (self.lcom, "lcom"),
+            (self.cbo, "cbo"),
+            (self.all, "ca"),
+            (self.all, "fan_in"),
+            (self.all, "instability"),
+            (self.wmc, "wmc"),
+            (self.mi, "mi"),
+            (self.lcom4, "lcom4"),
+            (self.cognitive, "cognitive"),
+            (self.sloc, "sloc"),
+        ]
+        .into_iter()
+        .filter_map(|(selected, name)| selected.then_some(name))
+        .collect()
+    }
+
+    /// Whether a JSON or CSV field of a struct is reported
+    pub fn includes_field(&self, field: &str) -> bool {
+        match field {
+            "struct_name" | "module_path" | "file_path" | "span" | "start_line" | "end_line" | "kind" | "derives" => true,
+            _ if self.all => true,
+            "lcom" => self.lcom,
+            "cbo" | "trait_coupling" | "derived_coupling" => self.cbo,
+            "wmc" => self.wmc,
+            "maintainability" => self.mi,
+            "lcom4" => self.lcom4,
+            "cognitive" => self.cognitive,
+            "sloc" | "mean_method_sloc" => self.sloc,
+            _ => false,
+        }
+    }
+}
+
+impl std::str::FromStr for MetricSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut selection = Self {
+            all: false,
+            lcom: false,
+            cbo: false,
+            wmc: false,
+            mi: false,
+            lcom4: false,
+            cognitive: false,
+            sloc: false,
+        };
+        for name in s.split(',').map(str::trim) {
+            match name.to_lowercase().as_str() {
+                "all" => selection.all = true,
+                "lcom" => selection.lcom = true,
+                "cbo" => selection.cbo = true,
+                "wmc" => selection.wmc = true,
+                "mi" => selection.mi = true,
+                "lcom4" => selection.lcom4 = true,
+                "cognitive" => selection.cognitive = true,
+                "sloc" => selection.sloc = true,
+                _ => {
+                    return Err(format!(
+                        "Unknown metric: {:?} (expected all or a comma-separated list of lcom, cbo, wmc, mi, lcom4, cognitive, and sloc)",
+                        name
+                    ))
+                }
+            }
+        }
+        if selection.all {
+            let default = Self::default();
+            selection.lcom = default.lcom;
+            selection.cbo = default.cbo;
+            selection.wmc = default.wmc;
+            selection.mi = default.mi;
+        }
+        Ok(selection)
+    }
+}
+
+/// Unit that `--group-by` rolls struct metrics up to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    Module,
+    File,
+    Crate,
+}
+
+impl GroupBy {
+    /// Group of an analyzed struct
+    ///
+    /// The crate of a struct is the name of the innermost of `crate_roots`
+    /// (canonical input paths and their crate names) holding its file, or
+    /// else the first segment of its module path.
+    pub fn key(&self, result: &AnalysisResult, crate_roots: &[(PathBuf, String)]) -> String {
+        match self {
+            GroupBy::Module => result.module_path.clone(),
+            GroupBy::File => result.file_path.clone(),
+            GroupBy::Crate => {
+                let file = Path::new(&result.file_path).canonicalize().ok();
+                let root = crate_roots
+                    .iter()
+                    .filter(|(root, _)| file.as_ref().is_some_and(|file| file.starts_with(root)))
+                    .max_by_key(|(root, _)| root.components().count());
+                match root {
+                    Some((_, name)) => name.clone(),
+                    None => result.module_path.split("::").next().unwrap_or_default().to_string(),
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for GroupBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GroupBy::Module => write!(f, "Module"),
+            GroupBy::File => write!(f, "File"),
+            GroupBy::Crate => write!(f, "Crate"),
+        }
+    }
+}
+
+impl std::str::FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "module" => Ok(GroupBy::Module),
+            "file" => Ok(GroupBy::File),
+            "crate" => Ok(GroupBy::Crate),
+            _ => Err(format!("Unknown grouping: {} (expected module, file, or crate)", s)),
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Crate roots to follow `mod` declarations from, with their module paths
+///
+/// A file is its own root. A directory has the `lib.rs`, `main.rs`, and
+/// `bin/*.rs` it holds, or else those of its `src/` directory. `lib.rs` and
+/// `main.rs` are `crate`; `bin/tool.rs` is `crate::bin::tool`, as without
+/// `--follow-mods`, so the modules of each binary keep apart.
+pub fn crate_roots(path: &Path) -> Vec<(PathBuf, String)> {
+    if path.is_file() {
+        return vec![(path.to_path_buf(), "crate".to_string())];
+    }
+
+    for dir in [path.to_path_buf(), path.join("src")] {
+        let mut roots: Vec<(PathBuf, String)> = ["lib.rs", "main.rs"]
+            .iter()
+            .map(|name| dir.join(name))
+            .filter(|f| f.is_file())
+            .map(|f| (f, "crate".to_string()))
+            .collect();
+        if let Ok(entries) = std::fs::read_dir(dir.join("bin")) {
+            let mut bins: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|f| f.is_file() && f.extension().is_some_and(|e| e == "rs"))
+                .collect();
+            bins.sort();
+            roots.extend(bins.into_iter().map(|bin| {
+                let name = bin.file_stem().unwrap_or_default().to_string_lossy().replace('-', "_");
+                (bin, format!("crate::bin::{}", name))
+            }));
+        }
+        if !roots.is_empty() {
+            return roots;
+        }
+    }
+    Vec::new()
+}
+
+/// Files of the module tree rooted at `root`, the module `root_module`,
+/// with their module paths, e.g. `crate::net::server` for `mod net;` then
+/// `mod server;`
+///
+/// `mod name;` is looked up as `name.rs` or `name/mod.rs` next to the
+/// declaring file, or under its directory for a file that is not a
+/// `mod.rs`, and `#[path = "..."]` overrides the location, as rustc does.
+/// Files declared under any `#[cfg]` are followed; declared modules whose
+/// file does not exist, e.g. for another platform, are skipped.
+pub fn module_files(root: &Path, root_module: &str) -> Vec<(PathBuf, String)> {
+    let mut files = Vec::new();
+    let mut seen = HashSet::new();
+    let dir = root.parent().unwrap_or(Path::new("")).to_path_buf();
+    visit_file(root, root_module.to_string(), &dir, &mut files, &mut seen);
+    files
+}
+
+/// Record `file` as `module_path` and follow its declarations, with the
+/// files of child modules looked up in `dir`
+fn visit_file(file: &Path, module_path: String, dir: &Path, files: &mut Vec<(PathBuf, String)>, seen: &mut HashSet<PathBuf>) {
+    if !seen.insert(file.canonicalize().unwrap_or_else(|_| file.to_path_buf())) {
+        return;
+    }
+    let Ok(content) = std::fs::read_to_string(file) else {
+        return;
+    };
+    files.push((file.to_path_buf(), module_path.clone()));
+
+    // A file that fails to parse is still analyzed, and reported, like any other
+    if let Ok(syntax) = syn::parse_file(&content) {
+        let file_dir = file.parent().unwrap_or(Path::new(""));
+        visit_items(&syntax.items, &module_path, dir, file_dir, files, seen);
+    }
+}
+
+/// Follow the `mod` declarations among `items`; `#[path]` is relative to
+/// `file_dir` outside inline modules and to `dir` within them
+fn visit_items(
+    items: &[syn::Item],
+    module_path: &str,
+    dir: &Path,
+    file_dir: &Path,
+    files: &mut Vec<(PathBuf, String)>,
+    seen: &mut HashSet<PathBuf>,
+) {
+    for item in items {
+        let syn::Item::Mod(module) = item else {
+            continue;
+        };
+        let name = module.ident.to_string();
+        let child_module = format!("{}::{}", module_path, name);
+        let path_attr = path_attribute(&module.attrs);
+
+        match &module.content {
+            Some((_, inline_items)) => {
+                let child_dir = dir.join(path_attr.as_deref().unwrap_or(&name));
+                visit_items(inline_items, &child_module, &child_dir, &child_dir, files, seen);
+            }
+            None => {
+                let candidates = match &path_attr {
+                    // A file named by `#[path]` looks up its children next to it, like a `mod.rs`
+                    Some(path) => vec![normalize(&file_dir.join(path))],
+                    None => vec![dir.join(format!("{}.rs", name)), dir.join(&name).join("mod.rs")],
+                };
+                if let Some(file) = candidates.into_iter().find(|f| f.is_file()) {
+                    let child_dir = match path_attr {
+                        Some(_) => file.parent().unwrap_or(Path::new("")).to_path_buf(),
+                        None => dir.join(&name),
+                    };
+                    visit_file(&file, child_module, &child_dir, files, seen);
+                }
+            }
+        }
+    }
+}
+
+/// `path` without `.` components, and with `..` applied where possible,
+/// so files reached through `#[path]` are reported where they are
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// The value of a `#[path = "..."]` attribute, if any
+fn path_attribute(attrs: &[syn::Attribute]) -> Option<String> {
+    attrs.iter().find(|attr| attr.path().is_ident("path")).and_then(|attr| match &attr.meta {
+        syn::Meta::NameValue(syn::MetaNameValue {
+            value: syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(path), .. }),
+            ..
+        }) => Some(path.value()),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_files_follow_mod_declarations() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        for (file, content) in [
+            ("src/lib.rs", "mod net; mod db; mod inline { mod nested; } #[cfg(windows)] mod missing;"),
+            ("src/net.rs", "mod server;"),
+            ("src/net/server.rs", "#[path = \"../platform/unix.rs\"] mod sys;"),
+            ("src/platform/unix.rs", "pub struct Fd;"),
+            ("src/db/mod.rs", "mod pool;"),
+            ("src/db/pool.rs", ""),
+            ("src/inline/nested.rs", ""),
+            ("src/bin/load-test.rs", "mod cli;"),
+            ("src/bin/cli/mod.rs", ""),
+            ("src/orphan.rs", "pub struct Orphan;"),
+            ("tests/fixtures/sample.rs", ""),
+        ] {
+            std::fs::create_dir_all(dir.join(file).parent().unwrap()).unwrap();
+            std::fs::write(dir.join(file), content).unwrap();
+        }
+
+        let roots = crate_roots(dir);
+        let files: Vec<(String, String)> = module_files(&roots[0].0, &roots[0].1)
+            .into_iter()
+            .map(|(file, module)| (file.strip_prefix(dir).unwrap().display().to_string(), module))
+            .collect();
+
+        let expected = [
+            ("src/lib.rs", "crate"),
+            ("src/net.rs", "crate::net"),
+            ("src/net/server.rs", "crate::net::server"),
+            ("src/platform/unix.rs", "crate::net::server::sys"),
+            ("src/db/mod.rs", "crate::db"),
+            ("src/db/pool.rs", "crate::db::pool"),
+            ("src/inline/nested.rs", "crate::inline::nested"),
+        ];
+        assert_eq!(files, expected.map(|(f, m)| (f.to_string(), m.to_string())));
+
+        // A binary's modules keep apart from the library's
+        assert_eq!(roots.len(), 2);
+        let bin_files: Vec<String> = module_files(&roots[1].0, &roots[1].1).into_iter().map(|(_, module)| module).collect();
+        assert_eq!(bin_files, vec!["crate::bin::load_test", "crate::bin::load_test::cli"]);
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::{AnalysisResult, Ownership, OwnershipEdge, StructInfo};
+use crate::resolve::StructIndex;
+
+/// A reference cycle among structs formed through their fields
+///
+/// Each hop lists every field of `from` holding `to`. A cycle of a single
+/// struct holds itself through several fields, e.g. `parent` and
+/// `children` of a tree node, and each field counts as a hop.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct OwnershipCycle {
+    pub structs: Vec<String>, // Qualified names, in cycle order
+    pub hops: Vec<Hop>,
+    pub weak: bool, // Some hop only holds its target through `Weak`
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Hop {
+    pub from: String,
+    pub to: String,
+    pub fields: Vec<(String, Ownership)>,
+}
+
+impl Hop {
+    /// Whether this hop keeps its target alive
+    fn is_strong(&self) -> bool {
+        self.fields.iter().any(|(_, kind)| *kind != Ownership::Weak)
+    }
+}
+
+/// Fields of `struct_info` holding other analyzed structs, and how
+///
+/// References and raw pointers do not own their target and are skipped.
+/// Inside `Rc`/`Arc` a struct is shared, inside `Weak` it is weak, even
+/// when further wrapped, e.g. `Rc<RefCell<Vec<Node>>>` is shared.
+pub fn edges(struct_info: &StructInfo, index: &StructIndex) -> Vec<OwnershipEdge> {
+    let mut edges = Vec::new();
+    for field in &struct_info.fields {
+        let Ok(ty) = syn::parse_str::<syn::Type>(&field.ty) else {
+            continue;
+        };
+        let mut targets = Vec::new();
+        collect_held(&ty, Ownership::Owned, &mut targets);
+
+        for (path, kind) in targets {
+            if let Some(target) = index.find_struct(&path, &struct_info.module_path) {
+                edges.push(OwnershipEdge {
+                    field: field.name.to_string(),
+                    target: target.qualified_name(),
+                    kind,
+                });
+            }
+        }
+    }
+    edges
+}
+
+fn collect_held(ty: &syn::Type, kind: Ownership, out: &mut Vec<(String, Ownership)>) {
+    match ty {
+        syn::Type::Path(type_path) => {
+            let path = type_path
+                .path
+                .segments
+                .iter()
+                .map(|seg| seg.ident.to_string())
+                .collect::<Vec<_>>()
+                .join("::");
+            let Some(last) = type_path.path.segments.last() else {
+                return;
+            };
+            let inner_kind = match last.ident.to_string().as_str() {
+                "Rc" | "Arc" => Ownership::Shared,
+                "Weak" => Ownership::Weak,
+                _ => kind,
+            };
+            out.push((path, kind));
+
+            if let syn::PathArguments::AngleBracketed(args) = &last.arguments {
+                for arg in &args.args {
+                    if let syn::GenericArgument::Type(inner) = arg {
+                        collect_held(inner, inner_kind, out);
+                    }
+                }
+            }
+        }
+        syn::Type::Array(array) => collect_held(&array.elem, kind, out),
+        syn::Type::Slice(slice) => collect_held(&slice.elem, kind, out),
+        syn::Type::Paren(paren) => collect_held(&paren.elem, kind, out),
+        syn::Type::Group(group) => collect_held(&group.elem, kind, out),
+        syn::Type::Tuple(tuple) => {
+            for elem in &tuple.elems {
+                collect_held(elem, kind, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Reference cycles among `results` that involve `Rc`, `Arc`, or `Weak`
+///
+/// Cycles of plain ownership cannot exist at runtime, and recursive types
+/// through `Box` or `Vec` form trees, so every cycle reported has at least
+/// one shared or weak field. A cycle is `weak` when one of its hops holds
+/// its target only through `Weak`; otherwise it can leak.
+pub fn find_cycles(results: &[AnalysisResult]) -> Vec<OwnershipCycle> {
+    let index: HashMap<String, usize> = results
+        .iter()
+        .enumerate()
+        .map(|(i, r)| (r.qualified_name(), i))
+        .collect();
+
+    // Fields grouped per (from, to) pair
+    let mut hops: BTreeMap<(usize, usize), Vec<(String, Ownership)>> = BTreeMap::new();
+    for (from, result) in results.iter().enumerate() {
+        for edge in &result.ownership {
+            if let Some(&to) = index.get(&edge.target) {
+                hops.entry((from, to)).or_default().push((edge.field.clone(), edge.kind));
+            }
+        }
+    }
+    let name = |i: usize| results[i].qualified_name();
+
+    let mut cycles = Vec::new();
+
+    // A struct holding itself through several fields
+    for (&(from, to), fields) in &hops {
+        if from == to && fields.len() > 1 {
+            let hops: Vec<Hop> = fields
+                .iter()
+                .map(|field| Hop {
+                    from: name(from),
+                    to: name(to),
+                    fields: vec![field.clone()],
+                })
+                .collect();
+            cycles.extend(cycle(vec![name(from)], hops));
+        }
+    }
+
+    // Elementary cycles through several structs, each found once from its
+    // lowest-indexed struct
+    let mut successors: Vec<Vec<usize>> = vec![Vec::new(); results.len()];
+    for &(from, to) in hops.keys() {
+        if from != to {
+            successors[from].push(to);
+        }
+    }
+    for start in 0..results.len() {
+        let mut path = vec![start];
+        let mut pending = vec![successors[start].clone().into_iter()];
+        while let Some(next) = pending.last_mut().map(Iterator::next) {
+            match next {
+                Some(node) if node == start => {
+                    let hops = path
+                        .iter()
+                        .zip(path.iter().skip(1).chain([&start]))
+                        .map(|(&from, &to)| Hop {
+                            from: name(from),
+                            to: name(to),
+                            fields: hops[&(from, to)].clone(),
+                        })
+                        .collect();
+                    cycles.extend(cycle(path.iter().map(|&i| name(i)).collect(), hops));
+                }
+                Some(node) if node > start && !path.contains(&node) => {
+                    path.push(node);
+                    pending.push(successors[node].clone().into_iter());
+                }
+                Some(_) => {}
+                None => {
+                    path.pop();
+                    pending.pop();
+                }
+            }
+        }
+    }
+
+    cycles
+}
+
+/// The cycle, unless it only consists of plain ownership
+fn cycle(structs: Vec<String>, hops: Vec<Hop>) -> Option<OwnershipCycle> {
+    let counted = hops
+        .iter()
+        .flat_map(|hop| &hop.fields)
+        .any(|(_, kind)| *kind != Ownership::Owned);
+    if !counted {
+        return None;
+    }
+
+    Some(OwnershipCycle {
+        weak: hops.iter().any(|hop| !hop.is_strong()),
+        structs,
+        hops,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::analyze_struct;
+    use crate::parser::{parse_file, ParseOptions};
+
+    #[test]
+    fn test_find_cycles_notes_weak_edges() {
+        let source = r#"
+            struct Parent { children: Vec<Rc<RefCell<Child>>> }
+            struct Child { parent: Rc<RefCell<Parent>> }
+
+            struct Document { root: Box<Element> }
+            struct Element { document: std::rc::Weak<Document>, text: Vec<Element> }
+
+            struct Node { parent: Weak<Node>, children: Vec<Rc<Node>> }
+            struct List { next: Option<Box<List>> }
+            struct Borrowed<'a> { parent: &'a Parent }
+        "#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let results: Vec<AnalysisResult> = structs.iter().map(|s| analyze_struct(s, &structs)).collect();
+
+        let element = &results[3];
+        assert_eq!(
+            element.ownership,
+            vec![
+                OwnershipEdge { field: "document".into(), target: "crate::Document".into(), kind: Ownership::Weak },
+                OwnershipEdge { field: "text".into(), target: "crate::Element".into(), kind: Ownership::Owned },
+            ]
+        );
+
+        let cycles: Vec<(Vec<String>, bool)> = find_cycles(&results)
+            .into_iter()
+            .map(|c| (c.structs, c.weak))
+            .collect();
+        assert_eq!(
+            cycles,
+            vec![
+                (vec!["crate::Node".to_string()], true),
+                (vec!["crate::Parent".to_string(), "crate::Child".to_string()], false),
+                (vec!["crate::Document".to_string(), "crate::Element".to_string()], true),
+            ]
+        );
+    }
+}
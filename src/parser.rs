@@ -1,71 +1,576 @@
-use std::collections::HashSet;
-use syn::{visit::Visit, File, ItemStruct, ItemImpl, ImplItemFn};
-use crate::models::{FieldInfo, MethodInfo, StructInfo};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::{visit::Visit, File, ItemStruct, ItemImpl, ItemTrait, ItemType, ItemUnion, TraitItemFn};
+use crate::models::{DeriveMode, EnumInfo, ExtensionTraitMode, FieldInfo, ForeignAccess, HalsteadCounts, ImplBlock, Interner, MethodInfo, MethodRole, ParsedFile, Reexport, SourceSpan, StructInfo, StructKind};
+use crate::resolve;
 
-pub struct StructVisitor {
+/// Options controlling how source files are parsed into `StructInfo`s
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParseOptions {
+    pub extension_traits: ExtensionTraitMode,
+    pub derives: DeriveMode,
+    pub skip_trivial_impls: bool, // Leave out branch-free impls of derivable traits
+    pub error_path_weight: usize, // Decision points each `?` and `return Err(..)` adds
+    pub await_weight: usize, // Decision points each `.await` adds
+}
+
+/// A locally defined trait whose only implementation targets a single struct
+#[derive(Clone)]
+struct ExtensionTrait {
+    target: String,
+    provided_methods: Vec<TraitItemFn>, // Methods with a default body in the trait
+}
+
+/// Declarations gathered in a pre-pass over a file, needed to attach impl blocks
+#[derive(Default)]
+struct Declarations {
+    aliases: HashMap<String, String>, // Type alias name -> aliased type name
+    extension_traits: HashMap<String, ExtensionTrait>,
+}
+
+/// The two passes of `StructVisitor` over a crate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pass {
+    Definitions, // Structs, enums, and re-exports of every file
+    Impls,       // Impl blocks, attached to the structs of any file
+}
+
+struct StructVisitor {
     pub structs: Vec<StructInfo>,
+    pub enums: Vec<EnumInfo>,
+    pub reexports: Vec<Reexport>,
+    pub aliases: Vec<Reexport>, // Type aliases and private `use ... as` renames
+    struct_ids: HashMap<String, Vec<usize>>, // Struct name -> positions in `structs`
+    interner: Interner, // Field names and types, shared by the structs and methods using them
     current_struct: Option<String>,
+    module_stack: Vec<String>, // Enclosing modules, starting with `crate`
+    file_path: String, // File being visited
+    imports: HashMap<String, String>, // `module::Name` -> imported path, from every `use`
+    uses: Vec<Reexport>, // Every `use`, globs included, as `module::Name` -> imported path
+    pass: Pass,
+    declarations: Declarations,
+    options: ParseOptions,
 }
 
 impl StructVisitor {
-    pub fn new() -> Self {
+    fn new() -> Self {
         Self {
             structs: Vec::new(),
+            enums: Vec::new(),
+            reexports: Vec::new(),
+            aliases: Vec::new(),
+            struct_ids: HashMap::new(),
+            interner: Interner::default(),
             current_struct: None,
+            module_stack: vec!["crate".to_string()],
+            file_path: String::new(),
+            imports: HashMap::new(),
+            uses: Vec::new(),
+            pass: Pass::Definitions,
+            declarations: Declarations::default(),
+            options: ParseOptions::default(),
+        }
+    }
+
+    fn with_declarations(declarations: Declarations, options: ParseOptions) -> Self {
+        Self {
+            declarations,
+            options,
+            ..Self::new()
+        }
+    }
+
+    /// Visit `source` in the current pass
+    fn visit_source(&mut self, source: &SourceFile) {
+        self.module_stack = crate::resolve::split_path(&source.module_path);
+        self.file_path = source.file_path.clone();
+        self.visit_file(&source.syntax);
+    }
+
+    /// The struct an impl block written in the current module targets
+    ///
+    /// `path` is the target as written, e.g. `Engine` or `super::Engine`.
+    /// The struct it names through the module path or a `use` import wins,
+    /// then one of the same file, then the only struct of that name.
+    fn find_impl_target(&self, path: &str) -> Option<usize> {
+        let module_path = self.module_stack.join("::");
+        let name = crate::resolve::split_path(path).pop()?;
+        let expected = match self.imports.get(&format!("{}::{}", module_path, path)) {
+            Some(imported) => imported.clone(),
+            None if path.contains("::") => crate::resolve::resolve_path(path, &module_path),
+            None => format!("{}::{}", module_path, name),
+        };
+        let candidates = self.struct_ids.get(&name).map_or(&[][..], Vec::as_slice);
+
+        candidates
+            .iter()
+            .find(|&&i| self.structs[i].qualified_name() == expected)
+            .or_else(|| candidates.iter().find(|&&i| self.structs[i].file_path == self.file_path))
+            .or(match candidates {
+                [only] => Some(only),
+                _ => None,
+            })
+            .copied()
+    }
+
+    /// Follow type aliases until reaching a name that is not an alias
+    fn resolve_alias(&self, name: &str) -> String {
+        let mut resolved = name.to_string();
+        let mut seen = HashSet::new();
+
+        while let Some(target) = self.declarations.aliases.get(&resolved) {
+            if !seen.insert(resolved.clone()) {
+                break; // Alias cycle, give up
+            }
+            resolved = target.clone();
         }
+
+        resolved
     }
 }
 
-impl<'ast> Visit<'ast> for StructVisitor {
+/// Collects type aliases, trait definitions, and trait impl targets.
+///
+/// Aliases let impl blocks written against `type Alias = Target<...>;` be
+/// attached to the aliased struct. Traits and their impl targets are used to
+/// recognize extension traits.
+#[derive(Default)]
+struct DeclarationCollector {
+    aliases: HashMap<String, String>,
+    traits: HashMap<String, ItemTrait>,
+    trait_impl_targets: HashMap<String, Vec<Option<String>>>, // None = blanket or non-path impl
+}
+
+impl DeclarationCollector {
+    fn into_declarations(self, options: &ParseOptions) -> Declarations {
+        let mut declarations = Declarations {
+            aliases: self.aliases,
+            extension_traits: HashMap::new(),
+        };
+
+        if options.extension_traits == ExtensionTraitMode::Off {
+            return declarations;
+        }
+
+        for (trait_name, item_trait) in self.traits {
+            let Some(targets) = self.trait_impl_targets.get(&trait_name) else {
+                continue;
+            };
+
+            // Only a trait implemented for exactly one concrete type extends that type
+            let [Some(target)] = targets.as_slice() else {
+                continue;
+            };
+
+            let provided_methods = item_trait
+                .items
+                .iter()
+                .filter_map(|item| match item {
+                    syn::TraitItem::Fn(method) if method.default.is_some() => Some(method.clone()),
+                    _ => None,
+                })
+                .collect();
+
+            declarations.extension_traits.insert(
+                trait_name,
+                ExtensionTrait {
+                    target: target.clone(),
+                    provided_methods,
+                },
+            );
+        }
+
+        declarations
+    }
+}
+
+impl<'ast> Visit<'ast> for DeclarationCollector {
+    fn visit_item_type(&mut self, node: &'ast ItemType) {
+        if let syn::Type::Path(type_path) = &*node.ty {
+            if let Some(seg) = type_path.path.segments.last() {
+                self.aliases.insert(node.ident.to_string(), seg.ident.to_string());
+            }
+        }
+        syn::visit::visit_item_type(self, node);
+    }
+
+    fn visit_item_trait(&mut self, node: &'ast ItemTrait) {
+        self.traits.insert(node.ident.to_string(), node.clone());
+        syn::visit::visit_item_trait(self, node);
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast ItemImpl) {
+        if let Some((_, path, _)) = &node.trait_ {
+            if let Some(seg) = path.segments.last() {
+                // `impl<T: Bound> Ext for T` has no single concrete target
+                let is_generic_param = |name: &String| {
+                    node.generics
+                        .type_params()
+                        .any(|param| param.ident == name.as_str())
+                };
+                let target = impl_target_name(&node.self_ty).filter(|name| !is_generic_param(name));
+
+                self.trait_impl_targets
+                    .entry(seg.ident.to_string())
+                    .or_default()
+                    .push(target);
+            }
+        }
+        syn::visit::visit_item_impl(self, node);
+    }
+}
+
+/// Name of the type an impl block targets, looking through references
+fn impl_target_name(ty: &syn::Type) -> Option<String> {
+    match ty {
+        syn::Type::Path(type_path) => type_path.path.segments.last().map(|seg| seg.ident.to_string()),
+        syn::Type::Reference(reference) => impl_target_name(&reference.elem),
+        syn::Type::Paren(paren) => impl_target_name(&paren.elem),
+        syn::Type::Group(group) => impl_target_name(&group.elem),
+        _ => None,
+    }
+}
+
+/// Names through which parsing a file can depend on the other files of
+/// its crate, as the impl blocks of one file attach to the structs of
+/// another through names, type aliases, and extension traits
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Links {
+    pub names: BTreeSet<String>, // Structs and unions, type aliases and their targets, impl targets
+    pub traits: BTreeSet<String>, // Traits declared
+    pub impl_traits: BTreeSet<String>, // Traits implemented, which only link files declaring them
+}
+
+impl<'ast> Visit<'ast> for Links {
     fn visit_item_struct(&mut self, node: &'ast ItemStruct) {
-        let struct_name = node.ident.to_string();
-        let mut fields = Vec::new();
-
-        for field in &node.fields {
-            if let Some(ident) = &field.ident {
-                // Extract the type tokens properly
-                let ty = &field.ty;
-                let type_str = quote::quote!(#ty).to_string();
-                fields.push(FieldInfo {
-                    name: ident.to_string(),
-                    ty: type_str,
-                });
+        self.names.insert(node.ident.to_string());
+        syn::visit::visit_item_struct(self, node);
+    }
+
+    fn visit_item_union(&mut self, node: &'ast ItemUnion) {
+        self.names.insert(node.ident.to_string());
+        syn::visit::visit_item_union(self, node);
+    }
+
+    fn visit_item_type(&mut self, node: &'ast ItemType) {
+        self.names.insert(node.ident.to_string());
+        self.names.extend(impl_target_name(&node.ty));
+        syn::visit::visit_item_type(self, node);
+    }
+
+    fn visit_item_trait(&mut self, node: &'ast ItemTrait) {
+        self.traits.insert(node.ident.to_string());
+        syn::visit::visit_item_trait(self, node);
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast ItemImpl) {
+        self.names.extend(impl_target_name(&node.self_ty));
+        if let Some(seg) = node.trait_.as_ref().and_then(|(_, path, _)| path.segments.last()) {
+            self.impl_traits.insert(seg.ident.to_string());
+        }
+        syn::visit::visit_item_impl(self, node);
+    }
+}
+
+impl StructVisitor {
+    fn record_struct<'a>(
+        &mut self,
+        name: String,
+        kind: StructKind,
+        span: SourceSpan,
+        sloc: usize,
+        item: (&syn::Visibility, &[syn::Attribute], &syn::Generics),
+        fields: impl IntoIterator<Item = &'a syn::Field>,
+    ) {
+        let (vis, attrs, generics) = item;
+        let mut field_infos = Vec::new();
+        let mut dyn_counter = DynCounter::default();
+        let mut type_refs = TypeRefCollector::default();
+        type_refs.visit_generics(generics);
+        let mut pub_fields = 0;
+
+        for (index, field) in fields.into_iter().enumerate() {
+            dyn_counter.visit_type(&field.ty);
+            type_refs.visit_type(&field.ty);
+            let is_pub = is_public(&field.vis);
+            if is_pub {
+                pub_fields += 1;
             }
+            // Positional fields of tuple structs are named by index, as in `self.0`
+            let name = field.ident.as_ref().map_or_else(|| index.to_string(), ToString::to_string);
+            let ty = &field.ty;
+            field_infos.push(FieldInfo {
+                name: self.interner.intern(&name),
+                ty: self.interner.intern(&quote::quote!(#ty).to_string()),
+                is_pub,
+            });
         }
 
+        // Derives stay out of `traits`, apart from CBO
+        let derives = match self.options.derives {
+            DeriveMode::Off => Vec::new(),
+            DeriveMode::On | DeriveMode::List => derived_traits(attrs),
+        };
+
+        // Field types count through `extract_all_types`; only their traits are kept
+        let mut referenced_traits: Vec<String> = type_refs.traits.into_iter().collect();
+        referenced_traits.sort();
+
+        self.struct_ids.entry(name.clone()).or_default().push(self.structs.len());
         self.structs.push(StructInfo {
-            name: struct_name.clone(),
-            fields,
+            name,
+            kind,
+            module_path: self.module_stack.join("::"),
+            file_path: self.file_path.clone(),
+            span,
+            sloc,
+            is_pub: is_public(vis),
+            non_exhaustive: is_non_exhaustive(attrs),
+            generic_params: generics.type_params().count() + generics.const_params().count(),
+            type_params: generics.type_params().map(|param| param.ident.to_string()).collect(),
+            pub_fields,
+            fields: field_infos,
             methods: Vec::new(),
+            impl_blocks: Vec::new(),
             external_types: Vec::new(),
             traits: Vec::new(),
+            derives,
+            referenced_traits,
+            crate_traits: Vec::new(),
+            dyn_dispatch: dyn_counter.count,
+            reexported_as: Vec::new(),
+            aliases: Vec::new(),
+            imports: Vec::new(),
         });
+    }
+}
+
+impl<'ast> Visit<'ast> for StructVisitor {
+    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+        self.module_stack.push(node.ident.to_string());
+        syn::visit::visit_item_mod(self, node);
+        self.module_stack.pop();
+    }
+
+    fn visit_item_struct(&mut self, node: &'ast ItemStruct) {
+        if self.pass != Pass::Definitions {
+            return syn::visit::visit_item_struct(self, node);
+        }
+        let struct_name = node.ident.to_string();
+        let item = (&node.vis, node.attrs.as_slice(), &node.generics);
+        let sloc = definition_sloc(node, |bare| bare.attrs.clear());
+        self.record_struct(struct_name.clone(), StructKind::Struct, source_span(node), sloc, item, &node.fields);
 
         self.current_struct = Some(struct_name);
         syn::visit::visit_item_struct(self, node);
         self.current_struct = None;
     }
 
+    fn visit_item_union(&mut self, node: &'ast ItemUnion) {
+        if self.pass != Pass::Definitions {
+            return syn::visit::visit_item_union(self, node);
+        }
+        // Unions have named fields and impl blocks just like structs
+        let union_name = node.ident.to_string();
+        let item = (&node.vis, node.attrs.as_slice(), &node.generics);
+        let sloc = definition_sloc(node, |bare| bare.attrs.clear());
+        self.record_struct(union_name.clone(), StructKind::Union, source_span(node), sloc, item, &node.fields.named);
+
+        self.current_struct = Some(union_name);
+        syn::visit::visit_item_union(self, node);
+        self.current_struct = None;
+    }
+
+    fn visit_item_enum(&mut self, node: &'ast syn::ItemEnum) {
+        if self.pass == Pass::Definitions && is_public(&node.vis) {
+            self.enums.push(EnumInfo {
+                name: node.ident.to_string(),
+                module_path: self.module_stack.join("::"),
+                file_path: self.file_path.clone(),
+                variants: node.variants.len(),
+                non_exhaustive: is_non_exhaustive(&node.attrs),
+            });
+        }
+        syn::visit::visit_item_enum(self, node);
+    }
+
+    fn visit_item_use(&mut self, node: &'ast syn::ItemUse) {
+        if self.pass == Pass::Definitions {
+            let module_path = self.module_stack.join("::");
+            let mut imports = Vec::new();
+            flatten_use_tree(&node.tree, &mut Vec::new(), &mut imports);
+
+            for (segments, alias) in imports {
+                let renamed = segments.last() != Some(&alias);
+                let path = format!("{}::{}", module_path, alias);
+                let target = resolve_use_path(&segments, &module_path);
+                self.imports.insert(path.clone(), target.clone());
+                self.uses.push(Reexport { path: path.clone(), target: target.clone() });
+
+                // Only `pub use` (including restricted visibility) re-exports an item
+                if !matches!(node.vis, syn::Visibility::Inherited) {
+                    self.reexports.push(Reexport { path, target });
+                } else if renamed {
+                    self.aliases.push(Reexport { path, target });
+                }
+            }
+        }
+        syn::visit::visit_item_use(self, node);
+    }
+
+    fn visit_item_type(&mut self, node: &'ast ItemType) {
+        if self.pass == Pass::Definitions {
+            if let syn::Type::Path(type_path) = &*node.ty {
+                if type_path.qself.is_none() {
+                    let module_path = self.module_stack.join("::");
+                    let mut segments: Vec<String> = type_path.path.segments.iter().map(|seg| seg.ident.to_string()).collect();
+                    // A first segment imported by an earlier `use`, e.g. `Customer` or `model` of `model::Customer`
+                    if let Some(imported) = self.imports.get(&format!("{}::{}", module_path, segments[0])) {
+                        segments.splice(..1, crate::resolve::split_path(imported));
+                    }
+                    self.aliases.push(Reexport {
+                        path: format!("{}::{}", module_path, node.ident),
+                        target: resolve_use_path(&segments, &module_path),
+                    });
+                }
+            }
+        }
+        syn::visit::visit_item_type(self, node);
+    }
+
     fn visit_item_impl(&mut self, node: &'ast ItemImpl) {
+        if self.pass != Pass::Impls {
+            return syn::visit::visit_item_impl(self, node);
+        }
         let trait_name = node.trait_.as_ref().map(|(_, path, _)| {
             quote::quote!(#path).to_string()
         });
+        let extension = node
+            .trait_
+            .as_ref()
+            .and_then(|(_, path, _)| path.segments.last())
+            .and_then(|seg| self.declarations.extension_traits.get(&seg.ident.to_string()))
+            .cloned();
 
-        if let syn::Type::Path(type_path) = &*node.self_ty {
-            if let Some(seg) = type_path.path.segments.last() {
-                let struct_name = seg.ident.to_string();
+        // Extension traits are attributed to the struct they extend, even
+        // when implemented for `&Struct`
+        let target_name = match &extension {
+            Some(ext) => Some(ext.target.clone()),
+            None => match &*node.self_ty {
+                syn::Type::Path(type_path) => type_path.path.segments.last().map(|seg| seg.ident.to_string()),
+                _ => None,
+            },
+        };
+
+        if let Some(target_name) = target_name {
+            let struct_name = self.resolve_alias(&target_name);
+
+            // The target as written, e.g. `super::Engine`, unless it was an alias
+            let written = match &*node.self_ty {
+                syn::Type::Path(type_path) => type_path
+                    .path
+                    .segments
+                    .iter()
+                    .map(|seg| seg.ident.to_string())
+                    .collect::<Vec<_>>()
+                    .join("::"),
+                _ => String::new(),
+            };
+            let target_path = if written.rsplit("::").next() == Some(struct_name.as_str()) {
+                written
+            } else {
+                struct_name.clone()
+            };
+            let target = self.find_impl_target(&target_path);
+
+            // `cargo expand` output spells derives out as impls; they stay
+            // derives, whose generated methods never count
+            if is_automatically_derived(&node.attrs) {
+                let derived = node.trait_.as_ref().and_then(|(_, path, _)| path.segments.last()).map(|seg| seg.ident.to_string());
+                if let (Some(struct_info), Some(derived)) = (target.map(|i| &mut self.structs[i]), derived) {
+                    // The marker impls `derive(PartialEq)` and `derive(Eq)` emit alongside are not derives of their own
+                    if self.options.derives != DeriveMode::Off
+                        && !derived.starts_with("Structural")
+                        && !struct_info.derives.contains(&derived)
+                    {
+                        struct_info.derives.push(derived);
+                    }
+                }
+                return;
+            }
+
+            // The trait of a trivial impl still couples, but its boilerplate
+            // methods don't count
+            if self.options.skip_trivial_impls && is_trivial_impl(node) {
+                if let Some(struct_info) = target.map(|i| &mut self.structs[i]) {
+                    struct_info.traits.extend(trait_name);
+                }
+                syn::visit::visit_item_impl(self, node);
+                return;
+            }
 
-                // Find the struct in our list
-                if let Some(struct_info) = self.structs.iter_mut().find(|s| s.name == struct_name) {
-                    // If this is a trait impl, record the trait
-                    if let Some(trait_str) = trait_name {
-                        struct_info.traits.push(trait_str);
+            // Find the struct in our list
+            if let Some(struct_info) = target.map(|i| &mut self.structs[i]) {
+                // If this is a trait impl, record the trait
+                let label = match &trait_name {
+                    Some(trait_str) => format!("impl {}", trait_str.replace(' ', "")),
+                    None => {
+                        let inherent = struct_info.impl_blocks.iter().filter(|b| b.label.starts_with("impl #")).count();
+                        format!("impl #{}", inherent + 1)
                     }
+                };
+                let impl_block = struct_info.impl_blocks.len();
+                struct_info.impl_blocks.push(ImplBlock {
+                    label,
+                    span: source_span(node),
+                });
+                if let Some(trait_str) = trait_name {
+                    struct_info.traits.push(trait_str);
+                }
+                let mut bounds = TypeRefCollector::default();
+                bounds.visit_generics(&node.generics);
+                record_referenced_traits(bounds.traits, struct_info);
+
+                // Associated types, to resolve `Result<T, Self::Err>`
+                let associated: HashMap<String, String> = node
+                    .items
+                    .iter()
+                    .filter_map(|item| match item {
+                        syn::ImplItem::Type(assoc) => {
+                            let ty = &assoc.ty;
+                            Some((format!("Self::{}", assoc.ident), quote::quote!(#ty).to_string().replace(' ', "")))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+
+                // Process methods for both direct impl and trait impl
+                let mut implemented = HashSet::new();
+                for item in &node.items {
+                    if let syn::ImplItem::Fn(method) = item {
+                        implemented.insert(method.sig.ident.to_string());
+                        record_signature_refs(&method.sig, struct_info, &mut self.interner);
+                        let mut method_info = analyze_method(&method.sig, &method.block, source_span(method), struct_info, &mut self.interner, &self.options);
+                        method_info.impl_block = impl_block;
+                        method_info.is_pub = node.trait_.is_none() && is_public(&method.vis);
+                        if let Some(resolved) = method_info.error_type.as_ref().and_then(|e| associated.get(e)) {
+                            method_info.error_type = Some(resolved.clone());
+                        }
+                        struct_info.methods.push(method_info);
+                    }
+                }
 
-                    // Process methods for both direct impl and trait impl
-                    for item in &node.items {
-                        if let syn::ImplItem::Fn(method) = item {
-                            let method_info = analyze_method(method, struct_info);
+                // Default methods of an extension trait also extend the struct
+                if let Some(ext) = &extension {
+                    for method in &ext.provided_methods {
+                        if implemented.contains(&method.sig.ident.to_string()) {
+                            continue;
+                        }
+                        if let Some(block) = &method.default {
+                            record_signature_refs(&method.sig, struct_info, &mut self.interner);
+                            let mut method_info = analyze_method(&method.sig, block, source_span(method), struct_info, &mut self.interner, &self.options);
+                            method_info.impl_block = impl_block;
                             struct_info.methods.push(method_info);
                         }
                     }
@@ -77,193 +582,1982 @@ impl<'ast> Visit<'ast> for StructVisitor {
     }
 }
 
-fn analyze_method(method: &ImplItemFn, struct_info: &StructInfo) -> MethodInfo {
-    let mut fields_accessed = HashSet::new();
-    let mut external_types = HashSet::new();
-
-    // Analyze method body for field access
-    analyze_expr(&method.block, struct_info, &mut fields_accessed, &mut external_types);
+/// Traits `#[derive(...)]` can implement
+const DERIVABLE_TRAITS: &[&str] = &["Clone", "Copy", "Debug", "Default", "PartialEq", "Eq", "PartialOrd", "Ord", "Hash"];
 
-    // Calculate cyclomatic complexity (basic version)
-    let cyclomatic_complexity = calculate_cyclomatic_complexity(&method.block);
-
-    MethodInfo {
-        fields_accessed: fields_accessed.into_iter().collect(),
-        cyclomatic_complexity,
-    }
+/// An impl generated by `#[derive(...)]`, as marked in expanded source
+fn is_automatically_derived(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| attr.path().is_ident("automatically_derived"))
 }
 
-fn analyze_expr(
-    expr: &syn::Block,
-    struct_info: &StructInfo,
-    fields_accessed: &mut HashSet<String>,
-    external_types: &mut HashSet<String>,
-) {
-    for stmt in &expr.stmts {
-        analyze_stmt(stmt, struct_info, fields_accessed, external_types);
-    }
+/// A hand-written impl of a derivable trait whose methods have no branches,
+/// i.e. what `#[derive(...)]` could have generated
+fn is_trivial_impl(node: &ItemImpl) -> bool {
+    let Some(seg) = node.trait_.as_ref().and_then(|(_, path, _)| path.segments.last()) else {
+        return false;
+    };
+    DERIVABLE_TRAITS.contains(&seg.ident.to_string().as_str())
+        && node.items.iter().all(|item| match item {
+            syn::ImplItem::Fn(method) => calculate_cyclomatic_complexity(&method.block) == 1,
+            _ => true,
+        })
 }
 
-fn analyze_stmt(
-    stmt: &syn::Stmt,
-    struct_info: &StructInfo,
-    fields_accessed: &mut HashSet<String>,
-    external_types: &mut HashSet<String>,
+/// Flatten a use tree into `(path segments, imported name)` pairs
+///
+/// e.g. `a::{B, c::D as E, f::*}` yields `([a, B], B)`, `([a, c, D], E)` and
+/// `([a, f, *], *)`
+fn flatten_use_tree(
+    tree: &syn::UseTree,
+    prefix: &mut Vec<String>,
+    out: &mut Vec<(Vec<String>, String)>,
 ) {
-    match stmt {
-        syn::Stmt::Local(local) => {
-            if let Some(init) = &local.init {
-                analyze_expr_expr(&init.expr, struct_info, fields_accessed, external_types);
+    match tree {
+        syn::UseTree::Path(path) => {
+            prefix.push(path.ident.to_string());
+            flatten_use_tree(&path.tree, prefix, out);
+            prefix.pop();
+        }
+        syn::UseTree::Name(name) => {
+            // `use a::{self}` imports the module itself, never a struct
+            if name.ident != "self" {
+                let mut segments = prefix.clone();
+                segments.push(name.ident.to_string());
+                out.push((segments, name.ident.to_string()));
             }
         }
-        syn::Stmt::Expr(expr, _) => {
-            analyze_expr_expr(expr, struct_info, fields_accessed, external_types);
+        syn::UseTree::Rename(rename) => {
+            let mut segments = prefix.clone();
+            segments.push(rename.ident.to_string());
+            out.push((segments, rename.rename.to_string()));
+        }
+        syn::UseTree::Glob(_) => {
+            let mut segments = prefix.clone();
+            segments.push("*".to_string());
+            out.push((segments, "*".to_string()));
+        }
+        syn::UseTree::Group(group) => {
+            for item in &group.items {
+                flatten_use_tree(item, prefix, out);
+            }
         }
-        _ => {}
     }
 }
 
-fn analyze_expr_expr(
-    expr: &syn::Expr,
-    struct_info: &StructInfo,
-    fields_accessed: &mut HashSet<String>,
-    external_types: &mut HashSet<String>,
-) {
-    match expr {
-        syn::Expr::Field(field_expr) => {
-            // Check if accessing self.field
-            if let syn::Expr::Path(path) = &*field_expr.base {
-                if path.path.is_ident("self") {
-                    if let syn::Member::Named(ident) = &field_expr.member {
-                        fields_accessed.insert(ident.to_string());
-                    }
-                }
+/// Resolve the path of a `use` item declared in `module_path`
+///
+/// Since the 2018 edition, use paths not starting with `crate`, `self` or
+/// `super` are relative to the current module (or name an external crate,
+/// which never matches an analyzed struct).
+fn resolve_use_path(segments: &[String], module_path: &str) -> String {
+    let path = segments.join("::");
+    match segments.first().map(String::as_str) {
+        Some("crate" | "self" | "super") => crate::resolve::resolve_path(&path, module_path),
+        _ => format!("{}::{}", module_path, path),
+    }
+}
+
+/// Collects the traits a type or signature refers to, through `impl Trait`,
+/// `dyn Trait`, and generic bounds, and every type path it names, e.g.
+/// `Vec` and `Order` of `Vec<Order>`
+#[derive(Default)]
+struct TypeRefCollector {
+    traits: HashSet<String>,
+    types: HashSet<String>,
+}
+
+impl TypeRefCollector {
+    /// The generic bounds, parameter types, and return type of `sig`
+    fn visit_param_and_return_types(&mut self, sig: &syn::Signature) {
+        self.visit_generics(&sig.generics);
+        for input in &sig.inputs {
+            if let syn::FnArg::Typed(arg) = input {
+                self.visit_type(&arg.ty);
             }
         }
-        syn::Expr::MethodCall(call) => {
-            analyze_expr_expr(&call.receiver, struct_info, fields_accessed, external_types);
-            for arg in &call.args {
-                analyze_expr_expr(arg, struct_info, fields_accessed, external_types);
-            }
+        if let syn::ReturnType::Type(_, ty) = &sig.output {
+            self.visit_type(ty);
         }
-        syn::Expr::Call(call) => {
-            analyze_expr_expr(&call.func, struct_info, fields_accessed, external_types);
-            for arg in &call.args {
-                analyze_expr_expr(arg, struct_info, fields_accessed, external_types);
+    }
+}
+
+impl<'ast> Visit<'ast> for TypeRefCollector {
+    fn visit_trait_bound(&mut self, node: &'ast syn::TraitBound) {
+        if let (syn::TraitBoundModifier::None, Some(seg)) = (&node.modifier, node.path.segments.last()) {
+            let name = seg.ident.to_string();
+            // Closure and auto traits are language plumbing; the argument types of closures still count
+            if !matches!(name.as_str(), "Fn" | "FnMut" | "FnOnce" | "Send" | "Sync" | "Sized" | "Unpin") {
+                self.traits.insert(name);
             }
         }
-        syn::Expr::Binary(bin) => {
-            analyze_expr_expr(&bin.left, struct_info, fields_accessed, external_types);
-            analyze_expr_expr(&bin.right, struct_info, fields_accessed, external_types);
-        }
-        syn::Expr::Unary(unary) => {
-            analyze_expr_expr(&unary.expr, struct_info, fields_accessed, external_types);
-        }
-        syn::Expr::Reference(ref_expr) => {
-            analyze_expr_expr(&ref_expr.expr, struct_info, fields_accessed, external_types);
-        }
-        syn::Expr::Block(block) => {
-            analyze_expr(&block.block, struct_info, fields_accessed, external_types);
+
+        syn::visit::visit_trait_bound(self, node);
+    }
+
+    fn visit_type_path(&mut self, node: &'ast syn::TypePath) {
+        if node.qself.is_none() && !node.path.is_ident("Self") {
+            self.types.insert(node.path.segments.iter().map(|seg| seg.ident.to_string()).collect::<Vec<_>>().join("::"));
         }
-        syn::Expr::If(if_expr) => {
-            analyze_expr_expr(&if_expr.cond, struct_info, fields_accessed, external_types);
-            analyze_expr(&if_expr.then_branch, struct_info, fields_accessed, external_types);
-            if let Some((_, else_branch)) = &if_expr.else_branch {
-                analyze_expr_expr(else_branch, struct_info, fields_accessed, external_types);
-            }
+        syn::visit::visit_type_path(self, node);
+    }
+}
+
+/// Counts `dyn Trait` types, e.g. in `Box<dyn Handler>` or `&dyn Display`
+#[derive(Default)]
+struct DynCounter {
+    count: usize,
+}
+
+impl<'ast> Visit<'ast> for DynCounter {
+    fn visit_type_trait_object(&mut self, node: &'ast syn::TypeTraitObject) {
+        if node.dyn_token.is_some() {
+            self.count += 1;
         }
-        syn::Expr::While(while_expr) => {
-            analyze_expr_expr(&while_expr.cond, struct_info, fields_accessed, external_types);
-            analyze_expr(&while_expr.body, struct_info, fields_accessed, external_types);
+        syn::visit::visit_type_trait_object(self, node);
+    }
+}
+
+/// Record the parameter and return types of a method as references of the
+/// struct, e.g. `Order` of `fn add(&mut self, order: &Order)`, along with
+/// the traits of its `impl Trait` and `dyn Trait` types and generic bounds,
+/// e.g. `-> impl Responder` or `<V: Validator>`, and count its `dyn Trait`
+/// types
+fn record_signature_refs(sig: &syn::Signature, struct_info: &mut StructInfo, interner: &mut Interner) {
+    let mut collector = TypeRefCollector::default();
+    collector.visit_param_and_return_types(sig);
+
+    let mut dyn_counter = DynCounter::default();
+    dyn_counter.visit_signature(sig);
+    struct_info.dyn_dispatch += dyn_counter.count;
+
+    record_referenced_traits(collector.traits, struct_info);
+    for type_name in collector.types {
+        if !struct_info.external_types.iter().any(|known| **known == type_name) {
+            struct_info.external_types.push(interner.intern(&type_name));
         }
-        syn::Expr::ForLoop(for_expr) => {
-            analyze_expr_expr(&for_expr.expr, struct_info, fields_accessed, external_types);
-            analyze_expr(&for_expr.body, struct_info, fields_accessed, external_types);
+    }
+}
+
+/// Record traits the struct refers to, keeping the first occurrence of each
+fn record_referenced_traits(traits: impl IntoIterator<Item = String>, struct_info: &mut StructInfo) {
+    let mut traits: Vec<String> = traits.into_iter().collect();
+    // Sorted, since collectors gather them unordered
+    traits.sort();
+    for trait_name in traits {
+        if !struct_info.referenced_traits.contains(&trait_name) {
+            struct_info.referenced_traits.push(trait_name);
         }
-        syn::Expr::Match(match_expr) => {
-            analyze_expr_expr(&match_expr.expr, struct_info, fields_accessed, external_types);
-            for arm in &match_expr.arms {
-                if let Some((_, guard)) = &arm.guard {
-                    analyze_expr_expr(guard, struct_info, fields_accessed, external_types);
-                }
-                analyze_expr_expr(&arm.body, struct_info, fields_accessed, external_types);
-            }
+    }
+}
+
+/// Fully public, as opposed to private or `pub(crate)`-style restricted
+fn is_public(vis: &syn::Visibility) -> bool {
+    matches!(vis, syn::Visibility::Public(_))
+}
+
+fn is_non_exhaustive(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| attr.path().is_ident("non_exhaustive"))
+}
+
+/// Trait paths of every `#[derive(...)]` attribute, e.g. `serde::Serialize`
+fn derived_traits(attrs: &[syn::Attribute]) -> Vec<String> {
+    let mut traits = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("derive")) {
+        let paths = attr.parse_args_with(
+            syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated,
+        );
+        for path in paths.into_iter().flatten() {
+            traits.push(quote::quote!(#path).to_string().replace(' ', ""));
         }
-        syn::Expr::Struct(struct_expr) => {
-            let type_name = quote::quote!(#struct_expr.path).to_string();
-            if !struct_info.fields.iter().any(|f| type_name.contains(&f.name)) {
-                external_types.insert(type_name);
-            }
-            for field in &struct_expr.fields {
-                analyze_expr_expr(&field.expr, struct_info, fields_accessed, external_types);
-            }
+    }
+    traits
+}
+
+/// Line range covered by a syntax node
+fn source_span(node: &impl Spanned) -> SourceSpan {
+    let span = node.span();
+    SourceSpan {
+        start_line: span.start().line,
+        end_line: span.end().line,
+    }
+}
+
+fn analyze_method(
+    sig: &syn::Signature,
+    body: &syn::Block,
+    span: SourceSpan,
+    struct_info: &StructInfo,
+    interner: &mut Interner,
+    options: &ParseOptions,
+) -> MethodInfo {
+    let mut field_collector = FieldAccessCollector::new(&struct_info.name);
+    field_collector.visit_block(body);
+
+    let mut call_collector = CallCollector::default();
+    call_collector.visit_block(body);
+
+    // `?` in a method returning `Option` short-circuits on `None`, no error
+    let returns_option = matches!(&sig.output, syn::ReturnType::Type(_, ty)
+        if matches!(&**ty, syn::Type::Path(p) if p.path.segments.last().is_some_and(|seg| seg.ident == "Option")));
+    let propagations = if returns_option { 0 } else { call_collector.propagations };
+
+    // Calculate cyclomatic complexity (basic version), with the early
+    // returns of error paths and the await points weighted per
+    // `--error-path-weight` and `--await-weight`
+    let error_paths = propagations + call_collector.error_returns;
+    let cyclomatic_complexity = calculate_cyclomatic_complexity(body)
+        + options.error_path_weight * error_paths
+        + options.await_weight * call_collector.awaits;
+    let cognitive_complexity = calculate_cognitive_complexity(body);
+    let halstead = count_halstead(body);
+    let mut lines = BTreeSet::new();
+    token_lines(sig.to_token_stream(), &mut lines);
+    token_lines(body.to_token_stream(), &mut lines);
+
+    let mut foreign = ForeignAccessCollector::new(sig, struct_info);
+    foreign.visit_block(body);
+
+    let error_type = match &sig.output {
+        syn::ReturnType::Type(_, ty) => result_error_type(ty),
+        syn::ReturnType::Default => None,
+    };
+
+    MethodInfo {
+        name: sig.ident.to_string(),
+        span,
+        fields_accessed: field_collector.fields.iter().map(|name| interner.intern(name)).collect(),
+        cyclomatic_complexity,
+        cognitive_complexity,
+        halstead,
+        sloc: lines.len(),
+        impl_block: 0,
+        calls: call_collector.calls,
+        error_type,
+        propagations,
+        error_returns: call_collector.error_returns,
+        is_async: sig.asyncness.is_some(),
+        awaits: call_collector.awaits,
+        is_pub: false,
+        params: sig
+            .inputs
+            .iter()
+            .filter(|arg| matches!(arg, syn::FnArg::Typed(_)))
+            .count(),
+        role: method_role(sig, body, &struct_info.name),
+        demeter_violations: demeter_violations(body),
+        foreign_accesses: foreign.accesses,
+        used_types: foreign.used_types,
+        foreign_data: Vec::new(),
+    }
+}
+
+/// Classify a method as an accessor, a constructor, or behavior
+///
+/// A getter takes only `self` and returns one of its fields, possibly
+/// borrowed or through a conversion without arguments such as `clone()`.
+/// A setter takes one parameter and assigns it to a field, possibly
+/// returning `self` as builders do. An associated function returning
+/// `Self` or the struct, even inside `Option` or `Result`, constructs it.
+fn method_role(sig: &syn::Signature, body: &syn::Block, struct_name: &str) -> MethodRole {
+    let params: Vec<&syn::PatType> = sig
+        .inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            syn::FnArg::Typed(typed) => Some(typed),
+            syn::FnArg::Receiver(_) => None,
+        })
+        .collect();
+
+    if sig.receiver().is_none() {
+        let returns_self = match &sig.output {
+            syn::ReturnType::Type(_, ty) => quote::quote!(#ty)
+                .into_iter()
+                .any(|token| matches!(token, proc_macro2::TokenTree::Ident(ident) if ident == "Self" || ident == struct_name)),
+            syn::ReturnType::Default => false,
+        };
+        return if returns_self { MethodRole::Constructor } else { MethodRole::Behavior };
+    }
+
+    match (body.stmts.as_slice(), params.as_slice()) {
+        ([syn::Stmt::Expr(expr, None)], []) if self_field(strip_conversions(expr)) => MethodRole::Getter,
+        ([assign] | [assign, _], [param]) if assigns_param(assign, param) && body.stmts[1..].iter().all(is_self) => {
+            MethodRole::Setter
         }
-        syn::Expr::Path(path) => {
-            let path_str = quote::quote!(#path).to_string();
-            // Check if it's a type that might be external
-            if path_str.contains("::") && !path_str.starts_with("self") && !path_str.starts_with("crate") {
-                external_types.insert(path_str);
-            }
+        _ => MethodRole::Behavior,
+    }
+}
+
+/// Whether `stmt` is `self.field = param`, possibly converted or in `Some`
+fn assigns_param(stmt: &syn::Stmt, param: &syn::PatType) -> bool {
+    let syn::Stmt::Expr(syn::Expr::Assign(assign), Some(_)) = stmt else {
+        return false;
+    };
+    let syn::Pat::Ident(name) = &*param.pat else {
+        return false;
+    };
+    let mut value = strip_conversions(&assign.right);
+    if let syn::Expr::Call(call) = value {
+        if matches!(&*call.func, syn::Expr::Path(path) if path.path.is_ident("Some")) && call.args.len() == 1 {
+            value = strip_conversions(&call.args[0]);
         }
-        _ => {}
     }
+    self_field(&assign.left) && matches!(value, syn::Expr::Path(path) if path.path.is_ident(&name.ident))
 }
 
-fn calculate_cyclomatic_complexity(block: &syn::Block) -> usize {
-    let mut complexity = 1; // Base complexity
+/// Whether `stmt` is a trailing `self`
+fn is_self(stmt: &syn::Stmt) -> bool {
+    matches!(stmt, syn::Stmt::Expr(syn::Expr::Path(path), None) if path.path.is_ident("self"))
+}
 
-    for stmt in &block.stmts {
-        complexity += stmt_complexity(stmt);
+/// `expr` without borrows, parentheses, and calls without arguments,
+/// e.g. `self.name` of `&self.name.clone()`
+fn strip_conversions(mut expr: &syn::Expr) -> &syn::Expr {
+    loop {
+        expr = match expr {
+            syn::Expr::Reference(reference) => &reference.expr,
+            syn::Expr::Paren(paren) => &paren.expr,
+            syn::Expr::MethodCall(call) if call.args.is_empty() => &call.receiver,
+            _ => return expr,
+        };
     }
+}
 
-    complexity
+/// Whether `expr` is a field of `self`, e.g. `self.name` or `self.0`
+fn self_field(expr: &syn::Expr) -> bool {
+    matches!(expr, syn::Expr::Field(field) if matches!(&*field.base, syn::Expr::Path(path) if path.path.is_ident("self")))
 }
 
-fn stmt_complexity(stmt: &syn::Stmt) -> usize {
-    match stmt {
-        syn::Stmt::Expr(expr, _) => expr_complexity(expr),
-        syn::Stmt::Local(local) => {
-            if let Some(init) = &local.init {
-                expr_complexity(&init.expr)
-            } else {
-                0
-            }
+/// Error type of a `Result` return type
+///
+/// `Result<T, E>` gives `E`. Aliases without an error argument follow the
+/// `type Result<T> = Result<T, Error>` convention of their module, so
+/// `io::Result<T>` gives `io::Error`, `fmt::Result` gives `fmt::Error`, and
+/// a bare `Result<T>` gives `Error`.
+fn result_error_type(ty: &syn::Type) -> Option<String> {
+    let syn::Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segments = &type_path.path.segments;
+    let last = segments.last()?;
+    if last.ident != "Result" {
+        return None;
+    }
+
+    let type_args: Vec<&syn::Type> = match &last.arguments {
+        syn::PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    match type_args.as_slice() {
+        [_, error] => Some(quote::quote!(#error).to_string().replace(' ', "")),
+        _ => {
+            let mut path: Vec<String> = segments.iter().map(|seg| seg.ident.to_string()).collect();
+            *path.last_mut()? = "Error".to_string();
+            Some(path.join("::"))
         }
-        _ => 0,
     }
 }
 
-fn expr_complexity(expr: &syn::Expr) -> usize {
-    match expr {
-        syn::Expr::If(if_expr) => {
-            let mut complexity = 1; // if statement
-            complexity += expr_complexity(&if_expr.cond);
-            for stmt in &if_expr.then_branch.stmts {
-                complexity += stmt_complexity(stmt);
+/// Collects the distinct methods and functions called in a body, in order,
+/// and counts its `?` operators, `return Err(..)` sites, and `.await`s
+///
+/// Method calls are recorded by name (`push`), other calls by their path
+/// (`Config::new`). Calls of closures, e.g. `(self.handler)()`, are skipped.
+/// The arguments of expression macros such as `write!` count too.
+#[derive(Default)]
+struct CallCollector {
+    calls: Vec<String>,
+    propagations: usize, // `?` operators
+    error_returns: usize, // `return Err(..)` expressions
+    awaits: usize, // `.await` expressions, including those of async blocks
+}
+
+impl CallCollector {
+    fn record(&mut self, name: String) {
+        if !self.calls.contains(&name) {
+            self.calls.push(name);
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for CallCollector {
+    fn visit_expr_try(&mut self, node: &'ast syn::ExprTry) {
+        self.propagations += 1;
+        syn::visit::visit_expr_try(self, node);
+    }
+
+    fn visit_expr_await(&mut self, node: &'ast syn::ExprAwait) {
+        self.awaits += 1;
+        syn::visit::visit_expr_await(self, node);
+    }
+
+    fn visit_expr_return(&mut self, node: &'ast syn::ExprReturn) {
+        if let Some(syn::Expr::Call(call)) = node.expr.as_deref() {
+            if matches!(&*call.func, syn::Expr::Path(path) if path.path.segments.last().is_some_and(|seg| seg.ident == "Err")) {
+                self.error_returns += 1;
             }
-            if let Some((_, else_branch)) = &if_expr.else_branch {
-                complexity += expr_complexity(else_branch);
+        }
+        syn::visit::visit_expr_return(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+        self.record(node.method.to_string());
+        syn::visit::visit_expr_method_call(self, node);
+    }
+
+    fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+        if let syn::Expr::Path(path) = &*node.func {
+            let name = path
+                .path
+                .segments
+                .iter()
+                .map(|seg| seg.ident.to_string())
+                .collect::<Vec<_>>()
+                .join("::");
+            self.record(name);
+        }
+        syn::visit::visit_expr_call(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        for arg in macro_args(node) {
+            self.visit_expr(&arg);
+        }
+    }
+}
+
+/// Collects the members of other types a method body uses, and the types
+/// it calls or constructs
+///
+/// Only receivers of a known type count: parameters and `let` bindings
+/// with a type annotation (`order.total`), and fields of `self`
+/// (`self.customer.name()`). References and `Box`, `Rc`, and `Arc` are
+/// seen through, as field access auto-derefs through them. Types are
+/// used through any method call on such a receiver, an associated
+/// function call (`Config::new()`), or a struct literal.
+struct ForeignAccessCollector {
+    bindings: HashMap<String, String>, // Receiver, e.g. `order` or `self.customer`, to type path
+    accesses: Vec<ForeignAccess>,
+    used_types: Vec<String>,
+}
+
+impl ForeignAccessCollector {
+    fn new(sig: &syn::Signature, struct_info: &StructInfo) -> Self {
+        let mut bindings = HashMap::new();
+        for field in &struct_info.fields {
+            if let Some(ty) = syn::parse_str::<syn::Type>(&field.ty).ok().as_ref().and_then(held_type_path) {
+                bindings.insert(format!("self.{}", field.name), ty);
             }
-            complexity
         }
-        syn::Expr::Match(_) => 1, // match statement
-        syn::Expr::While(_) => 1, // while loop
-        syn::Expr::ForLoop(_) => 1, // for loop
-        syn::Expr::Loop(_) => 1, // loop
-        syn::Expr::Block(block) => {
-            let mut complexity = 0;
-            for stmt in &block.block.stmts {
-                complexity += stmt_complexity(stmt);
+        for input in &sig.inputs {
+            if let syn::FnArg::Typed(arg) = input {
+                if let (syn::Pat::Ident(pat), Some(ty)) = (&*arg.pat, held_type_path(&arg.ty)) {
+                    bindings.insert(pat.ident.to_string(), ty);
+                }
             }
-            complexity
         }
-        _ => 0,
+        Self {
+            bindings,
+            accesses: Vec::new(),
+            used_types: Vec::new(),
+        }
     }
-}
 
-pub fn parse_file(content: &str) -> Result<Vec<StructInfo>, syn::Error> {
-    let file: File = syn::parse_str(content)?;
-    let mut visitor = StructVisitor::new();
-    visitor.visit_file(&file);
-    Ok(visitor.structs)
+    /// Type of a receiver expression: a binding, or a field of `self`
+    fn receiver_type(&self, expr: &syn::Expr) -> Option<&String> {
+        let key = match expr {
+            syn::Expr::Path(path) => path.path.get_ident()?.to_string(),
+            syn::Expr::Field(field) => match &*field.base {
+                syn::Expr::Path(base) if base.path.is_ident("self") => {
+                    format!("self.{}", member_name(&field.member))
+                }
+                _ => return None,
+            },
+            _ => return None,
+        };
+        self.bindings.get(&key)
+    }
+
+    fn use_type(&mut self, ty: String) {
+        if !self.used_types.contains(&ty) {
+            self.used_types.push(ty);
+        }
+    }
+
+    fn record(&mut self, receiver: &syn::Expr, member: String, call: bool) {
+        if let Some(ty) = self.receiver_type(receiver) {
+            let access = ForeignAccess {
+                ty: ty.clone(),
+                member,
+                call,
+            };
+            if !self.accesses.contains(&access) {
+                self.accesses.push(access);
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for ForeignAccessCollector {
+    fn visit_local(&mut self, node: &'ast syn::Local) {
+        if let syn::Pat::Type(typed) = &node.pat {
+            if let (syn::Pat::Ident(pat), Some(ty)) = (&*typed.pat, held_type_path(&typed.ty)) {
+                self.bindings.insert(pat.ident.to_string(), ty);
+            }
+        }
+        syn::visit::visit_local(self, node);
+    }
+
+    fn visit_expr_field(&mut self, node: &'ast syn::ExprField) {
+        self.record(&node.base, member_name(&node.member), false);
+        syn::visit::visit_expr_field(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+        if node.args.is_empty() {
+            self.record(&node.receiver, node.method.to_string(), true);
+        }
+        if let Some(ty) = self.receiver_type(&node.receiver).cloned() {
+            self.use_type(ty);
+        }
+        syn::visit::visit_expr_method_call(self, node);
+    }
+
+    fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+        // `Config::new()` or `crate::net::Server::bind()`, not `Self::new()` or `<T as Trait>::f()`
+        if let syn::Expr::Path(path) = &*node.func {
+            let segments: Vec<String> = path.path.segments.iter().map(|seg| seg.ident.to_string()).collect();
+            if path.qself.is_none() && segments.len() >= 2 && segments[0] != "Self" {
+                self.use_type(segments[..segments.len() - 1].join("::"));
+            }
+        }
+        syn::visit::visit_expr_call(self, node);
+    }
+
+    fn visit_expr_struct(&mut self, node: &'ast syn::ExprStruct) {
+        if node.qself.is_none() && !node.path.is_ident("Self") {
+            self.use_type(node.path.segments.iter().map(|seg| seg.ident.to_string()).collect::<Vec<_>>().join("::"));
+        }
+        syn::visit::visit_expr_struct(self, node);
+    }
+
+    fn visit_item(&mut self, _node: &'ast syn::Item) {}
+}
+
+/// Path of the type a value of type `ty` gives field access to, e.g.
+/// `Order` for `&Box<Order>`; `None` for `Self` and non-path types
+fn held_type_path(ty: &syn::Type) -> Option<String> {
+    match ty {
+        syn::Type::Reference(reference) => held_type_path(&reference.elem),
+        syn::Type::Paren(paren) => held_type_path(&paren.elem),
+        syn::Type::Group(group) => held_type_path(&group.elem),
+        syn::Type::Path(type_path) => {
+            let last = type_path.path.segments.last()?;
+            if matches!(last.ident.to_string().as_str(), "Box" | "Rc" | "Arc") {
+                if let syn::PathArguments::AngleBracketed(args) = &last.arguments {
+                    if let Some(syn::GenericArgument::Type(inner)) = args.args.first() {
+                        return held_type_path(inner);
+                    }
+                }
+            }
+            if type_path.path.is_ident("Self") {
+                return None;
+            }
+            Some(
+                type_path
+                    .path
+                    .segments
+                    .iter()
+                    .map(|seg| seg.ident.to_string())
+                    .collect::<Vec<_>>()
+                    .join("::"),
+            )
+        }
+        _ => None,
+    }
+}
+
+fn member_name(member: &syn::Member) -> String {
+    match member {
+        syn::Member::Named(ident) => ident.to_string(),
+        syn::Member::Unnamed(index) => index.index.to_string(),
+    }
+}
+
+/// Collects the fields of `self` a method body reads or writes
+///
+/// Every expression is walked, including closures, async blocks, and the
+/// arguments of `EXPRESSION_MACROS`. Chains such as `self.config.timeout`
+/// credit their first-level field, and patterns destructuring `self`, e.g.
+/// `let Self { name, .. } = self;`, the fields they bind. Items nested in
+/// the body, such as inner functions, are skipped.
+struct FieldAccessCollector<'a> {
+    struct_name: &'a str,
+    fields: HashSet<String>,
+}
+
+impl<'a> FieldAccessCollector<'a> {
+    fn new(struct_name: &'a str) -> Self {
+        Self {
+            struct_name,
+            fields: HashSet::new(),
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for FieldAccessCollector<'_> {
+    fn visit_expr_field(&mut self, node: &'ast syn::ExprField) {
+        match &*node.base {
+            syn::Expr::Path(path) if path.path.is_ident("self") => {
+                self.fields.insert(member_name(&node.member));
+            }
+            base => self.visit_expr(base),
+        }
+    }
+
+    fn visit_local(&mut self, node: &'ast syn::Local) {
+        if let Some(init) = &node.init {
+            if refers_to_self(&init.expr) {
+                destructured_fields(&node.pat, self.struct_name, &mut self.fields);
+            }
+        }
+        syn::visit::visit_local(self, node);
+    }
+
+    // `if let Self { id, .. } = self`
+    fn visit_expr_let(&mut self, node: &'ast syn::ExprLet) {
+        if refers_to_self(&node.expr) {
+            destructured_fields(&node.pat, self.struct_name, &mut self.fields);
+        }
+        syn::visit::visit_expr_let(self, node);
+    }
+
+    fn visit_expr_match(&mut self, node: &'ast syn::ExprMatch) {
+        if refers_to_self(&node.expr) {
+            for arm in &node.arms {
+                destructured_fields(&arm.pat, self.struct_name, &mut self.fields);
+            }
+        }
+        syn::visit::visit_expr_match(self, node);
+    }
+
+    // `format!("{}", self.name)`, `assert!(self.len > 0)`
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        for arg in macro_args(node) {
+            self.visit_expr(&arg);
+        }
+    }
+
+    fn visit_item(&mut self, _node: &'ast syn::Item) {}
+}
+
+/// Whether `expr` is `self`, possibly dereferenced or borrowed, e.g. `&*self`
+fn refers_to_self(expr: &syn::Expr) -> bool {
+    match expr {
+        syn::Expr::Path(path) => path.path.is_ident("self"),
+        syn::Expr::Unary(syn::ExprUnary { op: syn::UnOp::Deref(_), expr, .. }) => refers_to_self(expr),
+        syn::Expr::Reference(reference) => refers_to_self(&reference.expr),
+        syn::Expr::Paren(paren) => refers_to_self(&paren.expr),
+        _ => false,
+    }
+}
+
+/// Record the fields a pattern matched against `self` destructures, e.g.
+/// `name` and `email` of `Self { name, email, .. }` or `0` of `Self(id, _)`
+///
+/// The struct is named `Self` or by its name; fields matched by `_` or
+/// left out through `..` are not accessed.
+fn destructured_fields(pat: &syn::Pat, struct_name: &str, fields_accessed: &mut HashSet<String>) {
+    let names_struct = |path: &syn::Path| {
+        path.is_ident("Self") || path.segments.last().is_some_and(|seg| seg.ident == struct_name)
+    };
+    match pat {
+        syn::Pat::Struct(pat_struct) if names_struct(&pat_struct.path) => {
+            for field in &pat_struct.fields {
+                if matches!(*field.pat, syn::Pat::Wild(_)) {
+                    continue;
+                }
+                fields_accessed.insert(match &field.member {
+                    syn::Member::Named(ident) => ident.to_string(),
+                    syn::Member::Unnamed(index) => index.index.to_string(),
+                });
+            }
+        }
+        syn::Pat::TupleStruct(pat_tuple) if names_struct(&pat_tuple.path) => {
+            // Positions after a `..` rest pattern count from the end, so stop there
+            for (index, elem) in pat_tuple.elems.iter().enumerate() {
+                match elem {
+                    syn::Pat::Rest(_) => break,
+                    syn::Pat::Wild(_) => {}
+                    _ => {
+                        fields_accessed.insert(index.to_string());
+                    }
+                }
+            }
+        }
+        syn::Pat::Reference(reference) => destructured_fields(&reference.pat, struct_name, fields_accessed),
+        syn::Pat::Paren(paren) => destructured_fields(&paren.pat, struct_name, fields_accessed),
+        syn::Pat::Ident(ident) => {
+            if let Some((_, subpat)) = &ident.subpat {
+                destructured_fields(subpat, struct_name, fields_accessed);
+            }
+        }
+        syn::Pat::Or(or) => {
+            for case in &or.cases {
+                destructured_fields(case, struct_name, fields_accessed);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Macros whose arguments are expressions, analyzed like the rest of a
+/// method body: the formatting and assertion macros of `std`, `vec!`, and
+/// `matches!`
+const EXPRESSION_MACROS: &[&str] = &[
+    "format", "format_args", "print", "println", "eprint", "eprintln", "write", "writeln", "panic", "vec",
+    "assert", "assert_eq", "assert_ne", "debug_assert", "debug_assert_eq", "debug_assert_ne", "matches",
+];
+
+/// The arguments of a call to one of `EXPRESSION_MACROS`, parsed as
+/// expressions; other macros, and arguments that do not parse, give none
+///
+/// `matches!(value, pattern if guard)` becomes `if let pattern = value {
+/// if guard {} }`, branching on the pattern and the guard as the `match`
+/// it abbreviates does.
+fn macro_args(mac: &syn::Macro) -> Vec<syn::Expr> {
+    let Some(name) = mac.path.segments.last().map(|seg| seg.ident.to_string()) else {
+        return Vec::new();
+    };
+    if !EXPRESSION_MACROS.contains(&name.as_str()) {
+        return Vec::new();
+    }
+
+    if name == "matches" {
+        let matches = mac.parse_body_with(|input: syn::parse::ParseStream| {
+            let value: syn::Expr = input.parse()?;
+            input.parse::<syn::Token![,]>()?;
+            let pattern = syn::Pat::parse_multi_with_leading_vert(input)?;
+            let guard = match input.parse::<Option<syn::Token![if]>>()? {
+                Some(_) => Some(input.parse::<syn::Expr>()?),
+                None => None,
+            };
+            input.parse::<Option<syn::Token![,]>>()?;
+            Ok(match guard {
+                Some(guard) => syn::parse_quote!(if let #pattern = #value { if #guard {} }),
+                None => syn::parse_quote!(if let #pattern = #value {}),
+            })
+        });
+        return matches.into_iter().collect();
+    }
+
+    // Format arguments, assertion operands, and `vec!` elements read like
+    // those of an array, `vec![value; count]` like a repeat expression
+    let tokens = &mac.tokens;
+    match syn::parse2::<syn::Expr>(quote::quote!([#tokens])) {
+        Ok(syn::Expr::Array(array)) => array.elems.into_iter().collect(),
+        Ok(syn::Expr::Repeat(repeat)) => vec![*repeat.expr, *repeat.len],
+        _ => Vec::new(),
+    }
+}
+
+/// Cyclomatic complexity of a method body: 1 plus its decision points
+fn calculate_cyclomatic_complexity(block: &syn::Block) -> usize {
+    let mut counter = ComplexityCounter::default();
+    counter.visit_block(block);
+    1 + counter.decisions
+}
+
+/// Counts the decision points of every expression in a method body,
+/// including those inside closures, call arguments, and match arms
+///
+/// `if`, `if let`, `while`, `while let`, `for`, `loop`, `&&`, `||`, every
+/// match arm, and every match guard count once, as do those among the
+/// arguments of `EXPRESSION_MACROS`. Items nested in the body, such as
+/// inner functions, are their own units and are skipped.
+#[derive(Default)]
+struct ComplexityCounter {
+    decisions: usize,
+}
+
+impl<'ast> Visit<'ast> for ComplexityCounter {
+    fn visit_expr_if(&mut self, node: &'ast syn::ExprIf) {
+        self.decisions += 1;
+        syn::visit::visit_expr_if(self, node);
+    }
+
+    fn visit_expr_while(&mut self, node: &'ast syn::ExprWhile) {
+        self.decisions += 1;
+        syn::visit::visit_expr_while(self, node);
+    }
+
+    fn visit_expr_for_loop(&mut self, node: &'ast syn::ExprForLoop) {
+        self.decisions += 1;
+        syn::visit::visit_expr_for_loop(self, node);
+    }
+
+    fn visit_expr_loop(&mut self, node: &'ast syn::ExprLoop) {
+        self.decisions += 1;
+        syn::visit::visit_expr_loop(self, node);
+    }
+
+    fn visit_arm(&mut self, node: &'ast syn::Arm) {
+        self.decisions += 1 + usize::from(node.guard.is_some());
+        syn::visit::visit_arm(self, node);
+    }
+
+    fn visit_expr_binary(&mut self, node: &'ast syn::ExprBinary) {
+        if matches!(node.op, syn::BinOp::And(_) | syn::BinOp::Or(_)) {
+            self.decisions += 1;
+        }
+        syn::visit::visit_expr_binary(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        for arg in macro_args(node) {
+            self.visit_expr(&arg);
+        }
+    }
+
+    fn visit_item(&mut self, _node: &'ast syn::Item) {}
+}
+
+/// Keywords counted as Halstead operators; every other identifier is an operand
+const OPERATOR_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "fn", "for", "if", "impl", "in",
+    "let", "loop", "match", "move", "mut", "ref", "return", "static", "unsafe", "where", "while", "yield",
+];
+
+/// Count the operators and operands of a method body
+///
+/// Works on tokens: punctuation (joined, so `::` and `+=` are one
+/// operator), paired delimiters, and control-flow keywords are operators;
+/// other identifiers and literals are operands. Items declared directly
+/// in the body, such as inner functions, are skipped.
+fn count_halstead(block: &syn::Block) -> HalsteadCounts {
+    fn walk(
+        tokens: proc_macro2::TokenStream,
+        operators: &mut HashMap<String, usize>,
+        operands: &mut HashMap<String, usize>,
+    ) {
+        let mut punct = String::new();
+        for token in tokens {
+            match token {
+                proc_macro2::TokenTree::Punct(p) => {
+                    punct.push(p.as_char());
+                    if p.spacing() == proc_macro2::Spacing::Joint {
+                        continue;
+                    }
+                }
+                proc_macro2::TokenTree::Ident(ident) => {
+                    let name = ident.to_string();
+                    if OPERATOR_KEYWORDS.contains(&name.as_str()) {
+                        *operators.entry(name).or_default() += 1;
+                    } else {
+                        *operands.entry(name).or_default() += 1;
+                    }
+                }
+                proc_macro2::TokenTree::Literal(literal) => {
+                    *operands.entry(literal.to_string()).or_default() += 1;
+                }
+                proc_macro2::TokenTree::Group(group) => {
+                    let delimiters = match group.delimiter() {
+                        proc_macro2::Delimiter::Parenthesis => Some("()"),
+                        proc_macro2::Delimiter::Brace => Some("{}"),
+                        proc_macro2::Delimiter::Bracket => Some("[]"),
+                        proc_macro2::Delimiter::None => None,
+                    };
+                    if let Some(delimiters) = delimiters {
+                        *operators.entry(delimiters.to_string()).or_default() += 1;
+                    }
+                    walk(group.stream(), operators, operands);
+                }
+            }
+            if !punct.is_empty() {
+                *operators.entry(std::mem::take(&mut punct)).or_default() += 1;
+            }
+        }
+    }
+
+    let mut operators = HashMap::new();
+    let mut operands = HashMap::new();
+    for stmt in &block.stmts {
+        if !matches!(stmt, syn::Stmt::Item(_)) {
+            walk(stmt.to_token_stream(), &mut operators, &mut operands);
+        }
+    }
+
+    HalsteadCounts {
+        distinct_operators: operators.len(),
+        distinct_operands: operands.len(),
+        operators: operators.values().sum(),
+        operands: operands.values().sum(),
+    }
+}
+
+/// Lines holding code of a definition, without its attributes and doc comments
+fn definition_sloc<T: Clone + ToTokens>(node: &T, strip_attrs: impl FnOnce(&mut T)) -> usize {
+    let mut bare = node.clone();
+    strip_attrs(&mut bare);
+    let mut lines = BTreeSet::new();
+    token_lines(bare.to_token_stream(), &mut lines);
+    lines.len()
+}
+
+/// Add the lines holding any of `tokens` to `lines`
+///
+/// Comments and blank lines hold no tokens, so they are never added.
+fn token_lines(tokens: proc_macro2::TokenStream, lines: &mut BTreeSet<usize>) {
+    for token in tokens {
+        match token {
+            proc_macro2::TokenTree::Group(group) => {
+                lines.insert(group.span_open().start().line);
+                lines.insert(group.span_close().start().line);
+                token_lines(group.stream(), lines);
+            }
+            // Literals such as raw strings may span several lines
+            other => lines.extend(other.span().start().line..=other.span().end().line),
+        }
+    }
+}
+
+/// Cognitive complexity of a method body, after SonarSource's definition
+fn calculate_cognitive_complexity(block: &syn::Block) -> usize {
+    let mut counter = CognitiveCounter::default();
+    counter.visit_block(block);
+    counter.score
+}
+
+/// Scores the breaks in linear flow of a method body, weighted by nesting
+///
+/// `if`, `match`, and loops score 1 plus their nesting depth and nest what
+/// they contain; `else if` and `else` score 1 without nesting. A whole
+/// `match` scores once however many arms it has. Each run of the same
+/// logical operator scores 1, so `a && b && c` scores 1 and `a && b || c`
+/// scores 2. Labeled `break` and `continue` score 1, and closures and
+/// async blocks nest their body without scoring. The arguments of
+/// `EXPRESSION_MACROS` score as if written out; items nested in the body
+/// are skipped.
+#[derive(Default)]
+struct CognitiveCounter {
+    score: usize,
+    nesting: usize,
+}
+
+impl CognitiveCounter {
+    fn visit_nested(&mut self, visit: impl FnOnce(&mut Self)) {
+        self.nesting += 1;
+        visit(self);
+        self.nesting -= 1;
+    }
+
+    /// The `else` branches of an `if` chain continue it at the same depth
+    fn visit_else(&mut self, else_branch: &syn::Expr) {
+        self.score += 1;
+        match else_branch {
+            syn::Expr::If(node) => {
+                self.visit_expr(&node.cond);
+                self.visit_nested(|this| this.visit_block(&node.then_branch));
+                if let Some((_, else_branch)) = &node.else_branch {
+                    self.visit_else(else_branch);
+                }
+            }
+            other => self.visit_nested(|this| this.visit_expr(other)),
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for CognitiveCounter {
+    fn visit_expr_if(&mut self, node: &'ast syn::ExprIf) {
+        self.score += 1 + self.nesting;
+        self.visit_expr(&node.cond);
+        self.visit_nested(|this| this.visit_block(&node.then_branch));
+        if let Some((_, else_branch)) = &node.else_branch {
+            self.visit_else(else_branch);
+        }
+    }
+
+    fn visit_expr_match(&mut self, node: &'ast syn::ExprMatch) {
+        self.score += 1 + self.nesting;
+        self.visit_expr(&node.expr);
+        self.visit_nested(|this| {
+            for arm in &node.arms {
+                this.visit_arm(arm);
+            }
+        });
+    }
+
+    fn visit_expr_while(&mut self, node: &'ast syn::ExprWhile) {
+        self.score += 1 + self.nesting;
+        self.visit_expr(&node.cond);
+        self.visit_nested(|this| this.visit_block(&node.body));
+    }
+
+    fn visit_expr_for_loop(&mut self, node: &'ast syn::ExprForLoop) {
+        self.score += 1 + self.nesting;
+        self.visit_expr(&node.expr);
+        self.visit_nested(|this| this.visit_block(&node.body));
+    }
+
+    fn visit_expr_loop(&mut self, node: &'ast syn::ExprLoop) {
+        self.score += 1 + self.nesting;
+        self.visit_nested(|this| this.visit_block(&node.body));
+    }
+
+    fn visit_expr_closure(&mut self, node: &'ast syn::ExprClosure) {
+        self.visit_nested(|this| this.visit_expr(&node.body));
+    }
+
+    fn visit_expr_async(&mut self, node: &'ast syn::ExprAsync) {
+        self.visit_nested(|this| this.visit_block(&node.block));
+    }
+
+    fn visit_expr_break(&mut self, node: &'ast syn::ExprBreak) {
+        self.score += usize::from(node.label.is_some());
+        syn::visit::visit_expr_break(self, node);
+    }
+
+    fn visit_expr_continue(&mut self, node: &'ast syn::ExprContinue) {
+        self.score += usize::from(node.label.is_some());
+    }
+
+    fn visit_expr_binary(&mut self, node: &'ast syn::ExprBinary) {
+        let logical = |op: &syn::BinOp| matches!(op, syn::BinOp::And(_) | syn::BinOp::Or(_));
+        if logical(&node.op) {
+            // Binary operators associate to the left, so a run continues
+            // in the left operand
+            let continues_run = match &*node.left {
+                syn::Expr::Binary(left) => {
+                    std::mem::discriminant(&left.op) == std::mem::discriminant(&node.op)
+                }
+                _ => false,
+            };
+            if !continues_run {
+                self.score += 1;
+            }
+        }
+        syn::visit::visit_expr_binary(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        for arg in macro_args(node) {
+            self.visit_expr(&arg);
+        }
+    }
+
+    fn visit_item(&mut self, _node: &'ast syn::Item) {}
+}
+
+/// Methods that return a view of their receiver rather than another
+/// object, so calling them does not step further from it, e.g. the
+/// `iter()` and `map()` of `self.items.iter().map(..)`
+const DEMETER_TRANSPARENT: &[&str] = &[
+    "iter", "iter_mut", "into_iter", "map", "filter", "filter_map", "flat_map", "enumerate", "rev", "zip",
+    "chain", "skip", "take", "cloned", "copied", "peekable", "clone", "to_owned", "to_string", "into", "as_ref",
+    "as_mut", "as_deref", "as_deref_mut", "as_str", "as_slice", "borrow", "borrow_mut", "lock", "read", "write",
+    "unwrap", "expect", "unwrap_or", "unwrap_or_default", "ok", "err",
+];
+
+/// Lines of the member access chains of a method body that break the Law
+/// of Demeter
+fn demeter_violations(block: &syn::Block) -> Vec<usize> {
+    let mut counter = DemeterCounter::default();
+    counter.visit_block(block);
+    counter.lines
+}
+
+/// Finds member accesses on objects a method only reached through another
+///
+/// A method may use its own fields and the members of `self`, its
+/// parameters, and its locals, so `self.a.b()` and `order.total()` are
+/// fine, but `self.a.b.c()`, `order.customer().name()`, and
+/// `foo().bar().baz()` reach through a stranger. A chain counts once, at
+/// its outermost access; `?`, `.await`, and `DEMETER_TRANSPARENT` methods
+/// do not add a step.
+#[derive(Default)]
+struct DemeterCounter {
+    lines: Vec<usize>,
+}
+
+impl DemeterCounter {
+    /// The receiver of a member access, unless `expr` is none
+    fn receiver(expr: &syn::Expr) -> Option<&syn::Expr> {
+        match expr {
+            syn::Expr::Field(field) => Some(&field.base),
+            syn::Expr::MethodCall(call) => Some(&call.receiver),
+            _ => None,
+        }
+    }
+
+    /// The steps from the root of a chain to `expr`, and whether the root
+    /// is `self`
+    fn steps(mut expr: &syn::Expr) -> (usize, bool) {
+        let mut steps = 0;
+        loop {
+            expr = match expr {
+                syn::Expr::Field(field) => {
+                    steps += 1;
+                    &field.base
+                }
+                syn::Expr::MethodCall(call) => {
+                    if !DEMETER_TRANSPARENT.contains(&call.method.to_string().as_str()) {
+                        steps += 1;
+                    }
+                    &call.receiver
+                }
+                syn::Expr::Try(node) => &node.expr,
+                syn::Expr::Await(node) => &node.base,
+                syn::Expr::Paren(node) => &node.expr,
+                syn::Expr::Path(path) => return (steps, path.path.is_ident("self")),
+                _ => return (steps, false),
+            };
+        }
+    }
+
+    fn violates(expr: &syn::Expr) -> bool {
+        if let syn::Expr::MethodCall(call) = expr {
+            if DEMETER_TRANSPARENT.contains(&call.method.to_string().as_str()) {
+                return false;
+            }
+        }
+        let Some(receiver) = Self::receiver(expr) else {
+            return false;
+        };
+        let (steps, from_self) = Self::steps(receiver);
+        steps > usize::from(from_self)
+    }
+}
+
+impl<'ast> Visit<'ast> for DemeterCounter {
+    fn visit_expr(&mut self, node: &'ast syn::Expr) {
+        if !Self::violates(node) {
+            return syn::visit::visit_expr(self, node);
+        }
+        self.lines.push(node.span().start().line);
+
+        // The rest of the chain belongs to this violation; only its
+        // arguments and root can hold others
+        let mut link = node;
+        loop {
+            link = match link {
+                syn::Expr::Field(field) => &field.base,
+                syn::Expr::MethodCall(call) => {
+                    for arg in &call.args {
+                        self.visit_expr(arg);
+                    }
+                    &call.receiver
+                }
+                syn::Expr::Try(node) => &node.expr,
+                syn::Expr::Await(node) => &node.base,
+                syn::Expr::Paren(node) => &node.expr,
+                root => return syn::visit::visit_expr(self, root),
+            };
+        }
+    }
+
+    fn visit_item(&mut self, _node: &'ast syn::Item) {}
+}
+
+/// A source file parsed into syntax, not yet analyzed
+pub struct SourceFile {
+    pub file_path: String, // Empty when the source is not a file on disk
+    pub module_path: String, // Module its items live in, e.g. `crate::net`
+    syntax: File,
+}
+
+impl SourceFile {
+    pub fn parse(content: &str, module_path: &str, file_path: &str) -> Result<Self, syn::Error> {
+        Ok(Self {
+            file_path: file_path.to_string(),
+            module_path: module_path.to_string(),
+            syntax: syn::parse_str(content)?,
+        })
+    }
+}
+
+/// Parse a source file whose items live in module `module_path` (e.g. `crate::net`)
+pub fn parse_file(
+    content: &str,
+    module_path: &str,
+    options: &ParseOptions,
+) -> Result<ParsedFile, syn::Error> {
+    Ok(parse_sources(&[SourceFile::parse(content, module_path, "")?], options))
+}
+
+/// The names `source` links to other files through
+pub fn links(source: &SourceFile) -> Links {
+    let mut links = Links::default();
+    links.visit_file(&source.syntax);
+    links
+}
+
+/// The `pub use` re-exports of `source`, and its type aliases and private
+/// `use ... as` renames, which don't depend on other files
+pub fn reexports_and_aliases(source: &SourceFile) -> (Vec<Reexport>, Vec<Reexport>) {
+    let mut visitor = StructVisitor::new();
+    visitor.visit_source(source);
+    (visitor.reexports, visitor.aliases)
+}
+
+/// Parse the files of a crate together, in two passes
+///
+/// Structs of every file are collected before any impl block is attached,
+/// so impls written before their struct or in another file still count.
+pub fn parse_sources(sources: &[SourceFile], options: &ParseOptions) -> ParsedFile {
+    // Aliases and traits may be declared after, or apart from, the impl blocks using them
+    let mut collector = DeclarationCollector::default();
+    for source in sources {
+        collector.visit_file(&source.syntax);
+    }
+    let mut traits: Vec<String> = collector.traits.keys().cloned().collect();
+    traits.sort();
+
+    let mut visitor = StructVisitor::with_declarations(collector.into_declarations(options), *options);
+    for pass in [Pass::Definitions, Pass::Impls] {
+        visitor.pass = pass;
+        for source in sources {
+            visitor.visit_source(source);
+        }
+    }
+
+    // Types a struct names resolve through the `use` items of its module
+    let mut uses: HashMap<&str, Vec<Reexport>> = HashMap::new();
+    for item in &visitor.uses {
+        let module = item.path.rsplit_once("::").map_or("", |(module, _)| module);
+        uses.entry(module).or_default().push(item.clone());
+    }
+    let mut structs = visitor.structs;
+    for s in &mut structs {
+        s.imports = uses.get(s.module_path.as_str()).cloned().unwrap_or_default();
+    }
+    resolve::apply_crate_traits(&mut structs, &traits);
+    ParsedFile {
+        structs,
+        enums: visitor.enums,
+        reexports: visitor.reexports,
+        aliases: visitor.aliases,
+        traits,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assignments_are_field_accesses() {
+        let source = r#"
+            struct Counter { count: u32, name: String, total: Box<u64>, items: Vec<u8>, limit: usize }
+
+            impl Counter {
+                fn increment(&mut self) { self.count += 1; }
+                fn rename(&mut self, name: String) { self.name = name; }
+                fn reset(&mut self) { *self.total = 0; }
+                fn set(&mut self, i: usize) { self.items[i] = (self.limit) as u8; }
+            }
+        "#;
+
+        let counter = &parse_file(source, "crate", &ParseOptions::default()).unwrap().structs[0];
+        let accessed = |i: usize| -> Vec<&str> {
+            let mut fields: Vec<&str> = counter.methods[i].fields_accessed.iter().map(|f| &**f).collect();
+            fields.sort_unstable();
+            fields
+        };
+        assert_eq!(accessed(0), vec!["count"]);
+        assert_eq!(accessed(1), vec!["name"]);
+        assert_eq!(accessed(2), vec!["total"]);
+        assert_eq!(accessed(3), vec!["items", "limit"]);
+    }
+
+    #[test]
+    fn test_destructuring_self_accesses_fields() {
+        let source = r#"
+            struct User { id: u64, name: String, email: String }
+            struct Pair(u8, u8, u8);
+
+            impl User {
+                fn contact(&self) -> String { let Self { name, email, .. } = self; format!("{name} <{email}>") }
+                fn id(&self) -> u64 { match self { User { id, name: _, .. } => *id } }
+                fn named(&self) -> bool { if let Self { name, .. } = &*self { !name.is_empty() } else { false } }
+                fn other(&self, user: &User) -> u64 { let User { id, .. } = user; *id }
+            }
+
+            impl Pair {
+                fn ends(&self) -> u8 { let Pair(first, _, ..) = self; *first }
+            }
+        "#;
+
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let accessed = |s: usize, m: usize| -> Vec<&str> {
+            let mut fields: Vec<&str> = structs[s].methods[m].fields_accessed.iter().map(|f| &**f).collect();
+            fields.sort_unstable();
+            fields
+        };
+        assert_eq!(accessed(0, 0), vec!["email", "name"]);
+        assert_eq!(accessed(0, 1), vec!["id"]);
+        assert_eq!(accessed(0, 2), vec!["name"]);
+        assert!(accessed(0, 3).is_empty());
+        assert_eq!(accessed(1, 0), vec!["0"]);
+    }
+
+    #[test]
+    fn test_nested_field_access_credits_first_level_field() {
+        let source = r#"
+            struct Client { config: Config, pool: (Pool, usize), name: String }
+
+            impl Client {
+                fn timeout(&self) -> u64 { self.config.retry.timeout }
+                fn size(&self) -> usize { self.pool.0.size + self.pool.1 }
+                fn label(&self) -> usize { self.name().len.max }
+            }
+        "#;
+
+        let client = &parse_file(source, "crate", &ParseOptions::default()).unwrap().structs[0];
+        let accessed = |i: usize| -> Vec<&str> { client.methods[i].fields_accessed.iter().map(|f| &**f).collect() };
+        assert_eq!(accessed(0), vec!["config"]);
+        assert_eq!(accessed(1), vec!["pool"]);
+        assert!(accessed(2).is_empty());
+    }
+
+    #[test]
+    fn test_field_access_in_closures_and_async_blocks() {
+        let source = r#"
+            struct Feed { items: Vec<u32>, offset: u32, client: Client }
+
+            impl Feed {
+                fn shifted(&self) -> Vec<u32> { self.items.iter().map(|i| i + self.offset).collect() }
+                fn fetch(&self) -> impl Future<Output = u32> {
+                    async move { if self.offset > 0 { self.client.get().await } else { 0 } }
+                }
+            }
+        "#;
+
+        let feed = &parse_file(source, "crate", &ParseOptions::default()).unwrap().structs[0];
+        let accessed = |i: usize| -> Vec<&str> {
+            let mut fields: Vec<&str> = feed.methods[i].fields_accessed.iter().map(|f| &**f).collect();
+            fields.sort_unstable();
+            fields
+        };
+        assert_eq!(accessed(0), vec!["items", "offset"]);
+        assert_eq!(accessed(1), vec!["client", "offset"]);
+        // The `if` nests in the async block: 1 + 1, and `else` 1
+        assert_eq!(feed.methods[1].cognitive_complexity, 3);
+        assert_eq!(feed.methods[1].cyclomatic_complexity, 2);
+    }
+
+    #[test]
+    fn test_field_access_in_returns_loops_tuples_and_arrays() {
+        let source = r#"
+            struct Buffer { count: usize, items: Vec<u8>, start: usize, fill: u8 }
+
+            impl Buffer {
+                fn count(&self) -> usize { return self.count; }
+                fn spin(&mut self) { loop { self.count += 1; } }
+                fn stats(&self) -> (usize, usize) { (self.count, self.items.len()) }
+                fn bounds(&self) -> [usize; 2] { [self.start, self.count] }
+                fn range(&self) -> std::ops::Range<usize> { self.start..self.count }
+                fn first(&self) -> u8 { loop { break self.items[0]; } }
+                fn blank(&self) -> [u8; 4] { [self.fill; 4] }
+            }
+        "#;
+
+        let buffer = &parse_file(source, "crate", &ParseOptions::default()).unwrap().structs[0];
+        let accessed = |i: usize| -> Vec<&str> {
+            let mut fields: Vec<&str> = buffer.methods[i].fields_accessed.iter().map(|f| &**f).collect();
+            fields.sort_unstable();
+            fields
+        };
+        assert_eq!(accessed(0), vec!["count"]);
+        assert_eq!(accessed(1), vec!["count"]);
+        assert_eq!(accessed(2), vec!["count", "items"]);
+        assert_eq!(accessed(3), vec!["count", "start"]);
+        assert_eq!(accessed(4), vec!["count", "start"]);
+        assert_eq!(accessed(5), vec!["items"]);
+        assert_eq!(accessed(6), vec!["fill"]);
+    }
+
+    #[test]
+    fn test_field_names_and_types_are_interned() {
+        let source = r#"
+            struct Reader { path: String, buffer: Vec<u8> }
+            struct Writer { path: String }
+
+            impl Writer {
+                fn path(&self) -> &str { &self.path }
+            }
+        "#;
+
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let (reader, writer) = (&structs[0], &structs[1]);
+        assert!(std::sync::Arc::ptr_eq(&reader.fields[0].name, &writer.fields[0].name));
+        assert!(std::sync::Arc::ptr_eq(&reader.fields[0].ty, &writer.fields[0].ty));
+        assert!(std::sync::Arc::ptr_eq(&writer.methods[0].fields_accessed[0], &writer.fields[0].name));
+    }
+
+    #[test]
+    fn test_parse_union_with_impl() {
+        let source = r#"
+            union Value {
+                int: i64,
+                float: f64,
+            }
+
+            impl Value {
+                fn as_int(&self) -> i64 {
+                    unsafe { self.int }
+                }
+            }
+        "#;
+
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        assert_eq!(structs.len(), 1);
+
+        let value = &structs[0];
+        assert_eq!(value.kind, StructKind::Union);
+        assert_eq!(value.fields.len(), 2);
+        assert_eq!(value.methods.len(), 1);
+        assert_eq!(value.methods[0].fields_accessed, vec!["int".into()]);
+    }
+
+    #[test]
+    fn test_methods_grouped_by_impl_block() {
+        let source = r#"
+            struct Server { port: u16 }
+
+            impl Server {
+                fn port(&self) -> u16 { self.port }
+            }
+
+            impl std::fmt::Display for Server {
+                fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result { write!(f, "{}", self.port) }
+            }
+
+            impl Server {
+                fn reset(&mut self) { self.port = 0 }
+                fn close(&self) {}
+            }
+        "#;
+
+        let server = &parse_file(source, "crate", &ParseOptions::default()).unwrap().structs[0];
+        let labels: Vec<&str> = server.impl_blocks.iter().map(|b| b.label.as_str()).collect();
+        assert_eq!(labels, vec!["impl #1", "impl std::fmt::Display", "impl #2"]);
+        assert_eq!(server.impl_blocks[1].span.start_line, 8);
+
+        let blocks: Vec<usize> = server.methods.iter().map(|m| m.impl_block).collect();
+        assert_eq!(blocks, vec![0, 1, 2, 2]);
+    }
+
+    #[test]
+    fn test_dyn_dispatch_in_fields_and_signatures() {
+        let source = r#"
+            struct Router {
+                routes: Vec<Box<dyn Handler>>,
+                fallback: Arc<dyn Handler + Send>,
+                name: String,
+            }
+
+            impl Router {
+                fn add(&mut self, handler: Box<dyn Handler>) {
+                    let unrelated: Box<dyn Any> = Box::new(1);
+                    self.routes.push(handler);
+                }
+                fn describe(&self, out: &mut dyn std::fmt::Write) -> Result<(), Box<dyn Error>> { Ok(()) }
+                fn generic(&self, handler: impl Handler) {}
+            }
+        "#;
+
+        let router = &parse_file(source, "crate", &ParseOptions::default()).unwrap().structs[0];
+        // Two fields, one argument, and an argument and a return type; not locals
+        assert_eq!(router.dyn_dispatch, 5);
+    }
+
+    #[test]
+    fn test_error_types_from_result_signatures() {
+        let source = r#"
+            struct Store { path: String }
+
+            impl Store {
+                fn load(&self) -> Result<String, StoreError> { let s = read(&self.path)?; Ok(s) }
+                fn save(&self) -> std::io::Result<()> { write(&self.path)?; flush()?; Ok(()) }
+                fn check(&self) -> Result<()> { Ok(()) }
+                fn len(&self) -> usize { 0 }
+                fn ext(&self) -> Option<&str> { let (_, ext) = self.path.rsplit_once('.')?; Some(ext) }
+            }
+
+            impl std::str::FromStr for Store {
+                type Err = ParseError;
+                fn from_str(s: &str) -> Result<Self, Self::Err> { todo!() }
+            }
+        "#;
+
+        let store = &parse_file(source, "crate", &ParseOptions::default()).unwrap().structs[0];
+        let errors: Vec<Option<&str>> = store.methods.iter().map(|m| m.error_type.as_deref()).collect();
+        assert_eq!(
+            errors,
+            vec![Some("StoreError"), Some("std::io::Error"), Some("Error"), None, None, Some("ParseError")]
+        );
+        // `?` on an `Option` propagates no error
+        let propagations: Vec<usize> = store.methods.iter().map(|m| m.propagations).collect();
+        assert_eq!(propagations, vec![1, 2, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_error_paths_weighted_as_decisions() {
+        let source = r#"
+            struct Loader { path: String }
+            impl Loader {
+                fn load(&self) -> Result<String, Error> {
+                    if self.path.is_empty() {
+                        return Err(Error::Empty);
+                    }
+                    let text = read(&self.path)?;
+                    parse(&text)?;
+                    Ok(text)
+                }
+                fn dump(&self, out: &mut String) -> Result<(), Error> {
+                    write!(out, "{}", read(&self.path)?)?;
+                    println!("{}", if self.path.is_empty() { return Err(Error::Empty) } else { 0 });
+                    Ok(())
+                }
+            }
+        "#;
+        let complexity = |error_path_weight| {
+            let options = ParseOptions { error_path_weight, ..Default::default() };
+            let method = &parse_file(source, "crate", &options).unwrap().structs[0].methods[0];
+            (method.error_returns, method.cyclomatic_complexity)
+        };
+
+        assert_eq!(complexity(0), (1, 2));
+        assert_eq!(complexity(2), (1, 8));
+
+        // Inside the arguments of `write!` and `println!` too
+        let dump = &parse_file(source, "crate", &ParseOptions::default()).unwrap().structs[0].methods[1];
+        assert_eq!((dump.propagations, dump.error_returns), (2, 1));
+        assert!(dump.calls.contains(&"read".to_string()));
+    }
+
+    #[test]
+    fn test_fields_behind_error_propagation_are_accessed() {
+        let source = r#"
+            struct Source { a: Reader, b: Parser }
+            impl Source {
+                fn next(&mut self) -> io::Result<u8> { Ok(self.a.read()?) }
+                fn parse(&mut self) -> Result<Item, Error> { let item = self.b.parse()?.item?; Ok(item) }
+            }
+        "#;
+
+        let source = &parse_file(source, "crate", &ParseOptions::default()).unwrap().structs[0];
+        let accessed = |i: usize| -> Vec<&str> { source.methods[i].fields_accessed.iter().map(|f| &**f).collect() };
+        assert_eq!(accessed(0), vec!["a"]);
+        assert_eq!(accessed(1), vec!["b"]);
+    }
+
+    #[test]
+    fn test_async_methods_and_await_points() {
+        let source = r#"
+            struct Client { conn: Conn }
+            impl Client {
+                async fn fetch(&self) -> Vec<u8> {
+                    self.conn.ready().await;
+                    let body = self.conn.send().await;
+                    body
+                }
+                fn spawn(&self) { spawn(async move { self.conn.close().await }); }
+                fn id(&self) -> u32 { 0 }
+            }
+        "#;
+        let methods = |await_weight| -> Vec<(bool, usize, usize)> {
+            let options = ParseOptions { await_weight, ..Default::default() };
+            let structs = parse_file(source, "crate", &options).unwrap().structs;
+            structs[0].methods.iter().map(|m| (m.is_async, m.awaits, m.cyclomatic_complexity)).collect()
+        };
+
+        assert_eq!(methods(0), vec![(true, 2, 1), (false, 1, 1), (false, 0, 1)]);
+        assert_eq!(methods(1), vec![(true, 2, 3), (false, 1, 2), (false, 0, 1)]);
+    }
+
+    #[test]
+    fn test_impl_on_type_alias_attaches_to_struct() {
+        let source = r#"
+            struct Pool<T> { size: usize, marker: T }
+
+            impl Db {
+                fn size(&self) -> usize { self.size }
+            }
+
+            type Db = Connections<Postgres>;
+            type Connections<T> = Pool<T>;
+
+            impl Connections<Mysql> {
+                fn marker(&self) -> &Mysql { &self.marker }
+            }
+        "#;
+
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let pool = structs.iter().find(|s| s.name == "Pool").unwrap();
+        assert_eq!(pool.methods.len(), 2);
+    }
+
+    const EXTENSION_TRAIT_SOURCE: &str = r#"
+        struct Config { name: String }
+
+        trait ConfigExt {
+            fn describe(&self) -> String {
+                if self.is_named() { "named".into() } else { "anonymous".into() }
+            }
+            fn is_named(&self) -> bool;
+        }
+
+        impl ConfigExt for &Config {
+            fn is_named(&self) -> bool { !self.name.is_empty() }
+        }
+    "#;
+
+    #[test]
+    fn test_extension_trait_methods_attributed_to_struct() {
+        let structs = parse_file(EXTENSION_TRAIT_SOURCE, "crate", &ParseOptions::default()).unwrap().structs;
+        let config = &structs[0];
+
+        // The impl method plus the trait's default method
+        assert_eq!(config.methods.len(), 2);
+        assert_eq!(config.traits, vec!["ConfigExt".to_string()]);
+    }
+
+    #[test]
+    fn test_extension_trait_mode_off() {
+        let options = ParseOptions {
+            extension_traits: ExtensionTraitMode::Off,
+            ..Default::default()
+        };
+        let structs = parse_file(EXTENSION_TRAIT_SOURCE, "crate", &options).unwrap().structs;
+
+        // `impl ConfigExt for &Config` is not a plain impl on Config
+        assert!(structs[0].methods.is_empty());
+    }
+
+    #[test]
+    fn test_trait_with_multiple_impls_is_not_extension() {
+        let source = r#"
+            struct A { x: u8 }
+            struct B { y: u8 }
+            trait Shared { fn hello(&self) -> u8 { 1 } }
+            impl Shared for A {}
+            impl Shared for B {}
+        "#;
+
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        assert!(structs.iter().all(|s| s.methods.is_empty()));
+    }
+
+    #[test]
+    fn test_impl_trait_signature_refs() {
+        let source = r#"
+            struct Config { timeout: u32 }
+            struct Server { port: u16 }
+
+            impl Server {
+                fn handler(&self) -> impl Responder { todo!() }
+                fn run(&self, f: impl FnOnce(&Config)) {}
+            }
+        "#;
+
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let server = structs.iter().find(|s| s.name == "Server").unwrap();
+        assert_eq!(server.referenced_traits, vec!["Responder".to_string()]);
+        assert!(server.external_types.contains(&"Config".into()));
+    }
+
+    #[test]
+    fn test_derive_modes() {
+        let source = r#"
+            #[derive(Debug, Clone)]
+            #[derive(serde::Serialize)]
+            struct Point { x: i32 }
+        "#;
+        let parse = |derives| {
+            let options = ParseOptions { derives, ..Default::default() };
+            parse_file(source, "crate", &options).unwrap().structs.remove(0)
+        };
+
+        let off = parse(DeriveMode::Off);
+        assert!(off.derives.is_empty() && off.traits.is_empty());
+
+        let list = parse(DeriveMode::List);
+        assert_eq!(list.derives, vec!["Debug", "Clone", "serde::Serialize"]);
+        assert!(list.traits.is_empty());
+
+        let on = parse(DeriveMode::On);
+        assert_eq!(on.derives, list.derives);
+        assert!(on.traits.is_empty());
+    }
+
+    #[test]
+    fn test_expanded_derives_stay_derives() {
+        let source = r#"
+            struct Point { x: i32 }
+            #[automatically_derived]
+            impl ::core::clone::Clone for Point {
+                fn clone(&self) -> Point { Point { x: ::core::clone::Clone::clone(&self.x) } }
+            }
+            #[automatically_derived]
+            impl ::core::marker::StructuralPartialEq for Point {}
+            #[automatically_derived]
+            impl ::core::cmp::PartialEq for Point {
+                fn eq(&self, other: &Point) -> bool { self.x == other.x && true }
+            }
+            impl Point {
+                fn norm(&self) -> i32 { if self.x > 0 { self.x } else { -self.x } }
+            }
+        "#;
+        let parse = |derives| {
+            let options = ParseOptions { derives, ..Default::default() };
+            parse_file(source, "crate", &options).unwrap().structs.remove(0)
+        };
+
+        let on = parse(DeriveMode::On);
+        assert_eq!(on.derives, vec!["Clone", "PartialEq"]);
+        assert!(on.traits.is_empty());
+        let methods: Vec<&str> = on.methods.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(methods, vec!["norm"]);
+
+        let result = crate::metrics::analyze_struct(&on, std::slice::from_ref(&on));
+        assert_eq!((result.derived_coupling, result.cbo, result.wmc), (2, 0, 2));
+        let without_cbo = crate::models::MetricSelection { all: false, cbo: false, ..Default::default() };
+        let index = crate::resolve::StructIndex::new(std::slice::from_ref(&on));
+        assert_eq!(crate::metrics::analyze_selected(&on, &index, &without_cbo).derived_coupling, 0);
+
+        assert_eq!(parse(DeriveMode::List).derives, on.derives);
+        assert!(parse(DeriveMode::Off).derives.is_empty());
+    }
+
+    #[test]
+    fn test_skip_trivial_impls() {
+        let source = r#"
+            struct Point { x: i32, y: i32 }
+            impl Clone for Point {
+                fn clone(&self) -> Self { Point { x: self.x, y: self.y } }
+            }
+            impl PartialEq for Point {
+                fn eq(&self, other: &Self) -> bool {
+                    if self.x != other.x { return false; }
+                    self.y == other.y
+                }
+            }
+            impl std::fmt::Display for Point {
+                fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result { Ok(()) }
+            }
+        "#;
+        let all = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        assert_eq!(all[0].methods.len(), 3);
+
+        let options = ParseOptions { skip_trivial_impls: true, ..Default::default() };
+        let point = parse_file(source, "crate", &options).unwrap().structs.remove(0);
+        let methods: Vec<&str> = point.methods.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(methods, vec!["eq", "fmt"]);
+        assert_eq!(point.traits.len(), 3);
+    }
+
+    #[test]
+    fn test_impls_before_struct_and_in_other_files() {
+        let lib = r#"
+            impl Engine {
+                fn start(&mut self) { self.running = true; }
+            }
+            pub struct Engine { running: bool }
+            mod other { pub struct Engine; }
+        "#;
+        let ops = r#"
+            use crate::Engine;
+            impl Engine {
+                fn stop(&mut self) { self.running = false; }
+            }
+        "#;
+        let sources = [
+            SourceFile::parse(lib, "crate", "src/lib.rs").unwrap(),
+            SourceFile::parse(ops, "crate::ops", "src/ops.rs").unwrap(),
+        ];
+        let structs = parse_sources(&sources, &ParseOptions::default()).structs;
+
+        let engine = &structs[0];
+        assert_eq!(engine.file_path, "src/lib.rs");
+        let methods: Vec<&str> = engine.methods.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(methods, vec!["start", "stop"]);
+        assert!(structs[1].methods.is_empty());
+    }
+
+    #[test]
+    fn test_complexity_counts_nested_decisions() {
+        let source = r#"
+            struct Router { routes: Vec<String> }
+            impl Router {
+                fn plain(&self) -> usize { self.routes.len() }
+                fn nested(&self, key: Option<&str>) -> usize {
+                    let found = self.routes.iter().filter(|r| if r.is_empty() { false } else { true }).count();
+                    if let Some(key) = key {
+                        return match key.len() {
+                            0 => 0,
+                            n if n > 10 && found > 0 => n,
+                            _ => 1,
+                        };
+                    }
+                    while let Some(_) = None::<u8> {}
+                    fn helper(x: bool) -> bool { if x { x } else { !x } }
+                    found
+                }
+            }
+        "#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let complexity: Vec<usize> = structs[0].methods.iter().map(|m| m.cyclomatic_complexity).collect();
+
+        // closure `if`, `if let`, three arms, a guard, `&&`, `while let`; not `helper`
+        assert_eq!(complexity, vec![1, 9]);
+    }
+
+    #[test]
+    fn test_macro_arguments_count_toward_fields_and_complexity() {
+        let source = r#"
+            struct Report { title: String, rows: Vec<u32>, width: usize, kind: Option<u8> }
+            impl Report {
+                fn render(&self) -> String {
+                    assert!(!self.rows.is_empty() && self.width > 0, "empty {}", self.title);
+                    let cells = vec![if self.width > 80 { 1 } else { 2 }; self.rows.len()];
+                    format!("{:>w$}", cells.len(), w = self.width)
+                }
+                fn is_plain(&self) -> bool {
+                    matches!(self.kind, Some(0) | None if self.width < 10)
+                }
+            }
+        "#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let fields = |i: usize| -> Vec<String> {
+            let method: &MethodInfo = &structs[0].methods[i];
+            let mut names: Vec<String> = method.fields_accessed.iter().map(|f| f.to_string()).collect();
+            names.sort();
+            names
+        };
+        let complexity: Vec<usize> = structs[0].methods.iter().map(|m| m.cyclomatic_complexity).collect();
+
+        assert_eq!(fields(0), vec!["rows", "title", "width"]);
+        assert_eq!(fields(1), vec!["kind", "width"]);
+        // `&&` and the `if` inside `vec!`; the pattern and the guard of `matches!`
+        assert_eq!(complexity, vec![3, 3]);
+    }
+
+    #[test]
+    fn test_halstead_counts() {
+        let source = r#"
+            struct Rect { w: u32, h: u32 }
+            impl Rect {
+                fn area(&self) -> u32 { self.w * self.h }
+                fn scaled(&self) -> u32 {
+                    fn unused() {}
+                    let area = Self::scale(2) + self.w;
+                    area
+                }
+            }
+        "#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let counts: Vec<HalsteadCounts> = structs[0].methods.iter().map(|m| m.halstead).collect();
+
+        // `.` `*`; `self` `w` `h`
+        assert_eq!(
+            counts[0],
+            HalsteadCounts { distinct_operators: 2, distinct_operands: 3, operators: 3, operands: 4 }
+        );
+        // `let` `=` `::` `()` `+` `.` `;`; `area` `Self` `scale` `2` `self` `w`
+        assert_eq!(
+            counts[1],
+            HalsteadCounts { distinct_operators: 7, distinct_operands: 6, operators: 7, operands: 7 }
+        );
+    }
+
+    #[test]
+    fn test_cognitive_complexity() {
+        let source = r#"
+            struct Parser { tokens: Vec<u8> }
+            impl Parser {
+                fn kind(&self, token: u8) -> &str {
+                    match token {
+                        b'a' => "letter",
+                        b'0' => "digit",
+                        b' ' => "space",
+                        _ => "other",
+                    }
+                }
+                fn scan(&self, strict: bool) -> usize {
+                    let mut count = 0;
+                    'outer: for token in &self.tokens {
+                        if *token == 0 && strict && count > 0 || !strict {
+                            continue 'outer;
+                        } else if *token == 1 {
+                            self.tokens.iter().for_each(|t| if *t == 2 { count += 1 });
+                        } else {
+                            count += 1;
+                        }
+                    }
+                    fn helper(x: bool) -> bool { if x { x } else { !x } }
+                    count
+                }
+            }
+        "#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let cognitive: Vec<usize> = structs[0].methods.iter().map(|m| m.cognitive_complexity).collect();
+        let cyclomatic: Vec<usize> = structs[0].methods.iter().map(|m| m.cyclomatic_complexity).collect();
+
+        // A flat match scores once, where every arm is a decision
+        assert_eq!((cognitive[0], cyclomatic[0]), (1, 5));
+        // `for` 1, `if` 2, `&&` run 1, `||` run 1, `continue 'outer` 1,
+        // `else if` 1, closure `if` 4 (nested in for, else if, and closure), `else` 1
+        assert_eq!(cognitive[1], 12);
+    }
+
+    #[test]
+    fn test_tuple_struct_fields_by_index() {
+        let source = r#"
+            struct Meters(f64, String);
+            impl Meters {
+                fn value(&self) -> f64 { self.0 }
+                fn unit(&self) -> &str { &self.1 }
+            }
+        "#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let meters = &structs[0];
+
+        let fields: Vec<&str> = meters.fields.iter().map(|f| &*f.name).collect();
+        assert_eq!(fields, vec!["0", "1"]);
+        assert_eq!(meters.methods[0].fields_accessed, vec!["0".into()]);
+        assert_eq!(meters.methods[1].fields_accessed, vec!["1".into()]);
+    }
+
+    #[test]
+    fn test_pub_use_reexports() {
+        let source = r#"
+            pub use internal::Engine;
+            pub use self::internal::{Gearbox as Transmission, parts::*};
+            use internal::Private;
+        "#;
+
+        let parsed = parse_file(source, "crate::car", &ParseOptions::default()).unwrap();
+        let reexports: Vec<(&str, &str)> = parsed
+            .reexports
+            .iter()
+            .map(|r| (r.path.as_str(), r.target.as_str()))
+            .collect();
+
+        assert_eq!(
+            reexports,
+            vec![
+                ("crate::car::Engine", "crate::car::internal::Engine"),
+                ("crate::car::Transmission", "crate::car::internal::Gearbox"),
+                ("crate::car::*", "crate::car::internal::parts::*"),
+            ]
+        );
+    }
 }
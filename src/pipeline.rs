@@ -0,0 +1,720 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+
+use crate::cache;
+use crate::expand;
+use crate::metrics;
+use crate::models::{AnalysisResult, DeriveMode, Metric, MetricSelection, ParsedFile, StructInfo};
+use crate::modules;
+use crate::parser::{self, ParseOptions, SourceFile};
+use crate::query::{Formula, Query};
+use crate::resolve::{self, StructIndex};
+
+/// Settings shared by every stage of an analysis
+#[derive(Default)]
+pub struct AnalysisContext {
+    pub parse_options: ParseOptions,
+    pub include: Vec<String>, // Only analyze files matching one of these globs
+    pub exclude: Vec<String>, // Skip files matching these globs, or names containing these words
+    pub no_ignore: bool, // Also analyze gitignored files, `target/`, and `vendor/`
+    pub follow_mods: bool, // Collect the files `mod` declarations reach from the crate roots
+    pub expand: bool, // Parse `cargo expand` output instead of the files on disk
+    pub cache_dir: Option<PathBuf>, // Reuse the parse of unchanged files from this directory
+    pub crate_names: HashMap<String, String>, // Analyzed path -> root module of its files in place of `crate`, e.g. a workspace member's package
+    pub metrics: MetricSelection, // Metrics to compute; the rest stay zero
+    pub lcom_transitive: bool, // Propagate field access through self-calls for LCOM
+    pub separate_trait_coupling: bool, // Report trait coupling apart from CBO
+    pub formulas: Vec<(String, Formula)>, // Derived metrics
+    pub query: Option<Query>,
+    pub minimums: Vec<(Metric, f64)>, // Keep only results reaching every minimum
+    pub sort_key: Option<Formula>,
+    pub descending: bool, // Sort by `sort_key` from the highest value
+    pub top: Option<usize>, // Keep only this many results after sorting
+}
+
+/// Extension points between the stages of an `AnalysisPipeline`
+///
+/// Every method defaults to doing nothing.
+pub trait PipelineHook {
+    /// After collecting the files to parse, once per analyzed path with the
+    /// files under it; not called under `--expand`
+    fn files(&mut self, _path: &str, _files: &mut Vec<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
+    /// After parsing and indexing re-exports
+    fn parsed(&mut self, _parsed: &mut ParsedFile) {}
+
+    /// After analyzing, before `--query` and `--sort-by` apply
+    fn results(&mut self, _results: &mut Vec<AnalysisResult>) {}
+}
+
+/// Orchestrates collect → parse → index → analyze → select
+///
+/// Every entry point runs the same stages: `run` for all of them, or
+/// `parse`, `analyze`, and `select` separately to act between them.
+pub struct AnalysisPipeline<'a> {
+    context: &'a AnalysisContext,
+    hooks: Vec<Box<dyn PipelineHook + 'a>>,
+}
+
+/// Output of a full pipeline run
+pub struct Analysis {
+    pub parsed: ParsedFile,
+    pub results: Vec<AnalysisResult>, // Selected and sorted
+}
+
+impl<'a> AnalysisPipeline<'a> {
+    pub fn new(context: &'a AnalysisContext) -> Self {
+        Self {
+            context,
+            hooks: Vec::new(),
+        }
+    }
+
+    pub fn with_hook(mut self, hook: impl PipelineHook + 'a) -> Self {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Run every stage on the crate at `path`
+    pub fn run(&mut self, path: &str) -> Result<Analysis, Box<dyn std::error::Error>> {
+        let parsed = self.parse(path)?;
+        let mut results = self.analyze(&parsed.structs);
+        self.select(&mut results);
+        Ok(Analysis { parsed, results })
+    }
+
+    /// Collect, parse, and index everything under `path`, from disk or
+    /// from `cargo expand` output
+    pub fn parse(&mut self, path: &str) -> Result<ParsedFile, Box<dyn std::error::Error>> {
+        self.parse_paths(&[path.to_string()])
+    }
+
+    /// Like `parse`, with the files under every one of `paths` as one crate
+    ///
+    /// Hooks see each path with its files. Files under several paths are
+    /// parsed once.
+    pub fn parse_paths(&mut self, paths: &[String]) -> Result<ParsedFile, Box<dyn std::error::Error>> {
+        if paths.is_empty() {
+            return Err("No paths to analyze".into());
+        }
+        let mut parsed = if self.context.expand {
+            parse_expanded_crates(paths, &self.context.parse_options)?
+        } else {
+            let filter = PathFilter::new(&self.context.include, &self.context.exclude).map_err(|e| e.to_string())?;
+            let mut files_by_path = Vec::new();
+            let mut module_paths = HashMap::new();
+            for path in paths {
+                let mut files = Vec::new();
+                if self.context.follow_mods {
+                    let roots = modules::crate_roots(Path::new(path));
+                    if roots.is_empty() {
+                        return Err(format!("No lib.rs or main.rs found in {} to follow mod declarations from", path).into());
+                    }
+                    for (file, module_path) in roots.iter().flat_map(|(root, module)| modules::module_files(root, module)) {
+                        if filter.accepts(file.strip_prefix(path).unwrap_or(&file)) {
+                            files.push(file.clone());
+                            module_paths.entry(file).or_insert(module_path);
+                        }
+                    }
+                } else {
+                    files.extend(collect_rust_files(path, &filter, self.context.no_ignore)?);
+                }
+                files_by_path.push(files);
+            }
+            if files_by_path.iter().all(Vec::is_empty) {
+                return Err(format!("No Rust files found in {}", paths.join(", ")).into());
+            }
+            for (path, files) in paths.iter().zip(&mut files_by_path) {
+                for hook in &mut self.hooks {
+                    hook.files(path, files)?;
+                }
+            }
+            let mut files: Vec<PathBuf> = files_by_path.into_iter().flatten().collect();
+            files.sort();
+            files.dedup();
+            let files: Vec<(PathBuf, String)> = files
+                .into_iter()
+                .map(|file| {
+                    let module_path = module_paths.remove(&file).unwrap_or_else(|| module_path(&file, paths));
+                    let crate_name = paths.iter().find(|path| file.starts_with(path)).and_then(|path| self.context.crate_names.get(path));
+                    let module_path = match crate_name {
+                        Some(name) => renamed_root(&module_path, name),
+                        None => module_path,
+                    };
+                    (file, module_path)
+                })
+                .collect();
+            match &self.context.cache_dir {
+                Some(dir) => cache::parse_files(&files, &self.context.parse_options, dir)?,
+                None => parse_files(&files, &self.context.parse_options)?,
+            }
+        };
+
+        // Index: record the paths every struct is re-exported and aliased
+        // under, and the traits it shares with the rest of the crate
+        resolve::apply_reexports(&mut parsed.structs, &parsed.reexports);
+        resolve::apply_aliases(&mut parsed.structs, &parsed.aliases);
+        resolve::apply_crate_traits(&mut parsed.structs, &parsed.traits);
+
+        for hook in &mut self.hooks {
+            hook.parsed(&mut parsed);
+        }
+        Ok(parsed)
+    }
+
+    /// Calculate the metrics of every struct, including derived metrics
+    pub fn analyze(&mut self, all_structs: &[StructInfo]) -> Vec<AnalysisResult> {
+        let index = StructIndex::new(all_structs);
+        let mut results: Vec<AnalysisResult> =
+            all_structs.iter().map(|s| self.analyze_struct(s, &index)).collect();
+        metrics::afferent::apply(&mut results);
+        for result in &mut results {
+            metrics::apply_derived(result, &self.context.formulas);
+        }
+
+        for hook in &mut self.hooks {
+            hook.results(&mut results);
+        }
+        results
+    }
+
+    /// Like `analyze` followed by `select`, handing every selected result
+    /// to `emit` as soon as it is analyzed instead of collecting them
+    ///
+    /// Afferent metrics come from a first pass over the dependencies of
+    /// every struct. Hooks see each result alone, and `--sort-by`, which
+    /// needs every result, is not applied.
+    pub fn stream(
+        &mut self,
+        all_structs: &[StructInfo],
+        mut emit: impl FnMut(&AnalysisResult) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let index = StructIndex::new(all_structs);
+        let dependencies: Vec<_> = all_structs.iter().map(|s| metrics::dependencies(s, &index)).collect();
+        let incoming = metrics::afferent::Incoming::new(dependencies.iter().map(Vec::as_slice));
+        drop(dependencies);
+
+        let mut emitted = 0;
+        for struct_info in all_structs {
+            if self.context.top.is_some_and(|top| emitted >= top) {
+                break;
+            }
+            let mut result = self.analyze_struct(struct_info, &index);
+            incoming.apply(&mut result);
+            metrics::apply_derived(&mut result, &self.context.formulas);
+
+            let mut results = vec![result];
+            for hook in &mut self.hooks {
+                hook.results(&mut results);
+            }
+            for result in results.iter().filter(|r| self.keeps(r)) {
+                emit(result)?;
+                emitted += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Metrics of one struct, before afferent and derived metrics
+    fn analyze_struct(&self, struct_info: &StructInfo, index: &StructIndex) -> AnalysisResult {
+        let mut result = metrics::analyze_selected(struct_info, index, &self.context.metrics);
+        if self.context.lcom_transitive && (self.context.metrics.all || self.context.metrics.lcom) {
+            result.lcom = metrics::lcom::calculate_transitive(struct_info);
+        }
+        if self.context.separate_trait_coupling && (self.context.metrics.all || self.context.metrics.cbo) {
+            result.cbo = metrics::cbo::struct_coupling(struct_info, index);
+            result.trait_coupling = Some(metrics::cbo::trait_coupling(struct_info));
+        }
+        if self.context.parse_options.derives != DeriveMode::On {
+            result.derived_coupling = 0; // Listed derives are only reported
+        }
+        result
+    }
+
+    /// Keep the results matching `--query` and the `--min-*` flags, order
+    /// them by `--sort-by`, and keep the first `--top`
+    pub fn select(&self, results: &mut Vec<AnalysisResult>) {
+        results.retain(|r| self.keeps(r));
+        if let Some(key) = &self.context.sort_key {
+            // Stable; NaN (e.g. division by zero) sorts last either way
+            results.sort_by(|a, b| {
+                let (a, b) = (key.eval(a), key.eval(b));
+                let order = if self.context.descending { b.total_cmp(&a) } else { a.total_cmp(&b) };
+                a.is_nan().cmp(&b.is_nan()).then(order)
+            });
+        }
+        if let Some(top) = self.context.top {
+            results.truncate(top);
+        }
+    }
+
+    /// Whether `result` matches `--query` and reaches every `--min-*`
+    fn keeps(&self, result: &AnalysisResult) -> bool {
+        self.context.query.as_ref().is_none_or(|query| query.matches(result))
+            && self.context.minimums.iter().all(|(metric, min)| metric.value(result) >= *min)
+    }
+}
+
+/// Parse `files`, each with its module path, as one crate
+fn parse_files(
+    rust_files: &[(PathBuf, String)],
+    options: &ParseOptions,
+) -> Result<ParsedFile, Box<dyn std::error::Error>> {
+    let mut sources = Vec::with_capacity(rust_files.len());
+
+    for (file_path, module_path) in rust_files {
+        let content = std::fs::read_to_string(file_path)?;
+
+        match SourceFile::parse(&content, module_path, &file_path.display().to_string()) {
+            Ok(source) => sources.push(source),
+            Err(e) => {
+                eprintln!("Warning: Failed to parse {}: {}", file_path.display(), e);
+            }
+        }
+    }
+
+    Ok(parser::parse_sources(&sources, options))
+}
+
+/// Module path of a file, relative to the analyzed path containing it
+pub(crate) fn module_path(file_path: &Path, paths: &[String]) -> String {
+    let root = paths
+        .iter()
+        .map(Path::new)
+        .find(|root| file_path.starts_with(root))
+        .unwrap_or(Path::new(&paths[0]));
+    resolve::module_path_for_file(file_path, root)
+}
+
+/// `module_path` with its leading `crate` segment replaced by `name`,
+/// e.g. `core::net` for `crate::net`
+fn renamed_root(module_path: &str, name: &str) -> String {
+    match module_path.strip_prefix("crate") {
+        Some(rest) if rest.is_empty() || rest.starts_with("::") => format!("{}{}", name, rest),
+        _ => module_path.to_string(),
+    }
+}
+
+/// Expand the library and binary targets of the crates holding `paths`
+/// and parse them together, as the files of one directory are
+fn parse_expanded_crates(paths: &[String], options: &ParseOptions) -> Result<ParsedFile, Box<dyn std::error::Error>> {
+    let mut manifests = Vec::new();
+    for path in paths {
+        let manifest = expand::find_manifest(Path::new(path)).ok_or_else(|| format!("No Cargo.toml found for {}", path))?;
+        if !manifests.contains(&manifest) {
+            manifests.push(manifest);
+        }
+    }
+
+    let mut sources = Vec::new();
+    for manifest in &manifests {
+        for target in expand::targets(manifest)? {
+            let expanded = expand::expand_crate(manifest, &target.args)?;
+
+            // Each expanded target is a single file with all modules inlined,
+            // reported at its root source file
+            let source = SourceFile::parse(&expanded, &target.module_path, &target.src_path.display().to_string()).map_err(|e| {
+                format!("Failed to parse expanded source of {} {}: {}", manifest.display(), target.args.join(" "), e)
+            })?;
+            sources.push(source);
+        }
+    }
+
+    Ok(parser::parse_sources(&sources, options))
+}
+
+/// `--include` and `--exclude` patterns
+///
+/// Globs match the path relative to the analyzed directory, e.g.
+/// `**/tests/**` or `*_generated.rs`, where `*` also crosses directories.
+/// An exclude pattern without glob characters keeps its original meaning:
+/// any file or directory whose name contains it is skipped.
+pub struct PathFilter {
+    include: Option<GlobSet>, // None includes everything
+    exclude: GlobSet,
+    words: Vec<String>,
+}
+
+impl PathFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, globset::Error> {
+        let is_glob = |pattern: &str| pattern.contains(['*', '?', '[', '{']);
+        let glob_set = |patterns: &[&String]| {
+            let mut builder = GlobSetBuilder::new();
+            for pattern in patterns {
+                builder.add(Glob::new(pattern)?);
+            }
+            builder.build()
+        };
+
+        let (globs, words): (Vec<&String>, Vec<&String>) = exclude.iter().partition(|p| is_glob(p));
+        Ok(Self {
+            include: if include.is_empty() {
+                None
+            } else {
+                Some(glob_set(&include.iter().collect::<Vec<_>>())?)
+            },
+            exclude: glob_set(&globs)?,
+            words: words.into_iter().cloned().collect(),
+        })
+    }
+
+    /// Whether to analyze the file at `relative` path
+    fn accepts(&self, relative: &Path) -> bool {
+        let named = |word: &String| relative.components().any(|c| c.as_os_str().to_string_lossy().contains(word.as_str()));
+        !self.words.iter().any(named)
+            && !self.exclude.is_match(relative)
+            && self.include.as_ref().is_none_or(|set| set.is_match(relative))
+    }
+}
+
+/// Directories skipped unless `no_ignore`: build output, where generated
+/// code would count twice, and vendored dependencies
+const SKIPPED_DIRS: [&str; 2] = ["target", "vendor"];
+
+/// Rust files under `path` passing `filter`
+///
+/// Like git, skips what `.gitignore` (and `.ignore`) files exclude and
+/// hidden files, and also `SKIPPED_DIRS`, unless `no_ignore`. A file given
+/// as `path` is always analyzed.
+fn collect_rust_files(
+    path: &str,
+    filter: &PathFilter,
+    no_ignore: bool,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut files = Vec::new();
+    let path = Path::new(path);
+
+    if path.is_file() {
+        if path.extension().is_some_and(|e| e == "rs") {
+            files.push(path.to_path_buf());
+        }
+    } else if path.is_dir() {
+        let words = filter.words.clone();
+        let walker = WalkBuilder::new(path)
+            .standard_filters(!no_ignore)
+            .require_git(false)
+            .filter_entry(move |e| {
+                if e.depth() == 0 {
+                    return true;
+                }
+                let name = e.file_name().to_string_lossy();
+                let skipped_dir = e.file_type().is_some_and(|t| t.is_dir()) && SKIPPED_DIRS.contains(&name.as_ref());
+                !words.iter().any(|word| name.contains(word.as_str())) && (no_ignore || !skipped_dir)
+            })
+            .build();
+        for entry in walker.filter_map(|e| e.ok()) {
+            if entry.file_type().is_some_and(|t| t.is_file()) {
+                let file = entry.path();
+                let relative = file.strip_prefix(path).unwrap_or(file);
+                if file.extension().is_some_and(|e| e == "rs") && filter.accepts(relative) {
+                    files.push(file.to_path_buf());
+                }
+            }
+        }
+        // The walk order depends on the platform's directory listing
+        files.sort();
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Drops every file but `lib.rs` and records the struct count
+    struct OnlyLib<'a> {
+        structs: &'a mut usize,
+    }
+
+    impl PipelineHook for OnlyLib<'_> {
+        fn files(&mut self, _path: &str, files: &mut Vec<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+            files.retain(|f| f.ends_with("lib.rs"));
+            Ok(())
+        }
+
+        fn parsed(&mut self, parsed: &mut ParsedFile) {
+            *self.structs = parsed.structs.len();
+        }
+    }
+
+    #[test]
+    fn test_pipeline_runs_hooks_between_stages() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::write(dir.join("lib.rs"), "pub struct Engine; pub struct Wheel;").unwrap();
+        std::fs::write(dir.join("other.rs"), "pub struct Ignored;").unwrap();
+
+        let context = AnalysisContext {
+            sort_key: Some(Formula::parse("methods", &[]).unwrap()),
+            query: Some(Query::parse("name != 'Wheel'", &[]).unwrap()),
+            ..Default::default()
+        };
+        let mut parsed_structs = 0;
+        let analysis = AnalysisPipeline::new(&context)
+            .with_hook(OnlyLib {
+                structs: &mut parsed_structs,
+            })
+            .run(dir.to_str().unwrap())
+            .unwrap();
+
+        assert_eq!(parsed_structs, 2);
+        let names: Vec<&str> = analysis.results.iter().map(|r| r.struct_name.as_str()).collect();
+        assert_eq!(names, vec!["Engine"]);
+    }
+
+    #[test]
+    fn test_select_filters_sorts_descending_and_keeps_top() {
+        let result = |name: &str, wmc| AnalysisResult {
+            struct_name: name.to_string(),
+            wmc,
+            ..Default::default()
+        };
+        let mut results = vec![
+            result("Small", 3),
+            result("Large", 40),
+            result("Tiny", 1),
+            result("Medium", 12),
+            result("Huge", 90),
+        ];
+        let context = AnalysisContext {
+            sort_key: Some(Formula::parse("wmc * (wmc - 12) / (wmc - 12)", &[]).unwrap()),
+            minimums: vec![(Metric::Wmc, 3.0)],
+            descending: true,
+            top: Some(3),
+            ..Default::default()
+        };
+        AnalysisPipeline::new(&context).select(&mut results);
+
+        // Medium divides zero by zero
+        let names: Vec<&str> = results.iter().map(|r| r.struct_name.as_str()).collect();
+        assert_eq!(names, vec!["Huge", "Large", "Small"]);
+    }
+
+    #[test]
+    fn test_select_keeps_results_at_every_minimum() {
+        let result = |name: &str, wmc, lcom, cbo| AnalysisResult {
+            struct_name: name.to_string(),
+            wmc,
+            lcom,
+            cbo,
+            ..Default::default()
+        };
+        let mut results = vec![
+            result("AtEvery", 10, 0.5, 3),
+            result("Above", 11, 0.9, 4),
+            result("WmcBelow", 9, 0.9, 4),
+            result("LcomBelow", 11, 0.499, 4),
+            result("CboBelow", 11, 0.9, 2),
+        ];
+        let context = AnalysisContext {
+            minimums: vec![(Metric::Wmc, 10.0), (Metric::Lcom, 0.5), (Metric::Cbo, 3.0)],
+            ..Default::default()
+        };
+        AnalysisPipeline::new(&context).select(&mut results);
+
+        // `--min-*` are inclusive
+        let names: Vec<&str> = results.iter().map(|r| r.struct_name.as_str()).collect();
+        assert_eq!(names, vec!["AtEvery", "Above"]);
+    }
+
+    #[test]
+    fn test_run_keeps_structs_reaching_min_wmc() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::write(
+            dir.join("lib.rs"),
+            "pub struct One;\nimpl One { fn a(&self) {} }\n\
+             pub struct Two;\nimpl Two { fn a(&self) {} fn b(&self) {} }\n\
+             pub struct Three;\nimpl Three { fn a(&self) {} fn b(&self) {} fn c(&self) {} }",
+        )
+        .unwrap();
+        let context = AnalysisContext {
+            minimums: vec![(Metric::Wmc, 2.0)],
+            ..Default::default()
+        };
+
+        let analysis = AnalysisPipeline::new(&context).run(dir.to_str().unwrap()).unwrap();
+        let kept: Vec<(&str, usize)> = analysis.results.iter().map(|r| (r.struct_name.as_str(), r.wmc)).collect();
+        assert_eq!(kept, vec![("Two", 2), ("Three", 3)]);
+    }
+
+    #[test]
+    fn test_stream_matches_analyze_and_select() {
+        let source = "struct Config { name: String }\n\
+                      struct Server { config: Config }\n\
+                      struct Client { config: Config, server: Server }";
+        let structs = parser::parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let context = AnalysisContext {
+            minimums: vec![(Metric::Cbo, 1.0)],
+            top: Some(1),
+            ..Default::default()
+        };
+        let mut pipeline = AnalysisPipeline::new(&context);
+        let mut expected = pipeline.analyze(&structs);
+        pipeline.select(&mut expected);
+
+        let mut streamed = Vec::new();
+        pipeline
+            .stream(&structs, |result| {
+                streamed.push(result.clone());
+                Ok(())
+            })
+            .unwrap();
+        let summary = |results: &[AnalysisResult]| -> Vec<(String, usize, usize, f64)> {
+            results.iter().map(|r| (r.struct_name.clone(), r.cbo, r.ca, r.instability)).collect()
+        };
+        assert_eq!(summary(&streamed), summary(&expected));
+        assert_eq!(summary(&streamed), vec![("Server".to_string(), 1, 1, 0.5)]);
+    }
+
+    #[test]
+    fn test_parse_paths_as_one_crate() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        for sub in ["app", "tools"] {
+            std::fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        std::fs::write(dir.join("app/engine.rs"), "pub struct Engine { cache: Cache }").unwrap();
+        std::fs::write(dir.join("tools/cache.rs"), "pub struct Cache;").unwrap();
+
+        let paths = [
+            dir.join("app").display().to_string(),
+            dir.join("tools").display().to_string(),
+            dir.join("tools/cache.rs").display().to_string(),
+        ];
+        let context = AnalysisContext::default();
+        let mut pipeline = AnalysisPipeline::new(&context);
+        let parsed = pipeline.parse_paths(&paths).unwrap();
+        let results = pipeline.analyze(&parsed.structs);
+
+        let names: Vec<String> = results.iter().map(AnalysisResult::qualified_name).collect();
+        assert_eq!(names, vec!["crate::engine::Engine", "crate::cache::Cache"]);
+        assert_eq!(results[0].cbo, 1);
+    }
+
+    #[test]
+    fn test_parse_paths_roots_named_crates_at_their_name() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        for member in ["a", "b"] {
+            std::fs::create_dir_all(dir.join(member).join("src")).unwrap();
+        }
+        std::fs::write(dir.join("a/src/lib.rs"), "pub struct Config;").unwrap();
+        std::fs::write(dir.join("b/src/lib.rs"), "pub struct Config;\npub struct Tool { config: Config }").unwrap();
+        std::fs::write(dir.join("b/src/remote.rs"), "use a::Config;\npub struct Remote { config: Config }").unwrap();
+
+        let paths: Vec<String> = ["a", "b"].iter().map(|m| dir.join(m).join("src").display().to_string()).collect();
+        let context = AnalysisContext {
+            crate_names: paths.iter().cloned().zip(["a".to_string(), "b".to_string()]).collect(),
+            ..Default::default()
+        };
+        let mut pipeline = AnalysisPipeline::new(&context);
+        let parsed = pipeline.parse_paths(&paths).unwrap();
+        let results = pipeline.analyze(&parsed.structs);
+
+        let summary: Vec<(String, usize, usize)> = results.iter().map(|r| (r.qualified_name(), r.cbo, r.ca)).collect();
+        let expected = [("a::Config", 0, 1), ("b::Config", 0, 1), ("b::Tool", 1, 0), ("b::remote::Remote", 1, 0)];
+        assert_eq!(summary, expected.map(|(name, cbo, ca)| (name.to_string(), cbo, ca)));
+    }
+
+    /// Records the path and file count of every `files` call
+    struct FilesPerPath<'a> {
+        calls: &'a mut Vec<(String, usize)>,
+    }
+
+    impl PipelineHook for FilesPerPath<'_> {
+        fn files(&mut self, path: &str, files: &mut Vec<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+            self.calls.push((path.to_string(), files.len()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_hooks_see_each_path_with_its_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        for (file, content) in [("app/lib.rs", "pub struct App;"), ("tools/a.rs", ""), ("tools/b.rs", "")] {
+            std::fs::create_dir_all(dir.join(file).parent().unwrap()).unwrap();
+            std::fs::write(dir.join(file), content).unwrap();
+        }
+
+        let paths = [dir.join("app").display().to_string(), dir.join("tools").display().to_string()];
+        let context = AnalysisContext::default();
+        let mut calls = Vec::new();
+        AnalysisPipeline::new(&context)
+            .with_hook(FilesPerPath { calls: &mut calls })
+            .parse_paths(&paths)
+            .unwrap();
+
+        assert_eq!(calls, vec![(paths[0].clone(), 1), (paths[1].clone(), 2)]);
+    }
+
+    #[test]
+    fn test_collect_skips_ignored_files_and_build_output() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        for sub in ["src", "target/debug", "vendor/dep"] {
+            std::fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        std::fs::write(dir.join(".gitignore"), "generated.rs\n").unwrap();
+        for file in ["src/lib.rs", "src/generated.rs", "target/debug/out.rs", "vendor/dep/lib.rs"] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+
+        let root = dir.to_str().unwrap();
+        let relative = |files: Vec<PathBuf>| -> Vec<String> {
+            files
+                .iter()
+                .map(|f| f.strip_prefix(dir).unwrap().display().to_string())
+                .collect()
+        };
+        let no_filter = PathFilter::new(&[], &[]).unwrap();
+        let default = relative(collect_rust_files(root, &no_filter, false).unwrap());
+        let all = relative(collect_rust_files(root, &no_filter, true).unwrap());
+
+        assert_eq!(default, vec!["src/lib.rs"]);
+        assert_eq!(all, vec!["src/generated.rs", "src/lib.rs", "target/debug/out.rs", "vendor/dep/lib.rs"]);
+    }
+
+    #[test]
+    fn test_collect_applies_include_and_exclude_patterns() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        for sub in ["src/api", "src/tests", "benches"] {
+            std::fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        for file in [
+            "src/lib.rs",
+            "src/api/client.rs",
+            "src/api/schema_generated.rs",
+            "src/tests/helpers.rs",
+            "src/mock_db.rs",
+            "benches/parse.rs",
+        ] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+
+        let root = dir.to_str().unwrap();
+        let collect = |include: &[&str], exclude: &[&str]| -> Vec<String> {
+            let strings = |patterns: &[&str]| patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+            let filter = PathFilter::new(&strings(include), &strings(exclude)).unwrap();
+            collect_rust_files(root, &filter, false)
+                .unwrap()
+                .iter()
+                .map(|f| f.strip_prefix(dir).unwrap().display().to_string())
+                .collect()
+        };
+        let excluded = collect(&[], &["**/tests/**", "*_generated.rs", "mock"]);
+        let included = collect(&["src/**"], &["src/api/*"]);
+
+        assert_eq!(excluded, vec!["benches/parse.rs", "src/api/client.rs", "src/lib.rs"]);
+        assert_eq!(included, vec!["src/lib.rs", "src/mock_db.rs", "src/tests/helpers.rs"]);
+        assert!(PathFilter::new(&[], &["src/[".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,601 @@
+use crate::models::AnalysisResult;
+
+/// A filter expression over analysis results, e.g. `wmc > 40 && lcom > 0.7`
+///
+/// Comparisons can be combined with `&&`, `||`, `!` and parentheses.
+/// Numeric comparisons take arithmetic on either side (`wmc * lcom > 10`)
+/// over the fields `lcom`, `lcom4`, `tcc`, `lcc`, `cbo`, `atfd`, `ca`, `fan_in`,
+/// `instability`, `wmc`, `cognitive`, `volume`, `difficulty`, `effort`,
+/// `mi` (Maintainability Index), `sloc`, `method_sloc` (mean method
+/// length), `methods` (method count), and derived metrics defined in the
+/// config; they support `==`, `!=`, `<`, `<=`, `>`, `>=`.
+/// Text comparisons take one of the fields `name`, `path` (qualified
+/// name), `module`, `file`, or `kind` on the left and a literal in single
+/// or double quotes on the right; they support `==`, `!=`, and
+/// `~` (contains).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    expr: Expr,
+}
+
+/// An arithmetic formula over the numeric fields, e.g. `wmc * lcom + cbo`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula {
+    expr: NumExpr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Number(NumExpr, Op, NumExpr),
+    Text(TextField, Op, String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum NumExpr {
+    Const(f64),
+    Field(NumField),
+    Derived(String),
+    Neg(Box<NumExpr>),
+    Binary(Box<NumExpr>, Arith, Box<NumExpr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NumField {
+    Lcom,
+    Lcom4,
+    Tcc,
+    Lcc,
+    Cbo,
+    TraitCoupling,
+    DerivedCoupling,
+    Atfd,
+    Ca,
+    FanIn,
+    Instability,
+    Wmc,
+    Cognitive,
+    Volume,
+    Difficulty,
+    Effort,
+    Mi,
+    Sloc,
+    MethodSloc,
+    Methods,
+    Dyn,
+    Errors,
+    ErrorDensity,
+    AsyncMethods,
+    Awaits,
+    Api,
+    Types,
+    Domain,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TextField {
+    Name,
+    Path,
+    Module,
+    File,
+    Kind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Arith {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Text(String),
+    Op(Op),
+    Arith(Arith),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+impl std::str::FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Query::parse(s, &[])
+    }
+}
+
+impl Query {
+    /// Parse a query that may also refer to the given derived metrics
+    pub fn parse(s: &str, derived: &[&str]) -> Result<Self, String> {
+        let mut parser = QueryParser::new(s, derived)?;
+        let expr = parser.parse_or()?;
+        parser.expect_end()?;
+        Ok(Query { expr })
+    }
+
+    pub fn matches(&self, result: &AnalysisResult) -> bool {
+        self.expr.eval(result)
+    }
+}
+
+impl std::str::FromStr for Formula {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Formula::parse(s, &[])
+    }
+}
+
+impl Formula {
+    /// Parse a formula that may also refer to the given derived metrics
+    pub fn parse(s: &str, derived: &[&str]) -> Result<Self, String> {
+        let mut parser = QueryParser::new(s, derived)?;
+        let expr = parser.parse_sum()?;
+        parser.expect_end()?;
+        Ok(Formula { expr })
+    }
+
+    /// Value for a struct; derived metrics missing from it count as NaN
+    pub fn eval(&self, result: &AnalysisResult) -> f64 {
+        self.expr.eval(result)
+    }
+}
+
+impl Expr {
+    fn eval(&self, result: &AnalysisResult) -> bool {
+        match self {
+            Expr::And(a, b) => a.eval(result) && b.eval(result),
+            Expr::Or(a, b) => a.eval(result) || b.eval(result),
+            Expr::Not(e) => !e.eval(result),
+            Expr::Number(left, op, right) => {
+                let (actual, value) = (left.eval(result), right.eval(result));
+                match op {
+                    Op::Eq => actual == value,
+                    Op::Ne => actual != value,
+                    Op::Lt => actual < value,
+                    Op::Le => actual <= value,
+                    Op::Gt => actual > value,
+                    Op::Ge => actual >= value,
+                    Op::Contains => unreachable!("`~` is rejected for numbers"),
+                }
+            }
+            Expr::Text(field, op, value) => {
+                let actual = match field {
+                    TextField::Name => result.struct_name.clone(),
+                    TextField::Path => result.qualified_name(),
+                    TextField::Module => result.module_path.clone(),
+                    TextField::File => result.file_path.clone(),
+                    TextField::Kind => result.kind.to_string(),
+                };
+                match op {
+                    Op::Eq => actual == *value,
+                    Op::Ne => actual != *value,
+                    Op::Contains => actual.contains(value.as_str()),
+                    _ => unreachable!("ordering is rejected for text"),
+                }
+            }
+        }
+    }
+}
+
+impl NumExpr {
+    fn eval(&self, result: &AnalysisResult) -> f64 {
+        match self {
+            NumExpr::Const(value) => *value,
+            NumExpr::Field(NumField::Lcom) => result.lcom,
+            NumExpr::Field(NumField::Lcom4) => result.lcom4 as f64,
+            NumExpr::Field(NumField::Tcc) => result.tcc,
+            NumExpr::Field(NumField::Lcc) => result.lcc,
+            NumExpr::Field(NumField::Cbo) => result.cbo as f64,
+            // NaN without `--separate-trait-coupling`, like a missing derived metric
+            NumExpr::Field(NumField::TraitCoupling) => result.trait_coupling.map_or(f64::NAN, |n| n as f64),
+            NumExpr::Field(NumField::DerivedCoupling) => result.derived_coupling as f64,
+            NumExpr::Field(NumField::Atfd) => result.atfd as f64,
+            NumExpr::Field(NumField::Ca) => result.ca as f64,
+            NumExpr::Field(NumField::FanIn) => result.fan_in as f64,
+            NumExpr::Field(NumField::Instability) => result.instability,
+            NumExpr::Field(NumField::Wmc) => result.wmc as f64,
+            NumExpr::Field(NumField::Cognitive) => result.cognitive as f64,
+            NumExpr::Field(NumField::Volume) => result.volume,
+            NumExpr::Field(NumField::Difficulty) => result.difficulty,
+            NumExpr::Field(NumField::Effort) => result.effort,
+            NumExpr::Field(NumField::Mi) => result.maintainability,
+            NumExpr::Field(NumField::Sloc) => result.sloc as f64,
+            NumExpr::Field(NumField::MethodSloc) => result.mean_method_sloc,
+            NumExpr::Field(NumField::Methods) => result.methods.len() as f64,
+            NumExpr::Field(NumField::Dyn) => result.dyn_dispatch as f64,
+            NumExpr::Field(NumField::Errors) => result.error_types.len() as f64,
+            NumExpr::Field(NumField::ErrorDensity) => result.error_density,
+            NumExpr::Field(NumField::AsyncMethods) => result.async_methods as f64,
+            NumExpr::Field(NumField::Awaits) => result.awaits as f64,
+            NumExpr::Field(NumField::Api) => result.api.map_or(0.0, |api| api.score as f64),
+            NumExpr::Field(NumField::Types) => result.field_types as f64,
+            NumExpr::Field(NumField::Domain) => result.domain_ratio,
+            NumExpr::Derived(name) => result.derived.get(name).copied().unwrap_or(f64::NAN),
+            NumExpr::Neg(e) => -e.eval(result),
+            NumExpr::Binary(left, op, right) => {
+                let (a, b) = (left.eval(result), right.eval(result));
+                match op {
+                    Arith::Add => a + b,
+                    Arith::Sub => a - b,
+                    Arith::Mul => a * b,
+                    Arith::Div => a / b,
+                }
+            }
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        let token = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '(' => {
+                chars.next();
+                Token::LParen
+            }
+            ')' => {
+                chars.next();
+                Token::RParen
+            }
+            '~' => {
+                chars.next();
+                Token::Op(Op::Contains)
+            }
+            '+' | '-' | '*' | '/' => {
+                chars.next();
+                Token::Arith(match c {
+                    '+' => Arith::Add,
+                    '-' => Arith::Sub,
+                    '*' => Arith::Mul,
+                    _ => Arith::Div,
+                })
+            }
+            '&' | '|' => {
+                chars.next();
+                if chars.next() != Some(c) {
+                    return Err(format!("Expected `{0}{0}` in query", c));
+                }
+                if c == '&' { Token::And } else { Token::Or }
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let followed_by_eq = chars.next_if_eq(&'=').is_some();
+                match (c, followed_by_eq) {
+                    ('=', true) => Token::Op(Op::Eq),
+                    ('=', false) => return Err("Use `==` for equality in query".to_string()),
+                    ('!', true) => Token::Op(Op::Ne),
+                    ('!', false) => Token::Not,
+                    ('<', true) => Token::Op(Op::Le),
+                    ('<', false) => Token::Op(Op::Lt),
+                    ('>', true) => Token::Op(Op::Ge),
+                    _ => Token::Op(Op::Gt),
+                }
+            }
+            '\'' | '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some(q) if q == c => break,
+                        Some(ch) => text.push(ch),
+                        None => return Err("Unterminated string in query".to_string()),
+                    }
+                }
+                Token::Text(text)
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut number = String::new();
+                while let Some(ch) = chars.next_if(|ch| ch.is_ascii_digit() || *ch == '.') {
+                    number.push(ch);
+                }
+                Token::Number(number.parse().map_err(|_| format!("Invalid number `{}` in query", number))?)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(ch) = chars.next_if(|ch| ch.is_alphanumeric() || *ch == '_') {
+                    ident.push(ch);
+                }
+                Token::Ident(ident)
+            }
+            _ => return Err(format!("Unexpected character `{}` in query", c)),
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser
+///
+/// From loosest to tightest: `||`, `&&`, `!`, comparisons, `+ -`, `* /`,
+/// unary `-`.
+struct QueryParser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    derived: &'a [&'a str],
+}
+
+impl<'a> QueryParser<'a> {
+    fn new(input: &str, derived: &'a [&'a str]) -> Result<Self, String> {
+        Ok(Self {
+            tokens: tokenize(input)?,
+            pos: 0,
+            derived,
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_end(&self) -> Result<(), String> {
+        match self.peek() {
+            Some(token) => Err(format!("Unexpected {:?} in query", token)),
+            None => Ok(()),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_and()?;
+        while self.eat(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_unary()?;
+        while self.eat(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        if self.eat(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+
+        // A parenthesis opens either a boolean group or an arithmetic one
+        // such as `(wmc + cbo) > 10`; try the boolean reading first
+        if self.peek() == Some(&Token::LParen) {
+            let start = self.pos;
+            self.pos += 1;
+            if let Ok(expr) = self.parse_or() {
+                if self.eat(&Token::RParen) && !matches!(self.peek(), Some(Token::Op(_) | Token::Arith(_))) {
+                    return Ok(expr);
+                }
+            }
+            self.pos = start;
+        }
+
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, String> {
+        if let Some(Token::Ident(name)) = self.peek() {
+            if let Some(field) = text_field(name) {
+                self.pos += 1;
+                let op = match self.next() {
+                    Some(Token::Op(op @ (Op::Eq | Op::Ne | Op::Contains))) => op,
+                    Some(Token::Op(_)) => return Err("Text fields only support `==`, `!=`, and `~`".to_string()),
+                    _ => return Err("Expected a comparison operator after field in query".to_string()),
+                };
+                return match self.next() {
+                    Some(Token::Text(value)) => Ok(Expr::Text(field, op, value)),
+                    _ => Err("Text fields must be compared with quoted text".to_string()),
+                };
+            }
+        }
+
+        let left = self.parse_sum()?;
+        let op = match self.next() {
+            Some(Token::Op(Op::Contains)) => return Err("`~` only applies to text fields".to_string()),
+            Some(Token::Op(op)) => op,
+            _ => return Err("Expected a comparison operator in query".to_string()),
+        };
+        let right = self.parse_sum()?;
+        Ok(Expr::Number(left, op, right))
+    }
+
+    fn parse_sum(&mut self) -> Result<NumExpr, String> {
+        let mut expr = self.parse_product()?;
+        while let Some(Token::Arith(op @ (Arith::Add | Arith::Sub))) = self.peek().cloned() {
+            self.pos += 1;
+            expr = NumExpr::Binary(Box::new(expr), op, Box::new(self.parse_product()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_product(&mut self) -> Result<NumExpr, String> {
+        let mut expr = self.parse_factor()?;
+        while let Some(Token::Arith(op @ (Arith::Mul | Arith::Div))) = self.peek().cloned() {
+            self.pos += 1;
+            expr = NumExpr::Binary(Box::new(expr), op, Box::new(self.parse_factor()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_factor(&mut self) -> Result<NumExpr, String> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(NumExpr::Const(value)),
+            Some(Token::Arith(Arith::Sub)) => Ok(NumExpr::Neg(Box::new(self.parse_factor()?))),
+            Some(Token::LParen) => {
+                let expr = self.parse_sum()?;
+                if !self.eat(&Token::RParen) {
+                    return Err("Missing `)` in query".to_string());
+                }
+                Ok(expr)
+            }
+            Some(Token::Ident(name)) => match num_field(&name) {
+                Some(field) => Ok(NumExpr::Field(field)),
+                None if self.derived.contains(&name.as_str()) => Ok(NumExpr::Derived(name)),
+                None if text_field(&name).is_some() => {
+                    Err(format!("Text field `{}` cannot be used in arithmetic", name))
+                }
+                None => Err(format!("Unknown field in query: {}", name)),
+            },
+            Some(Token::Text(_)) => Err("Numeric fields must be compared with numbers".to_string()),
+            Some(token) => Err(format!("Unexpected {:?} in query", token)),
+            None => Err("Unexpected end of query".to_string()),
+        }
+    }
+}
+
+/// Numeric fields of queries and formulas, by name, in help order
+const NUM_FIELDS: &[(&str, NumField)] = &[
+    ("lcom", NumField::Lcom),
+    ("lcom4", NumField::Lcom4),
+    ("tcc", NumField::Tcc),
+    ("lcc", NumField::Lcc),
+    ("cbo", NumField::Cbo),
+    ("trait_coupling", NumField::TraitCoupling),
+    ("derived_coupling", NumField::DerivedCoupling),
+    ("atfd", NumField::Atfd),
+    ("ca", NumField::Ca),
+    ("fan_in", NumField::FanIn),
+    ("instability", NumField::Instability),
+    ("wmc", NumField::Wmc),
+    ("cognitive", NumField::Cognitive),
+    ("volume", NumField::Volume),
+    ("difficulty", NumField::Difficulty),
+    ("effort", NumField::Effort),
+    ("mi", NumField::Mi),
+    ("sloc", NumField::Sloc),
+    ("method_sloc", NumField::MethodSloc),
+    ("methods", NumField::Methods),
+    ("dyn", NumField::Dyn),
+    ("errors", NumField::Errors),
+    ("error_density", NumField::ErrorDensity),
+    ("async_methods", NumField::AsyncMethods),
+    ("awaits", NumField::Awaits),
+    ("api", NumField::Api),
+    ("types", NumField::Types),
+    ("domain", NumField::Domain),
+];
+
+/// Text fields of queries, by name, in help order
+const TEXT_FIELDS: &[(&str, TextField)] = &[
+    ("name", TextField::Name),
+    ("path", TextField::Path),
+    ("module", TextField::Module),
+    ("file", TextField::File),
+    ("kind", TextField::Kind),
+];
+
+fn num_field(name: &str) -> Option<NumField> {
+    NUM_FIELDS.iter().find(|(field, _)| *field == name).map(|(_, field)| *field)
+}
+
+fn text_field(name: &str) -> Option<TextField> {
+    TEXT_FIELDS.iter().find(|(field, _)| *field == name).map(|(_, field)| *field)
+}
+
+/// Names of the numeric fields, for `--query` and `--sort-by` help
+pub fn num_field_names() -> impl Iterator<Item = &'static str> {
+    NUM_FIELDS.iter().map(|(name, _)| *name)
+}
+
+/// Names of the text fields, for `--query` help
+pub fn text_field_names() -> impl Iterator<Item = &'static str> {
+    TEXT_FIELDS.iter().map(|(name, _)| *name)
+}
+
+/// Whether `name` is a built-in field, which derived metrics may not shadow
+pub fn is_builtin_field(name: &str) -> bool {
+    num_field(name).is_some() || text_field(name).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(name: &str, module_path: &str, lcom: f64, wmc: usize) -> AnalysisResult {
+        AnalysisResult {
+            struct_name: name.to_string(),
+            module_path: module_path.to_string(),
+            lcom,
+            wmc,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_query_precedence_and_text() {
+        let query: Query = "wmc > 40 && lcom > 0.7 || module ~ 'legacy'".parse().unwrap();
+
+        assert!(query.matches(&result("God", "crate", 0.9, 41)));
+        assert!(!query.matches(&result("Busy", "crate", 0.5, 41)));
+        assert!(query.matches(&result("Old", "crate::legacy", 0.0, 1)));
+
+        let query: Query = "!(name == \"Config\")".parse().unwrap();
+        assert!(!query.matches(&result("Config", "crate", 0.0, 0)));
+    }
+
+    #[test]
+    fn test_query_errors() {
+        assert!("wmc = 4".parse::<Query>().is_err());
+        assert!("wmc > 'x'".parse::<Query>().is_err());
+        assert!("name < 'x'".parse::<Query>().is_err());
+        assert!("size > 4".parse::<Query>().is_err());
+        assert!("(wmc > 4".parse::<Query>().is_err());
+        assert!("wmc > 4 lcom".parse::<Query>().is_err());
+    }
+
+    #[test]
+    fn test_arithmetic_and_derived_metrics() {
+        let formula: Formula = "wmc * lcom + -2 / (1 + 1)".parse().unwrap();
+        assert_eq!(formula.eval(&result("S", "crate", 0.5, 10)), 4.0);
+
+        let mut busy = result("Busy", "crate", 0.5, 10);
+        busy.derived.insert("debt".to_string(), 12.0);
+
+        let query = Query::parse("(debt - wmc) * 2 >= 4 && (lcom < 1 || wmc < 1)", &["debt"]).unwrap();
+        assert!(query.matches(&busy));
+
+        assert!("debt > 1".parse::<Query>().is_err());
+        assert!("name * 2 > 1".parse::<Query>().is_err());
+    }
+}
@@ -0,0 +1,51 @@
+use crate::api::ApiReport;
+use crate::models::OutputFormat;
+
+/// Render the API surface report as a table or JSON
+pub fn generate_api(report: &ApiReport, format: OutputFormat) -> Result<String, Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Table => Ok(generate_table(report)),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(report)?),
+        _ => Err("api supports only table and json output".into()),
+    }
+}
+
+fn generate_table(report: &ApiReport) -> String {
+    let mut output = String::new();
+
+    output.push_str(&format!(
+        "{:<40} {:>6} {:>10} {:>11} {:>9} {:>15}\n",
+        "Pub Struct", "Score", "Pub Fields", "Pub Methods", "Generics", "Non-Exhaustive"
+    ));
+    output.push_str(&"-".repeat(96));
+    output.push('\n');
+    if report.structs.is_empty() {
+        output.push_str("No pub structs found.\n");
+    }
+    for entry in &report.structs {
+        let surface = &entry.surface;
+        output.push_str(&format!(
+            "{:<40} {:>6} {:>10} {:>11} {:>9} {:>15}\n",
+            entry.struct_name,
+            surface.score,
+            surface.pub_fields,
+            surface.pub_methods,
+            surface.generic_params,
+            if surface.non_exhaustive { "yes" } else { "no" }
+        ));
+    }
+
+    output.push_str(&format!(
+        "\nExhaustively matchable pub enums ({}):\n",
+        report.exhaustive_enums.len()
+    ));
+    for info in &report.exhaustive_enums {
+        output.push_str(&format!(
+            "  {}::{} ({} variants)\n",
+            info.module_path, info.name, info.variants
+        ));
+    }
+
+    output.push_str("\nScore: pub methods + 2 per pub field (1 if #[non_exhaustive]) + 2 per generic parameter\n");
+    output
+}
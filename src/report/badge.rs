@@ -0,0 +1,66 @@
+use crate::score::Grade;
+
+/// Render a shields.io-style SVG badge showing the grade and score
+pub fn generate_badge(grade: &Grade) -> String {
+    let label = "architecture";
+    let message = format!("{} ({:.0})", grade.grade, grade.score);
+
+    // Approximate Verdana 11px text width; good enough for short labels
+    let label_width = text_width(label);
+    let message_width = text_width(&message);
+    let width = label_width + message_width;
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}">
+<title>{label}: {message}</title>
+<linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient>
+<clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+<g clip-path="url(#r)">
+<rect width="{label_width}" height="20" fill="#555"/>
+<rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/>
+<rect width="{width}" height="20" fill="url(#s)"/>
+</g>
+<g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+<text x="{label_x}" y="14">{label}</text>
+<text x="{message_x}" y="14">{message}</text>
+</g>
+</svg>
+"##,
+        color = grade_color(grade.grade),
+        label_x = label_width / 2,
+        message_x = label_width + message_width / 2,
+    )
+}
+
+fn text_width(text: &str) -> usize {
+    text.chars().count() * 7 + 10
+}
+
+fn grade_color(grade: &str) -> &'static str {
+    match grade {
+        "A" => "#4c1",
+        "B" => "#97ca00",
+        "C" => "#dfb317",
+        "D" => "#fe7d37",
+        _ => "#e05d44",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_badge_shows_grade_and_score() {
+        let grade = Grade {
+            score: 84.6,
+            grade: "B",
+            struct_count: 3,
+            violations: 0,
+        };
+
+        let svg = generate_badge(&grade);
+        assert!(svg.contains("<text x=\"120\" y=\"14\">B (85)</text>"));
+        assert!(svg.contains("fill=\"#97ca00\""));
+    }
+}
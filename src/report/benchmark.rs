@@ -0,0 +1,84 @@
+use crate::benchmark::{Benchmark, MetricRank};
+use crate::models::OutputFormat;
+
+/// Render percentile ranks against a reference dataset as a table or JSON
+pub fn generate_benchmark(benchmark: &Benchmark, format: OutputFormat) -> Result<String, Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Table => Ok(generate_table(benchmark)),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(benchmark)?),
+        _ => Err("benchmark supports only table and json output".into()),
+    }
+}
+
+fn generate_table(benchmark: &Benchmark) -> String {
+    let mut output = format!(
+        "Percentiles against {} reference crates (higher means worse than more of them)\n\n",
+        benchmark.reference_crates
+    );
+
+    output.push_str("Project:\n");
+    for rank in &benchmark.project {
+        output.push_str(&format!(
+            "  Average {:<5} {:>8.3}   {} percentile\n",
+            rank.metric.to_uppercase(),
+            rank.value,
+            ordinal(rank.percentile)
+        ));
+    }
+
+    if benchmark.structs.is_empty() {
+        return output;
+    }
+
+    output.push_str(&format!(
+        "\n{:<40} {:>14} {:>14} {:>14}\n",
+        "Struct", "LCOM", "CBO", "WMC"
+    ));
+    output.push_str(&"-".repeat(85));
+    output.push('\n');
+    for rank in &benchmark.structs {
+        output.push_str(&format!("{:<40}", rank.struct_name));
+        for metric in &rank.metrics {
+            output.push_str(&format!(" {:>14}", cell(metric)));
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+fn cell(rank: &MetricRank) -> String {
+    let value = if rank.metric == "lcom" {
+        format!("{:.2}", rank.value)
+    } else {
+        format!("{}", rank.value)
+    };
+    format!("{} ({})", value, ordinal(rank.percentile))
+}
+
+/// `87.4` -> `87th`, `1` -> `1st`, `12` -> `12th`
+fn ordinal(percentile: f64) -> String {
+    let n = percentile.round() as u32;
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ordinal() {
+        assert_eq!(ordinal(87.4), "87th");
+        assert_eq!(ordinal(1.0), "1st");
+        assert_eq!(ordinal(12.0), "12th");
+        assert_eq!(ordinal(22.0), "22nd");
+        assert_eq!(ordinal(100.0), "100th");
+    }
+}
@@ -0,0 +1,73 @@
+use crate::models::OutputFormat;
+use crate::thresholds::CheckOutcome;
+
+/// Render the outcome of the `check` gate as a table or JSON
+pub fn generate_check(outcome: &CheckOutcome, format: OutputFormat) -> Result<String, Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Table => Ok(generate_table(outcome)),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(outcome)?),
+        _ => Err("check supports only table and json output".into()),
+    }
+}
+
+fn generate_table(outcome: &CheckOutcome) -> String {
+    let mut output = match &outcome.base {
+        Some(base) => format!(
+            "Checked {} struct(s) in files changed since {} ({} unchanged skipped)\n",
+            outcome.checked, base, outcome.skipped
+        ),
+        None => format!("Checked {} struct(s)\n", outcome.checked),
+    };
+
+    if outcome.violations.is_empty() {
+        output.push_str("No threshold violations.\n");
+        return output;
+    }
+
+    output.push_str(&format!(
+        "\n{:<50} {:>8} {:>10} {:>10}\n",
+        "Struct", "Metric", "Value", "Threshold"
+    ));
+    output.push_str(&"-".repeat(81));
+    output.push('\n');
+    for violation in &outcome.violations {
+        output.push_str(&format!(
+            "{:<50} {:>8} {:>10.3} {:>10.3}\n",
+            violation.struct_name,
+            violation.metric.to_uppercase(),
+            violation.value,
+            violation.threshold
+        ));
+    }
+    output.push_str(&format!("\n{} threshold violation(s).\n", outcome.violations.len()));
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thresholds::Violation;
+
+    #[test]
+    fn test_check_table_lists_violations() {
+        let outcome = CheckOutcome {
+            base: Some("origin/main".to_string()),
+            checked: 2,
+            skipped: 40,
+            violations: vec![Violation {
+                struct_name: "crate::God".to_string(),
+                file_path: "src/god.rs".to_string(),
+                span: Default::default(),
+                metric: "wmc".to_string(),
+                value: 52.0,
+                threshold: 40.0,
+            }],
+        };
+
+        let table = generate_check(&outcome, OutputFormat::Table).unwrap();
+        assert!(table.starts_with("Checked 2 struct(s) in files changed since origin/main (40 unchanged skipped)"));
+        assert!(table.contains("crate::God"));
+        assert!(table.contains("1 threshold violation(s)."));
+    }
+}
@@ -0,0 +1,117 @@
+use crate::compare::Comparison;
+use crate::models::OutputFormat;
+
+/// Render a comparison of two source trees as a table or JSON
+pub fn generate_comparison(
+    comparison: &Comparison,
+    format: OutputFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Table => Ok(generate_table(comparison)),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(comparison)?),
+        _ => Err("compare supports only table and json output".into()),
+    }
+}
+
+fn generate_table(comparison: &Comparison) -> String {
+    let (a, b) = (&comparison.summary_a, &comparison.summary_b);
+    let mut output = String::new();
+
+    output.push_str(&format!("A: {}\nB: {}\n\n", comparison.path_a, comparison.path_b));
+
+    // Aggregates
+    output.push_str(&format!(
+        "{:<20} {:>10} {:>10} {:>10}  {}\n",
+        "Aggregate", "A", "B", "Delta", "Effect"
+    ));
+    output.push_str(&"-".repeat(70));
+    output.push('\n');
+    let effect = |metric: &str| {
+        comparison
+            .significance
+            .iter()
+            .find(|s| s.metric == metric)
+            .map_or(String::new(), |s| format!("d={:+.2} {}", s.effect_size, s.verdict))
+    };
+    let rows = [
+        ("Structs", a.struct_count as f64, b.struct_count as f64, String::new()),
+        ("Mean LCOM", a.mean_lcom, b.mean_lcom, effect("lcom")),
+        ("Mean CBO", a.mean_cbo, b.mean_cbo, effect("cbo")),
+        ("Mean WMC", a.mean_wmc, b.mean_wmc, effect("wmc")),
+        ("Total WMC", a.total_wmc as f64, b.total_wmc as f64, String::new()),
+        ("Max WMC", a.max_wmc as f64, b.max_wmc as f64, String::new()),
+    ];
+    for (label, value_a, value_b, effect) in rows {
+        output.push_str(
+            format!(
+                "{:<20} {:>10} {:>10} {:>10}  {}",
+                label,
+                format_number(value_a),
+                format_number(value_b),
+                format_delta(value_b - value_a),
+                effect
+            )
+            .trim_end(),
+        );
+        output.push('\n');
+    }
+    output.push_str("Effect: mean change in pooled standard deviations (Cohen's d); |d| < 0.2 is noise\n");
+
+    // Matched structs
+    output.push_str(&format!("\nMatched structs ({}):\n", comparison.matched.len()));
+    if !comparison.matched.is_empty() {
+        output.push_str(&format!(
+            "{:<50} {:>15} {:>11} {:>11}\n",
+            "Struct", "LCOM", "CBO", "WMC"
+        ));
+        output.push_str(&"-".repeat(90));
+        output.push('\n');
+        for delta in &comparison.matched {
+            output.push_str(&format!(
+                "{:<50} {:>15} {:>11} {:>11}\n",
+                delta.struct_name,
+                format!("{:.2}->{:.2}", delta.lcom_a, delta.lcom_b),
+                format!("{}->{}", delta.cbo_a, delta.cbo_b),
+                format!("{}->{}", delta.wmc_a, delta.wmc_b),
+            ));
+        }
+    }
+
+    for (label, names) in [("Only in A", &comparison.only_in_a), ("Only in B", &comparison.only_in_b)] {
+        output.push_str(&format!("\n{} ({}):\n", label, names.len()));
+        for name in names {
+            output.push_str(&format!("  {}\n", name));
+        }
+    }
+
+    output
+}
+
+/// Whole numbers without decimals, everything else with three
+pub(super) fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value)
+    } else {
+        format!("{:.3}", value)
+    }
+}
+
+pub(super) fn format_delta(delta: f64) -> String {
+    if delta > 0.0 {
+        format!("+{}", format_number(delta))
+    } else {
+        format_number(delta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_delta() {
+        assert_eq!(format_delta(3.0), "+3");
+        assert_eq!(format_delta(-0.25), "-0.250");
+        assert_eq!(format_delta(0.0), "0");
+    }
+}
@@ -0,0 +1,42 @@
+use crate::graph::{find_cycles, DependencyCycle};
+use crate::models::{AnalysisResult, OutputFormat};
+
+/// Render the dependency cycles among structs, with the paths forming them
+pub fn generate_cycle_report(
+    results: &[AnalysisResult],
+    format: OutputFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let cycles = find_cycles(results);
+
+    match format {
+        OutputFormat::Table => Ok(generate_table(&cycles)),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(&cycles)?),
+        _ => Err("--detect-cycles supports only table and json output".into()),
+    }
+}
+
+fn generate_table(cycles: &[DependencyCycle]) -> String {
+    if cycles.is_empty() {
+        return "No dependency cycles between structs found.".to_string();
+    }
+
+    let mut output = String::new();
+
+    let structs: usize = cycles.iter().map(|c| c.structs.len()).sum();
+    output.push_str(&format!(
+        "Dependency cycles: {} ({} structs involved)\n",
+        cycles.len(),
+        structs
+    ));
+
+    for cycle in cycles {
+        output.push_str(&format!("\n{} structs: {}\n", cycle.structs.len(), cycle.structs.join(", ")));
+        for path in &cycle.cycles {
+            let mut path = path.clone();
+            path.push(path[0].clone());
+            output.push_str(&format!("  {}\n", path.join(" -> ")));
+        }
+    }
+
+    output
+}
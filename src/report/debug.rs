@@ -0,0 +1,48 @@
+use crate::debug::StructDump;
+use crate::models::OutputFormat;
+
+/// Render the parsed data of structs as a text dump or JSON
+pub fn generate_debug(dumps: &[StructDump], format: OutputFormat) -> Result<String, Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Table => Ok(generate_text(dumps)),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(dumps)?),
+        _ => Err("--debug-struct supports only table and json output".into()),
+    }
+}
+
+fn generate_text(dumps: &[StructDump]) -> String {
+    let mut output = String::new();
+
+    for (i, s) in dumps.iter().enumerate() {
+        if i > 0 {
+            output.push('\n');
+        }
+        output.push_str(&format!("=== Debug: {} ({}) ===\n", s.qualified_name, s.kind));
+        if !s.file_path.is_empty() {
+            output.push_str(&format!("File: {}:{}-{}\n", s.file_path, s.span.start_line, s.span.end_line));
+        }
+        output.push_str(&format!("Fields ({}):\n", s.fields.len()));
+        for f in &s.fields {
+            output.push_str(&format!("  - {}: {}\n", f.name, f.ty));
+        }
+        output.push_str(&format!("\nMethods ({}):\n", s.methods.len()));
+        for m in &s.methods {
+            output.push_str(&format!(
+                "  {} (lines {}-{}, {}): fields_accessed={:?}, complexity={}, calls={:?}\n",
+                m.name, m.span.start_line, m.span.end_line, m.impl_block, m.fields_accessed, m.complexity, m.calls
+            ));
+        }
+        output.push_str(&format!("\nExternal types: {:?}\n", s.external_types));
+        output.push_str(&format!("Traits implemented: {:?}\n", s.traits));
+        output.push_str(&format!("Traits derived: {:?}\n", s.derives));
+        output.push_str(&format!("Traits referenced (bounds, dyn, impl Trait): {:?}\n", s.referenced_traits));
+        output.push_str(&format!("Re-exported as: {:?}\n", s.reexported_as));
+        output.push_str(&format!("Aliased as: {:?}\n", s.aliases));
+        output.push_str(&format!("\nCoupling sources ({}):\n", s.coupling.len()));
+        for c in &s.coupling {
+            output.push_str(&format!("  - {} via {}\n", c.target, c.source));
+        }
+    }
+
+    output
+}
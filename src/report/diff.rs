@@ -0,0 +1,50 @@
+use crate::diff::Diff;
+use crate::models::OutputFormat;
+
+use super::compare::{format_delta, format_number};
+
+/// Render the changes between two analysis runs as a table or JSON
+pub fn generate_diff(diff: &Diff, format: OutputFormat) -> Result<String, Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Table => Ok(generate_table(diff)),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(diff)?),
+        _ => Err("diff supports only table and json output".into()),
+    }
+}
+
+fn generate_table(diff: &Diff) -> String {
+    let mut output = format!("Old: {}\nNew: {}\n", diff.old, diff.new);
+
+    output.push_str(&format!("\nChanged structs ({}):\n", diff.changed.len()));
+    if !diff.changed.is_empty() {
+        output.push_str(&format!(
+            "{:<50} {:<10} {:>10} {:>10} {:>10}\n",
+            "Struct", "Metric", "Old", "New", "Delta"
+        ));
+        output.push_str(&"-".repeat(94));
+        output.push('\n');
+        for change in &diff.changed {
+            for (i, delta) in change.deltas.iter().enumerate() {
+                let name = if i == 0 { change.struct_name.as_str() } else { "" };
+                output.push_str(&format!(
+                    "{:<50} {:<10} {:>10} {:>10} {:>10}\n",
+                    name,
+                    delta.metric,
+                    format_number(delta.old),
+                    format_number(delta.new),
+                    format_delta(delta.delta()),
+                ));
+            }
+        }
+    }
+
+    for (label, sign, names) in [("Added", '+', &diff.added), ("Removed", '-', &diff.removed)] {
+        output.push_str(&format!("\n{} structs ({}):\n", label, names.len()));
+        for name in names {
+            output.push_str(&format!("  {} {}\n", sign, name));
+        }
+    }
+
+    output.push_str(&format!("\nUnchanged structs: {}\n", diff.unchanged));
+    output
+}
@@ -0,0 +1,121 @@
+use crate::aggregate::{aggregate_by_directory, DirectoryAggregate};
+use crate::models::{AnalysisResult, OutputFormat};
+
+/// Render metrics rolled up per directory instead of per struct
+///
+/// Supports the tabular formats; the table indents each directory under
+/// its parent so the subtrees carrying the most complexity stand out.
+pub fn generate_directory_report(
+    results: &[AnalysisResult],
+    format: OutputFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let directories = aggregate_by_directory(results);
+
+    match format {
+        OutputFormat::Table => Ok(generate_table(&directories)),
+        OutputFormat::Json => Ok(generate_json(&directories)?),
+        OutputFormat::Csv => Ok(generate_csv(&directories)?),
+        _ => Err("--by-directory supports only table, json, and csv output".into()),
+    }
+}
+
+fn generate_table(directories: &[DirectoryAggregate]) -> String {
+    if directories.is_empty() {
+        return "No source files found to aggregate.".to_string();
+    }
+
+    let mut output = String::new();
+
+    output.push_str(&format!(
+        "{:<40} {:>8} {:>10} {:>10} {:>10} {:>10}\n",
+        "Directory", "Structs", "Mean LCOM", "Total CBO", "Total WMC", "Max WMC"
+    ));
+    output.push_str(&"-".repeat(93));
+    output.push('\n');
+
+    for dir in directories {
+        let label = format!("{}{}", "  ".repeat(dir.depth), dir.path);
+        output.push_str(&format!(
+            "{:<40} {:>8} {:>10.3} {:>10} {:>10} {:>10}\n",
+            label, dir.struct_count, dir.mean_lcom, dir.total_cbo, dir.total_wmc, dir.max_wmc
+        ));
+    }
+
+    output
+}
+
+fn generate_json(directories: &[DirectoryAggregate]) -> Result<String, serde_json::Error> {
+    #[derive(serde::Serialize)]
+    struct JsonDirectory<'a> {
+        path: &'a str,
+        depth: usize,
+        struct_count: usize,
+        mean_lcom: f64,
+        total_cbo: usize,
+        total_wmc: usize,
+        max_wmc: usize,
+    }
+
+    let json_directories: Vec<JsonDirectory> = directories
+        .iter()
+        .map(|d| JsonDirectory {
+            path: &d.path,
+            depth: d.depth,
+            struct_count: d.struct_count,
+            mean_lcom: d.mean_lcom,
+            total_cbo: d.total_cbo,
+            total_wmc: d.total_wmc,
+            max_wmc: d.max_wmc,
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&json_directories)
+}
+
+fn generate_csv(directories: &[DirectoryAggregate]) -> Result<String, Box<dyn std::error::Error>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    writer.write_record(["path", "depth", "struct_count", "mean_lcom", "total_cbo", "total_wmc", "max_wmc"])?;
+    for dir in directories {
+        writer.write_record([
+            &dir.path,
+            &dir.depth.to_string(),
+            &dir.struct_count.to_string(),
+            &dir.mean_lcom.to_string(),
+            &dir.total_cbo.to_string(),
+            &dir.total_wmc.to_string(),
+            &dir.max_wmc.to_string(),
+        ])?;
+    }
+
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directory_table_indents_subtrees() {
+        let results = vec![
+            AnalysisResult {
+                struct_name: "Cli".to_string(),
+                file_path: "src/main.rs".to_string(),
+                wmc: 3,
+                ..Default::default()
+            },
+            AnalysisResult {
+                struct_name: "Visitor".to_string(),
+                file_path: "src/parser/visit.rs".to_string(),
+                wmc: 12,
+                ..Default::default()
+            },
+        ];
+
+        let table = generate_directory_report(&results, OutputFormat::Table).unwrap();
+        assert!(table.contains("\nsrc "));
+        assert!(table.contains("\n  src/parser "));
+
+        assert!(generate_directory_report(&results, OutputFormat::Dot).is_err());
+    }
+}
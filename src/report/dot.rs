@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+
+use crate::graph::DependencyGraph;
+use crate::models::AnalysisResult;
+
+use super::heat_rgb;
+
+/// Render the dependency graph in Graphviz DOT format
+///
+/// Metrics are encoded into node and edge attributes:
+/// - fill color goes from green (LCOM 0) through yellow to red (LCOM 1)
+/// - node size grows with WMC
+/// - edge weight and pen width grow with the number of references
+/// - edges within a dependency cycle are red
+///
+/// With `cluster_modules`, structs are grouped into one cluster per module.
+pub fn generate_dot(results: &[AnalysisResult], cluster_modules: bool) -> String {
+    let graph = DependencyGraph::build(results);
+    let mut output = String::new();
+
+    output.push_str("digraph dependencies {\n");
+    output.push_str("    rankdir=LR;\n");
+    output.push_str("    node [shape=box, style=\"filled,rounded\", fontname=\"Helvetica\"];\n");
+    output.push_str("    edge [fontname=\"Helvetica\", fontsize=10];\n");
+
+    if cluster_modules {
+        let mut modules: BTreeMap<&str, Vec<&AnalysisResult>> = BTreeMap::new();
+        for node in &graph.nodes {
+            modules.entry(node.module_path.as_str()).or_default().push(node);
+        }
+
+        for (i, (module, nodes)) in modules.iter().enumerate() {
+            output.push_str(&format!("    subgraph cluster_{} {{\n", i));
+            output.push_str(&format!("        label=\"{}\";\n", escape(module)));
+            output.push_str("        style=dashed;\n");
+            for node in nodes {
+                output.push_str(&format!("        {}\n", node_statement(node)));
+            }
+            output.push_str("    }\n");
+        }
+    } else {
+        for node in &graph.nodes {
+            output.push_str(&format!("    {}\n", node_statement(node)));
+        }
+    }
+
+    // Component of every node in a cycle
+    let mut component = vec![None; graph.nodes.len()];
+    for (i, members) in graph.cyclic_components().iter().enumerate() {
+        for &node in members {
+            component[node] = Some(i);
+        }
+    }
+
+    for edge in &graph.edges {
+        let cyclic = component[edge.from].is_some() && component[edge.from] == component[edge.to];
+        output.push_str(&format!(
+            "    \"{}\" -> \"{}\" [weight={}, penwidth={:.1}, label=\"{}\"{}];\n",
+            escape(&graph.nodes[edge.from].qualified_name()),
+            escape(&graph.nodes[edge.to].qualified_name()),
+            edge.references,
+            1.0 + (edge.references as f64).ln(),
+            edge.references,
+            if cyclic { ", color=red" } else { "" },
+        ));
+    }
+
+    output.push_str("}\n");
+    output
+}
+
+fn node_statement(result: &AnalysisResult) -> String {
+    // Grow with the square root so a WMC of 100 is not ten times as wide as 1
+    let scale = (result.wmc as f64).sqrt();
+
+    format!(
+        "\"{}\" [label=\"{}\\nLCOM {:.2} | CBO {} | WMC {}\", fillcolor=\"{}\", width={:.2}, height={:.2}];",
+        escape(&result.qualified_name()),
+        escape(&result.struct_name),
+        result.lcom,
+        result.cbo,
+        result.wmc,
+        lcom_color(result.lcom),
+        1.5 + scale * 0.3,
+        0.5 + scale * 0.1,
+    )
+}
+
+/// Green (cohesive) through yellow to red (not cohesive)
+fn lcom_color(lcom: f64) -> String {
+    let (red, green, blue) = heat_rgb(lcom);
+    format!("#{:02x}{:02x}{:02x}", red, green, blue)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Dependency;
+
+    #[test]
+    fn test_lcom_color_gradient() {
+        assert_eq!(lcom_color(0.0), "#00ff80");
+        assert_eq!(lcom_color(0.5), "#ffff80");
+        assert_eq!(lcom_color(1.0), "#ff0080");
+    }
+
+    #[test]
+    fn test_dot_clusters_and_weighted_edges() {
+        let results = vec![
+            AnalysisResult {
+                struct_name: "Server".to_string(),
+                module_path: "crate::net".to_string(),
+                wmc: 16,
+                dependencies: vec![Dependency {
+                    target: "crate::Config".to_string(),
+                    references: 3,
+                }],
+                ..Default::default()
+            },
+            AnalysisResult {
+                struct_name: "Config".to_string(),
+                module_path: "crate".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let dot = generate_dot(&results, true);
+        assert!(dot.contains("label=\"crate::net\";"));
+        assert!(dot.contains("\"crate::net::Server\" [label=\"Server\\nLCOM 0.00 | CBO 0 | WMC 16\", fillcolor=\"#00ff80\", width=2.70, height=0.90];"));
+        assert!(dot.contains("\"crate::net::Server\" -> \"crate::Config\" [weight=3"));
+
+        assert!(!dot.contains("color=red"));
+
+        let flat = generate_dot(&results, false);
+        assert!(!flat.contains("subgraph"));
+    }
+
+    #[test]
+    fn test_dot_marks_cycle_edges() {
+        let result = |name: &str, target: &str| AnalysisResult {
+            struct_name: name.to_string(),
+            module_path: "crate".to_string(),
+            dependencies: vec![Dependency {
+                target: target.to_string(),
+                references: 1,
+            }],
+            ..Default::default()
+        };
+        let results = vec![
+            result("Order", "crate::Customer"),
+            result("Customer", "crate::Order"),
+            result("Report", "crate::Order"),
+        ];
+
+        let dot = generate_dot(&results, false);
+        assert!(dot.contains("\"crate::Order\" -> \"crate::Customer\" [weight=1, penwidth=1.0, label=\"1\", color=red];"));
+        assert!(dot.contains("\"crate::Report\" -> \"crate::Order\" [weight=1, penwidth=1.0, label=\"1\"];"));
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+use serde::Serialize;
+
+use crate::aggregate::MetricStats;
+use crate::snapshot::{self, RunSettings};
+
+use super::JsonResult;
+
+/// Version of the `--format json` layout, raised only when a field is
+/// removed or changes meaning; new fields keep it
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Where a report came from, written around the results of `--format json`
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct Metadata {
+    /// UTC time of the run, RFC 3339
+    pub generated_at: String,
+    /// Analyzed paths, or the result files read back
+    pub path: String,
+    /// Settings the results were produced with
+    pub config: RunSettings,
+}
+
+impl Metadata {
+    pub fn new(path: String, config: RunSettings) -> Self {
+        Self {
+            generated_at: snapshot::rfc3339(SystemTime::now()),
+            path,
+            config,
+        }
+    }
+}
+
+/// `--format json` output: the results with the metadata of their run
+#[derive(Serialize, schemars::JsonSchema)]
+#[schemars(title = "rust-arch-metrics results")]
+pub(super) struct Envelope<'a, R> {
+    schema_version: u32,
+    tool: &'static str,
+    version: &'static str,
+    #[serde(flatten)]
+    metadata: &'a Metadata,
+    results: R,
+    /// Statistics of each reported metric over the results, as in `--summary-only`
+    summary: BTreeMap<String, MetricStats>,
+}
+
+impl<'a, R> Envelope<'a, R> {
+    pub(super) fn new(metadata: &'a Metadata, results: R, summary: BTreeMap<String, MetricStats>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            tool: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            metadata,
+            results,
+            summary,
+        }
+    }
+}
+
+/// JSON Schema of `--format json` output, published in `schema/`
+pub fn json_schema() -> String {
+    let schema = schemars::schema_for!(Envelope<'static, Vec<JsonResult<'static>>>);
+    serde_json::to_string_pretty(&schema).expect("schemas serialize") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_fields_appear_once() {
+        let metadata = Metadata::new("src".to_string(), RunSettings::default());
+        let envelope = Envelope::new(&metadata, Vec::<u8>::new(), BTreeMap::new());
+        let json = serde_json::to_value(&envelope).unwrap();
+        let keys = |value: &serde_json::Value| -> Vec<String> { value.as_object().unwrap().keys().cloned().collect() };
+
+        let mut top = vec!["schema_version", "tool", "version", "generated_at", "path", "config", "results", "summary"];
+        top.sort();
+        assert_eq!(keys(&json), top);
+        assert_eq!(json["path"], "src");
+
+        let mut config = vec![
+            "include",
+            "exclude",
+            "no_ignore",
+            "follow_mods",
+            "expand",
+            "extension_traits",
+            "count_derives",
+            "skip_trivial_impls",
+            "lcom_transitive",
+            "separate_trait_coupling",
+            "error_path_weight",
+            "await_weight",
+            "profile",
+            "thresholds",
+        ];
+        config.sort();
+        assert_eq!(keys(&json["config"]), config);
+    }
+
+    #[test]
+    fn test_published_schema_is_up_to_date() {
+        // Regenerate with `rust-arch-metrics schema --output schema/results.schema.json`
+        assert_eq!(json_schema(), include_str!("../../schema/results.schema.json"));
+    }
+}
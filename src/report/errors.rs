@@ -0,0 +1,118 @@
+use crate::aggregate::{error_types_by_module, ModuleErrors};
+use crate::models::{AnalysisResult, OutputFormat};
+
+/// Render each struct's and module's error-type fan-out
+///
+/// Structs without methods returning a `Result` are left out of the
+/// struct listing. Supports the tabular formats.
+pub fn generate_error_report(
+    results: &[AnalysisResult],
+    format: OutputFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let structs: Vec<&AnalysisResult> = results.iter().filter(|r| !r.error_types.is_empty()).collect();
+    let modules: Vec<ModuleErrors> = error_types_by_module(results)
+        .into_iter()
+        .filter(|m| !m.error_types.is_empty())
+        .collect();
+
+    match format {
+        OutputFormat::Table => Ok(generate_table(&structs, &modules)),
+        OutputFormat::Json => Ok(generate_json(&structs, &modules)?),
+        OutputFormat::Csv => Ok(generate_csv(&structs)?),
+        _ => Err("--error-coupling supports only table, json, and csv output".into()),
+    }
+}
+
+fn generate_table(structs: &[&AnalysisResult], modules: &[ModuleErrors]) -> String {
+    if structs.is_empty() {
+        return "No methods returning a Result found.".to_string();
+    }
+
+    let mut output = String::new();
+
+    output.push_str(&format!("{:<30} {:>7} {:>5} {:>8}  {}\n", "Struct", "Fan-out", "?", "Density", "Error Types"));
+    output.push_str(&"-".repeat(89));
+    output.push('\n');
+    for (result, name) in structs.iter().zip(super::display_names(structs.iter().copied())) {
+        output.push_str(&format!(
+            "{:<30} {:>7} {:>5} {:>8.3}  {}\n",
+            name,
+            result.error_types.len(),
+            propagations(result),
+            result.error_density,
+            result.error_types.join(", ")
+        ));
+    }
+
+    output.push_str(&format!("\n{:<30} {:>7} {:>5}  {}\n", "Module", "Fan-out", "?", "Error Types"));
+    output.push_str(&"-".repeat(80));
+    output.push('\n');
+    for module in modules {
+        output.push_str(&format!(
+            "{:<30} {:>7} {:>5}  {}\n",
+            module.module_path,
+            module.error_types.len(),
+            module.propagations,
+            module.error_types.join(", ")
+        ));
+    }
+
+    output.push_str("\nFan-out: distinct error types returned; ?: error propagation sites; \
+                     Density: error-path sites per line of method code\n");
+    output
+}
+
+fn generate_json(structs: &[&AnalysisResult], modules: &[ModuleErrors]) -> Result<String, serde_json::Error> {
+    #[derive(serde::Serialize)]
+    struct JsonStruct<'a> {
+        struct_name: String, // Qualified name
+        error_fanout: usize,
+        propagations: usize,
+        error_density: f64,
+        error_types: &'a [String],
+    }
+
+    #[derive(serde::Serialize)]
+    struct JsonReport<'a> {
+        structs: Vec<JsonStruct<'a>>,
+        modules: &'a [ModuleErrors],
+    }
+
+    let report = JsonReport {
+        structs: structs
+            .iter()
+            .map(|r| JsonStruct {
+                struct_name: r.qualified_name(),
+                error_fanout: r.error_types.len(),
+                propagations: propagations(r),
+                error_density: r.error_density,
+                error_types: &r.error_types,
+            })
+            .collect(),
+        modules,
+    };
+
+    serde_json::to_string_pretty(&report)
+}
+
+fn generate_csv(structs: &[&AnalysisResult]) -> Result<String, Box<dyn std::error::Error>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    writer.write_record(["struct_name", "module_path", "error_fanout", "propagations", "error_density", "error_types"])?;
+    for result in structs {
+        writer.write_record([
+            result.struct_name.clone(),
+            result.module_path.clone(),
+            result.error_types.len().to_string(),
+            propagations(result).to_string(),
+            result.error_density.to_string(),
+            result.error_types.join(";"),
+        ])?;
+    }
+
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+fn propagations(result: &AnalysisResult) -> usize {
+    result.methods.iter().map(|m| m.propagations).sum()
+}
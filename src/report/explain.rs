@@ -0,0 +1,63 @@
+use crate::explain::Explanation;
+use crate::models::OutputFormat;
+
+/// Render the evidence behind a struct's metrics as a table or JSON
+pub fn generate_explanation(
+    explanation: &Explanation,
+    format: OutputFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Table => Ok(generate_table(explanation)),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(explanation)?),
+        _ => Err("explain supports only table and json output".into()),
+    }
+}
+
+fn generate_table(explanation: &Explanation) -> String {
+    let mut output = String::new();
+
+    let location = if explanation.file_path.is_empty() {
+        format!("lines {}-{}", explanation.span.start_line, explanation.span.end_line)
+    } else {
+        format!("{}:{}", explanation.file_path, explanation.span.start_line)
+    };
+    output.push_str(&format!("{} ({})\n", explanation.struct_name, location));
+    output.push_str(&format!(
+        "LCOM {:.3}   CBO {}   WMC {}   RFC {}\n",
+        explanation.lcom, explanation.cbo, explanation.wmc, explanation.rfc
+    ));
+
+    // LCOM and WMC evidence
+    output.push_str(&format!("\nMethods ({}):\n", explanation.methods.len()));
+    for method in &explanation.methods {
+        output.push_str(&format!(
+            "  {:<30} line {:>5}  complexity {:>3}  fields [{}]\n",
+            method.name,
+            method.line,
+            method.complexity,
+            method.fields_accessed.join(", ")
+        ));
+    }
+
+    // RFC evidence
+    output.push_str(&format!("\nResponse set ({}):\n", explanation.rfc));
+    for method in &explanation.methods {
+        if !method.calls.is_empty() {
+            output.push_str(&format!("  {} calls {}\n", method.name, method.calls.join(", ")));
+        }
+    }
+
+    // CBO evidence
+    output.push_str(&format!("\nCoupling ({}):\n", explanation.coupling.len()));
+    for edge in &explanation.coupling {
+        output.push_str(&format!("  {} {}\n", edge.kind, edge.target));
+        if edge.sites.is_empty() {
+            output.push_str("      (not mentioned by name, e.g. through an alias)\n");
+        }
+        for site in &edge.sites {
+            output.push_str(&format!("      {:>5}: {}\n", site.line, site.code));
+        }
+    }
+
+    output
+}
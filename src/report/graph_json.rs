@@ -0,0 +1,112 @@
+use serde::Serialize;
+
+use crate::graph::DependencyGraph;
+use crate::models::{AnalysisResult, StructKind};
+
+#[derive(Serialize)]
+struct GraphDocument {
+    directed: bool,
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphLink>,
+}
+
+#[derive(Serialize)]
+struct GraphNode {
+    id: String,
+    label: String,
+    module: String,
+    kind: StructKind,
+    lcom: f64,
+    cbo: usize,
+    wmc: usize,
+}
+
+#[derive(Serialize)]
+struct GraphLink {
+    id: String,
+    source: String,
+    target: String,
+    weight: usize,
+}
+
+/// Render the dependency graph as a nodes/edges JSON document
+///
+/// Nodes are keyed by the struct's qualified name and carry its metrics;
+/// edges reference nodes by id through `source`/`target` and carry the
+/// number of references as `weight`. This is the shape D3 force layouts,
+/// Cytoscape.js (wrapping each element in `data`), and Gephi's JSON
+/// importer expect.
+pub fn generate_graph_json(results: &[AnalysisResult]) -> Result<String, serde_json::Error> {
+    let graph = DependencyGraph::build(results);
+
+    let nodes = graph
+        .nodes
+        .iter()
+        .map(|r| GraphNode {
+            id: r.qualified_name(),
+            label: r.struct_name.clone(),
+            module: r.module_path.clone(),
+            kind: r.kind,
+            lcom: r.lcom,
+            cbo: r.cbo,
+            wmc: r.wmc,
+        })
+        .collect();
+
+    let edges = graph
+        .edges
+        .iter()
+        .map(|edge| {
+            let source = graph.nodes[edge.from].qualified_name();
+            let target = graph.nodes[edge.to].qualified_name();
+            GraphLink {
+                id: format!("{}->{}", source, target),
+                source,
+                target,
+                weight: edge.references,
+            }
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&GraphDocument {
+        directed: true,
+        nodes,
+        edges,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Dependency;
+
+    #[test]
+    fn test_graph_json_nodes_and_edges() {
+        let results = vec![
+            AnalysisResult {
+                struct_name: "Server".to_string(),
+                module_path: "crate::net".to_string(),
+                wmc: 7,
+                dependencies: vec![Dependency {
+                    target: "crate::Config".to_string(),
+                    references: 2,
+                }],
+                ..Default::default()
+            },
+            AnalysisResult {
+                struct_name: "Config".to_string(),
+                module_path: "crate".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let json = generate_graph_json(&results).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["nodes"][0]["id"], "crate::net::Server");
+        assert_eq!(value["nodes"][0]["wmc"], 7);
+        assert_eq!(value["edges"][0]["source"], "crate::net::Server");
+        assert_eq!(value["edges"][0]["target"], "crate::Config");
+        assert_eq!(value["edges"][0]["weight"], 2);
+    }
+}
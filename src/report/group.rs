@@ -0,0 +1,79 @@
+use std::path::PathBuf;
+
+use crate::aggregate::{aggregate_groups, GroupAggregate};
+use crate::models::{AnalysisResult, GroupBy, OutputFormat};
+
+/// Render metrics rolled up per module, file, or crate
+///
+/// Supports the tabular formats. The internal ratio is the share of a
+/// group's dependencies that stay within it; a low ratio with many edges
+/// marks a group that mostly glues others together.
+pub fn generate_group_report(
+    results: &[AnalysisResult],
+    group_by: GroupBy,
+    crate_roots: &[(PathBuf, String)],
+    format: OutputFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let groups = aggregate_groups(results, group_by, crate_roots);
+
+    match format {
+        OutputFormat::Table => Ok(generate_table(&groups, group_by)),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(&groups)?),
+        OutputFormat::Csv => Ok(generate_csv(&groups)?),
+        _ => Err("--group-by supports only table, json, and csv output".into()),
+    }
+}
+
+fn generate_table(groups: &[GroupAggregate], group_by: GroupBy) -> String {
+    if groups.is_empty() {
+        return "No structs found to aggregate.".to_string();
+    }
+
+    let mut output = String::new();
+
+    output.push_str(&format!(
+        "{:<40} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10}\n",
+        group_by.to_string(), "Structs", "Mean LCOM", "Mean WMC", "Max WMC", "CBO Edges", "Internal"
+    ));
+    output.push_str(&"-".repeat(104));
+    output.push('\n');
+
+    for group in groups {
+        output.push_str(&format!(
+            "{:<40} {:>8} {:>10.3} {:>10.1} {:>10} {:>10} {:>10.3}\n",
+            group.group,
+            group.struct_count,
+            group.mean_lcom,
+            group.mean_wmc,
+            group.max_wmc,
+            group.cbo_edges,
+            group.internal_ratio
+        ));
+    }
+
+    output
+}
+
+fn generate_csv(groups: &[GroupAggregate]) -> Result<String, Box<dyn std::error::Error>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    writer.write_record([
+        "group", "struct_count", "mean_lcom", "mean_wmc", "max_wmc", "total_wmc", "cbo_edges", "internal_edges",
+        "internal_ratio",
+    ])?;
+    for group in groups {
+        writer.write_record([
+            group.group.clone(),
+            group.struct_count.to_string(),
+            group.mean_lcom.to_string(),
+            group.mean_wmc.to_string(),
+            group.max_wmc.to_string(),
+            group.total_wmc.to_string(),
+            group.cbo_edges.to_string(),
+            group.internal_edges.to_string(),
+            group.internal_ratio.to_string(),
+        ])?;
+    }
+
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
@@ -0,0 +1,265 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::aggregate::common_ancestor;
+use crate::models::{AnalysisResult, Metric, OutputFormat};
+
+use super::heat_rgb;
+use super::html::escape;
+
+const STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; margin: 2rem; color: #24292f; }
+.tree { font-family: monospace; line-height: 1.6; }
+.dir { font-weight: bold; }
+.struct { color: #57606a; }
+.swatch { display: inline-block; width: 1.5em; height: 0.9em; margin-right: 0.5em; border-radius: 0.2em; vertical-align: middle; }
+.value { color: #57606a; }
+";
+
+#[derive(Debug, PartialEq)]
+enum RowKind {
+    Directory,
+    File,
+    Struct,
+}
+
+/// One line of the heatmap tree
+#[derive(Debug)]
+struct Row {
+    kind: RowKind,
+    depth: usize,
+    label: String,
+    value: f64,
+    heat: f64, // 0.0 (coolest) to 1.0 (hottest), unused for directories
+}
+
+/// Render the source tree with each file and struct shaded by `metric`
+///
+/// Structs are shaded by their own value and files by the value of all
+/// their structs together (mean LCOM, summed CBO and WMC). CBO and WMC are
+/// scaled against the largest value at the same level. Table output uses
+/// 24-bit ANSI colors; HTML output is a self-contained page.
+pub fn generate_heatmap(
+    results: &[AnalysisResult],
+    metric: Metric,
+    format: OutputFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let rows = build_rows(results, metric);
+
+    match format {
+        OutputFormat::Table => Ok(render_ansi(&rows, metric)),
+        OutputFormat::Html => Ok(render_html(&rows, metric)),
+        _ => Err("--heatmap supports only table and html output".into()),
+    }
+}
+
+fn build_rows(results: &[AnalysisResult], metric: Metric) -> Vec<Row> {
+    let mut files: BTreeMap<&Path, Vec<&AnalysisResult>> = BTreeMap::new();
+    for result in results.iter().filter(|r| !r.file_path.is_empty()) {
+        files.entry(Path::new(&result.file_path)).or_default().push(result);
+    }
+
+    let Some(root) = common_ancestor(files.keys().map(|f| f.parent().unwrap_or(Path::new("")))) else {
+        return Vec::new();
+    };
+
+    let file_value = |structs: &[&AnalysisResult]| {
+        let total: f64 = structs.iter().map(|r| metric.value(r)).sum();
+        match metric {
+            Metric::Lcom => total / structs.len() as f64,
+            Metric::Cbo | Metric::Wmc => total,
+        }
+    };
+    let max_file = files.values().map(|s| file_value(s)).fold(0.0, f64::max);
+    let max_struct = results.iter().map(|r| metric.value(r)).fold(0.0, f64::max);
+
+    let mut rows = vec![Row {
+        kind: RowKind::Directory,
+        depth: 0,
+        label: if root.as_os_str().is_empty() { ".".to_string() } else { root.display().to_string() },
+        value: 0.0,
+        heat: 0.0,
+    }];
+
+    // Directories below the root that have already been printed
+    let mut open_dirs: Vec<PathBuf> = Vec::new();
+
+    for (file, structs) in &files {
+        let relative_dir = file
+            .parent()
+            .and_then(|dir| dir.strip_prefix(&root).ok())
+            .unwrap_or(Path::new(""));
+        let components: Vec<PathBuf> = relative_dir.iter().map(PathBuf::from).collect();
+
+        let shared = open_dirs.iter().zip(&components).take_while(|(a, b)| a == b).count();
+        open_dirs.truncate(shared);
+        for component in &components[shared..] {
+            open_dirs.push(component.clone());
+            rows.push(Row {
+                kind: RowKind::Directory,
+                depth: open_dirs.len(),
+                label: component.display().to_string(),
+                value: 0.0,
+                heat: 0.0,
+            });
+        }
+
+        let value = file_value(structs);
+        rows.push(Row {
+            kind: RowKind::File,
+            depth: components.len() + 1,
+            label: file.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            value,
+            heat: heat(metric, value, max_file),
+        });
+
+        for result in structs {
+            let value = metric.value(result);
+            rows.push(Row {
+                kind: RowKind::Struct,
+                depth: components.len() + 2,
+                label: result.struct_name.clone(),
+                value,
+                heat: heat(metric, value, max_struct),
+            });
+        }
+    }
+
+    rows
+}
+
+/// LCOM is already on a 0-1 scale; counts are relative to the largest one
+fn heat(metric: Metric, value: f64, max: f64) -> f64 {
+    match metric {
+        Metric::Lcom => value,
+        Metric::Cbo | Metric::Wmc if max > 0.0 => value / max,
+        Metric::Cbo | Metric::Wmc => 0.0,
+    }
+}
+
+fn format_value(metric: Metric, value: f64) -> String {
+    match metric {
+        Metric::Lcom => format!("{:.2}", value),
+        Metric::Cbo | Metric::Wmc => format!("{}", value),
+    }
+}
+
+fn render_ansi(rows: &[Row], metric: Metric) -> String {
+    if rows.is_empty() {
+        return "No source files found to map.".to_string();
+    }
+
+    let mut output = format!("{} heatmap (green = low, red = high)\n\n", metric);
+    for row in rows {
+        let indent = "  ".repeat(row.depth);
+        if row.kind == RowKind::Directory {
+            output.push_str(&format!("{}\x1b[1m{}/\x1b[0m\n", indent, row.label));
+            continue;
+        }
+
+        let (red, green, blue) = heat_rgb(row.heat);
+        output.push_str(&format!(
+            "{}\x1b[38;2;{};{};{}m\u{2588}\u{2588}\x1b[0m {} ({})\n",
+            indent,
+            red,
+            green,
+            blue,
+            row.label,
+            format_value(metric, row.value)
+        ));
+    }
+
+    output
+}
+
+fn render_html(rows: &[Row], metric: Metric) -> String {
+    let mut output = String::new();
+
+    output.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    output.push_str(&format!("<title>{} Heatmap</title>\n", metric));
+    output.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", STYLE));
+    output.push_str(&format!("<h1>{} Heatmap</h1>\n", metric));
+
+    if rows.is_empty() {
+        output.push_str("<p>No source files found to map.</p>\n");
+    } else {
+        output.push_str("<div class=\"tree\">\n");
+        for row in rows {
+            let padding = format!("padding-left: {}em", row.depth * 2);
+            match row.kind {
+                RowKind::Directory => output.push_str(&format!(
+                    "<div class=\"dir\" style=\"{}\">{}/</div>\n",
+                    padding,
+                    escape(&row.label)
+                )),
+                RowKind::File | RowKind::Struct => {
+                    let (red, green, blue) = heat_rgb(row.heat);
+                    output.push_str(&format!(
+                        "<div class=\"{}\" style=\"{}\"><span class=\"swatch\" style=\"background: #{:02x}{:02x}{:02x}\"></span>{} <span class=\"value\">{}</span></div>\n",
+                        if row.kind == RowKind::File { "file" } else { "struct" },
+                        padding,
+                        red,
+                        green,
+                        blue,
+                        escape(&row.label),
+                        format_value(metric, row.value)
+                    ));
+                }
+            }
+        }
+        output.push_str("</div>\n");
+    }
+
+    output.push_str("</body>\n</html>\n");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heatmap_rows_follow_file_tree() {
+        let results = vec![
+            AnalysisResult {
+                struct_name: "Cli".to_string(),
+                file_path: "src/main.rs".to_string(),
+                wmc: 5,
+                ..Default::default()
+            },
+            AnalysisResult {
+                struct_name: "Lexer".to_string(),
+                file_path: "src/parser/lex.rs".to_string(),
+                wmc: 10,
+                ..Default::default()
+            },
+            AnalysisResult {
+                struct_name: "Token".to_string(),
+                file_path: "src/parser/lex.rs".to_string(),
+                wmc: 10,
+                ..Default::default()
+            },
+        ];
+
+        let rows = build_rows(&results, Metric::Wmc);
+        let summary: Vec<(usize, &str, f64)> = rows
+            .iter()
+            .map(|r| (r.depth, r.label.as_str(), r.heat))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (0, "src", 0.0),
+                (1, "main.rs", 0.25),
+                (2, "Cli", 0.5),
+                (1, "parser", 0.0),
+                (2, "lex.rs", 1.0),
+                (3, "Lexer", 1.0),
+                (3, "Token", 1.0),
+            ]
+        );
+
+        assert!(generate_heatmap(&results, Metric::Wmc, OutputFormat::Csv).is_err());
+    }
+}
@@ -0,0 +1,64 @@
+use crate::hotspots::Hotspot;
+use crate::models::OutputFormat;
+
+use super::impl_blocks::location;
+
+/// Render the structs ranked by churn times complexity
+pub fn generate_hotspot_report(
+    hotspots: &[Hotspot],
+    format: OutputFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Table => Ok(generate_table(hotspots)),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(hotspots)?),
+        OutputFormat::Csv => Ok(generate_csv(hotspots)?),
+        _ => Err("--hotspots supports only table, json, and csv output".into()),
+    }
+}
+
+fn generate_table(hotspots: &[Hotspot]) -> String {
+    if hotspots.is_empty() {
+        return "No hotspots found: no analyzed struct has both commits and complexity.".to_string();
+    }
+
+    let mut output = String::new();
+    output.push_str(&format!(
+        "{:>4}  {:<50} {:>8} {:>6} {:>6}  {}\n",
+        "Rank", "Struct", "Commits", "WMC", "Score", "Location"
+    ));
+    output.push_str(&"-".repeat(110));
+    output.push('\n');
+
+    for (i, hotspot) in hotspots.iter().enumerate() {
+        output.push_str(&format!(
+            "{:>4}  {:<50} {:>8} {:>6} {:>6.2}  {}\n",
+            i + 1,
+            hotspot.struct_name,
+            hotspot.commits,
+            hotspot.wmc,
+            hotspot.score,
+            location(&hotspot.file_path, hotspot.span)
+        ));
+    }
+
+    output
+}
+
+fn generate_csv(hotspots: &[Hotspot]) -> Result<String, Box<dyn std::error::Error>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    writer.write_record(["struct_name", "file_path", "start_line", "end_line", "commits", "wmc", "score"])?;
+    for hotspot in hotspots {
+        writer.write_record([
+            hotspot.struct_name.clone(),
+            hotspot.file_path.clone(),
+            hotspot.span.start_line.to_string(),
+            hotspot.span.end_line.to_string(),
+            hotspot.commits.to_string(),
+            hotspot.wmc.to_string(),
+            hotspot.score.to_string(),
+        ])?;
+    }
+
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
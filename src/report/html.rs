@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+
+use crate::metrics::maintainability;
+use crate::models::{AnalysisResult, SourceSpan, Symbol};
+use crate::thresholds::Thresholds;
+
+const STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; margin: 2rem; color: #24292f; }
+table { border-collapse: collapse; }
+th, td { padding: 0.3rem 0.8rem; border-bottom: 1px solid #d0d7de; text-align: left; }
+td.num { text-align: right; font-variant-numeric: tabular-nums; }
+.struct-page { display: none; }
+.struct-page:target { display: block; }
+body:has(.struct-page:target) #index { display: none; }
+.kind { font-size: 0.8em; color: #57606a; font-weight: normal; }
+.location { color: #57606a; font-family: monospace; }
+pre.source { background: #f6f8fa; padding: 0.5rem; overflow-x: auto; }
+.line-no { display: inline-block; width: 3.5em; color: #8c959f; user-select: none; }
+.complexity { font-size: 0.8em; background: #ddf4ff; border-radius: 0.5em; padding: 0.1em 0.5em; }
+mark { background: #fff8c5; }
+th[data-sort] { cursor: pointer; user-select: none; }
+th[data-sort]::after { content: ' \\2195'; color: #8c959f; }
+td.over { background: #ffebe9; color: #a40e26; font-weight: 600; }
+td.mi-good { color: #1a7f37; }
+td.mi-fair { color: #9a6700; }
+td.mi-poor { background: #ffebe9; color: #a40e26; }
+#filter { margin-bottom: 0.8rem; padding: 0.3rem; width: 20rem; }
+details table { margin: 0.3rem 0; font-size: 0.9em; }
+";
+
+/// Sorts the index by a clicked column and filters it by the search box
+const SCRIPT: &str = "
+const table = document.querySelector('#structs');
+const body = table.tBodies[0];
+document.querySelectorAll('#structs th[data-sort]').forEach((th, column) => {
+  th.addEventListener('click', () => {
+    const numeric = th.dataset.sort === 'num';
+    const descending = th.dataset.order !== 'desc';
+    th.dataset.order = descending ? 'desc' : 'asc';
+    const key = row => {
+      const cell = row.cells[column];
+      return numeric ? parseFloat(cell.dataset.value) : cell.textContent.toLowerCase();
+    };
+    const rows = Array.from(body.rows).sort((a, b) => {
+      const [x, y] = [key(a), key(b)];
+      const order = x < y ? -1 : x > y ? 1 : 0;
+      return descending ? -order : order;
+    });
+    rows.forEach(row => body.appendChild(row));
+  });
+});
+document.querySelector('#filter').addEventListener('input', event => {
+  const text = event.target.value.toLowerCase();
+  Array.from(body.rows).forEach(row => {
+    row.hidden = !row.dataset.search.includes(text);
+  });
+});
+";
+
+/// Render a self-contained HTML report
+///
+/// The index is a table of all structs, sortable by clicking a column and
+/// filterable by name or location, with metrics above `thresholds` and the
+/// Maintainability Index bands colored. Each row expands to its methods'
+/// metrics and links to the struct's own page showing the struct
+/// definition and every method's source, annotated with the method's
+/// complexity and highlighting the `self.field` accesses that were counted
+/// for LCOM.
+pub fn generate_html(results: &[AnalysisResult], thresholds: &Thresholds) -> String {
+    let mut sources = SourceCache::default();
+    let names = super::display_names(results);
+    let mut output = String::new();
+
+    output.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    output.push_str("<title>Architecture Metrics</title>\n");
+    output.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", STYLE));
+
+    // Index
+    output.push_str("<section id=\"index\">\n<h1>Architecture Metrics</h1>\n");
+    if results.is_empty() {
+        output.push_str("<p>No structs found to analyze.</p>\n");
+    } else {
+        output.push_str("<input id=\"filter\" type=\"search\" placeholder=\"Filter by struct or location\">\n");
+        output.push_str(
+            "<table id=\"structs\">\n<thead><tr><th data-sort=\"text\">Struct</th><th data-sort=\"text\">Kind</th>\
+             <th data-sort=\"num\">LCOM</th><th data-sort=\"num\">CBO</th><th data-sort=\"num\">WMC</th>\
+             <th data-sort=\"num\">Cognitive</th><th data-sort=\"num\">MI</th><th data-sort=\"num\">SLOC</th>\
+             <th data-sort=\"num\">Methods</th><th data-sort=\"text\">Location</th></tr></thead>\n<tbody>\n",
+        );
+        for (i, (result, name)) in results.iter().zip(&names).enumerate() {
+            let location = location(&result.file_path, result.span);
+            output.push_str(&format!(
+                "<tr data-search=\"{}\"><td><a href=\"#struct-{}\">{}</a></td><td>{}</td>{}{}{}{}{}{}<td class=\"num\" data-value=\"{}\">{}</td><td class=\"location\">{}</td></tr>\n",
+                escape(&format!("{} {}", result.qualified_name(), location).to_lowercase()),
+                i,
+                escape(name),
+                result.kind,
+                metric_cell(format!("{:.3}", result.lcom), result.lcom, result.lcom > thresholds.lcom),
+                metric_cell(result.cbo.to_string(), result.cbo as f64, result.cbo > thresholds.cbo),
+                metric_cell(result.wmc.to_string(), result.wmc as f64, result.wmc > thresholds.wmc),
+                metric_cell(result.cognitive.to_string(), result.cognitive as f64, false),
+                maintainability_cell(result.maintainability),
+                metric_cell(result.sloc.to_string(), result.sloc as f64, false),
+                result.methods.len(),
+                method_details(result),
+                escape(&location),
+            ));
+        }
+        output.push_str("</tbody>\n</table>\n");
+        output.push_str(&format!("<script>{}</script>\n", SCRIPT));
+    }
+    output.push_str("</section>\n");
+
+    // Drill-down pages
+    for (i, (result, name)) in results.iter().zip(&names).enumerate() {
+        output.push_str(&format!("<section class=\"struct-page\" id=\"struct-{}\">\n", i));
+        output.push_str("<p><a href=\"#index\">&larr; All structs</a></p>\n");
+        output.push_str(&format!(
+            "<h2>{} <span class=\"kind\">{}</span></h2>\n",
+            escape(name),
+            result.kind
+        ));
+        output.push_str(&format!(
+            "<p>LCOM {:.3} &middot; CBO {} &middot; WMC {}</p>\n",
+            result.lcom, result.cbo, result.wmc
+        ));
+        output.push_str(&format!(
+            "<p class=\"location\">{}</p>\n",
+            escape(&location(&result.file_path, result.span))
+        ));
+
+        let lines = sources.lines(&result.file_path);
+
+        output.push_str("<h3>Definition</h3>\n");
+        output.push_str(&render_snippet(lines, result.span, &[]));
+
+        output.push_str(&format!("<h3>Methods ({})</h3>\n", result.methods.len()));
+        for method in &result.methods {
+            let mut fields: Vec<&str> = method.fields_accessed.iter().map(|f| &**f).collect();
+            fields.sort_unstable();
+
+            output.push_str(&format!(
+                "<h4>fn {} <span class=\"complexity\">complexity {}</span></h4>\n",
+                escape(&method.name),
+                method.cyclomatic_complexity
+            ));
+            output.push_str(&format!(
+                "<p>Fields accessed: {}</p>\n",
+                if fields.is_empty() {
+                    "none".to_string()
+                } else {
+                    escape(&fields.join(", "))
+                }
+            ));
+            output.push_str(&render_snippet(lines, method.span, &method.fields_accessed));
+        }
+
+        output.push_str("</section>\n");
+    }
+
+    output.push_str("</body>\n</html>\n");
+    output
+}
+
+/// A numeric cell sorting by `value`, marked when over its threshold
+fn metric_cell(text: String, value: f64, over: bool) -> String {
+    let class = if over { "num over" } else { "num" };
+    format!("<td class=\"{}\" data-value=\"{}\">{}</td>", class, value, text)
+}
+
+/// The Maintainability Index cell, colored by the bands of the table format
+fn maintainability_cell(mi: f64) -> String {
+    let band = if mi >= 20.0 {
+        "mi-good"
+    } else if mi >= 10.0 {
+        "mi-fair"
+    } else {
+        "mi-poor"
+    };
+    format!("<td class=\"num {}\" data-value=\"{}\">{:.1}</td>", band, mi, mi)
+}
+
+/// The method count, expanding to a table of each method's metrics
+fn method_details(result: &AnalysisResult) -> String {
+    if result.methods.is_empty() {
+        return "0".to_string();
+    }
+
+    let mut output = format!(
+        "<details><summary>{}</summary><table><thead><tr><th>Method</th><th>Complexity</th><th>Cognitive</th>\
+         <th>MI</th><th>SLOC</th><th>Fields</th></tr></thead><tbody>",
+        result.methods.len()
+    );
+    for method in &result.methods {
+        let mi = maintainability::index(
+            method.halstead.volume(),
+            method.cyclomatic_complexity.max(1) as f64,
+            method.sloc as f64,
+        );
+        output.push_str(&format!(
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>{}<td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+            escape(&method.name),
+            method.cyclomatic_complexity,
+            method.cognitive_complexity,
+            maintainability_cell(mi),
+            method.sloc,
+            method.fields_accessed.len(),
+        ));
+    }
+    output.push_str("</tbody></table></details>");
+    output
+}
+
+/// Source files read on demand, each at most once
+#[derive(Default)]
+struct SourceCache {
+    files: HashMap<String, Option<Vec<String>>>,
+}
+
+impl SourceCache {
+    fn lines(&mut self, file_path: &str) -> Option<&[String]> {
+        self.files
+            .entry(file_path.to_string())
+            .or_insert_with(|| {
+                if file_path.is_empty() {
+                    return None;
+                }
+                std::fs::read_to_string(file_path)
+                    .ok()
+                    .map(|content| content.lines().map(str::to_string).collect())
+            })
+            .as_deref()
+    }
+}
+
+fn location(file_path: &str, span: SourceSpan) -> String {
+    if file_path.is_empty() {
+        return String::new();
+    }
+    format!("{}:{}-{}", file_path, span.start_line, span.end_line)
+}
+
+/// Render the lines of `span` with line numbers, highlighting accesses to `fields`
+fn render_snippet(lines: Option<&[String]>, span: SourceSpan, fields: &[Symbol]) -> String {
+    let Some(lines) = lines else {
+        return "<p><em>Source unavailable</em></p>\n".to_string();
+    };
+    if span.start_line == 0 || span.start_line > lines.len() {
+        return "<p><em>Source unavailable</em></p>\n".to_string();
+    }
+
+    let end = span.end_line.clamp(span.start_line, lines.len());
+    let mut output = String::from("<pre class=\"source\">");
+    for line_no in span.start_line..=end {
+        output.push_str(&format!(
+            "<span class=\"line-no\">{}</span>{}\n",
+            line_no,
+            highlight_fields(&lines[line_no - 1], fields)
+        ));
+    }
+    output.push_str("</pre>\n");
+    output
+}
+
+/// Escape a source line, wrapping `self.field` accesses to `fields` in `<mark>`
+fn highlight_fields(line: &str, fields: &[Symbol]) -> String {
+    let mut output = String::new();
+    let mut rest = line;
+
+    while let Some(pos) = rest.find("self.") {
+        let after = &rest[pos + "self.".len()..];
+        let ident_len = after
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(after.len());
+        let ident = &after[..ident_len];
+        let access = &rest[pos..pos + "self.".len() + ident_len];
+
+        // `myself.name` is not a field access
+        let part_of_ident = rest[..pos]
+            .chars()
+            .last()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_');
+
+        output.push_str(&escape(&rest[..pos]));
+        if !part_of_ident && fields.iter().any(|f| **f == *ident) {
+            output.push_str(&format!("<mark>{}</mark>", escape(access)));
+        } else {
+            output.push_str(&escape(access));
+        }
+        rest = &after[ident_len..];
+    }
+
+    output.push_str(&escape(rest));
+    output
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MethodInfo;
+
+    #[test]
+    fn test_highlight_fields() {
+        let fields: Vec<Symbol> = vec!["name".into()];
+        assert_eq!(
+            highlight_fields("if self.name < self.age && myself.name {", &fields),
+            "if <mark>self.name</mark> &lt; self.age &amp;&amp; myself.name {"
+        );
+    }
+
+    #[test]
+    fn test_html_drill_down_page() {
+        let result = AnalysisResult {
+            struct_name: "Parser<'a>".to_string(),
+            methods: vec![MethodInfo {
+                name: "parse".to_string(),
+                cyclomatic_complexity: 4,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let html = generate_html(&[result], &Thresholds::default());
+        assert!(html.contains("<a href=\"#struct-0\">Parser&lt;'a&gt;</a>"));
+        assert!(html.contains("<section class=\"struct-page\" id=\"struct-0\">"));
+        assert!(html.contains("fn parse <span class=\"complexity\">complexity 4</span>"));
+        assert!(html.contains("Source unavailable"));
+        assert!(html.contains("<details><summary>1</summary>"));
+    }
+
+    #[test]
+    fn test_html_index_marks_threshold_violations() {
+        let result = |name: &str, wmc| AnalysisResult {
+            struct_name: name.to_string(),
+            module_path: "crate".to_string(),
+            wmc,
+            maintainability: 15.0,
+            ..Default::default()
+        };
+
+        let html = generate_html(&[result("Small", 12), result("Large", 41)], &Thresholds::default());
+        assert!(html.contains("<tr data-search=\"crate::small \">"));
+        // Unique names are listed unqualified, as in the table
+        assert!(html.contains("<a href=\"#struct-0\">Small</a>"));
+        assert!(html.contains("<td class=\"num\" data-value=\"12\">12</td>"));
+        assert!(html.contains("<td class=\"num over\" data-value=\"41\">41</td>"));
+        assert!(html.contains("<td class=\"num mi-fair\" data-value=\"15\">15.0</td>"));
+        assert!(html.contains("<th data-sort=\"num\">WMC</th>"));
+        assert!(html.contains("<script>"));
+    }
+}
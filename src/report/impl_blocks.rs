@@ -0,0 +1,115 @@
+use crate::models::{AnalysisResult, ImplBlockMetrics, OutputFormat, SourceSpan};
+
+/// Render metrics broken down per impl block instead of per struct
+///
+/// Supports the tabular formats. Structs without impl blocks are left
+/// out; the table lists each struct's blocks indented under it so the one
+/// problematic block of a large struct stands out.
+pub fn generate_impl_report(
+    results: &[AnalysisResult],
+    format: OutputFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let results: Vec<&AnalysisResult> = results.iter().filter(|r| !r.impl_blocks.is_empty()).collect();
+
+    match format {
+        OutputFormat::Table => Ok(generate_table(&results)),
+        OutputFormat::Json => Ok(generate_json(&results)?),
+        OutputFormat::Csv => Ok(generate_csv(&results)?),
+        _ => Err("--by-impl supports only table, json, and csv output".into()),
+    }
+}
+
+fn generate_table(results: &[&AnalysisResult]) -> String {
+    if results.is_empty() {
+        return "No impl blocks found to analyze.".to_string();
+    }
+
+    let mut output = String::new();
+
+    output.push_str(&format!(
+        "{:<36} {:>8} {:>10} {:>10}  {}\n",
+        "Struct / Impl Block", "Methods", "LCOM", "WMC", "Location"
+    ));
+    output.push_str(&"-".repeat(100));
+    output.push('\n');
+
+    for (result, name) in results.iter().zip(super::display_names(results.iter().copied())) {
+        output.push_str(&format!(
+            "{:<36} {:>8} {:>10.3} {:>10}  {}\n",
+            name,
+            result.methods.len(),
+            result.lcom,
+            result.wmc,
+            location(&result.file_path, result.span)
+        ));
+        for block in &result.impl_blocks {
+            output.push_str(&format!(
+                "{:<36} {:>8} {:>10.3} {:>10}  {}\n",
+                format!("  {}", block.label),
+                block.method_count,
+                block.lcom,
+                block.wmc,
+                location(&result.file_path, block.span)
+            ));
+        }
+    }
+
+    output
+}
+
+fn generate_json(results: &[&AnalysisResult]) -> Result<String, serde_json::Error> {
+    #[derive(serde::Serialize)]
+    struct JsonStruct<'a> {
+        struct_name: &'a str,
+        module_path: &'a str,
+        file_path: &'a str,
+        impl_blocks: &'a [ImplBlockMetrics],
+    }
+
+    let json_structs: Vec<JsonStruct> = results
+        .iter()
+        .map(|r| JsonStruct {
+            struct_name: &r.struct_name,
+            module_path: &r.module_path,
+            file_path: &r.file_path,
+            impl_blocks: &r.impl_blocks,
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&json_structs)
+}
+
+fn generate_csv(results: &[&AnalysisResult]) -> Result<String, Box<dyn std::error::Error>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    writer.write_record([
+        "struct_name", "module_path", "file_path", "impl_block", "start_line", "end_line", "method_count", "lcom", "wmc",
+    ])?;
+    for result in results {
+        for block in &result.impl_blocks {
+            writer.write_record([
+                result.struct_name.clone(),
+                result.module_path.clone(),
+                result.file_path.clone(),
+                block.label.clone(),
+                block.span.start_line.to_string(),
+                block.span.end_line.to_string(),
+                block.method_count.to_string(),
+                block.lcom.to_string(),
+                block.wmc.to_string(),
+            ])?;
+        }
+    }
+
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+/// `file:start-end`, or just the lines when the source is not on disk
+pub(super) fn location(file_path: &str, span: SourceSpan) -> String {
+    let lines = format!("{}-{}", span.start_line, span.end_line);
+    if file_path.is_empty() {
+        format!("lines {}", lines)
+    } else {
+        format!("{}:{}", file_path, lines)
+    }
+}
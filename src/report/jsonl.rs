@@ -0,0 +1,86 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::models::{AnalysisResult, MetricSelection};
+
+use super::{retain_selected, JsonResult};
+
+/// Writes results as JSON Lines, one compact object per line, as they are
+/// analyzed rather than once the whole report is built
+pub struct JsonLines {
+    writer: Box<dyn Write>,
+    metrics: MetricSelection,
+}
+
+impl JsonLines {
+    /// Write to the file `output`, or to stdout, which flushes every line
+    pub fn create(output: Option<&str>, metrics: MetricSelection) -> Result<Self, Box<dyn std::error::Error>> {
+        let writer: Box<dyn Write> = match output {
+            Some(file_path) => Box::new(BufWriter::new(
+                File::create(file_path).map_err(|e| format!("Failed to create {}: {}", file_path, e))?,
+            )),
+            None => Box::new(std::io::stdout()),
+        };
+        Ok(Self { writer, metrics })
+    }
+
+    pub fn write(&mut self, result: &AnalysisResult) -> Result<(), Box<dyn std::error::Error>> {
+        let line = to_line(result, &self.metrics)?;
+        writeln!(self.writer, "{}", line)?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Render collected results as JSON Lines, for subcommands that read them
+/// back before reporting
+pub(super) fn generate_jsonl(results: &[AnalysisResult], metrics: &MetricSelection) -> Result<String, serde_json::Error> {
+    let lines = results.iter().map(|r| to_line(r, metrics)).collect::<Result<Vec<_>, _>>()?;
+    Ok(lines.join("\n"))
+}
+
+/// The `--format json` object of `result`, on one line
+fn to_line(result: &AnalysisResult, metrics: &MetricSelection) -> Result<String, serde_json::Error> {
+    if metrics.all {
+        return serde_json::to_string(&JsonResult::from(result));
+    }
+    let derived: Vec<&str> = result.derived.keys().map(String::as_str).collect();
+    let mut value = serde_json::to_value(JsonResult::from(result))?;
+    if let Some(object) = value.as_object_mut() {
+        retain_selected(object, metrics, &derived);
+    }
+    serde_json::to_string(&value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_object_per_line_with_selected_metrics() {
+        let result = |name: &str, wmc| AnalysisResult {
+            struct_name: name.to_string(),
+            module_path: "crate".to_string(),
+            file_path: "src/lib.rs".to_string(),
+            wmc,
+            lcom: 0.5,
+            ..Default::default()
+        };
+        let results = [result("Engine", 12), result("Wheel", 3)];
+
+        let metrics: MetricSelection = "wmc".parse().unwrap();
+        assert_eq!(
+            generate_jsonl(&results, &metrics).unwrap(),
+            r#"{"file_path":"src/lib.rs","kind":"struct","module_path":"crate","span":{"end_line":0,"start_line":0},"struct_name":"Engine","wmc":12}
+{"file_path":"src/lib.rs","kind":"struct","module_path":"crate","span":{"end_line":0,"start_line":0},"struct_name":"Wheel","wmc":3}"#
+        );
+
+        let lines = generate_jsonl(&results, &MetricSelection::default()).unwrap();
+        assert_eq!(lines.lines().count(), 2);
+        assert!(lines.starts_with(r#"{"struct_name":"Engine","module_path":"crate","#));
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use crate::models::AnalysisResult;
+use crate::thresholds::{find_violations, Thresholds, Violation};
+
+use super::html::escape;
+use super::impl_blocks::location;
+
+/// Render the threshold checks as a JUnit XML report
+///
+/// Each struct is a test class with one test case per metric that has a
+/// threshold, failing when the metric exceeds it, so CI servers such as
+/// Jenkins or Azure Pipelines can trend violations like test failures.
+pub fn generate_junit(results: &[AnalysisResult], thresholds: &Thresholds) -> String {
+    let violations: HashMap<(String, String), Violation> = find_violations(results, thresholds)
+        .into_iter()
+        .map(|v| ((v.struct_name.clone(), v.metric.clone()), v))
+        .collect();
+
+    let mut cases = Vec::new();
+    for result in results {
+        let name = result.qualified_name();
+        let mut metrics = vec!["lcom".to_string(), "cbo".to_string(), "wmc".to_string()];
+        metrics.extend(thresholds.derived.keys().filter(|m| result.derived.contains_key(*m)).cloned());
+
+        for metric in metrics {
+            let mut case = format!(
+                "    <testcase classname=\"{}\" name=\"{}\" file=\"{}\"",
+                escape(&name),
+                escape(&metric),
+                escape(&result.file_path)
+            );
+            match violations.get(&(name.clone(), metric)) {
+                Some(violation) => case.push_str(&format!(
+                    ">\n      <failure type=\"threshold\" message=\"{} {} exceeds {}\">{}</failure>\n    </testcase>\n",
+                    escape(&violation.metric),
+                    format_value(violation.value),
+                    format_value(violation.threshold),
+                    escape(&location(&violation.file_path, violation.span))
+                )),
+                None => case.push_str("/>\n"),
+            }
+            cases.push(case);
+        }
+    }
+
+    let mut output = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    output.push_str(&format!(
+        "<testsuites name=\"rust-arch-metrics\" tests=\"{0}\" failures=\"{1}\">\n  <testsuite name=\"thresholds\" tests=\"{0}\" failures=\"{1}\">\n",
+        cases.len(),
+        violations.len()
+    ));
+    for case in cases {
+        output.push_str(&case);
+    }
+    output.push_str("  </testsuite>\n</testsuites>\n");
+    output
+}
+
+pub(super) fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value)
+    } else {
+        format!("{:.3}", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SourceSpan;
+
+    #[test]
+    fn test_junit_case_per_metric() {
+        let results = vec![AnalysisResult {
+            struct_name: "God".to_string(),
+            module_path: "crate::app".to_string(),
+            file_path: "src/app.rs".to_string(),
+            span: SourceSpan { start_line: 7, end_line: 90 },
+            lcom: 0.5,
+            wmc: 52,
+            ..Default::default()
+        }];
+
+        let xml = generate_junit(&results, &Thresholds::default());
+        assert!(xml.contains("<testsuite name=\"thresholds\" tests=\"3\" failures=\"1\">"));
+        assert!(xml.contains("<testcase classname=\"crate::app::God\" name=\"lcom\" file=\"src/app.rs\"/>"));
+        assert!(xml.contains(
+            "<testcase classname=\"crate::app::God\" name=\"wmc\" file=\"src/app.rs\">\n      \
+             <failure type=\"threshold\" message=\"wmc 52 exceeds 40\">src/app.rs:7-90</failure>"
+        ));
+    }
+}
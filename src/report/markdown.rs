@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+
+use crate::models::AnalysisResult;
+use crate::thresholds::{find_violations, Thresholds, Violation};
+
+use super::impl_blocks::location;
+
+/// Render the structs as a GitHub-flavored markdown table
+///
+/// Meant to be posted as a pull request comment: a summary line, then one
+/// row per struct with the values above `thresholds` in bold and the
+/// exceeded limits listed. With `violations_only`, structs within every
+/// threshold are left out.
+pub fn generate_markdown(results: &[AnalysisResult], thresholds: &Thresholds, violations_only: bool) -> String {
+    let mut violations: BTreeMap<String, Vec<Violation>> = BTreeMap::new();
+    for violation in find_violations(results, thresholds) {
+        violations.entry(violation.struct_name.clone()).or_default().push(violation);
+    }
+
+    let mut output = String::from("## Architecture Metrics\n\n");
+    output.push_str(&format!(
+        "{} structs analyzed, {} exceeding a threshold (LCOM > {}, CBO > {}, WMC > {})\n\n",
+        results.len(),
+        violations.len(),
+        thresholds.lcom,
+        thresholds.cbo,
+        thresholds.wmc,
+    ));
+
+    let rows: Vec<&AnalysisResult> = results
+        .iter()
+        .filter(|r| !violations_only || violations.contains_key(&r.qualified_name()))
+        .collect();
+    if rows.is_empty() {
+        output.push_str(if violations_only {
+            "No structs exceed the thresholds.\n"
+        } else {
+            "No structs found to analyze.\n"
+        });
+        return output;
+    }
+
+    output.push_str("| Struct | LCOM | CBO | WMC | MI | Exceeds | Location |\n");
+    output.push_str("|:--|--:|--:|--:|--:|:--|:--|\n");
+    for result in rows {
+        let exceeded = violations.get(&result.qualified_name()).map(Vec::as_slice).unwrap_or_default();
+        let cell = |metric: &str, text: String| {
+            if exceeded.iter().any(|v| v.metric == metric) {
+                format!("**{}**", text)
+            } else {
+                text
+            }
+        };
+        let limits: Vec<String> = exceeded
+            .iter()
+            .map(|v| format!("{} > {}", v.metric, v.threshold))
+            .collect();
+
+        output.push_str(&format!(
+            "| {} | {} | {} | {} | {:.1} | {} | {} |\n",
+            code(&result.qualified_name()),
+            cell("lcom", format!("{:.3}", result.lcom)),
+            cell("cbo", result.cbo.to_string()),
+            cell("wmc", result.wmc.to_string()),
+            result.maintainability,
+            limits.join(", "),
+            code(&location(&result.file_path, result.span)),
+        ));
+    }
+
+    output
+}
+
+/// `text` as inline code that is safe inside a table cell
+fn code(text: &str) -> String {
+    format!("`{}`", text.replace('|', "\\|"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_marks_violations() {
+        let result = |name: &str, wmc, cbo| AnalysisResult {
+            struct_name: name.to_string(),
+            module_path: "crate".to_string(),
+            file_path: "src/lib.rs".to_string(),
+            wmc,
+            cbo,
+            ..Default::default()
+        };
+        let results = [result("Small", 12, 1), result("Large", 41, 6)];
+        let thresholds = Thresholds::default();
+
+        let markdown = generate_markdown(&results, &thresholds, false);
+        assert!(markdown.contains("2 structs analyzed, 1 exceeding a threshold"));
+        assert!(markdown.contains("| `crate::Small` | 0.000 | 1 | 12 | 0.0 |  | `src/lib.rs:0-0` |"));
+        assert!(markdown.contains("| `crate::Large` | 0.000 | **6** | **41** | 0.0 | cbo > 5, wmc > 40 |"));
+
+        let markdown = generate_markdown(&results, &thresholds, true);
+        assert!(!markdown.contains("crate::Small"));
+        assert!(generate_markdown(&results[..1], &thresholds, true).contains("No structs exceed the thresholds."));
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::BTreeSet;
+
+use crate::graph::DependencyGraph;
+use crate::models::AnalysisResult;
+use crate::thresholds::{find_violations, Thresholds};
+
+/// Render the structs as a Mermaid class diagram
+///
+/// Each struct is a class with its fields and methods (`+` for `pub`
+/// methods); each dependency between two reported structs is an
+/// association labelled with its number of references. Structs violating
+/// a threshold are filled red.
+pub fn generate_mermaid(results: &[AnalysisResult], thresholds: &Thresholds) -> String {
+    let graph = DependencyGraph::build(results);
+    let violating: BTreeSet<String> = find_violations(results, thresholds)
+        .into_iter()
+        .map(|v| v.struct_name)
+        .collect();
+
+    let mut output = String::from("classDiagram\n");
+
+    for node in &graph.nodes {
+        output.push_str(&format!("    class {}[\"{}\"] {{\n", class_id(node), node.struct_name));
+        for field in &node.fields {
+            output.push_str(&format!("        {}: {}\n", field.name, member_type(&field.ty)));
+        }
+        for method in &node.methods {
+            let visibility = if method.is_pub { "+" } else { "-" };
+            output.push_str(&format!("        {}{}()\n", visibility, method.name));
+        }
+        output.push_str("    }\n");
+    }
+
+    for edge in &graph.edges {
+        output.push_str(&format!(
+            "    {} --> {} : {}\n",
+            class_id(graph.nodes[edge.from]),
+            class_id(graph.nodes[edge.to]),
+            edge.references,
+        ));
+    }
+
+    for node in &graph.nodes {
+        if violating.contains(&node.qualified_name()) {
+            output.push_str(&format!("    style {} fill:#f88,stroke:#c00\n", class_id(node)));
+        }
+    }
+
+    output
+}
+
+/// Identifier unique across modules, e.g. `crate_net_Server`
+fn class_id(result: &AnalysisResult) -> String {
+    result
+        .qualified_name()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '_' { c } else { '_' })
+        .collect::<String>()
+        .replace("__", "_")
+}
+
+/// Mermaid writes generics as `Vec~User~` and ends a class body at `}`
+///
+/// Types are stored as token streams, e.g. `Vec < User >`, so the spaces
+/// around punctuation are dropped first.
+fn member_type(ty: &str) -> String {
+    const TIGHT: &[char] = &['<', '>', ':', '&', '(', ')', '[', ']'];
+    let chars: Vec<char> = ty.chars().collect();
+    let mut compact = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == ' ' {
+            let before = compact.chars().last();
+            let after = chars.get(i + 1).copied();
+            let tight = before.is_some_and(|b| TIGHT.contains(&b))
+                || after.is_some_and(|a| TIGHT.contains(&a) || a == ',');
+            if tight {
+                continue;
+            }
+        }
+        compact.push(c);
+    }
+    compact.replace(['<', '>'], "~").replace(['{', '}'], "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Dependency, FieldInfo, MethodInfo};
+
+    #[test]
+    fn test_mermaid_classes_edges_and_violations() {
+        let results = vec![
+            AnalysisResult {
+                struct_name: "Server".to_string(),
+                module_path: "crate::net".to_string(),
+                fields: vec![FieldInfo {
+                    name: "clients".into(),
+                    ty: "Vec<Client>".into(),
+                    ..Default::default()
+                }],
+                methods: vec![MethodInfo {
+                    name: "run".to_string(),
+                    is_pub: true,
+                    ..Default::default()
+                }],
+                wmc: 100,
+                dependencies: vec![Dependency {
+                    target: "crate::Client".to_string(),
+                    references: 2,
+                }],
+                ..Default::default()
+            },
+            AnalysisResult {
+                struct_name: "Client".to_string(),
+                module_path: "crate".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let diagram = generate_mermaid(&results, &Thresholds::default());
+        assert!(diagram.starts_with("classDiagram\n"));
+        assert!(diagram.contains("    class crate_net_Server[\"Server\"] {\n        clients: Vec~Client~\n        +run()\n    }\n"));
+        assert!(diagram.contains("    crate_net_Server --> crate_Client : 2\n"));
+        assert!(diagram.contains("    style crate_net_Server fill:#f88,stroke:#c00\n"));
+        assert!(!diagram.contains("style crate_Client"));
+
+        assert_eq!(member_type("HashMap < String , Vec < & 'a User > >"), "HashMap~String, Vec~&'a User~~");
+        assert_eq!(member_type("std :: sync :: Arc < [u8] >"), "std::sync::Arc~[u8]~");
+    }
+}
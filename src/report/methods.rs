@@ -0,0 +1,155 @@
+use crate::models::{MethodResult, OutputFormat};
+
+use super::impl_blocks::location;
+
+/// Render one row per method instead of per struct
+///
+/// Supports the tabular formats; XLSX is written by the caller. Methods are
+/// listed under their struct in source order, so the method behind a high
+/// WMC can be found with `--sort-by` on the structs or by sorting the CSV.
+pub fn generate_method_report(
+    methods: &[MethodResult],
+    format: OutputFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Table => Ok(generate_table(methods)),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(methods)?),
+        OutputFormat::Csv => Ok(generate_csv(methods)?),
+        _ => Err("--per-method supports only table, json, csv, and xlsx output".into()),
+    }
+}
+
+fn generate_table(methods: &[MethodResult]) -> String {
+    if methods.is_empty() {
+        return "No methods found to analyze.".to_string();
+    }
+
+    let mut output = String::new();
+
+    output.push_str(&format!(
+        "{:<44} {:>10} {:>10} {:>8} {:>10} {:>10} {:>6} {:>6} {:>8} {:>8} {:>8} {:>6} {:>7}  {}\n",
+        "Struct::Method", "Complexity", "Cognitive", "Volume", "Difficulty", "Effort", "MI", "SLOC", "Fields",
+        "Params", "Demeter", "Async", "Awaits", "Location"
+    ));
+    output.push_str(&"-".repeat(180));
+    output.push('\n');
+
+    for method in methods {
+        output.push_str(&format!(
+            "{:<44} {:>10} {:>10} {:>8.1} {:>10.1} {:>10.0} {:>6.1} {:>6} {:>8} {:>8} {:>8} {:>6} {:>7}  {}\n",
+            format!("{}::{}", method.struct_name, method.method),
+            method.complexity,
+            method.cognitive,
+            method.volume,
+            method.difficulty,
+            method.effort,
+            method.maintainability,
+            method.sloc,
+            method.fields_accessed,
+            method.params,
+            method.demeter,
+            if method.is_async { "yes" } else { "" },
+            method.awaits,
+            location(&method.file_path, method.span)
+        ));
+        if !method.demeter_lines.is_empty() {
+            let lines: Vec<String> = method.demeter_lines.iter().map(ToString::to_string).collect();
+            output.push_str(&format!("{:<44} Demeter violations at lines {}\n", "", lines.join(", ")));
+        }
+    }
+
+    output
+}
+
+fn generate_csv(methods: &[MethodResult]) -> Result<String, Box<dyn std::error::Error>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    writer.write_record([
+        "struct_name", "module_path", "file_path", "method", "start_line", "end_line", "complexity",
+        "cognitive", "volume", "difficulty", "effort", "maintainability", "sloc", "fields_accessed", "params",
+        "demeter", "demeter_lines", "is_async", "awaits",
+    ])?;
+    for method in methods {
+        writer.write_record([
+            method.struct_name.clone(),
+            method.module_path.clone(),
+            method.file_path.clone(),
+            method.method.clone(),
+            method.span.start_line.to_string(),
+            method.span.end_line.to_string(),
+            method.complexity.to_string(),
+            method.cognitive.to_string(),
+            method.volume.to_string(),
+            method.difficulty.to_string(),
+            method.effort.to_string(),
+            method.maintainability.to_string(),
+            method.sloc.to_string(),
+            method.fields_accessed.to_string(),
+            method.params.to_string(),
+            method.demeter.to_string(),
+            method.demeter_lines.iter().map(ToString::to_string).collect::<Vec<_>>().join(";"),
+            method.is_async.to_string(),
+            method.awaits.to_string(),
+        ])?;
+    }
+
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{analyze_struct, method_results};
+    use crate::parser::{parse_file, ParseOptions};
+
+    #[test]
+    fn test_demeter_violations() {
+        let source = r#"
+            struct Shop { inventory: Inventory, orders: Vec<Order> }
+            impl Shop {
+                fn report(&self, order: &Order) -> usize {
+                    let name = order.customer().name();
+                    let count = self.inventory.items.len();
+                    let total = self.orders.iter().map(|o| o.total()).sum::<usize>();
+                    self.inventory.count() + build().finish().len()
+                }
+            }
+        "#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let methods = method_results(&[analyze_struct(&structs[0], &structs)]);
+        assert_eq!(methods[0].demeter, 3);
+        assert_eq!(methods[0].demeter_lines, vec![5, 6, 8]);
+
+        let table = generate_method_report(&methods, OutputFormat::Table).unwrap();
+        assert!(table.contains("Demeter violations at lines 5, 6, 8"));
+    }
+
+    #[test]
+    fn test_method_rows() {
+        let source = r#"
+            struct Counter { count: usize, step: usize }
+            impl Counter {
+                fn new() -> Self { Counter { count: 0, step: 1 } }
+                fn add(&mut self, times: usize, verbose: bool) {
+                    for _ in 0..times { self.count += self.step; }
+                    if verbose { println!("{}", self.count); }
+                }
+            }
+        "#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let results = vec![analyze_struct(&structs[0], &structs)];
+        let methods = method_results(&results);
+
+        let rows: Vec<(&str, usize, usize, usize)> = methods
+            .iter()
+            .map(|m| (m.method.as_str(), m.complexity, m.fields_accessed, m.params))
+            .collect();
+        assert_eq!(rows, vec![("new", 1, 0, 0), ("add", 3, 2, 2)]);
+
+        let csv = generate_method_report(&methods, OutputFormat::Csv).unwrap();
+        let row: Vec<&str> = csv.lines().nth(2).unwrap().split(',').collect();
+        assert_eq!(row[..8], ["Counter", "crate", "", "add", "5", "8", "3", "2"]);
+        assert_eq!(row[12..], ["4", "2", "2", "0", "", "false", "0"]);
+        assert!(methods[1].volume > methods[0].volume && methods[1].effort > 0.0);
+    }
+}
@@ -0,0 +1,860 @@
+mod api;
+mod badge;
+mod benchmark;
+mod check;
+mod compare;
+mod cycles;
+mod debug;
+mod diff;
+mod directory;
+mod dot;
+mod envelope;
+mod errors;
+mod explain;
+mod graph_json;
+mod group;
+mod heatmap;
+mod hotspots;
+mod html;
+mod impl_blocks;
+mod jsonl;
+mod junit;
+mod markdown;
+mod mermaid;
+mod methods;
+mod ownership;
+mod sarif;
+mod site;
+mod smells;
+mod sqlite;
+mod summary;
+mod teamcity;
+mod xlsx;
+mod yaml;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use crate::api::ApiReport;
+use crate::benchmark::Benchmark;
+use crate::compare::Comparison;
+use crate::debug::StructDump;
+use crate::diff::Diff;
+use crate::explain::Explanation;
+use crate::history::HistoryEntry;
+use crate::hotspots::Hotspot;
+use crate::score::Grade;
+use crate::smells::SmellThresholds;
+use crate::models::{AnalysisResult, GroupBy, Metric, MetricSelection, OutputFormat, SourceSpan, StructKind};
+use crate::thresholds::{CheckOutcome, Thresholds};
+
+pub use envelope::{json_schema, Metadata};
+pub use jsonl::JsonLines;
+
+/// Options affecting how a report is rendered
+#[derive(Debug, Clone, Default)]
+pub struct ReportOptions {
+    pub cluster_modules: bool, // Group DOT nodes into one cluster per module
+    pub by_directory: bool, // Roll metrics up per directory instead of per struct
+    pub group_by: Option<GroupBy>, // Roll metrics up per module, file, or crate
+    pub crate_roots: Vec<(PathBuf, String)>, // Canonical input paths and their crate names, for `--group-by crate`
+    pub metrics: MetricSelection, // Metric columns of the table, JSON, and CSV
+    pub color: bool, // Color-code table cells by metric band with ANSI escapes
+    pub by_impl: bool, // Break metrics down per impl block
+    pub per_method: bool, // One row per method instead of per struct
+    pub error_coupling: bool, // Report error types per struct and module
+    pub ownership_cycles: bool, // Report Rc/Arc reference cycles
+    pub detect_cycles: bool, // Report dependency cycles between structs
+    pub detect_smells: bool, // Report design smells such as Feature Envy
+    pub heatmap: Option<Metric>, // Shade the file tree by this metric
+    pub violations_only: bool, // List only structs exceeding a threshold (markdown)
+    pub summary_only: bool, // Report only the summary statistics of the structs
+    pub thresholds: Thresholds, // Limits used to report violations
+    pub smells: SmellThresholds, // Limits used to detect design smells
+    pub metadata: Option<Metadata>, // Run written around the results of `--format json`
+}
+
+impl ReportOptions {
+    /// Whether the report lists structs with their metrics, rather than a
+    /// view such as `--group-by` or `--detect-smells`
+    pub fn is_struct_report(&self) -> bool {
+        self.heatmap.is_none()
+            && !self.by_directory
+            && self.group_by.is_none()
+            && !self.error_coupling
+            && !self.ownership_cycles
+            && !self.detect_cycles
+            && !self.detect_smells
+            && !self.by_impl
+            && !self.per_method
+    }
+}
+
+pub fn generate_report(
+    results: &[AnalysisResult],
+    format: OutputFormat,
+    output: Option<&str>,
+    options: &ReportOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // YAML mirrors the JSON of every report
+    let content = if let OutputFormat::Yaml = format {
+        render_report(results, OutputFormat::Json, output, options)?
+            .map(|json| yaml::from_json(&json))
+            .transpose()?
+    } else {
+        render_report(results, format, output, options)?
+    };
+    content.map_or(Ok(()), |content| write_output(content, output))
+}
+
+/// Render the report in `format`, or write it to `output` and return
+/// `None` for XLSX, which is binary
+fn render_report(
+    results: &[AnalysisResult],
+    format: OutputFormat,
+    output: Option<&str>,
+    options: &ReportOptions,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    if let Some(metric) = options.heatmap {
+        let content = heatmap::generate_heatmap(results, metric, format)?;
+        return Ok(Some(content));
+    }
+
+    if options.by_directory {
+        let content = directory::generate_directory_report(results, format)?;
+        return Ok(Some(content));
+    }
+
+    if let Some(group_by) = options.group_by {
+        let content = group::generate_group_report(results, group_by, &options.crate_roots, format)?;
+        return Ok(Some(content));
+    }
+
+    if options.error_coupling {
+        let content = errors::generate_error_report(results, format)?;
+        return Ok(Some(content));
+    }
+
+    if options.ownership_cycles {
+        let content = ownership::generate_ownership_report(results, format)?;
+        return Ok(Some(content));
+    }
+
+    if options.detect_cycles {
+        let content = cycles::generate_cycle_report(results, format)?;
+        return Ok(Some(content));
+    }
+
+    if options.detect_smells {
+        let content = smells::generate_smell_report(&crate::smells::detect(results, &options.smells), format)?;
+        return Ok(Some(content));
+    }
+
+    if options.by_impl {
+        let content = impl_blocks::generate_impl_report(results, format)?;
+        return Ok(Some(content));
+    }
+
+    if options.per_method {
+        let methods = crate::metrics::method_results(results);
+        if let OutputFormat::Xlsx = format {
+            let file_path = output.ok_or("XLSX output requires --output <FILE>")?;
+            std::fs::write(file_path, xlsx::generate_methods_xlsx(&methods)?)?;
+            return Ok(None);
+        }
+        let content = methods::generate_method_report(&methods, format)?;
+        return Ok(Some(content));
+    }
+
+    if options.summary_only {
+        let stats = crate::aggregate::metric_stats(results, &options.metrics.names());
+        let content = summary::generate_summary(&stats, format)?;
+        return Ok(Some(content));
+    }
+
+    // XLSX and SQLite are binary and can only be written to a file
+    if let OutputFormat::Xlsx = format {
+        let file_path = output.ok_or("XLSX output requires --output <FILE>")?;
+        std::fs::write(file_path, xlsx::generate_xlsx(results, &options.thresholds)?)?;
+        return Ok(None);
+    }
+    if let OutputFormat::Sqlite = format {
+        let file_path = output.ok_or("SQLite output requires --output <FILE>")?;
+        sqlite::append_run(file_path, results, &options.metrics, options.metadata.as_ref())?;
+        return Ok(None);
+    }
+
+    let content = match format {
+        OutputFormat::Table => generate_table(results, options),
+        OutputFormat::Json => generate_json(results, &options.metrics, options.metadata.as_ref())?,
+        OutputFormat::Csv => generate_csv(results, &options.metrics)?,
+        OutputFormat::Html => html::generate_html(results, &options.thresholds),
+        OutputFormat::Dot => dot::generate_dot(results, options.cluster_modules),
+        OutputFormat::GraphJson => graph_json::generate_graph_json(results)?,
+        OutputFormat::Sarif => sarif::generate_sarif(results, &options.thresholds)?,
+        OutputFormat::Mermaid => mermaid::generate_mermaid(results, &options.thresholds),
+        OutputFormat::Junit => junit::generate_junit(results, &options.thresholds),
+        OutputFormat::Jsonl => jsonl::generate_jsonl(results, &options.metrics)?,
+        OutputFormat::Teamcity => teamcity::generate_teamcity(results, &options.metrics.names(), &options.thresholds),
+        OutputFormat::Markdown => markdown::generate_markdown(results, &options.thresholds, options.violations_only),
+        OutputFormat::Xlsx | OutputFormat::Sqlite => unreachable!("binary formats are written above"),
+        OutputFormat::Yaml => unreachable!("YAML is converted from JSON"),
+    };
+
+    Ok(Some(content))
+}
+
+/// Write the comparison of two source trees
+pub fn generate_comparison_report(
+    comparison: &Comparison,
+    format: OutputFormat,
+    output: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = render(format, |format| compare::generate_comparison(comparison, format))?;
+    write_output(content, output)
+}
+
+/// Write the changes between two analysis runs
+pub fn generate_diff_report(
+    diff: &Diff,
+    format: OutputFormat,
+    output: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = render(format, |format| diff::generate_diff(diff, format))?;
+    write_output(content, output)
+}
+
+/// Write the structs ranked by churn times complexity
+pub fn generate_hotspot_report(
+    hotspots: &[Hotspot],
+    format: OutputFormat,
+    output: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = render(format, |format| hotspots::generate_hotspot_report(hotspots, format))?;
+    write_output(content, output)
+}
+
+/// Write the evidence behind a struct's metrics
+pub fn generate_explain_report(
+    explanation: &Explanation,
+    format: OutputFormat,
+    output: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = render(format, |format| explain::generate_explanation(explanation, format))?;
+    write_output(content, output)
+}
+
+/// Write the parsed data of the structs selected by `--debug-struct`
+pub fn generate_debug_report(
+    dumps: &[StructDump],
+    format: OutputFormat,
+    output: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = render(format, |format| debug::generate_debug(dumps, format))?;
+    write_output(content, output)
+}
+
+/// Write the API surface of a crate's public types
+pub fn generate_api_report(
+    report: &ApiReport,
+    format: OutputFormat,
+    output: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = render(format, |format| api::generate_api(report, format))?;
+    write_output(content, output)
+}
+
+/// Write percentile ranks against a reference dataset
+pub fn generate_benchmark_report(
+    benchmark: &Benchmark,
+    format: OutputFormat,
+    output: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = render(format, |format| benchmark::generate_benchmark(benchmark, format))?;
+    write_output(content, output)
+}
+
+/// Write the outcome of the `check` gate
+pub fn generate_check_report(
+    outcome: &CheckOutcome,
+    format: OutputFormat,
+    output: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = render(format, |format| check::generate_check(outcome, format))?;
+    write_output(content, output)
+}
+
+/// Write the project grade as JSON, and as an SVG badge to `badge` if given
+pub fn generate_grade_report(
+    grade: &Grade,
+    output: Option<&str>,
+    badge: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(badge_path) = badge {
+        std::fs::write(badge_path, badge::generate_badge(grade))?;
+    }
+    write_output(serde_json::to_string_pretty(grade)?, output)
+}
+
+/// Write the static dashboard for snapshot `history` into `out_dir`
+///
+/// Returns the number of pages written.
+pub fn generate_site_report(
+    history: &[HistoryEntry],
+    out_dir: &std::path::Path,
+    thresholds: &Thresholds,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let pages = site::generate_site(history, thresholds);
+    for (path, content) in &pages {
+        let file_path = out_dir.join(path);
+        if let Some(parent) = file_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(file_path, content)?;
+    }
+    Ok(pages.len())
+}
+
+/// Render with `generate`, converting its JSON for YAML
+fn render(
+    format: OutputFormat,
+    generate: impl FnOnce(OutputFormat) -> Result<String, Box<dyn std::error::Error>>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Yaml => yaml::from_json(&generate(OutputFormat::Json)?),
+        _ => generate(format),
+    }
+}
+
+fn write_output(content: String, output: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(file_path) = output {
+        std::fs::write(file_path, content)?;
+    } else {
+        println!("{}", content);
+    }
+
+    Ok(())
+}
+
+fn generate_table(results: &[AnalysisResult], options: &ReportOptions) -> String {
+    if results.is_empty() {
+        return "No structs found to analyze.".to_string();
+    }
+
+    let metrics = &options.metrics;
+    let derived = derived_names(results);
+    let names = display_names(results);
+    let mut output = String::new();
+
+    // Header
+    let mut columns = Vec::new();
+    for (selected, column) in [
+        (metrics.lcom, "LCOM"),
+        (metrics.cbo, "CBO"),
+        (metrics.all, "Ca"),
+        (metrics.all, "Fan-in"),
+        (metrics.all, "Instab."),
+        (metrics.wmc, "WMC"),
+        (metrics.mi, "MI"),
+        (metrics.lcom4, "LCOM4"),
+        (metrics.cognitive, "Cognitive"),
+        (metrics.sloc, "SLOC"),
+        (metrics.sloc, "Avg Method"),
+    ] {
+        if selected {
+            columns.push(column);
+        }
+    }
+    columns.extend(&derived);
+    output.push_str(&format!("{:<30}", "Struct Name"));
+    for column in &columns {
+        output.push_str(&format!(" {:>10}", column));
+    }
+    output.push('\n');
+    output.push_str(&"-".repeat(29 + 11 * columns.len()));
+    output.push('\n');
+
+    // Rows
+    for (result, name) in results.iter().zip(&names) {
+        output.push_str(&format!("{:<30}", name));
+        if metrics.lcom {
+            output.push_str(&format!(" {}", lcom_cell(result.lcom, options.color)));
+        }
+        if metrics.cbo {
+            output.push_str(&format!(" {}", cbo_cell(result.cbo, options.color)));
+        }
+        if metrics.all {
+            output.push_str(&format!(" {:>10} {:>10} {:>10.3}", result.ca, result.fan_in, result.instability));
+        }
+        if metrics.wmc {
+            output.push_str(&format!(" {}", wmc_cell(result.wmc, options.color)));
+        }
+        if metrics.mi {
+            output.push_str(&format!(" {}", maintainability_cell(result.maintainability, options.color)));
+        }
+        if metrics.lcom4 {
+            output.push_str(&format!(" {:>10}", result.lcom4));
+        }
+        if metrics.cognitive {
+            output.push_str(&format!(" {:>10}", result.cognitive));
+        }
+        if metrics.sloc {
+            output.push_str(&format!(" {:>10} {:>10.1}", result.sloc, result.mean_method_sloc));
+        }
+        for name in &derived {
+            output.push_str(&format!(" {:>10.3}", derived_value(result, name)));
+        }
+        output.push('\n');
+    }
+
+    output.push('\n');
+    output.push_str(&summary::generate_table(&crate::aggregate::metric_stats(results, &metrics.names())));
+
+    // Summary
+    output.push('\n');
+    output.push_str("Metric Explanations:\n");
+    if metrics.lcom {
+        output.push_str("  LCOM (0-1): Lack of Cohesion in Methods (lower is better)\n");
+    }
+    if metrics.lcom4 {
+        output.push_str("  LCOM4:      Clusters of methods sharing fields or calls (1 is cohesive)\n");
+    }
+    if metrics.cbo {
+        output.push_str("  CBO:        Coupling Between Objects (lower is better)\n");
+    }
+    if metrics.all {
+        output.push_str("  Ca:         Afferent coupling, structs depending on this one\n");
+        output.push_str("  Fan-in:     References from other structs\n");
+        output.push_str("  Instab.:    Instability Ce / (Ca + Ce), 0 stable to 1 unstable\n");
+    }
+    if metrics.wmc {
+        output.push_str("  WMC:        Weighted Methods per Class (complexity)\n");
+    }
+    if metrics.mi {
+        output.push_str("  MI (0-100): Maintainability Index (20+ good, 10-19 moderate, below 10 poor)\n");
+    }
+    if metrics.cognitive {
+        output.push_str("  Cognitive:  Nesting-weighted control flow of the methods (lower is better)\n");
+    }
+    if metrics.sloc {
+        output.push_str("  SLOC:       Lines of code of the definition and methods; Avg Method is the mean method length\n");
+    }
+
+    output
+}
+
+const GREEN: &str = "32";
+const YELLOW: &str = "33";
+const RED: &str = "31";
+const BOLD_RED: &str = "1;31";
+
+/// LCOM, green below 0.5, yellow up to 0.8, red above
+fn lcom_cell(lcom: f64, color: bool) -> String {
+    paint(format!("{:>10.3}", lcom), lcom_band(lcom), color)
+}
+
+fn lcom_band(lcom: f64) -> &'static str {
+    if lcom < 0.5 {
+        GREEN
+    } else if lcom <= 0.8 {
+        YELLOW
+    } else {
+        RED
+    }
+}
+
+/// CBO, green up to 2, yellow up to 5, red from 6
+fn cbo_cell(cbo: usize, color: bool) -> String {
+    paint(format!("{:>10}", cbo), cbo_band(cbo), color)
+}
+
+fn cbo_band(cbo: usize) -> &'static str {
+    match cbo {
+        0..=2 => GREEN,
+        3..=5 => YELLOW,
+        _ => RED,
+    }
+}
+
+/// WMC, green up to 10, yellow up to 20, red up to 40, bold red above
+fn wmc_cell(wmc: usize, color: bool) -> String {
+    paint(format!("{:>10}", wmc), wmc_band(wmc), color)
+}
+
+fn wmc_band(wmc: usize) -> &'static str {
+    match wmc {
+        0..=10 => GREEN,
+        11..=20 => YELLOW,
+        21..=40 => RED,
+        _ => BOLD_RED,
+    }
+}
+
+/// The Maintainability Index, green, yellow, or red by its band
+fn maintainability_cell(mi: f64, color: bool) -> String {
+    let band = if mi >= 20.0 {
+        GREEN
+    } else if mi >= 10.0 {
+        YELLOW
+    } else {
+        RED
+    };
+    paint(format!("{:>10.1}", mi), band, color)
+}
+
+/// `cell` in the ANSI color `code` when `color`
+fn paint(cell: String, code: &str, color: bool) -> String {
+    if color {
+        format!("\x1b[{}m{}\x1b[0m", code, cell)
+    } else {
+        cell
+    }
+}
+
+/// Name of each result, qualified when another result shares its name
+fn display_names<'a>(results: impl IntoIterator<Item = &'a AnalysisResult>) -> Vec<String> {
+    let results: Vec<&AnalysisResult> = results.into_iter().collect();
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for result in &results {
+        *counts.entry(&result.struct_name).or_default() += 1;
+    }
+    results
+        .iter()
+        .map(|r| {
+            if counts[r.struct_name.as_str()] > 1 {
+                r.qualified_name()
+            } else {
+                r.struct_name.clone()
+            }
+        })
+        .collect()
+}
+
+/// Names of the config-defined metrics carried by any result
+fn derived_names(results: &[AnalysisResult]) -> Vec<&str> {
+    let names: BTreeSet<&str> = results
+        .iter()
+        .flat_map(|r| r.derived.keys().map(String::as_str))
+        .collect();
+    names.into_iter().collect()
+}
+
+/// Value of a derived metric, NaN for results that lack it
+fn derived_value(result: &AnalysisResult, name: &str) -> f64 {
+    result.derived.get(name).copied().unwrap_or(f64::NAN)
+}
+
+/// A result as written by `--format json`
+#[derive(serde::Serialize, schemars::JsonSchema)]
+#[schemars(transform = identity_required)]
+struct JsonResult<'a> {
+    struct_name: String,
+    module_path: &'a str,
+    file_path: &'a str,
+    span: SourceSpan,
+    kind: StructKind,
+    lcom: f64,
+    lcom4: usize,
+    tcc: f64,
+    lcc: f64,
+    cbo: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trait_coupling: Option<usize>,
+    #[serde(skip_serializing_if = "is_zero")]
+    derived_coupling: usize,
+    atfd: usize,
+    ca: usize,
+    fan_in: usize,
+    instability: f64,
+    wmc: usize,
+    cognitive: usize,
+    volume: f64,
+    difficulty: f64,
+    effort: f64,
+    maintainability: f64,
+    sloc: usize,
+    mean_method_sloc: f64,
+    dyn_dispatch: usize,
+    field_types: usize,
+    domain_ratio: f64,
+    error_fanout: usize,
+    error_density: f64,
+    async_methods: usize,
+    awaits: usize,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    derives: &'a [String],
+    #[serde(flatten)]
+    derived: &'a BTreeMap<String, f64>,
+}
+
+impl<'a> From<&'a AnalysisResult> for JsonResult<'a> {
+    fn from(r: &'a AnalysisResult) -> Self {
+        JsonResult {
+            struct_name: r.struct_name.clone(),
+            module_path: &r.module_path,
+            file_path: &r.file_path,
+            span: r.span,
+            kind: r.kind,
+            lcom: r.lcom,
+            lcom4: r.lcom4,
+            tcc: r.tcc,
+            lcc: r.lcc,
+            cbo: r.cbo,
+            trait_coupling: r.trait_coupling,
+            derived_coupling: r.derived_coupling,
+            atfd: r.atfd,
+            ca: r.ca,
+            fan_in: r.fan_in,
+            instability: r.instability,
+            wmc: r.wmc,
+            cognitive: r.cognitive,
+            volume: r.volume,
+            difficulty: r.difficulty,
+            effort: r.effort,
+            maintainability: r.maintainability,
+            sloc: r.sloc,
+            mean_method_sloc: r.mean_method_sloc,
+            dyn_dispatch: r.dyn_dispatch,
+            field_types: r.field_types,
+            domain_ratio: r.domain_ratio,
+            error_fanout: r.error_types.len(),
+            error_density: r.error_density,
+            async_methods: r.async_methods,
+            awaits: r.awaits,
+            derives: &r.derives,
+            derived: &r.derived,
+        }
+    }
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+/// Only identity fields are always present; `--metrics` leaves out the
+/// fields of unselected metrics
+fn identity_required(schema: &mut schemars::Schema) {
+    schema.insert(
+        "required".to_string(),
+        serde_json::json!(["struct_name", "module_path", "file_path", "span", "kind"]),
+    );
+}
+
+/// Leave the fields of unselected metrics out of a serialized result
+fn retain_selected(object: &mut serde_json::Map<String, serde_json::Value>, metrics: &MetricSelection, derived: &[&str]) {
+    object.retain(|field, _| metrics.includes_field(field) || derived.contains(&field.as_str()));
+}
+
+/// The results as a JSON array, inside an `Envelope` with their summary
+/// statistics when the run's `metadata` is known
+fn generate_json(
+    results: &[AnalysisResult],
+    metrics: &MetricSelection,
+    metadata: Option<&Metadata>,
+) -> Result<String, serde_json::Error> {
+    let json_results: Vec<JsonResult> = results.iter().map(JsonResult::from).collect();
+    let summary = || summary::by_metric(&crate::aggregate::metric_stats(results, &metrics.names()));
+
+    if metrics.all {
+        return match metadata {
+            Some(metadata) => serde_json::to_string_pretty(&envelope::Envelope::new(metadata, json_results, summary())),
+            None => serde_json::to_string_pretty(&json_results),
+        };
+    }
+    let derived = derived_names(results);
+    let mut value = serde_json::to_value(&json_results)?;
+    for object in value.as_array_mut().into_iter().flatten().filter_map(|v| v.as_object_mut()) {
+        retain_selected(object, metrics, &derived);
+    }
+    match metadata {
+        Some(metadata) => serde_json::to_string_pretty(&envelope::Envelope::new(metadata, value, summary())),
+        None => serde_json::to_string_pretty(&value),
+    }
+}
+
+fn generate_csv(results: &[AnalysisResult], metrics: &MetricSelection) -> Result<String, csv::Error> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    let derived = derived_names(results);
+
+    // Header
+    let mut header = vec![
+        "struct_name", "module_path", "file_path", "start_line", "end_line", "kind", "lcom", "lcom4", "tcc", "lcc", "cbo", "trait_coupling", "derived_coupling", "atfd", "ca", "fan_in", "instability", "wmc", "cognitive", "volume", "difficulty", "effort", "maintainability", "sloc", "mean_method_sloc", "dyn_dispatch", "field_types", "domain_ratio", "error_fanout", "error_density", "async_methods", "awaits",
+    ];
+    // Trait coupling is only reported apart from CBO under `--separate-trait-coupling`,
+    // and derived coupling once derives are recorded
+    let trait_coupling = results.iter().any(|r| r.trait_coupling.is_some());
+    let derived_coupling = results.iter().any(|r| r.derived_coupling > 0);
+    let included = |field: &&str| {
+        metrics.includes_field(field)
+            && match *field {
+                "trait_coupling" => trait_coupling,
+                "derived_coupling" => derived_coupling,
+                _ => true,
+            }
+    };
+    let selected: Vec<bool> = header.iter().map(included).collect();
+    header.retain(included);
+    header.extend(&derived);
+    writer.write_record(&header)?;
+
+    // Data
+    for result in results {
+        let mut record = vec![
+            result.struct_name.clone(),
+            result.module_path.clone(),
+            result.file_path.clone(),
+            result.span.start_line.to_string(),
+            result.span.end_line.to_string(),
+            result.kind.to_string(),
+            result.lcom.to_string(),
+            result.lcom4.to_string(),
+            result.tcc.to_string(),
+            result.lcc.to_string(),
+            result.cbo.to_string(),
+            result.trait_coupling.map_or_else(String::new, |n| n.to_string()),
+            result.derived_coupling.to_string(),
+            result.atfd.to_string(),
+            result.ca.to_string(),
+            result.fan_in.to_string(),
+            result.instability.to_string(),
+            result.wmc.to_string(),
+            result.cognitive.to_string(),
+            result.volume.to_string(),
+            result.difficulty.to_string(),
+            result.effort.to_string(),
+            result.maintainability.to_string(),
+            result.sloc.to_string(),
+            result.mean_method_sloc.to_string(),
+            result.dyn_dispatch.to_string(),
+            result.field_types.to_string(),
+            result.domain_ratio.to_string(),
+            result.error_types.len().to_string(),
+            result.error_density.to_string(),
+            result.async_methods.to_string(),
+            result.awaits.to_string(),
+        ];
+        let mut selected = selected.iter();
+        record.retain(|_| *selected.next().unwrap_or(&true));
+        record.extend(derived.iter().map(|name| derived_value(result, name).to_string()));
+        writer.write_record(&record)?;
+    }
+
+    writer.flush()?;
+    let inner = writer.into_inner().map_err(|e| {
+        std::io::Error::other(format!("CSV error: {:?}", e))
+    })?;
+    let data = String::from_utf8(inner).map_err(|e| {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+    })?;
+    Ok(data)
+}
+
+/// Green (0.0) through yellow to red (1.0), for shading by a normalized metric
+fn heat_rgb(fraction: f64) -> (u8, u8, u8) {
+    let fraction = fraction.clamp(0.0, 1.0);
+    if fraction < 0.5 {
+        ((fraction * 2.0 * 255.0) as u8, 255, 0x80)
+    } else {
+        (255, ((1.0 - fraction) * 2.0 * 255.0) as u8, 0x80)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_named_structs_stay_apart() {
+        let result = |module: &str, file: &str, line: usize| AnalysisResult {
+            struct_name: "Config".to_string(),
+            module_path: module.to_string(),
+            file_path: file.to_string(),
+            span: SourceSpan { start_line: line, end_line: line + 2 },
+            ..Default::default()
+        };
+        let results = vec![result("crate::db", "src/db.rs", 3), result("crate::net", "src/net.rs", 10)];
+
+        let table = generate_table(&results, &ReportOptions::default());
+        assert!(table.contains("crate::db::Config") && table.contains("crate::net::Config"));
+        assert!(!table.contains('\x1b'));
+
+        assert_eq!(maintainability_cell(45.0, true), "\x1b[32m      45.0\x1b[0m");
+        assert_eq!(maintainability_cell(9.5, true), "\x1b[31m       9.5\x1b[0m");
+        assert_eq!(lcom_cell(0.8, true), "\x1b[33m     0.800\x1b[0m");
+        assert_eq!(cbo_cell(6, true), "\x1b[31m         6\x1b[0m");
+        assert_eq!(wmc_cell(41, true), "\x1b[1;31m        41\x1b[0m");
+        assert_eq!(wmc_cell(41, false), "        41");
+
+        let csv = generate_csv(&results, &MetricSelection::default()).unwrap();
+        assert!(csv.lines().nth(2).unwrap().starts_with("Config,crate::net,src/net.rs,10,12,struct,0,0,0,0,0,0,0,0,"));
+    }
+
+    #[test]
+    fn test_metric_selection_limits_columns() {
+        let results = vec![AnalysisResult {
+            struct_name: "Engine".to_string(),
+            module_path: "crate".to_string(),
+            lcom: 0.5,
+            cbo: 3,
+            ca: 2,
+            fan_in: 3,
+            instability: 0.6,
+            wmc: 12,
+            sloc: 40,
+            ..Default::default()
+        }];
+        let options = ReportOptions {
+            metrics: "cbo,lcom".parse().unwrap(),
+            ..Default::default()
+        };
+
+        let table = generate_table(&results, &options);
+        assert!(table.starts_with(&format!("{:<30} {:>10} {:>10}\n", "Struct Name", "LCOM", "CBO")));
+        assert!(table.contains(&format!("{:<30} {:>10} {:>10}\n", "Engine", "0.500", "3")));
+        assert!(!table.contains("WMC") && !table.contains("Instab."));
+
+        // Afferent coupling and instability follow CBO in the full table
+        let full = generate_table(&results, &ReportOptions::default());
+        let header = format!("{:<30} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}", "Struct Name", "LCOM", "CBO", "Ca", "Fan-in", "Instab.", "WMC");
+        assert!(full.starts_with(&header));
+        assert!(full.contains(&format!(" {:>10} {:>10} {:>10} {:>10}", 3, 2, 3, "0.600")));
+
+        let csv = generate_csv(&results, &options.metrics).unwrap();
+        assert!(csv.starts_with("struct_name,module_path,file_path,start_line,end_line,kind,lcom,cbo\n"));
+        let json = generate_json(&results, &options.metrics, None).unwrap();
+        assert!(json.contains("\"cbo\": 3") && !json.contains("wmc") && !json.contains("tcc"));
+
+        let metadata = Metadata::new("src".to_string(), crate::snapshot::RunSettings::default());
+        let json: serde_json::Value = serde_json::from_str(&generate_json(&results, &options.metrics, Some(&metadata)).unwrap()).unwrap();
+        assert_eq!(json["results"][0]["cbo"], 3);
+        assert_eq!(json["summary"]["cbo"]["max"], 3.0);
+        assert_eq!(json["summary"]["lcom"]["mean"], 0.5);
+        assert!(json["summary"].get("wmc").is_none());
+
+        let extended: MetricSelection = "all, sloc".parse().unwrap();
+        assert!(extended.all && extended.wmc && extended.sloc && !extended.lcom4);
+        assert!("lcom,size".parse::<MetricSelection>().unwrap_err().contains("Unknown metric: \"size\""));
+    }
+
+    #[test]
+    fn test_bands_change_at_each_threshold() {
+        assert_eq!([0.0, 0.499, 0.5, 0.8, 0.801, 1.0].map(lcom_band), [GREEN, GREEN, YELLOW, YELLOW, RED, RED]);
+        assert_eq!([0, 2, 3, 5, 6].map(cbo_band), [GREEN, GREEN, YELLOW, YELLOW, RED]);
+        assert_eq!([0, 10, 11, 20, 21, 40, 41].map(wmc_band), [GREEN, GREEN, YELLOW, YELLOW, RED, RED, BOLD_RED]);
+    }
+
+    #[test]
+    fn test_no_color_turns_auto_color_off() {
+        use crate::models::ColorMode;
+        use std::ffi::OsStr;
+
+        assert!(ColorMode::Auto.enabled_with(true, None));
+        assert!(!ColorMode::Auto.enabled_with(false, None));
+        assert!(!ColorMode::Auto.enabled_with(true, Some(OsStr::new("1"))));
+        // An empty NO_COLOR counts as unset, per no-color.org
+        assert!(ColorMode::Auto.enabled_with(true, Some(OsStr::new(""))));
+        assert!(ColorMode::Always.enabled_with(false, Some(OsStr::new("1"))));
+        assert!(!ColorMode::Never.enabled_with(true, None));
+    }
+}
@@ -0,0 +1,48 @@
+use crate::models::{AnalysisResult, OutputFormat};
+use crate::ownership::{find_cycles, OwnershipCycle};
+
+/// Render the reference cycles among structs formed through `Rc`/`Arc`
+///
+/// Cycles that can leak are listed first, each with the fields forming it.
+pub fn generate_ownership_report(
+    results: &[AnalysisResult],
+    format: OutputFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut cycles = find_cycles(results);
+    cycles.sort_by_key(|c| c.weak);
+
+    match format {
+        OutputFormat::Table => Ok(generate_table(&cycles)),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(&cycles)?),
+        _ => Err("--ownership-cycles supports only table and json output".into()),
+    }
+}
+
+fn generate_table(cycles: &[OwnershipCycle]) -> String {
+    if cycles.is_empty() {
+        return "No ownership cycles through Rc or Arc found.".to_string();
+    }
+
+    let mut output = String::new();
+
+    let leaking = cycles.iter().filter(|c| !c.weak).count();
+    output.push_str(&format!("Ownership cycles: {} ({} may leak)\n", cycles.len(), leaking));
+
+    for cycle in cycles {
+        let mut path = cycle.structs.clone();
+        path.push(cycle.structs[0].clone());
+        let status = if cycle.weak { "weak" } else { "LEAK" };
+        output.push_str(&format!("\n{:<5} {}\n", status, path.join(" -> ")));
+
+        for hop in &cycle.hops {
+            for (field, kind) in &hop.fields {
+                output.push_str(&format!("      {}.{} ({}) -> {}\n", hop.from, field, kind, hop.to));
+            }
+        }
+        if !cycle.weak {
+            output.push_str("      Make one of these fields Weak, typically the back-reference to the owner\n");
+        }
+    }
+
+    output
+}
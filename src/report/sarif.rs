@@ -0,0 +1,277 @@
+use serde::Serialize;
+
+use crate::models::AnalysisResult;
+use crate::thresholds::{find_violations, Thresholds, Violation};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Built-in rules: metric, name, description, and reference
+const RULES: [(&str, &str, &str, &str); 3] = [
+    (
+        "lcom",
+        "LackOfCohesion",
+        "Methods share few fields; the struct may have several responsibilities",
+        "https://en.wikipedia.org/wiki/Lack_of_cohesion_in_methods",
+    ),
+    (
+        "cbo",
+        "HighCoupling",
+        "The struct depends on many other structs of the crate",
+        "https://en.wikipedia.org/wiki/Coupling_(computer_programming)",
+    ),
+    (
+        "wmc",
+        "HighComplexity",
+        "The summed cyclomatic complexity of the struct's methods is high",
+        "https://en.wikipedia.org/wiki/Cyclomatic_complexity",
+    ),
+];
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: String,
+    name: String,
+    short_description: Message,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help_uri: Option<&'static str>,
+    default_configuration: Configuration,
+}
+
+#[derive(Serialize)]
+struct Configuration {
+    level: &'static str,
+}
+
+#[derive(Serialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: String,
+    rule_index: usize,
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    physical_location: Option<PhysicalLocation>,
+    logical_locations: Vec<LogicalLocation>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    region: Region,
+}
+
+#[derive(Serialize)]
+struct ArtifactLocation {
+    uri: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: usize,
+    end_line: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LogicalLocation {
+    fully_qualified_name: String,
+    kind: &'static str,
+}
+
+/// Render threshold violations as a SARIF 2.1.0 log for code scanning
+///
+/// Every metric with a threshold is a rule, including config-defined
+/// metrics, and every violation is a warning at the struct definition.
+/// File paths are written as given on the command line, so run from the
+/// repository root for GitHub to place annotations.
+pub fn generate_sarif(results: &[AnalysisResult], thresholds: &Thresholds) -> Result<String, serde_json::Error> {
+    let mut rules: Vec<Rule> = RULES
+        .iter()
+        .map(|&(metric, name, description, uri)| Rule {
+            id: metric.to_uppercase(),
+            name: name.to_string(),
+            short_description: Message {
+                text: description.to_string(),
+            },
+            help_uri: Some(uri),
+            default_configuration: Configuration { level: "warning" },
+        })
+        .collect();
+    rules.extend(thresholds.derived.keys().map(|name| Rule {
+        id: name.clone(),
+        name: name.clone(),
+        short_description: Message {
+            text: format!("Config-defined metric `{}` exceeds its threshold", name),
+        },
+        help_uri: None,
+        default_configuration: Configuration { level: "warning" },
+    }));
+
+    let sarif_results: Vec<SarifResult> = find_violations(results, thresholds)
+        .iter()
+        .map(|violation| {
+            let rule_index = RULES
+                .iter()
+                .position(|(metric, ..)| *metric == violation.metric)
+                .or_else(|| {
+                    let derived = thresholds.derived.keys().position(|name| *name == violation.metric)?;
+                    Some(RULES.len() + derived)
+                })
+                .unwrap_or_default();
+            SarifResult {
+                rule_id: rules[rule_index].id.clone(),
+                rule_index,
+                level: "warning",
+                message: Message {
+                    text: message(violation),
+                },
+                locations: vec![location(violation)],
+            }
+        })
+        .collect();
+
+    #[derive(Serialize)]
+    struct Tool {
+        driver: Driver,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Driver {
+        name: &'static str,
+        version: &'static str,
+        information_uri: &'static str,
+        rules: Vec<Rule>,
+    }
+
+    #[derive(Serialize)]
+    struct Run {
+        tool: Tool,
+        results: Vec<SarifResult>,
+    }
+
+    #[derive(Serialize)]
+    struct Log {
+        #[serde(rename = "$schema")]
+        schema: &'static str,
+        version: &'static str,
+        runs: Vec<Run>,
+    }
+
+    let log = Log {
+        schema: SCHEMA,
+        version: "2.1.0",
+        runs: vec![Run {
+            tool: Tool {
+                driver: Driver {
+                    name: env!("CARGO_PKG_NAME"),
+                    version: env!("CARGO_PKG_VERSION"),
+                    information_uri: "https://github.com/gabrielelanaro/rust-arch-metrics",
+                    rules,
+                },
+            },
+            results: sarif_results,
+        }],
+    };
+
+    serde_json::to_string_pretty(&log)
+}
+
+fn message(violation: &Violation) -> String {
+    let metric = if RULES.iter().any(|(metric, ..)| *metric == violation.metric) {
+        violation.metric.to_uppercase()
+    } else {
+        violation.metric.clone()
+    };
+    format!(
+        "{} has {} {} (threshold {})",
+        violation.struct_name,
+        metric,
+        format_value(violation.value),
+        format_value(violation.threshold)
+    )
+}
+
+/// Whole numbers without decimals, others to three decimals
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value)
+    } else {
+        format!("{:.3}", value)
+    }
+}
+
+/// The struct definition, by file and line when the source is on disk
+fn location(violation: &Violation) -> Location {
+    let physical_location = (!violation.file_path.is_empty()).then(|| PhysicalLocation {
+        artifact_location: ArtifactLocation {
+            uri: violation.file_path.replace('\\', "/").trim_start_matches("./").to_string(),
+        },
+        region: Region {
+            start_line: violation.span.start_line.max(1),
+            end_line: violation.span.end_line.max(violation.span.start_line).max(1),
+        },
+    });
+    Location {
+        physical_location,
+        logical_locations: vec![LogicalLocation {
+            fully_qualified_name: violation.struct_name.clone(),
+            kind: "type",
+        }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SourceSpan;
+
+    #[test]
+    fn test_sarif_result_per_violation() {
+        let results = vec![AnalysisResult {
+            struct_name: "God".to_string(),
+            module_path: "crate::app".to_string(),
+            file_path: "./src/app.rs".to_string(),
+            span: SourceSpan { start_line: 7, end_line: 90 },
+            lcom: 0.5,
+            cbo: 6,
+            wmc: 52,
+            ..Default::default()
+        }];
+
+        let sarif: serde_json::Value =
+            serde_json::from_str(&generate_sarif(&results, &Thresholds::default()).unwrap()).unwrap();
+        let run = &sarif["runs"][0];
+        assert_eq!(sarif["version"], "2.1.0");
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 3);
+
+        let rule_ids: Vec<&str> = run["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["ruleId"].as_str().unwrap())
+            .collect();
+        assert_eq!(rule_ids, vec!["CBO", "WMC"]);
+
+        let wmc = &run["results"][1];
+        assert_eq!(wmc["ruleIndex"], 2);
+        assert_eq!(wmc["message"]["text"], "crate::app::God has WMC 52 (threshold 40)");
+        let physical = &wmc["locations"][0]["physicalLocation"];
+        assert_eq!(physical["artifactLocation"]["uri"], "src/app.rs");
+        assert_eq!(physical["region"]["startLine"], 7);
+    }
+}
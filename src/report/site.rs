@@ -0,0 +1,334 @@
+use std::collections::BTreeMap;
+
+use super::html::escape;
+use crate::aggregate::{aggregate_by_module, summarize};
+use crate::history::HistoryEntry;
+use crate::models::{AnalysisResult, Metric};
+use crate::score::grade;
+use crate::significance::{mean_changes, Significance, Verdict};
+use crate::thresholds::{find_violations, Thresholds};
+
+/// Structs listed per worst-offender table
+const WORST_OFFENDERS: usize = 10;
+
+const STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; margin: 2rem; color: #24292f; max-width: 70rem; }
+table { border-collapse: collapse; margin-bottom: 1.5rem; }
+th, td { padding: 0.3rem 0.8rem; border-bottom: 1px solid #d0d7de; text-align: left; }
+td.num { text-align: right; font-variant-numeric: tabular-nums; }
+td.violation { color: #cf222e; font-weight: bold; }
+.muted { color: #57606a; }
+.trends { display: flex; flex-wrap: wrap; gap: 1.5rem; margin-bottom: 1.5rem; }
+.trend { border: 1px solid #d0d7de; border-radius: 6px; padding: 0.5rem 0.8rem; }
+.trend .value { font-size: 1.4em; font-weight: bold; }
+.change { font-size: 0.8em; }
+.change.real { color: #9a6700; font-weight: bold; }
+.offenders { display: flex; flex-wrap: wrap; gap: 1.5rem; }
+";
+
+/// Render a static dashboard from snapshot history, oldest entry first
+///
+/// Returns `(relative path, HTML)` pages: `index.html` with trends of the
+/// summary metrics and grade, the worst offenders and the modules of the
+/// latest snapshot, and one `modules/<module>.html` page per module with
+/// its own trend and structs. Pages link relatively, so the directory can
+/// be published as-is, e.g. to GitHub Pages.
+pub fn generate_site(history: &[HistoryEntry], thresholds: &Thresholds) -> Vec<(String, String)> {
+    let Some(latest) = history.last() else {
+        return vec![(
+            "index.html".to_string(),
+            page("Architecture Metrics", "<h1>Architecture Metrics</h1>\n<p>No snapshots found.</p>\n"),
+        )];
+    };
+
+    let mut pages = vec![("index.html".to_string(), generate_index(history, latest, thresholds))];
+    for module in aggregate_by_module(&latest.results) {
+        pages.push((
+            format!("modules/{}.html", module_slug(&module.module_path)),
+            generate_module_page(history, latest, &module.module_path, thresholds),
+        ));
+    }
+    pages
+}
+
+fn generate_index(history: &[HistoryEntry], latest: &HistoryEntry, thresholds: &Thresholds) -> String {
+    let mut body = String::new();
+    let latest_grade = grade(&latest.results, thresholds);
+
+    body.push_str("<h1>Architecture Metrics</h1>\n");
+    body.push_str(&format!(
+        "<p class=\"muted\">Latest snapshot {} &middot; {} snapshot(s) &middot; {} structs &middot; {} violation(s)</p>\n",
+        escape(&latest.created_at),
+        history.len(),
+        latest_grade.struct_count,
+        latest_grade.violations,
+    ));
+
+    // Trends
+    let summaries: Vec<_> = history.iter().map(|e| summarize(&e.results)).collect();
+    let grades: Vec<_> = history.iter().map(|e| grade(&e.results, thresholds)).collect();
+    let changes = match history {
+        [.., previous, latest] => mean_changes(&previous.results, &latest.results),
+        _ => Vec::new(),
+    };
+    let change = |metric: &str| changes.iter().find(|c| c.metric == metric);
+    body.push_str("<h2>Trends</h2>\n<div class=\"trends\">\n");
+    body.push_str(&trend_card(
+        &format!("Score ({})", latest_grade.grade),
+        &grades.iter().map(|g| g.score).collect::<Vec<_>>(),
+        0,
+        None,
+    ));
+    body.push_str(&trend_card("Structs", &summaries.iter().map(|s| s.struct_count as f64).collect::<Vec<_>>(), 0, None));
+    body.push_str(&trend_card("Mean LCOM", &summaries.iter().map(|s| s.mean_lcom).collect::<Vec<_>>(), 3, change("lcom")));
+    body.push_str(&trend_card("Mean CBO", &summaries.iter().map(|s| s.mean_cbo).collect::<Vec<_>>(), 2, change("cbo")));
+    body.push_str(&trend_card("Mean WMC", &summaries.iter().map(|s| s.mean_wmc).collect::<Vec<_>>(), 2, change("wmc")));
+    body.push_str("</div>\n");
+
+    body.push_str("<table>\n<thead><tr><th>Snapshot</th><th>Structs</th><th>Mean LCOM</th><th>Mean CBO</th><th>Mean WMC</th><th>Score</th><th>Grade</th></tr></thead>\n<tbody>\n");
+    for ((entry, summary), snapshot_grade) in history.iter().zip(&summaries).zip(&grades).rev() {
+        body.push_str(&format!(
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{:.3}</td><td class=\"num\">{:.2}</td><td class=\"num\">{:.2}</td><td class=\"num\">{:.1}</td><td>{}</td></tr>\n",
+            escape(&entry.created_at),
+            summary.struct_count,
+            summary.mean_lcom,
+            summary.mean_cbo,
+            summary.mean_wmc,
+            snapshot_grade.score,
+            snapshot_grade.grade,
+        ));
+    }
+    body.push_str("</tbody>\n</table>\n");
+
+    // Worst offenders
+    body.push_str("<h2>Worst Offenders</h2>\n<div class=\"offenders\">\n");
+    for metric in [Metric::Wmc, Metric::Cbo, Metric::Lcom] {
+        let mut ranked: Vec<&AnalysisResult> = latest.results.iter().collect();
+        ranked.sort_by(|a, b| metric.value(b).total_cmp(&metric.value(a)));
+
+        body.push_str(&format!(
+            "<div>\n<h3>{}</h3>\n<table>\n<thead><tr><th>Struct</th><th>Module</th><th>{}</th></tr></thead>\n<tbody>\n",
+            metric, metric
+        ));
+        for result in ranked.into_iter().take(WORST_OFFENDERS) {
+            body.push_str(&format!(
+                "<tr><td>{}</td><td><a href=\"modules/{}.html\">{}</a></td><td class=\"num\">{}</td></tr>\n",
+                escape(&result.struct_name),
+                module_slug(&result.module_path),
+                escape(module_label(&result.module_path)),
+                format_metric(metric.value(result)),
+            ));
+        }
+        body.push_str("</tbody>\n</table>\n</div>\n");
+    }
+    body.push_str("</div>\n");
+
+    // Modules
+    body.push_str("<h2>Modules</h2>\n<table>\n<thead><tr><th>Module</th><th>Structs</th><th>Mean LCOM</th><th>Total CBO</th><th>Total WMC</th><th>Max WMC</th></tr></thead>\n<tbody>\n");
+    for module in aggregate_by_module(&latest.results) {
+        body.push_str(&format!(
+            "<tr><td><a href=\"modules/{}.html\">{}</a></td><td class=\"num\">{}</td><td class=\"num\">{:.3}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+            module_slug(&module.module_path),
+            escape(module_label(&module.module_path)),
+            module.struct_count,
+            module.mean_lcom,
+            module.total_cbo,
+            module.total_wmc,
+            module.max_wmc,
+        ));
+    }
+    body.push_str("</tbody>\n</table>\n");
+
+    page("Architecture Metrics", &body)
+}
+
+fn generate_module_page(
+    history: &[HistoryEntry],
+    latest: &HistoryEntry,
+    module_path: &str,
+    thresholds: &Thresholds,
+) -> String {
+    let members = |results: &[AnalysisResult]| -> Vec<AnalysisResult> {
+        results.iter().filter(|r| r.module_path == module_path).cloned().collect()
+    };
+    let label = module_label(module_path);
+    let mut body = String::new();
+
+    body.push_str("<p><a href=\"../index.html\">&larr; Dashboard</a></p>\n");
+    body.push_str(&format!("<h1>{}</h1>\n", escape(label)));
+
+    // Trend over the snapshots in which the module exists
+    let sets: Vec<Vec<AnalysisResult>> = history
+        .iter()
+        .map(|e| members(&e.results))
+        .filter(|set| !set.is_empty())
+        .collect();
+    let summaries: Vec<_> = sets.iter().map(|set| summarize(set)).collect();
+    let changes = match sets.as_slice() {
+        [.., previous, latest] => mean_changes(previous, latest),
+        _ => Vec::new(),
+    };
+    let change = |metric: &str| changes.iter().find(|c| c.metric == metric);
+    body.push_str("<div class=\"trends\">\n");
+    body.push_str(&trend_card("Structs", &summaries.iter().map(|s| s.struct_count as f64).collect::<Vec<_>>(), 0, None));
+    body.push_str(&trend_card("Mean LCOM", &summaries.iter().map(|s| s.mean_lcom).collect::<Vec<_>>(), 3, change("lcom")));
+    body.push_str(&trend_card("Mean CBO", &summaries.iter().map(|s| s.mean_cbo).collect::<Vec<_>>(), 2, change("cbo")));
+    body.push_str(&trend_card("Mean WMC", &summaries.iter().map(|s| s.mean_wmc).collect::<Vec<_>>(), 2, change("wmc")));
+    body.push_str(&trend_card("Total WMC", &summaries.iter().map(|s| s.total_wmc as f64).collect::<Vec<_>>(), 0, None));
+    body.push_str("</div>\n");
+
+    // Structs of the latest snapshot, violations highlighted
+    let structs = members(&latest.results);
+    let mut violated: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for violation in find_violations(&structs, thresholds) {
+        violated.entry(violation.struct_name).or_default().push(violation.metric);
+    }
+    let cell = |result: &AnalysisResult, metric: &str, value: String| {
+        let violation = violated
+            .get(&result.qualified_name())
+            .is_some_and(|metrics| metrics.iter().any(|m| m == metric));
+        let class = if violation { "num violation" } else { "num" };
+        format!("<td class=\"{}\">{}</td>", class, value)
+    };
+
+    body.push_str("<h2>Structs</h2>\n<table>\n<thead><tr><th>Struct</th><th>Kind</th><th>LCOM</th><th>CBO</th><th>WMC</th><th>File</th></tr></thead>\n<tbody>\n");
+    for result in &structs {
+        body.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td>{}{}{}<td class=\"muted\">{}</td></tr>\n",
+            escape(&result.struct_name),
+            result.kind,
+            cell(result, "lcom", format!("{:.3}", result.lcom)),
+            cell(result, "cbo", result.cbo.to_string()),
+            cell(result, "wmc", result.wmc.to_string()),
+            escape(&result.file_path),
+        ));
+    }
+    body.push_str("</tbody>\n</table>\n");
+
+    page(&format!("{} - Architecture Metrics", label), &body)
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        body
+    )
+}
+
+/// Latest value of a series with a sparkline of its history
+///
+/// `change` is the significance of the move from the previous snapshot,
+/// shown so that noise is not mistaken for real movement.
+fn trend_card(label: &str, values: &[f64], precision: usize, change: Option<&Significance>) -> String {
+    let latest = values.last().map_or("-".to_string(), |v| format!("{:.*}", precision, v));
+    let change = change.map_or(String::new(), |c| {
+        let class = if c.verdict == Verdict::Noise { "change muted" } else { "change real" };
+        format!(
+            "<div class=\"{}\" title=\"Cohen's d {:+.2} vs previous snapshot\">{} change</div>",
+            class, c.effect_size, c.verdict
+        )
+    });
+    format!(
+        "<div class=\"trend\"><div class=\"muted\">{}</div><div class=\"value\">{}</div>{}{}</div>\n",
+        escape(label),
+        latest,
+        sparkline(values),
+        change
+    )
+}
+
+/// Inline SVG line chart of `values`, scaled to their own range
+fn sparkline(values: &[f64]) -> String {
+    const WIDTH: f64 = 160.0;
+    const HEIGHT: f64 = 36.0;
+
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = if max > min { max - min } else { 1.0 };
+    let step = if values.len() > 1 { WIDTH / (values.len() - 1) as f64 } else { 0.0 };
+
+    let points: Vec<String> = values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            // A flat series is drawn through the middle
+            let y = if max > min { HEIGHT - 2.0 - (v - min) / range * (HEIGHT - 4.0) } else { HEIGHT / 2.0 };
+            format!("{:.1},{:.1}", i as f64 * step + 2.0, y)
+        })
+        .collect();
+
+    let shape = match points.as_slice() {
+        [] => String::new(),
+        [point] => {
+            let (x, y) = point.split_once(',').unwrap_or(("0", "0"));
+            format!("<circle cx=\"{}\" cy=\"{}\" r=\"2\" fill=\"#0969da\"/>", x, y)
+        }
+        _ => format!(
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"#0969da\" stroke-width=\"1.5\"/>",
+            points.join(" ")
+        ),
+    };
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" role=\"img\">{}</svg>",
+        WIDTH + 4.0,
+        HEIGHT,
+        shape
+    )
+}
+
+/// File name of a module's page, e.g. `crate-net-http` for `crate::net::http`
+fn module_slug(module_path: &str) -> String {
+    if module_path.is_empty() {
+        return "crate".to_string();
+    }
+    module_path
+        .replace("::", "-")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+fn module_label(module_path: &str) -> &str {
+    if module_path.is_empty() { "crate" } else { module_path }
+}
+
+/// Integers without decimals, fractions (LCOM) with three
+fn format_metric(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value)
+    } else {
+        format!("{:.3}", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_site_pages_and_links() {
+        let results: Vec<AnalysisResult> = serde_json::from_str(
+            r#"[{"struct_name": "Server", "module_path": "crate::net", "wmc": 50},
+                {"struct_name": "Config", "module_path": "crate", "wmc": 3}]"#,
+        )
+        .unwrap();
+        let history = vec![
+            HistoryEntry { created_at: "2024-05-01T00-00-00Z".to_string(), results: results[1..].to_vec() },
+            HistoryEntry { created_at: "2024-05-02T00-00-00Z".to_string(), results },
+        ];
+
+        let pages = generate_site(&history, &Thresholds::default());
+        let paths: Vec<&str> = pages.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, vec!["index.html", "modules/crate.html", "modules/crate-net.html"]);
+
+        let index = &pages[0].1;
+        assert!(index.contains("<a href=\"modules/crate-net.html\">crate::net</a>"));
+        assert!(index.contains("<polyline"));
+
+        // Server exceeds the WMC threshold
+        assert!(pages[2].1.contains("<td class=\"num violation\">50</td>"));
+    }
+}
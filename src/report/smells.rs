@@ -0,0 +1,65 @@
+use crate::models::{OutputFormat, SourceSpan};
+use crate::smells::Smell;
+
+use super::impl_blocks::location;
+
+/// Render the design smells detected, one row per smell
+pub fn generate_smell_report(smells: &[Smell], format: OutputFormat) -> Result<String, Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Table => Ok(generate_table(smells)),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(smells)?),
+        OutputFormat::Csv => Ok(generate_csv(smells)?),
+        _ => Err("--detect-smells supports only table, json, and csv output".into()),
+    }
+}
+
+fn generate_table(smells: &[Smell]) -> String {
+    if smells.is_empty() {
+        return "No design smells detected.".to_string();
+    }
+
+    let mut output = String::new();
+
+    output.push_str(&format!("{:<14} {:<44} {}\n", "Smell", "Struct::Method", "Reason"));
+    output.push_str(&"-".repeat(100));
+    output.push('\n');
+    for smell in smells {
+        output.push_str(&format!(
+            "{:<14} {:<44} {}\n",
+            smell.kind.to_string(),
+            subject(smell),
+            smell.reason
+        ));
+        output.push_str(&format!("{:<14} {}\n", "", location(&smell.file_path, smell.span)));
+    }
+
+    output
+}
+
+/// The struct, or the method for method-level smells
+fn subject(smell: &Smell) -> String {
+    match &smell.method {
+        Some(method) => format!("{}::{}", smell.struct_name, method),
+        None => smell.struct_name.clone(),
+    }
+}
+
+fn generate_csv(smells: &[Smell]) -> Result<String, Box<dyn std::error::Error>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    writer.write_record(["smell", "struct_name", "method", "file_path", "start_line", "end_line", "reason"])?;
+    for smell in smells {
+        let SourceSpan { start_line, end_line } = smell.span;
+        writer.write_record([
+            smell.kind.to_string(),
+            smell.struct_name.clone(),
+            smell.method.clone().unwrap_or_default(),
+            smell.file_path.clone(),
+            start_line.to_string(),
+            end_line.to_string(),
+            smell.reason.clone(),
+        ])?;
+    }
+
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
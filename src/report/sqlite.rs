@@ -0,0 +1,182 @@
+use std::time::SystemTime;
+
+use rusqlite::{params, Connection};
+
+use crate::models::{AnalysisResult, MetricSelection};
+use crate::snapshot;
+
+use super::{retain_selected, JsonResult, Metadata};
+
+/// Tables of the database, created on first use
+///
+/// Every invocation appends a `runs` row; its structs, their methods, and
+/// one `metrics` row per metric value of each struct hang off it, so the
+/// history of a struct is a join on `qualified_name`.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    generated_at TEXT NOT NULL,
+    path TEXT NOT NULL,
+    version TEXT NOT NULL,
+    config TEXT
+);
+CREATE TABLE IF NOT EXISTS structs (
+    id INTEGER PRIMARY KEY,
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    qualified_name TEXT NOT NULL,
+    name TEXT NOT NULL,
+    module_path TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    file_path TEXT NOT NULL,
+    start_line INTEGER NOT NULL,
+    end_line INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS methods (
+    id INTEGER PRIMARY KEY,
+    struct_id INTEGER NOT NULL REFERENCES structs(id),
+    name TEXT NOT NULL,
+    start_line INTEGER NOT NULL,
+    end_line INTEGER NOT NULL,
+    complexity INTEGER NOT NULL,
+    cognitive INTEGER NOT NULL,
+    sloc INTEGER NOT NULL,
+    params INTEGER NOT NULL,
+    fields_accessed INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS metrics (
+    struct_id INTEGER NOT NULL REFERENCES structs(id),
+    name TEXT NOT NULL,
+    value REAL,
+    PRIMARY KEY (struct_id, name)
+);
+CREATE INDEX IF NOT EXISTS structs_by_name ON structs(qualified_name);
+";
+
+/// Append a run with its structs, methods, and metric values to the
+/// SQLite database at `path`, creating it if needed; returns the run id
+///
+/// The metrics of a struct are the numeric fields of its `--format json`
+/// object, so `--metrics` and derived metrics apply alike. NaN values
+/// are stored as NULL.
+pub fn append_run(
+    path: &str,
+    results: &[AnalysisResult],
+    metrics: &MetricSelection,
+    metadata: Option<&Metadata>,
+) -> Result<i64, Box<dyn std::error::Error>> {
+    let mut connection = Connection::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    connection.execute_batch(SCHEMA)?;
+
+    let transaction = connection.transaction()?;
+    transaction.execute(
+        "INSERT INTO runs (generated_at, path, version, config) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            metadata.map_or_else(|| snapshot::rfc3339(SystemTime::now()), |m| m.generated_at.clone()),
+            metadata.map_or("", |m| m.path.as_str()),
+            env!("CARGO_PKG_VERSION"),
+            metadata.map(|m| serde_json::to_string(&m.config)).transpose()?,
+        ],
+    )?;
+    let run_id = transaction.last_insert_rowid();
+
+    {
+        let mut insert_struct = transaction.prepare(
+            "INSERT INTO structs (run_id, qualified_name, name, module_path, kind, file_path, start_line, end_line)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?;
+        let mut insert_method = transaction.prepare(
+            "INSERT INTO methods (struct_id, name, start_line, end_line, complexity, cognitive, sloc, params, fields_accessed)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )?;
+        let mut insert_metric = transaction.prepare("INSERT INTO metrics (struct_id, name, value) VALUES (?1, ?2, ?3)")?;
+
+        for result in results {
+            insert_struct.execute(params![
+                run_id,
+                result.qualified_name(),
+                result.struct_name,
+                result.module_path,
+                result.kind.to_string(),
+                result.file_path,
+                result.span.start_line,
+                result.span.end_line,
+            ])?;
+            let struct_id = transaction.last_insert_rowid();
+
+            for method in &result.methods {
+                insert_method.execute(params![
+                    struct_id,
+                    method.name,
+                    method.span.start_line,
+                    method.span.end_line,
+                    method.cyclomatic_complexity,
+                    method.cognitive_complexity,
+                    method.sloc,
+                    method.params,
+                    method.fields_accessed.len(),
+                ])?;
+            }
+
+            let mut object = match serde_json::to_value(JsonResult::from(result))? {
+                serde_json::Value::Object(object) => object,
+                _ => unreachable!("results serialize as objects"),
+            };
+            if !metrics.all {
+                let derived: Vec<&str> = result.derived.keys().map(String::as_str).collect();
+                retain_selected(&mut object, metrics, &derived);
+            }
+            for (name, value) in &object {
+                if value.is_number() || value.is_null() {
+                    insert_metric.execute(params![struct_id, name, value.as_f64()])?;
+                }
+            }
+        }
+    }
+
+    transaction.commit()?;
+    Ok(run_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MethodInfo;
+
+    #[test]
+    fn test_runs_append_with_structs_methods_and_metrics() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("runs.db");
+        let path = path.to_str().unwrap();
+        let results = [AnalysisResult {
+            struct_name: "Engine".to_string(),
+            module_path: "crate::core".to_string(),
+            wmc: 12,
+            lcom: f64::NAN,
+            methods: vec![MethodInfo {
+                name: "start".to_string(),
+                cyclomatic_complexity: 4,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }];
+        let metrics: MetricSelection = "wmc,lcom".parse().unwrap();
+
+        assert_eq!(append_run(path, &results, &metrics, None).unwrap(), 1);
+        assert_eq!(append_run(path, &results, &metrics, None).unwrap(), 2);
+
+        let connection = Connection::open(path).unwrap();
+        let count = |sql: &str| connection.query_row(sql, [], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!(count("SELECT COUNT(*) FROM structs WHERE qualified_name = 'crate::core::Engine'"), 2);
+        assert_eq!(count("SELECT COUNT(*) FROM methods WHERE name = 'start' AND complexity = 4"), 2);
+        assert_eq!(count("SELECT COUNT(*) FROM metrics WHERE name = 'lcom' AND value IS NULL"), 2);
+        let wmc: f64 = connection
+            .query_row(
+                "SELECT value FROM metrics JOIN structs ON structs.id = struct_id WHERE run_id = 2 AND metrics.name = 'wmc'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(wmc, 12.0);
+        assert_eq!(count("SELECT COUNT(DISTINCT name) FROM metrics"), 2);
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+
+use crate::aggregate::MetricStats;
+use crate::models::OutputFormat;
+
+/// Render the summary statistics of the analyzed structs alone
+///
+/// JSON is an object with a `summary` of the statistics keyed by metric.
+pub fn generate_summary(stats: &[MetricStats], format: OutputFormat) -> Result<String, Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Table => Ok(generate_table(stats)),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(&serde_json::json!({ "summary": by_metric(stats) }))?),
+        OutputFormat::Csv => generate_csv(stats),
+        _ => Err("--summary-only supports only table, json, and csv output".into()),
+    }
+}
+
+/// The statistics keyed by metric, as in JSON output
+pub(super) fn by_metric(stats: &[MetricStats]) -> BTreeMap<String, MetricStats> {
+    stats.iter().map(|s| (s.metric.clone(), s.clone())).collect()
+}
+
+/// Header and decimal places of the struct table column showing `metric`
+fn table_column(metric: &str) -> (String, usize) {
+    let (header, precision) = match metric {
+        "lcom" => ("LCOM", 3),
+        "cbo" => ("CBO", 0),
+        "ca" => ("Ca", 0),
+        "fan_in" => ("Fan-in", 0),
+        "instability" => ("Instab.", 3),
+        "wmc" => ("WMC", 0),
+        "mi" => ("MI", 1),
+        "lcom4" => ("LCOM4", 0),
+        "cognitive" => ("Cognitive", 0),
+        "sloc" => ("SLOC", 0),
+        _ => return (metric.to_string(), 3),
+    };
+    (header.to_string(), precision)
+}
+
+/// Statistics table, aligned with the columns of the struct table
+///
+/// Each metric is labelled and rounded as its column of the struct table.
+pub(super) fn generate_table(stats: &[MetricStats]) -> String {
+    let count = stats.first().map_or(0, |s| s.count);
+    let mut output = format!(
+        "{:<30} {:>10} {:>10} {:>10} {:>10}\n",
+        format!("Summary ({} structs)", count),
+        "Mean",
+        "Median",
+        "P90",
+        "Max"
+    );
+    output.push_str(&"-".repeat(73));
+    output.push('\n');
+    for s in stats {
+        let (header, precision) = table_column(&s.metric);
+        output.push_str(&format!(
+            "{:<30} {:>10.p$} {:>10.p$} {:>10.p$} {:>10.p$}\n",
+            header,
+            s.mean,
+            s.median,
+            s.p90,
+            s.max,
+            p = precision
+        ));
+    }
+    output
+}
+
+fn generate_csv(stats: &[MetricStats]) -> Result<String, Box<dyn std::error::Error>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(["metric", "count", "mean", "median", "p90", "max"])?;
+    for s in stats {
+        writer.write_record([
+            s.metric.clone(),
+            s.count.to_string(),
+            s.mean.to_string(),
+            s.median.to_string(),
+            s.p90.to_string(),
+            s.max.to_string(),
+        ])?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_json_keyed_by_metric() {
+        let stat = |metric: &str, mean, median, p90, max| MetricStats {
+            metric: metric.to_string(),
+            count: 3,
+            mean,
+            median,
+            p90,
+            max,
+        };
+        let stats = vec![stat("wmc", 4.5, 4.0, 8.2, 9.0), stat("fan_in", 1.0, 1.0, 2.0, 2.0), stat("lcom", 0.5, 0.25, 0.9, 1.0)];
+
+        let json: serde_json::Value = serde_json::from_str(&generate_summary(&stats, OutputFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["summary"]["wmc"]["p90"], 8.2);
+
+        // Labelled and rounded as the struct table columns
+        let table = generate_table(&stats);
+        let row = |cells: [&str; 5]| format!("{:<30} {:>10} {:>10} {:>10} {:>10}", cells[0], cells[1], cells[2], cells[3], cells[4]);
+        assert!(table.contains(&row(["WMC", "4", "4", "8", "9"])), "{}", table);
+        assert!(table.contains(&row(["Fan-in", "1", "1", "2", "2"])), "{}", table);
+        assert!(table.contains(&row(["LCOM", "0.500", "0.250", "0.900", "1.000"])), "{}", table);
+        assert!(generate_summary(&stats, OutputFormat::Dot).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+use crate::aggregate::metric_stats;
+use crate::models::AnalysisResult;
+use crate::thresholds::{find_violations, Thresholds};
+
+use super::junit::format_value;
+
+/// Render TeamCity service messages for the build log
+///
+/// `buildStatisticValue` messages report the struct and violation counts
+/// and the mean and maximum of each of `metrics`, which TeamCity graphs
+/// per build as `archMetrics.*` statistics. Each threshold violation is
+/// an `inspection` of the metric's inspection type, located at the struct
+/// definition.
+pub fn generate_teamcity(results: &[AnalysisResult], metrics: &[&str], thresholds: &Thresholds) -> String {
+    let violations = find_violations(results, thresholds);
+
+    let mut output = String::new();
+    let mut statistic = |key: &str, value: f64| {
+        output.push_str(&format!(
+            "##teamcity[buildStatisticValue key='archMetrics.{}' value='{}']\n",
+            escape(key),
+            format_value(value)
+        ));
+    };
+    statistic("structs", results.len() as f64);
+    statistic("violations", violations.len() as f64);
+    for stats in metric_stats(results, metrics) {
+        statistic(&format!("{}.mean", stats.metric), stats.mean);
+        statistic(&format!("{}.max", stats.metric), stats.max);
+    }
+
+    let mut types: Vec<&str> = violations.iter().map(|v| v.metric.as_str()).collect();
+    types.sort();
+    types.dedup();
+    for metric in types {
+        output.push_str(&format!(
+            "##teamcity[inspectionType id='arch-metrics.{0}' name='{0} above threshold' category='Architecture metrics' description='Structs whose {0} exceeds the threshold of the selected profile']\n",
+            escape(metric)
+        ));
+    }
+    for violation in &violations {
+        output.push_str(&format!(
+            "##teamcity[inspection typeId='arch-metrics.{}' message='{} {} {} exceeds {}' file='{}' line='{}' SEVERITY='WARNING']\n",
+            escape(&violation.metric),
+            escape(&violation.struct_name),
+            escape(&violation.metric),
+            format_value(violation.value),
+            format_value(violation.threshold),
+            escape(&violation.file_path),
+            violation.span.start_line
+        ));
+    }
+    output
+}
+
+/// Escape a service message attribute value with TeamCity's `|` escapes
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '|' => escaped.push_str("||"),
+            '\'' => escaped.push_str("|'"),
+            '\n' => escaped.push_str("|n"),
+            '\r' => escaped.push_str("|r"),
+            '[' => escaped.push_str("|["),
+            ']' => escaped.push_str("|]"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SourceSpan;
+
+    #[test]
+    fn test_teamcity_statistics_and_inspections() {
+        let result = |name: &str, wmc| AnalysisResult {
+            struct_name: name.to_string(),
+            module_path: "crate::app".to_string(),
+            file_path: "src/app.rs".to_string(),
+            span: SourceSpan { start_line: 7, end_line: 90 },
+            wmc,
+            ..Default::default()
+        };
+        let results = [result("God", 52), result("Small", 4)];
+
+        let messages = generate_teamcity(&results, &["wmc"], &Thresholds::default());
+        assert_eq!(
+            messages.lines().collect::<Vec<_>>(),
+            vec![
+                "##teamcity[buildStatisticValue key='archMetrics.structs' value='2']",
+                "##teamcity[buildStatisticValue key='archMetrics.violations' value='1']",
+                "##teamcity[buildStatisticValue key='archMetrics.wmc.mean' value='28']",
+                "##teamcity[buildStatisticValue key='archMetrics.wmc.max' value='52']",
+                "##teamcity[inspectionType id='arch-metrics.wmc' name='wmc above threshold' category='Architecture metrics' description='Structs whose wmc exceeds the threshold of the selected profile']",
+                "##teamcity[inspection typeId='arch-metrics.wmc' message='crate::app::God wmc 52 exceeds 40' file='src/app.rs' line='7' SEVERITY='WARNING']",
+            ]
+        );
+        assert_eq!(escape("a|b 'c' [d]\n"), "a||b |'c|' |[d|]|n");
+    }
+}
@@ -0,0 +1,158 @@
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+
+use crate::aggregate::aggregate_by_module;
+use crate::models::{AnalysisResult, MethodResult};
+use crate::thresholds::{find_violations, Thresholds};
+
+use super::{derived_names, derived_value};
+
+/// Render an XLSX workbook with one sheet per table
+///
+/// - Structs: per-struct metrics
+/// - Methods: per-method complexity and field access
+/// - Modules: metrics aggregated per module
+/// - Violations: metrics exceeding `thresholds`
+pub fn generate_xlsx(results: &[AnalysisResult], thresholds: &Thresholds) -> Result<Vec<u8>, XlsxError> {
+    let mut workbook = Workbook::new();
+    let header = Format::new().set_bold();
+
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("Structs")?;
+    let derived = derived_names(results);
+    let mut columns = vec!["Struct", "Module", "Kind", "File", "Line", "LCOM", "CBO", "WMC", "Dyn Dispatch"];
+    columns.extend(&derived);
+    write_header(sheet, &header, &columns)?;
+    for (i, result) in results.iter().enumerate() {
+        let row = i as u32 + 1;
+        sheet.write_string(row, 0, &result.struct_name)?;
+        sheet.write_string(row, 1, &result.module_path)?;
+        sheet.write_string(row, 2, result.kind.to_string())?;
+        sheet.write_string(row, 3, &result.file_path)?;
+        sheet.write_number(row, 4, result.span.start_line as f64)?;
+        sheet.write_number(row, 5, result.lcom)?;
+        sheet.write_number(row, 6, result.cbo as f64)?;
+        sheet.write_number(row, 7, result.wmc as f64)?;
+        sheet.write_number(row, 8, result.dyn_dispatch as f64)?;
+        for (j, name) in derived.iter().enumerate() {
+            sheet.write_number(row, 9 + j as u16, derived_value(result, name))?;
+        }
+    }
+    sheet.autofit();
+
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("Methods")?;
+    write_header(sheet, &header, &["Struct", "Method", "Line", "Complexity", "Fields Accessed"])?;
+    let mut row = 1;
+    for result in results {
+        for method in &result.methods {
+            let mut fields = method.fields_accessed.clone();
+            fields.sort();
+
+            sheet.write_string(row, 0, result.qualified_name())?;
+            sheet.write_string(row, 1, &method.name)?;
+            sheet.write_number(row, 2, method.span.start_line as f64)?;
+            sheet.write_number(row, 3, method.cyclomatic_complexity as f64)?;
+            sheet.write_string(row, 4, fields.join(", "))?;
+            row += 1;
+        }
+    }
+    sheet.autofit();
+
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("Modules")?;
+    write_header(
+        sheet,
+        &header,
+        &["Module", "Structs", "Mean LCOM", "Total CBO", "Max CBO", "Total WMC", "Max WMC", "Instability"],
+    )?;
+    for (i, module) in aggregate_by_module(results).iter().enumerate() {
+        let row = i as u32 + 1;
+        sheet.write_string(row, 0, &module.module_path)?;
+        sheet.write_number(row, 1, module.struct_count as f64)?;
+        sheet.write_number(row, 2, module.mean_lcom)?;
+        sheet.write_number(row, 3, module.total_cbo as f64)?;
+        sheet.write_number(row, 4, module.max_cbo as f64)?;
+        sheet.write_number(row, 5, module.total_wmc as f64)?;
+        sheet.write_number(row, 6, module.max_wmc as f64)?;
+        sheet.write_number(row, 7, module.instability)?;
+    }
+    sheet.autofit();
+
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("Violations")?;
+    write_header(sheet, &header, &["Struct", "Metric", "Value", "Threshold"])?;
+    for (i, violation) in find_violations(results, thresholds).iter().enumerate() {
+        let row = i as u32 + 1;
+        sheet.write_string(row, 0, &violation.struct_name)?;
+        sheet.write_string(row, 1, violation.metric.to_uppercase())?;
+        sheet.write_number(row, 2, violation.value)?;
+        sheet.write_number(row, 3, violation.threshold)?;
+    }
+    sheet.autofit();
+
+    workbook.save_to_buffer()
+}
+
+/// Render an XLSX workbook with a single sheet of per-method metrics
+pub fn generate_methods_xlsx(methods: &[MethodResult]) -> Result<Vec<u8>, XlsxError> {
+    let mut workbook = Workbook::new();
+    let header = Format::new().set_bold();
+
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("Methods")?;
+    write_header(
+        sheet,
+        &header,
+        &["Struct", "Module", "Method", "File", "Line", "Complexity", "Cognitive", "Volume", "Difficulty", "Effort", "MI",
+          "SLOC", "Fields Accessed", "Parameters", "Demeter Violations", "Async", "Await Points"],
+    )?;
+    for (i, method) in methods.iter().enumerate() {
+        let row = i as u32 + 1;
+        sheet.write_string(row, 0, &method.struct_name)?;
+        sheet.write_string(row, 1, &method.module_path)?;
+        sheet.write_string(row, 2, &method.method)?;
+        sheet.write_string(row, 3, &method.file_path)?;
+        sheet.write_number(row, 4, method.span.start_line as f64)?;
+        sheet.write_number(row, 5, method.complexity as f64)?;
+        sheet.write_number(row, 6, method.cognitive as f64)?;
+        sheet.write_number(row, 7, method.volume)?;
+        sheet.write_number(row, 8, method.difficulty)?;
+        sheet.write_number(row, 9, method.effort)?;
+        sheet.write_number(row, 10, method.maintainability)?;
+        sheet.write_number(row, 11, method.sloc as f64)?;
+        sheet.write_number(row, 12, method.fields_accessed as f64)?;
+        sheet.write_number(row, 13, method.params as f64)?;
+        sheet.write_number(row, 14, method.demeter as f64)?;
+        sheet.write_boolean(row, 15, method.is_async)?;
+        sheet.write_number(row, 16, method.awaits as f64)?;
+    }
+    sheet.autofit();
+
+    workbook.save_to_buffer()
+}
+
+fn write_header(sheet: &mut Worksheet, format: &Format, columns: &[&str]) -> Result<(), XlsxError> {
+    for (col, name) in columns.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *name, format)?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_xlsx_produces_zip_archive() {
+        let results = vec![AnalysisResult {
+            struct_name: "God".to_string(),
+            wmc: 50,
+            ..Default::default()
+        }];
+
+        let bytes = generate_xlsx(&results, &Thresholds::default()).unwrap();
+        // XLSX files are zip archives
+        assert_eq!(&bytes[..2], b"PK");
+    }
+}
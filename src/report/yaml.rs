@@ -0,0 +1,19 @@
+/// Convert a JSON report to YAML with the same structure and key order
+pub(super) fn from_json(json: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let value: serde_yaml_ng::Value = serde_json::from_str(json)?;
+    Ok(serde_yaml_ng::to_string(&value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yaml_keeps_json_structure() {
+        let json = r#"[{"struct_name": "Engine", "lcom": 0.5, "span": {"start_line": 3, "end_line": 9}, "derives": []}]"#;
+        assert_eq!(
+            from_json(json).unwrap(),
+            "- struct_name: Engine\n  lcom: 0.5\n  span:\n    start_line: 3\n    end_line: 9\n  derives: []\n"
+        );
+    }
+}
@@ -0,0 +1,578 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
+
+use crate::models::{Reexport, StructInfo};
+
+/// Split a path such as `super :: Config` or `crate::net::Server` into segments
+pub fn split_path(path: &str) -> Vec<String> {
+    path.split("::")
+        .map(|seg| seg.trim().to_string())
+        .filter(|seg| !seg.is_empty())
+        .collect()
+}
+
+/// Segments of the crate root module `module` lives in
+///
+/// The root is the first segment, `crate` or, under `--expand`, the package
+/// name, and the `bin::<name>` following it for the modules of a binary,
+/// which are named `crate::bin::tool` or `app::bin::tool` alike.
+///
+/// e.g. `crate` for `crate::net::server`, `app::bin::tool` for `app::bin::tool::cli`
+pub fn crate_root(module: &str) -> Vec<String> {
+    let mut segments = split_path(module);
+    let len = if segments.get(1).is_some_and(|seg| seg == "bin") && segments.len() > 2 { 3 } else { 1 };
+    segments.truncate(len);
+    segments
+}
+
+/// Resolve a path written inside module `current_module` to a crate-absolute path
+///
+/// `crate::`, `self::` and `super::` prefixes are resolved against the current
+/// module, `crate` naming its crate root. Other paths are returned unchanged
+/// since they may refer to imported or external items.
+///
+/// e.g. `super::Config` in `crate::net::server` resolves to `crate::net::Config`,
+/// and `crate::Config` in `app::bin::tool::cli` to `app::bin::tool::Config`
+pub fn resolve_path(path: &str, current_module: &str) -> String {
+    let segments = split_path(path);
+    let Some(first) = segments.first() else {
+        return String::new();
+    };
+
+    let resolved = match first.as_str() {
+        "crate" => {
+            let mut resolved = crate_root(current_module);
+            resolved.extend(segments.into_iter().skip(1));
+            resolved
+        }
+        "self" => {
+            let mut resolved = split_path(current_module);
+            resolved.extend(segments.into_iter().skip(1));
+            resolved
+        }
+        "super" => {
+            let root_len = crate_root(current_module).len();
+            let mut resolved = split_path(current_module);
+            let mut rest = segments.into_iter().peekable();
+            while rest.next_if(|seg| seg == "super").is_some() {
+                // Never climb above the crate root
+                if resolved.len() > root_len {
+                    resolved.pop();
+                }
+            }
+            resolved.extend(rest);
+            resolved
+        }
+        _ => segments,
+    };
+
+    resolved.join("::")
+}
+
+/// Position of a struct in the slice a `StructIndex` is built over
+pub type StructId = usize;
+
+/// Analyzed structs indexed by name and by the paths they are known under
+///
+/// Built once per analysis so that resolving a type path is a couple of
+/// hash lookups instead of a scan over every struct.
+pub struct StructIndex<'a> {
+    structs: &'a [StructInfo],
+    by_name: HashMap<String, Vec<StructId>>, // Name or re-exported name -> every such struct
+    by_path: HashMap<String, StructId>, // Qualified or re-exported path -> first such struct
+    modules: HashSet<String>, // Names of the modules holding or re-exporting structs, e.g. `net`
+    imports: HashMap<String, String>, // `module::Name` -> imported path, from the `use` items of struct modules
+    globs: HashMap<String, Vec<String>>, // Module -> modules it glob-imports, e.g. `crate::net` of `use crate::net::*;`
+}
+
+impl<'a> StructIndex<'a> {
+    pub fn new(structs: &'a [StructInfo]) -> Self {
+        let mut by_name = HashMap::new();
+        let mut by_path = HashMap::new();
+        let mut modules = HashSet::new();
+        let mut imports = HashMap::new();
+        let mut globs: HashMap<String, Vec<String>> = HashMap::new();
+        for (id, s) in structs.iter().enumerate() {
+            for import in &s.imports {
+                match (import.path.strip_suffix("::*"), import.target.strip_suffix("::*")) {
+                    (Some(module), Some(target_module)) => {
+                        let modules = globs.entry(module.to_string()).or_default();
+                        if !modules.iter().any(|known| known == target_module) {
+                            modules.push(target_module.to_string());
+                        }
+                    }
+                    _ => {
+                        imports.entry(import.path.clone()).or_insert_with(|| import.target.clone());
+                    }
+                }
+            }
+            by_name.entry(s.name.clone()).or_insert_with(Vec::new).push(id);
+            by_path.entry(s.qualified_name()).or_insert(id);
+            modules.extend(split_path(&s.module_path));
+            for path in s.reexported_as.iter().chain(&s.aliases) {
+                let ids: &mut Vec<StructId> = by_name.entry(last_segment(path).to_string()).or_default();
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+                by_path.entry(path.clone()).or_insert(id);
+                modules.extend(split_path(parent_path(path)));
+            }
+        }
+        Self { structs, by_name, by_path, modules, imports, globs }
+    }
+
+    /// Find the analyzed struct a type path written in `current_module` refers to
+    ///
+    /// Structs are known by their name and by the paths they are re-exported
+    /// or aliased under. A path whose first segment a `use` of the current
+    /// module imports, e.g. `Config` or `net::Config` after `use crate::net;`,
+    /// names the struct known under the imported path, if any. Otherwise a
+    /// crate-absolute match on one of those paths, rooted at `crate` or at the
+    /// package name under `--expand`, wins, as does a struct of the current
+    /// module or of a glob-imported one for unqualified names, and one under
+    /// the current module, an enclosing one, or another analyzed crate for
+    /// paths such as `db::Config`. Failing that, the last path segment
+    /// matches the struct of that name if there is only one. Paths qualified
+    /// by anything but a module of the analyzed code, such as `io::Error` or
+    /// `serde::Serializer`, name external types and match no struct.
+    pub fn find(&self, path: &str, current_module: &str) -> Option<StructId> {
+        let written = split_path(path);
+        let first = written.first()?;
+        if !matches!(first.as_str(), "crate" | "self" | "super") {
+            if let Some(imported) = self.imports.get(&format!("{}::{}", current_module, first)) {
+                let mut segments = split_path(imported);
+                segments.extend(written.into_iter().skip(1));
+                let imported = segments.join("::");
+                // A use path relative to the module may name another crate, e.g. a workspace member
+                let external = imported.strip_prefix(current_module).and_then(|rest| rest.strip_prefix("::"));
+                return self.by_path.get(&imported).or_else(|| self.by_path.get(external?)).copied();
+            }
+        }
+
+        let resolved = resolve_path(path, current_module);
+        let segments = split_path(&resolved);
+        let name = segments.last()?.clone();
+        if segments.len() > 1 && segments[0] != "crate" && !self.modules.contains(&segments[0]) {
+            return None;
+        }
+
+        let root = crate_root(current_module);
+        let absolute = segments[0] == "crate" || root.first() == Some(&segments[0]);
+        let found = if segments.len() > 1 && absolute {
+            self.by_path.get(&resolved)
+        } else if segments.len() > 1 {
+            // Relative to the current module, then to each enclosing one up to the crate root
+            let mut module = split_path(current_module);
+            std::iter::from_fn(|| {
+                let prefix = (module.len() >= root.len().max(1)).then(|| module.join("::"))?;
+                module.pop();
+                Some(prefix)
+            })
+            .find_map(|prefix| self.by_path.get(&format!("{}::{}", prefix, resolved)))
+            .or_else(|| self.by_path.get(&resolved)) // Rooted at another crate, e.g. a workspace member
+        } else {
+            let glob_imported = || {
+                let modules = self.globs.get(current_module)?;
+                modules.iter().find_map(|module| self.by_path.get(&format!("{}::{}", module, name)))
+            };
+            self.by_path.get(&format!("{}::{}", current_module, name)).or_else(glob_imported)
+        };
+
+        // Matched by name alone only when no other struct shares it
+        found.copied().or_else(|| match self.by_name.get(&name)?.as_slice() {
+            [only] => Some(*only),
+            _ => None,
+        })
+    }
+
+    /// Like `find`, returning the struct itself
+    pub fn find_struct(&self, path: &str, current_module: &str) -> Option<&'a StructInfo> {
+        self.find(path, current_module).map(|id| &self.structs[id])
+    }
+}
+
+impl std::ops::Index<StructId> for StructIndex<'_> {
+    type Output = StructInfo;
+
+    fn index(&self, id: StructId) -> &StructInfo {
+        &self.structs[id]
+    }
+}
+
+/// Record on each struct the paths it is re-exported under
+///
+/// Re-exports are followed transitively, so `pub use engine::*;` in a module
+/// that itself re-exports `pub use internal::Engine;` exposes `Engine` too.
+pub fn apply_reexports(structs: &mut [StructInfo], reexports: &[Reexport]) {
+    // Re-exported paths by the path or, for globs, the module they re-export
+    let mut by_target: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut by_target_module: HashMap<&str, Vec<&str>> = HashMap::new();
+    for reexport in reexports {
+        match (reexport.target.strip_suffix("::*"), reexport.path.strip_suffix("::*")) {
+            (Some(target_module), Some(path_module)) => by_target_module.entry(target_module).or_default().push(path_module),
+            _ => by_target.entry(reexport.target.as_str()).or_default().push(&reexport.path),
+        }
+    }
+
+    // Every path a struct is known under, each followed once as it is added
+    let mut known: Vec<HashSet<String>> = Vec::with_capacity(structs.len());
+    let mut pending: VecDeque<(StructId, String)> = VecDeque::new();
+    for (id, s) in structs.iter().enumerate() {
+        let paths: Vec<String> = std::iter::once(s.qualified_name()).chain(s.reexported_as.iter().cloned()).collect();
+        pending.extend(paths.iter().map(|path| (id, path.clone())));
+        known.push(paths.into_iter().collect());
+    }
+
+    while let Some((id, path)) = pending.pop_front() {
+        let plain = by_target.get(path.as_str()).into_iter().flatten().map(|&new_path| new_path.to_string());
+        let glob = by_target_module
+            .get(parent_path(&path))
+            .into_iter()
+            .flatten()
+            .map(|path_module| format!("{}::{}", path_module, last_segment(&path)));
+        let new_paths: Vec<String> = plain.chain(glob).collect();
+
+        for new_path in new_paths {
+            if known[id].insert(new_path.clone()) {
+                structs[id].reexported_as.push(new_path.clone());
+                pending.push_back((id, new_path));
+            }
+        }
+    }
+}
+
+/// Record on each struct the type aliases and `use ... as` renames naming it
+///
+/// Aliases are followed transitively, through re-exports and other aliases,
+/// so `type Id = Bill;` after `use crate::billing::Invoice as Bill;` names
+/// `Invoice` too. An unqualified target matching no known path, e.g. one
+/// brought in by a glob import, names the only struct of its name, if any.
+pub fn apply_aliases(structs: &mut [StructInfo], aliases: &[Reexport]) {
+    // Structs by name and by every path they are known under, built once
+    // and extended with each alias recorded
+    let qualified: Vec<String> = structs.iter().map(StructInfo::qualified_name).collect();
+    let mut by_path: HashMap<String, Vec<StructId>> = HashMap::new();
+    let mut by_name: HashMap<String, Vec<StructId>> = HashMap::new();
+    for (id, s) in structs.iter().enumerate() {
+        for path in std::iter::once(&qualified[id]).chain(&s.reexported_as).chain(&s.aliases) {
+            by_path.entry(path.clone()).or_default().push(id);
+        }
+        by_name.entry(s.name.clone()).or_default().push(id);
+    }
+
+    loop {
+        let mut changed = false;
+
+        for alias in aliases {
+            let mut targets: Vec<StructId> = by_path.get(&alias.target).cloned().unwrap_or_default();
+            if targets.is_empty() && parent_path(&alias.target) == parent_path(&alias.path) {
+                if let Some(&[only]) = by_name.get(last_segment(&alias.target)).map(Vec::as_slice) {
+                    targets.push(only);
+                }
+            }
+
+            for id in targets {
+                let s = &mut structs[id];
+                if alias.path != qualified[id] && !s.aliases.contains(&alias.path) {
+                    s.aliases.push(alias.path.clone());
+                    by_path.entry(alias.path.clone()).or_default().push(id);
+                    changed = true;
+                }
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+}
+
+/// Record on each struct the traits it implements or refers to that the
+/// analyzed code declares
+///
+/// Traits are matched by name, generics left out, so `std::fmt::Display`,
+/// `From<u8>`, or a `T: Clone` bound only count when a trait of that name
+/// is declared.
+pub fn apply_crate_traits(structs: &mut [StructInfo], traits: &[String]) {
+    let declared: HashSet<&str> = traits.iter().map(String::as_str).collect();
+    for s in structs {
+        let mut crate_traits: Vec<String> = Vec::new();
+        for name in s.traits.iter().chain(&s.referenced_traits).map(|path| trait_name(path)) {
+            if declared.contains(name) && !crate_traits.iter().any(|known| known == name) {
+                crate_traits.push(name.to_string());
+            }
+        }
+        s.crate_traits = crate_traits;
+    }
+}
+
+/// Name of the trait a path such as `std :: fmt :: Display` or `From < u8 >` refers to
+pub fn trait_name(path: &str) -> &str {
+    last_segment(path.split('<').next().unwrap_or(path)).trim()
+}
+
+fn last_segment(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+fn parent_path(path: &str) -> &str {
+    path.rsplit_once("::").map_or("", |(parent, _)| parent)
+}
+
+/// Module path of a source file, e.g. `crate::net::server` for `src/net/server.rs`
+///
+/// Paths are taken relative to the crate's `src/` directory when the file lives
+/// in a Cargo package, and relative to `root` otherwise.
+pub fn module_path_for_file(file: &Path, root: &Path) -> String {
+    let base = crate_src_dir(file).unwrap_or_else(|| {
+        if root.is_file() {
+            root.parent().unwrap_or(root).to_path_buf()
+        } else {
+            root.to_path_buf()
+        }
+    });
+
+    let relative = file.strip_prefix(&base).unwrap_or(file);
+    let mut segments = vec!["crate".to_string()];
+
+    let components: Vec<String> = relative
+        .with_extension("")
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+
+    for (i, component) in components.iter().enumerate() {
+        let is_last = i + 1 == components.len();
+        let is_crate_root = i == 0 && matches!(component.as_str(), "lib" | "main");
+        if is_last && (component == "mod" || is_crate_root) {
+            continue;
+        }
+        segments.push(component.replace('-', "_"));
+    }
+
+    segments.join("::")
+}
+
+/// The `src/` directory of the Cargo package containing `file`, if any
+fn crate_src_dir(file: &Path) -> Option<std::path::PathBuf> {
+    file.ancestors()
+        .skip(1)
+        .find(|dir| dir.file_name().is_some_and(|name| name == "src") && dir.with_file_name("Cargo.toml").is_file())
+        .map(Path::to_path_buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_prefixes() {
+        assert_eq!(resolve_path("super :: Config", "crate::net::server"), "crate::net::Config");
+        assert_eq!(resolve_path("super::super::Config", "crate::net::server"), "crate::Config");
+        assert_eq!(resolve_path("self::Handler", "crate::net"), "crate::net::Handler");
+        assert_eq!(resolve_path("crate::models::User", "crate::net"), "crate::models::User");
+        assert_eq!(resolve_path("super::Config", "crate"), "crate::Config");
+        assert_eq!(resolve_path("Config", "crate::net"), "Config");
+
+        // Under `--expand` the package name, or a binary, is the crate root
+        assert_eq!(resolve_path("crate::net::Config", "app::db"), "app::net::Config");
+        assert_eq!(resolve_path("super::super::Config", "app::net"), "app::Config");
+        assert_eq!(resolve_path("crate::Config", "app::bin::tool::cli"), "app::bin::tool::Config");
+        assert_eq!(resolve_path("super::super::Config", "crate::bin::tool::cli"), "crate::bin::tool::Config");
+    }
+
+    #[test]
+    fn test_find_struct_prefers_module_match() {
+        let net_config = StructInfo {
+            name: "Config".to_string(),
+            module_path: "crate::net".to_string(),
+            ..Default::default()
+        };
+        let db_config = StructInfo {
+            name: "Config".to_string(),
+            module_path: "crate::db".to_string(),
+            ..Default::default()
+        };
+        let pool = StructInfo {
+            name: "Pool".to_string(),
+            module_path: "crate::db::pool".to_string(),
+            imports: vec![Reexport {
+                path: "crate::db::pool::Config".to_string(),
+                target: "crate::net::Config".to_string(),
+            }],
+            ..Default::default()
+        };
+        let all_structs = vec![net_config, db_config, pool];
+        let index = StructIndex::new(&all_structs);
+
+        let found = index.find_struct("super::Config", "crate::db::pool").unwrap();
+        assert_eq!(found.module_path, "crate::db");
+
+        let found = index.find_struct("Config", "crate::db").unwrap();
+        assert_eq!(found.module_path, "crate::db");
+
+        // `use crate::net::Config;` in `crate::db::pool`
+        let found = index.find_struct("Config", "crate::db::pool").unwrap();
+        assert_eq!(found.module_path, "crate::net");
+
+        assert!(index.find_struct("super::Missing", "crate::db").is_none());
+
+        // Unqualified and not imported, either `Config` could be meant
+        assert!(index.find_struct("Config", "crate::cli").is_none());
+
+        // Qualified by a module of the analyzed code, or by std or another crate
+        assert_eq!(index.find_struct("db::Config", "crate::net").unwrap().module_path, "crate::db");
+        assert_eq!(index.find_struct("net::Config", "crate").unwrap().module_path, "crate::net");
+        assert!(index.find_struct("io::Config", "crate::db").is_none());
+        assert!(index.find_struct("std::fmt::Config", "crate::db").is_none());
+    }
+
+    #[test]
+    fn test_find_struct_through_use_imports() {
+        let source = r#"
+            mod db {
+                pub struct Config;
+                pub struct Error;
+                mod pool {
+                    use crate::net::Config;
+                    use std::io;
+                    pub struct Pool { config: Config, error: io::Error }
+                }
+                mod replica {
+                    use crate::net;
+                    pub struct Replica { config: net::Config }
+                }
+                mod shard {
+                    use crate::net::*;
+                    pub struct Shard { config: Config }
+                }
+            }
+            mod net { pub struct Config; }
+        "#;
+        let parsed = crate::parser::parse_file(source, "crate", &crate::parser::ParseOptions::default()).unwrap();
+        let index = StructIndex::new(&parsed.structs);
+
+        // The first `Config` is `db`'s; each struct names `net`'s, and `io::Error` none
+        for name in ["Pool", "Replica", "Shard"] {
+            let s = parsed.structs.iter().find(|s| s.name == name).unwrap();
+            let references: Vec<String> = crate::metrics::cbo::struct_references(s, &index)
+                .into_iter()
+                .map(|id| index[id].qualified_name())
+                .collect();
+            assert_eq!(references, vec!["crate::net::Config".to_string()], "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_find_struct_in_expanded_target() {
+        let source = r#"
+            mod net {
+                pub struct Config;
+                mod server { struct Server { config: super::Config, pool: crate::db::Config } }
+            }
+            mod db {
+                pub struct Config;
+                struct Pool { config: crate::db::Config, net: super::net::Config }
+            }
+        "#;
+        let parsed = crate::parser::parse_file(source, "app", &crate::parser::ParseOptions::default()).unwrap();
+        let index = StructIndex::new(&parsed.structs);
+
+        // The first `Config` is `net`'s, so a match by name alone would pick it
+        let found = index.find_struct("crate::db::Config", "app::net::server").unwrap();
+        assert_eq!(found.qualified_name(), "app::db::Config");
+        let found = index.find_struct("super::Config", "app::net::server").unwrap();
+        assert_eq!(found.qualified_name(), "app::net::Config");
+        let found = index.find_struct("Config", "app::db").unwrap();
+        assert_eq!(found.qualified_name(), "app::db::Config");
+        let found = index.find_struct("super::net::Config", "app::db").unwrap();
+        assert_eq!(found.qualified_name(), "app::net::Config");
+
+        // Each field couples to its own `Config`
+        for name in ["Server", "Pool"] {
+            let s = parsed.structs.iter().find(|s| s.name == name).unwrap();
+            assert_eq!(crate::metrics::cbo::struct_coupling(s, &index), 2);
+        }
+    }
+
+    #[test]
+    fn test_reexports_resolve_to_defining_struct() {
+        let mut structs = vec![
+            StructInfo {
+                name: "Engine".to_string(),
+                module_path: "crate::internal::engine".to_string(),
+                ..Default::default()
+            },
+            StructInfo {
+                name: "Engine".to_string(),
+                module_path: "crate::toy".to_string(),
+                ..Default::default()
+            },
+        ];
+        let reexports = vec![
+            Reexport {
+                path: "crate::internal::*".to_string(),
+                target: "crate::internal::engine::*".to_string(),
+            },
+            Reexport {
+                path: "crate::Motor".to_string(),
+                target: "crate::internal::Engine".to_string(),
+            },
+        ];
+
+        apply_reexports(&mut structs, &reexports);
+        let index = StructIndex::new(&structs);
+        assert_eq!(
+            structs[0].reexported_as,
+            vec!["crate::internal::Engine".to_string(), "crate::Motor".to_string()]
+        );
+
+        let found = index.find_struct("crate::Motor", "crate::car").unwrap();
+        assert_eq!(found.module_path, "crate::internal::engine");
+
+        let found = index.find_struct("crate::internal::Engine", "crate::car").unwrap();
+        assert_eq!(found.module_path, "crate::internal::engine");
+    }
+
+    #[test]
+    fn test_aliases_and_use_renames_resolve_to_aliased_struct() {
+        let source = r#"
+            mod billing { pub struct Invoice; pub struct Customer; }
+            mod orders {
+                use crate::billing::Invoice as Bill;
+                use crate::billing::Customer;
+                type CustomerId = Customer;
+                type Receipt = Bill;
+
+                struct Invoice;
+                struct Order { bill: Bill, customer: CustomerId, receipt: Receipt, buyer: Customer }
+            }
+        "#;
+        let mut parsed = crate::parser::parse_file(source, "crate", &crate::parser::ParseOptions::default()).unwrap();
+        apply_aliases(&mut parsed.structs, &parsed.aliases);
+        let index = StructIndex::new(&parsed.structs);
+
+        let invoice = index.find_struct("Bill", "crate::orders").unwrap();
+        assert_eq!(invoice.qualified_name(), "crate::billing::Invoice");
+        assert_eq!(invoice.aliases, vec!["crate::orders::Bill".to_string(), "crate::orders::Receipt".to_string()]);
+        assert_eq!(index.find_struct("CustomerId", "crate::orders").unwrap().qualified_name(), "crate::billing::Customer");
+
+        // Four fields naming two structs, neither of them the local `Invoice`
+        let order = parsed.structs.iter().find(|s| s.name == "Order").unwrap();
+        assert_eq!(crate::metrics::cbo::struct_coupling(order, &index), 2);
+    }
+
+    #[test]
+    fn test_module_path_for_file() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let src = root.join("src");
+
+        assert_eq!(module_path_for_file(&src.join("main.rs"), &src), "crate");
+        assert_eq!(module_path_for_file(&src.join("metrics/cbo.rs"), &src), "crate::metrics::cbo");
+        assert_eq!(module_path_for_file(&src.join("metrics/mod.rs"), &src), "crate::metrics");
+
+        // Outside a Cargo package paths are relative to the analyzed root
+        let other = Path::new("/tmp/project");
+        assert_eq!(module_path_for_file(&other.join("a/b.rs"), other), "crate::a::b");
+    }
+}
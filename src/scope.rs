@@ -0,0 +1,228 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::git;
+use crate::models::{AnalysisResult, ParsedFile};
+use crate::pipeline::PipelineHook;
+
+/// Pipeline hook narrowing the report of an analysis to what changed
+/// since a git ref
+///
+/// Every file is still parsed and analyzed, so coupling and afferent
+/// metrics are those of a full analysis. Kept are the structs of changed
+/// files and their dependents: the structs whose CBO targets include one
+/// of them, resolved through the `StructIndex` like CBO itself, so uses
+/// through type aliases, re-exports, and `use ... as` renames count.
+///
+/// With `narrow_parse`, only the files of [`parse_scope`] are parsed,
+/// which keeps the coupling of the reported structs exact but may lower
+/// the afferent metrics of their dependents.
+pub struct ChangedSince {
+    base: String,
+    narrow_parse: bool,
+    changed: HashSet<PathBuf>,        // Canonical paths of the changed files
+    changed_structs: HashSet<String>, // Qualified names of their structs
+}
+
+impl ChangedSince {
+    pub fn new(base: &str, narrow_parse: bool) -> Self {
+        Self {
+            base: base.to_string(),
+            narrow_parse,
+            changed: HashSet::new(),
+            changed_structs: HashSet::new(),
+        }
+    }
+
+    /// Whether `result` is changed or depends on a changed struct
+    fn is_affected(&self, result: &AnalysisResult) -> bool {
+        git::is_changed(&result.file_path, &self.changed)
+            || result.dependencies.iter().any(|d| self.changed_structs.contains(&d.target))
+    }
+}
+
+impl PipelineHook for ChangedSince {
+    fn files(&mut self, path: &str, files: &mut Vec<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+        let changed = git::changed_files(Path::new(path), &self.base)?;
+        if self.narrow_parse {
+            *files = parse_scope(files, &changed)?;
+        }
+        self.changed.extend(changed);
+        Ok(())
+    }
+
+    fn parsed(&mut self, parsed: &mut ParsedFile) {
+        self.changed_structs = parsed
+            .structs
+            .iter()
+            .filter(|s| git::is_changed(&s.file_path, &self.changed))
+            .map(|s| s.qualified_name())
+            .collect();
+    }
+
+    fn results(&mut self, results: &mut Vec<AnalysisResult>) {
+        results.retain(|r| self.is_affected(r));
+    }
+}
+
+/// The files of `files` to parse so that every struct a change to
+/// `changed` (canonical paths) can affect is reported with exact coupling
+///
+/// Uses a textual index instead of a parse: a file defines the names
+/// following `struct`, `union`, `type`, and `as`, and uses every
+/// identifier it contains. Kept are changed files, files using a name they
+/// define, directly or through an alias, and the files defining a name
+/// those use, again through aliases, so the struct index resolves their
+/// CBO targets as in a full parse. Identifiers match textually, so more
+/// files may be kept than strictly needed, never fewer.
+pub fn parse_scope(files: &[PathBuf], changed: &HashSet<PathBuf>) -> std::io::Result<Vec<PathBuf>> {
+    let mut identifiers = Vec::with_capacity(files.len());
+    let mut aliases = Vec::with_capacity(files.len()); // Names defined by `type` or `as`
+    let mut definitions: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut changed_names: HashSet<String> = HashSet::new();
+    let mut reported = vec![false; files.len()];
+
+    for (i, file) in files.iter().enumerate() {
+        let content = std::fs::read_to_string(file)?;
+        let tokens: Vec<&str> = tokens(&content).collect();
+        let mut defined = Vec::new();
+        let mut aliased = Vec::new();
+        for pair in tokens.windows(2) {
+            match pair[0] {
+                "struct" | "union" => defined.push(pair[1].to_string()),
+                "type" | "as" => aliased.push(pair[1].to_string()),
+                _ => {}
+            }
+        }
+        defined.extend(aliased.iter().cloned());
+        reported[i] = changed.contains(&file.canonicalize()?);
+        if reported[i] {
+            changed_names.extend(defined.iter().cloned());
+        }
+        for name in defined {
+            definitions.entry(name).or_default().push(i);
+        }
+        identifiers.push(tokens.into_iter().map(str::to_string).collect::<HashSet<String>>());
+        aliases.push(aliased);
+    }
+
+    // Dependents, also through the aliases they define
+    let mut grown = true;
+    while grown {
+        grown = false;
+        for i in 0..files.len() {
+            if !reported[i] && identifiers[i].iter().any(|id| changed_names.contains(id)) {
+                reported[i] = true;
+                changed_names.extend(aliases[i].iter().cloned());
+                grown = true;
+            }
+        }
+    }
+
+    // Dependencies of everything reported, and of the aliases among them
+    let mut parsed = reported.clone();
+    let mut pending: Vec<usize> = (0..files.len()).filter(|&i| reported[i]).collect();
+    while let Some(i) = pending.pop() {
+        for defining in identifiers[i].iter().filter_map(|id| definitions.get(id)) {
+            for &j in defining {
+                if !parsed[j] {
+                    parsed[j] = true;
+                    if !aliases[j].is_empty() {
+                        pending.push(j);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok((0..files.len()).filter(|&i| parsed[i]).map(|i| files[i].clone()).collect())
+}
+
+/// Identifier-like words of a source text, including those in comments and strings
+fn tokens(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| word.starts_with(|c: char| c.is_alphabetic() || c == '_'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::{AnalysisContext, AnalysisPipeline};
+
+    /// Run `git` in `dir`, with an identity for committing
+    fn git(dir: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    /// A repository whose `config.rs` changed since `HEAD`
+    fn changed_repo() -> tempfile::TempDir {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        for (file, content) in [
+            ("config.rs", "pub struct Config { retries: u32, pool: Handle }"),
+            ("handle.rs", "pub type Handle = Pool;"),
+            ("pool.rs", "pub struct Pool;"),
+            ("settings.rs", "pub type Settings = Config;"),
+            ("server.rs", "pub struct Server { settings: Settings }"),
+            ("cli.rs", "pub struct Cli { verbose: bool }"),
+        ] {
+            std::fs::write(dir.join(file), content).unwrap();
+        }
+        git(dir, &["init", "--quiet"]);
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "--quiet", "-m", "initial"]);
+        std::fs::write(dir.join("config.rs"), "pub struct Config { retries: u64, pool: Handle }").unwrap();
+        temp
+    }
+
+    #[test]
+    fn test_changed_since_reports_changed_structs_with_full_coupling() {
+        let temp = changed_repo();
+        let context = AnalysisContext::default();
+        let path = temp.path().to_str().unwrap();
+        let coupling = |results: &[AnalysisResult]| -> Vec<(String, usize, usize)> {
+            results.iter().map(|r| (r.struct_name.clone(), r.cbo, r.ca)).collect()
+        };
+        let full = AnalysisPipeline::new(&context).run(path).unwrap().results;
+        let scoped = AnalysisPipeline::new(&context).with_hook(ChangedSince::new("HEAD", false)).run(path).unwrap().results;
+
+        let config = |results: &[AnalysisResult]| coupling(results).into_iter().find(|(name, _, _)| name == "Config");
+        assert_eq!(config(&scoped), config(&full));
+        assert_eq!(config(&scoped), Some(("Config".to_string(), 1, 1)));
+
+        // Server reaches Config only through the `Settings` alias
+        let names: Vec<String> = coupling(&scoped).into_iter().map(|(name, _, _)| name).collect();
+        assert_eq!(names, vec!["Config", "Server"]);
+    }
+
+    #[test]
+    fn test_narrow_parse_skips_unaffected_files() {
+        let temp = changed_repo();
+        let dir = temp.path();
+        let files: Vec<PathBuf> = ["cli.rs", "config.rs", "handle.rs", "pool.rs", "server.rs", "settings.rs"]
+            .iter()
+            .map(|f| dir.join(f))
+            .collect();
+        let changed = HashSet::from([dir.join("config.rs").canonicalize().unwrap()]);
+
+        // Pool is reached through the `Handle` alias, Server through `Settings`
+        let parsed = parse_scope(&files, &changed).unwrap();
+        assert_eq!(parsed, files[1..].to_vec());
+
+        let context = AnalysisContext::default();
+        let path = dir.to_str().unwrap();
+        let coupling = |results: &[AnalysisResult]| -> Vec<(String, usize, usize)> {
+            results.iter().map(|r| (r.struct_name.clone(), r.cbo, r.ca)).collect()
+        };
+        let scoped = AnalysisPipeline::new(&context).with_hook(ChangedSince::new("HEAD", false)).run(path).unwrap().results;
+        let narrowed = AnalysisPipeline::new(&context).with_hook(ChangedSince::new("HEAD", true)).run(path).unwrap().results;
+        assert_eq!(coupling(&narrowed), coupling(&scoped));
+    }
+}
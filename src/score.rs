@@ -0,0 +1,95 @@
+use crate::models::AnalysisResult;
+use crate::thresholds::{find_violations, Thresholds};
+
+/// Overall architecture grade of a project
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Grade {
+    pub score: f64, // 0 to 100, higher is healthier
+    pub grade: &'static str,
+    pub struct_count: usize,
+    pub violations: usize,
+}
+
+/// Grade a project by its composite score
+pub fn grade(results: &[AnalysisResult], thresholds: &Thresholds) -> Grade {
+    let score = composite_score(results, thresholds);
+    Grade {
+        score,
+        grade: letter_grade(score),
+        struct_count: results.len(),
+        violations: find_violations(results, thresholds).len(),
+    }
+}
+
+/// Composite health score from 0 to 100
+///
+/// Each metric of each struct scores `1 - (value / threshold)^2`, floored
+/// at 0, so values well below their threshold cost little and a value at
+/// or above it scores nothing. The project score is the mean over all
+/// structs and metrics. A project without structs scores 100.
+pub fn composite_score(results: &[AnalysisResult], thresholds: &Thresholds) -> f64 {
+    if results.is_empty() {
+        return 100.0;
+    }
+
+    let health = |value: f64, threshold: f64| {
+        if threshold <= 0.0 {
+            return if value > 0.0 { 0.0 } else { 1.0 };
+        }
+        1.0 - (value / threshold).min(1.0).powi(2)
+    };
+
+    let total: f64 = results
+        .iter()
+        .map(|r| {
+            (health(r.lcom, thresholds.lcom)
+                + health(r.cbo as f64, thresholds.cbo as f64)
+                + health(r.wmc as f64, thresholds.wmc as f64))
+                / 3.0
+        })
+        .sum();
+
+    100.0 * total / results.len() as f64
+}
+
+/// School-style letter grade: A from 90, B from 80, C from 70, D from 60
+pub fn letter_grade(score: f64) -> &'static str {
+    match score {
+        s if s >= 90.0 => "A",
+        s if s >= 80.0 => "B",
+        s if s >= 70.0 => "C",
+        s if s >= 60.0 => "D",
+        _ => "F",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_composite_score() {
+        let thresholds = Thresholds::default();
+        let results = vec![
+            AnalysisResult {
+                struct_name: "Clean".to_string(),
+                ..Default::default()
+            },
+            AnalysisResult {
+                struct_name: "Busy".to_string(),
+                lcom: 0.4,
+                cbo: 10,
+                wmc: 20,
+                ..Default::default()
+            },
+        ];
+
+        // Busy: LCOM 0.75, CBO 0, WMC 0.75 -> 0.5; Clean: 1.0
+        assert!((composite_score(&results, &thresholds) - 75.0).abs() < 1e-9);
+
+        let grade = grade(&results, &thresholds);
+        assert_eq!(grade.grade, "C");
+        assert_eq!(grade.violations, 1);
+        assert_eq!(composite_score(&[], &thresholds), 100.0);
+    }
+}
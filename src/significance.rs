@@ -0,0 +1,120 @@
+use crate::models::{AnalysisResult, Metric};
+
+/// How meaningful a change of a metric's mean is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verdict {
+    Noise,
+    Small,
+    Medium,
+    Large,
+}
+
+impl Verdict {
+    /// Classify an effect size by Cohen's conventional bands
+    pub fn from_effect_size(effect_size: f64) -> Self {
+        match effect_size.abs() {
+            d if d < 0.2 => Verdict::Noise,
+            d if d < 0.5 => Verdict::Small,
+            d if d < 0.8 => Verdict::Medium,
+            _ => Verdict::Large,
+        }
+    }
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Noise => write!(f, "noise"),
+            Verdict::Small => write!(f, "small"),
+            Verdict::Medium => write!(f, "medium"),
+            Verdict::Large => write!(f, "large"),
+        }
+    }
+}
+
+/// Change of a metric's mean between two result sets, relative to its spread
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Significance {
+    pub metric: String,
+    pub mean_a: f64,
+    pub mean_b: f64,
+    pub effect_size: f64, // Cohen's d: mean change in pooled standard deviations
+    pub verdict: Verdict,
+}
+
+/// Significance of the change of each built-in metric's mean from `a` to `b`
+pub fn mean_changes(a: &[AnalysisResult], b: &[AnalysisResult]) -> Vec<Significance> {
+    [Metric::Lcom, Metric::Cbo, Metric::Wmc]
+        .into_iter()
+        .map(|metric| {
+            let values_a: Vec<f64> = a.iter().map(|r| metric.value(r)).collect();
+            let values_b: Vec<f64> = b.iter().map(|r| metric.value(r)).collect();
+            let effect_size = effect_size(&values_a, &values_b);
+            Significance {
+                metric: metric.to_string().to_lowercase(),
+                mean_a: mean(&values_a),
+                mean_b: mean(&values_b),
+                effect_size,
+                verdict: Verdict::from_effect_size(effect_size),
+            }
+        })
+        .collect()
+}
+
+/// Cohen's d of the change from `a` to `b`
+///
+/// The difference of means is divided by the pooled standard deviation,
+/// so a shift that is small against how much the metric varies between
+/// structs scores low. Without spread any shift is infinitely large, and
+/// with fewer than two values overall the effect is 0.
+pub fn effect_size(a: &[f64], b: &[f64]) -> f64 {
+    let difference = mean(b) - mean(a);
+    if a.len() + b.len() < 3 || difference == 0.0 {
+        return 0.0;
+    }
+
+    let squares = |values: &[f64]| {
+        let m = mean(values);
+        values.iter().map(|v| (v - m).powi(2)).sum::<f64>()
+    };
+    let pooled_sd = ((squares(a) + squares(b)) / (a.len() + b.len() - 2) as f64).sqrt();
+
+    if pooled_sd == 0.0 {
+        difference.signum() * f64::INFINITY
+    } else {
+        difference / pooled_sd
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_effect_size_separates_noise_from_movement() {
+        let spread: Vec<f64> = (0..20).map(|i| (i % 10) as f64 * 4.0).collect();
+
+        // One struct losing a branch in a widely spread codebase
+        let mut refactored = spread.clone();
+        refactored[3] -= 1.0;
+        let d = effect_size(&spread, &refactored);
+        assert!(d < 0.0);
+        assert_eq!(Verdict::from_effect_size(d), Verdict::Noise);
+
+        // Every struct getting much simpler
+        let simplified: Vec<f64> = spread.iter().map(|v| v / 2.0).collect();
+        assert_eq!(Verdict::from_effect_size(effect_size(&spread, &simplified)), Verdict::Large);
+
+        assert_eq!(effect_size(&[1.0, 1.0], &[2.0, 2.0]), f64::INFINITY);
+        assert_eq!(effect_size(&[], &[5.0]), 0.0);
+    }
+}
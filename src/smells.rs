@@ -0,0 +1,311 @@
+use std::collections::BTreeSet;
+
+use crate::models::{AnalysisResult, MethodRole, SourceSpan};
+
+/// "Few" in the detection rules of Lanza and Marinescu
+const FEW: usize = 2;
+
+/// A design problem detected from the metrics of a struct or method
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Smell {
+    pub kind: SmellKind,
+    pub struct_name: String, // Qualified name
+    pub method: Option<String>, // For method-level smells
+    pub file_path: String,
+    pub span: SourceSpan, // Of the method, or of the struct definition
+    pub reason: String, // The metric values that triggered it
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmellKind {
+    GodClass,    // A complex struct using much foreign data, with little cohesion
+    FeatureEnvy, // A method more interested in other structs' data than its own
+    DataClass,   // A struct exposing its data with next to no behavior
+}
+
+impl std::fmt::Display for SmellKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SmellKind::GodClass => write!(f, "God Class"),
+            SmellKind::FeatureEnvy => write!(f, "Feature Envy"),
+            SmellKind::DataClass => write!(f, "Data Class"),
+        }
+    }
+}
+
+/// Limits of the detection rules, from the `[smells]` table of the config
+///
+/// ```toml
+/// [smells.god_class]
+/// wmc = 47     # at least
+/// atfd = 2     # more than
+/// tcc = 0.33   # below
+///
+/// [smells.data_class]
+/// woc = 0.33       # below
+/// exposed = 2      # more than
+/// complexity = 3   # at most
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SmellThresholds {
+    pub god_class: GodClassThresholds,
+    pub data_class: DataClassThresholds,
+}
+
+/// A struct is a God Class when all three limits are crossed
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GodClassThresholds {
+    pub wmc: usize, // At least this complex
+    pub atfd: usize, // Using more foreign data than this
+    pub tcc: f64, // With cohesion below this
+}
+
+impl Default for GodClassThresholds {
+    /// "Very high" WMC, "few" ATFD, and one third TCC, as Lanza and
+    /// Marinescu calibrated them on Java systems
+    fn default() -> Self {
+        Self {
+            wmc: 47,
+            atfd: FEW,
+            tcc: 1.0 / 3.0,
+        }
+    }
+}
+
+/// A struct is a Data Class when all three limits are crossed
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DataClassThresholds {
+    pub woc: f64, // With a share of behavior in its interface below this
+    pub exposed: usize, // Exposing more public fields and accessors than this
+    pub complexity: usize, // With behavior at most this complex
+}
+
+impl Default for DataClassThresholds {
+    /// One third WOC and "few" exposed members, as Lanza and Marinescu
+    /// define it, and behavior no more complex than three straight methods
+    fn default() -> Self {
+        Self {
+            woc: 1.0 / 3.0,
+            exposed: FEW,
+            complexity: 3,
+        }
+    }
+}
+
+/// Every smell of `results`, in the order of the results, each struct's
+/// own smells before those of its methods
+pub fn detect(results: &[AnalysisResult], thresholds: &SmellThresholds) -> Vec<Smell> {
+    results
+        .iter()
+        .flat_map(|result| {
+            god_class(result, &thresholds.god_class)
+                .into_iter()
+                .chain(data_class(result, &thresholds.data_class))
+                .chain(feature_envy(result))
+        })
+        .collect()
+}
+
+/// Structs that are complex, use much foreign data, and lack cohesion
+///
+/// Such a struct centralizes the intelligence of its part of the system:
+/// it does much of the work itself (WMC), pulls in the data of others to
+/// do it (ATFD), and its methods share few fields (TCC).
+fn god_class(result: &AnalysisResult, limits: &GodClassThresholds) -> Option<Smell> {
+    if result.wmc < limits.wmc || result.atfd <= limits.atfd || result.tcc >= limits.tcc {
+        return None;
+    }
+    Some(Smell {
+        kind: SmellKind::GodClass,
+        struct_name: result.qualified_name(),
+        method: None,
+        file_path: result.file_path.clone(),
+        span: result.span,
+        reason: format!(
+            "WMC {} >= {}, ATFD {} > {}, TCC {:.2} < {:.2}",
+            result.wmc, limits.wmc, result.atfd, limits.atfd, result.tcc, limits.tcc
+        ),
+    })
+}
+
+/// Structs whose interface is mostly data, with the logic elsewhere
+///
+/// The weight of class (WOC) is the share of the public interface, public
+/// fields and `pub` methods other than constructors, that does more than
+/// get or set a field. A Data Class has a low WOC, exposes some data
+/// through public fields and accessors, and the rest of its methods are
+/// barely more than straight-line code, so whatever uses the data lives in
+/// other structs.
+fn data_class(result: &AnalysisResult, limits: &DataClassThresholds) -> Option<Smell> {
+    let pub_fields = result.fields.iter().filter(|field| field.is_pub).count();
+    let interface: Vec<MethodRole> = result
+        .methods
+        .iter()
+        .filter(|method| method.is_pub && method.role != MethodRole::Constructor)
+        .map(|method| method.role)
+        .collect();
+    let accessors = interface.iter().filter(|role| role.is_accessor()).count();
+    let functional = interface.len() - accessors;
+    let complexity: usize = result
+        .methods
+        .iter()
+        .filter(|method| method.role == MethodRole::Behavior)
+        .map(|method| method.cyclomatic_complexity)
+        .sum();
+
+    let exposed = pub_fields + accessors;
+    let woc = functional as f64 / (pub_fields + interface.len()).max(1) as f64;
+    if woc >= limits.woc || exposed <= limits.exposed || complexity > limits.complexity {
+        return None;
+    }
+    Some(Smell {
+        kind: SmellKind::DataClass,
+        struct_name: result.qualified_name(),
+        method: None,
+        file_path: result.file_path.clone(),
+        span: result.span,
+        reason: format!(
+            "WOC {:.2} < {:.2}, {} public fields and accessors > {}, behavior complexity {} <= {}",
+            woc, limits.woc, exposed, limits.exposed, complexity, limits.complexity
+        ),
+    })
+}
+
+/// Methods using more foreign data than their own fields
+///
+/// A method envies when it accesses at least `FEW` fields and getters of
+/// other structs (ATFD), they make up more than two thirds of the data it
+/// uses (locality of attribute accesses below 1/3), and they come from at
+/// most `FEW` structs, so the method would fit better in one of those.
+fn feature_envy(result: &AnalysisResult) -> Vec<Smell> {
+    result
+        .methods
+        .iter()
+        .filter_map(|method| {
+            let atfd = method.foreign_data.len();
+            let own = method.fields_accessed.len();
+            let providers: BTreeSet<&str> = method
+                .foreign_data
+                .iter()
+                .filter_map(|data| data.rsplit_once("::").map(|(owner, _)| owner))
+                .collect();
+            let locality = own as f64 / (own + atfd).max(1) as f64;
+
+            if atfd < FEW || locality >= 1.0 / 3.0 || providers.len() > FEW {
+                return None;
+            }
+            Some(Smell {
+                kind: SmellKind::FeatureEnvy,
+                struct_name: result.qualified_name(),
+                method: Some(method.name.clone()),
+                file_path: result.file_path.clone(),
+                span: method.span,
+                reason: format!(
+                    "uses {} fields of {} but {} of its own",
+                    atfd,
+                    providers.into_iter().collect::<Vec<_>>().join(", "),
+                    own
+                ),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::analyze_struct;
+    use crate::parser::{parse_file, ParseOptions};
+
+    #[test]
+    fn test_feature_envy() {
+        let source = r#"
+            struct Order { price: u32, quantity: u32, discount: u32 }
+            struct Invoice { tax: u32, lines: u32 }
+            impl Invoice {
+                fn line_total(&self, order: &Order) -> u32 {
+                    order.price * order.quantity - order.discount
+                }
+                fn taxed(&self, order: &Order) -> u32 {
+                    self.tax * self.lines + order.price
+                }
+            }
+        "#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let results: Vec<AnalysisResult> = structs.iter().map(|s| analyze_struct(s, &structs)).collect();
+
+        let smells = detect(&results, &SmellThresholds::default());
+        assert_eq!(smells.len(), 1);
+        assert_eq!(smells[0].kind, SmellKind::FeatureEnvy);
+        assert_eq!(smells[0].struct_name, "crate::Invoice");
+        assert_eq!(smells[0].method.as_deref(), Some("line_total"));
+        assert_eq!(smells[0].reason, "uses 3 fields of crate::Order but 0 of its own");
+    }
+
+    #[test]
+    fn test_data_class() {
+        let source = r#"
+            pub struct Customer { pub name: String, pub email: String, id: u64, tier: u8 }
+            impl Customer {
+                pub fn new(name: String) -> Self { todo!() }
+                pub fn id(&self) -> u64 { self.id }
+                pub fn tier(&self) -> &u8 { &self.tier }
+                pub fn set_tier(&mut self, tier: u8) { self.tier = tier; }
+            }
+
+            pub struct Account { pub owner: String, balance: u64 }
+            impl Account {
+                pub fn balance(&self) -> u64 { self.balance }
+                pub fn withdraw(&mut self, amount: u64) -> bool {
+                    if amount > self.balance { return false; }
+                    self.balance -= amount;
+                    true
+                }
+            }
+        "#;
+        let structs = parse_file(source, "crate", &ParseOptions::default()).unwrap().structs;
+        let roles: Vec<MethodRole> = structs[0].methods.iter().map(|m| m.role).collect();
+        assert_eq!(
+            roles,
+            vec![MethodRole::Constructor, MethodRole::Getter, MethodRole::Getter, MethodRole::Setter]
+        );
+        let results: Vec<AnalysisResult> = structs.iter().map(|s| analyze_struct(s, &structs)).collect();
+
+        let smells = detect(&results, &SmellThresholds::default());
+        assert_eq!(smells.len(), 1);
+        assert_eq!(smells[0].kind, SmellKind::DataClass);
+        assert_eq!(smells[0].struct_name, "crate::Customer");
+        assert_eq!(
+            smells[0].reason,
+            "WOC 0.00 < 0.33, 5 public fields and accessors > 2, behavior complexity 0 <= 3"
+        );
+    }
+
+    #[test]
+    fn test_god_class() {
+        let result = |wmc, atfd, tcc| AnalysisResult {
+            struct_name: "Manager".to_string(),
+            module_path: "crate".to_string(),
+            wmc,
+            atfd,
+            tcc,
+            ..Default::default()
+        };
+        let limits = GodClassThresholds::default();
+
+        let smell = god_class(&result(50, 3, 0.2), &limits).unwrap();
+        assert_eq!(smell.kind, SmellKind::GodClass);
+        assert_eq!(smell.reason, "WMC 50 >= 47, ATFD 3 > 2, TCC 0.20 < 0.33");
+
+        assert!(god_class(&result(46, 3, 0.2), &limits).is_none());
+        assert!(god_class(&result(50, 2, 0.2), &limits).is_none());
+        assert!(god_class(&result(50, 3, 0.5), &limits).is_none());
+
+        let lenient = GodClassThresholds { wmc: 80, ..limits };
+        assert!(god_class(&result(50, 3, 0.2), &lenient).is_none());
+    }
+}
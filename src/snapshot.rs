@@ -0,0 +1,130 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::aggregate::{summarize, Summary};
+use crate::models::AnalysisResult;
+use crate::score::{grade, Grade};
+use crate::thresholds::Thresholds;
+
+/// Settings a snapshot was produced with, stored next to its results
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct SnapshotConfig {
+    pub path: String,
+    #[serde(flatten)]
+    pub settings: RunSettings,
+    pub version: &'static str,
+}
+
+/// Analysis settings of a run, apart from the analyzed path and tool version
+#[derive(Debug, Clone, Default, serde::Serialize, schemars::JsonSchema)]
+pub struct RunSettings {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub no_ignore: bool,
+    pub follow_mods: bool,
+    pub expand: bool,
+    pub extension_traits: String,
+    pub count_derives: String,
+    pub skip_trivial_impls: bool,
+    pub lcom_transitive: bool,
+    pub separate_trait_coupling: bool,
+    pub error_path_weight: usize,
+    pub await_weight: usize,
+    pub profile: Option<String>,
+    pub thresholds: Thresholds,
+}
+
+#[derive(serde::Serialize)]
+struct SnapshotSummary<'a> {
+    created_at: &'a str,
+    summary: Summary,
+    grade: Grade,
+}
+
+/// Write a full result set into `<root>/<timestamp>/`
+///
+/// The directory holds `results.json` (every `AnalysisResult`),
+/// `summary.json` (aggregates and grade), and `config.json` (the settings
+/// used). Timestamps are UTC and sort chronologically by name, e.g.
+/// `2024-05-01T09-30-00Z`. Returns the created directory.
+pub fn write_snapshot(
+    root: &Path,
+    results: &[AnalysisResult],
+    config: &SnapshotConfig,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let timestamp = utc_timestamp(SystemTime::now());
+    let dir = root.join(&timestamp);
+    if dir.exists() {
+        return Err(format!("Snapshot {} already exists", dir.display()).into());
+    }
+    std::fs::create_dir_all(&dir)?;
+
+    let summary = SnapshotSummary {
+        created_at: &timestamp,
+        summary: summarize(results),
+        grade: grade(results, &config.settings.thresholds),
+    };
+
+    std::fs::write(dir.join("results.json"), serde_json::to_string_pretty(results)?)?;
+    std::fs::write(dir.join("summary.json"), serde_json::to_string_pretty(&summary)?)?;
+    std::fs::write(dir.join("config.json"), serde_json::to_string_pretty(config)?)?;
+
+    Ok(dir)
+}
+
+/// Filesystem-safe UTC timestamp, e.g. `2024-05-01T09-30-00Z`
+fn utc_timestamp(time: SystemTime) -> String {
+    format_utc(time, '-')
+}
+
+/// RFC 3339 UTC timestamp, e.g. `2024-05-01T09:30:00Z`
+pub fn rfc3339(time: SystemTime) -> String {
+    format_utc(time, ':')
+}
+
+fn format_utc(time: SystemTime, separator: char) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}{sep}{:02}{sep}{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        sep = separator
+    )
+}
+
+/// Gregorian date of a day count since 1970-01-01 (Howard Hinnant's algorithm)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_utc_timestamp() {
+        assert_eq!(utc_timestamp(UNIX_EPOCH), "1970-01-01T00-00-00Z");
+        assert_eq!(
+            utc_timestamp(UNIX_EPOCH + Duration::from_secs(1_709_210_096)),
+            "2024-02-29T12-34-56Z"
+        );
+        assert_eq!(rfc3339(UNIX_EPOCH + Duration::from_secs(1_709_210_096)), "2024-02-29T12:34:56Z");
+    }
+}
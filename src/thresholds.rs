@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+
+use crate::models::{AnalysisResult, SourceSpan};
+use crate::query::Query;
+
+/// Upper bounds for each metric; values above a bound are violations
+///
+/// The defaults follow the documented metric bands: LCOM above 0.8 means
+/// little cohesion, CBO of 6 or more is high coupling, and WMC above 40
+/// indicates a God class.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, schemars::JsonSchema)]
+pub struct Thresholds {
+    pub lcom: f64,
+    pub cbo: usize,
+    pub wmc: usize,
+    pub derived: BTreeMap<String, f64>, // Config-defined metrics that have a threshold
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            lcom: 0.8,
+            cbo: 5,
+            wmc: 40,
+            derived: BTreeMap::new(),
+        }
+    }
+}
+
+impl Thresholds {
+    /// Names of the built-in profiles, from strictest to most lenient
+    pub const PROFILES: [&'static str; 3] = ["strict", "default", "lenient"];
+
+    /// Built-in threshold profile by name
+    ///
+    /// `strict` suits new or well-factored code, `lenient` legacy code
+    /// that is being brought under control.
+    pub fn profile(name: &str) -> Option<Self> {
+        match name {
+            "strict" => Some(Self {
+                lcom: 0.6,
+                cbo: 3,
+                wmc: 20,
+                derived: BTreeMap::new(),
+            }),
+            "default" => Some(Self::default()),
+            "lenient" => Some(Self {
+                lcom: 0.9,
+                cbo: 8,
+                wmc: 60,
+                derived: BTreeMap::new(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// A metric of a struct exceeding its threshold
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Violation {
+    pub struct_name: String, // Qualified name
+    pub file_path: String,
+    pub span: SourceSpan, // Of the struct definition
+    pub metric: String,
+    pub value: f64,
+    pub threshold: f64,
+}
+
+/// Outcome of the `check` gate
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CheckOutcome {
+    pub base: Option<String>, // Git ref changes were taken from, if any
+    pub checked: usize,       // Structs the thresholds were applied to
+    pub skipped: usize,       // Structs outside the changed files
+    pub violations: Vec<Violation>,
+}
+
+/// All threshold violations in `results`, in result order
+pub fn find_violations(results: &[AnalysisResult], thresholds: &Thresholds) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    for result in results {
+        let checks = [
+            ("lcom", result.lcom, thresholds.lcom),
+            ("cbo", result.cbo as f64, thresholds.cbo as f64),
+            ("wmc", result.wmc as f64, thresholds.wmc as f64),
+        ];
+        let derived_checks = thresholds.derived.iter().filter_map(|(name, &threshold)| {
+            Some((name.as_str(), *result.derived.get(name)?, threshold))
+        });
+
+        for (metric, value, threshold) in checks.into_iter().chain(derived_checks) {
+            if value > threshold {
+                violations.push(Violation {
+                    struct_name: result.qualified_name(),
+                    file_path: result.file_path.clone(),
+                    span: result.span,
+                    metric: metric.to_string(),
+                    value,
+                    threshold,
+                });
+            }
+        }
+    }
+
+    violations
+}
+
+/// A `--fail-on` expression and the structs matching it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    pub condition: String,
+    pub structs: Vec<String>, // Qualified names, in result order
+}
+
+/// Conditions of `--fail-on` that some result matches
+pub fn find_failures(results: &[AnalysisResult], conditions: &[(String, Query)]) -> Vec<Failure> {
+    conditions
+        .iter()
+        .filter_map(|(condition, query)| {
+            let structs: Vec<String> = results
+                .iter()
+                .filter(|r| query.matches(r))
+                .map(AnalysisResult::qualified_name)
+                .collect();
+            (!structs.is_empty()).then(|| Failure {
+                condition: condition.clone(),
+                structs,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_violations() {
+        let results = vec![
+            AnalysisResult {
+                struct_name: "God".to_string(),
+                lcom: 0.9,
+                cbo: 6,
+                wmc: 40,
+                ..Default::default()
+            },
+            AnalysisResult {
+                struct_name: "Fine".to_string(),
+                lcom: 0.8,
+                cbo: 5,
+                wmc: 10,
+                ..Default::default()
+            },
+        ];
+
+        let violations = find_violations(&results, &Thresholds::default());
+        let metrics: Vec<&str> = violations.iter().map(|v| v.metric.as_str()).collect();
+        assert_eq!(metrics, vec!["lcom", "cbo"]);
+        assert!(violations.iter().all(|v| v.struct_name == "God"));
+    }
+
+    #[test]
+    fn test_find_failures() {
+        let result = |name: &str, wmc: usize, lcom: f64| AnalysisResult {
+            struct_name: name.to_string(),
+            module_path: "crate".to_string(),
+            wmc,
+            lcom,
+            ..Default::default()
+        };
+        let results = vec![result("God", 52, 0.5), result("Split", 12, 0.9), result("Fine", 3, 0.1)];
+        let conditions: Vec<(String, Query)> = ["wmc>40", "lcom>0.8", "cbo>5"]
+            .into_iter()
+            .map(|expr| (expr.to_string(), Query::parse(expr, &[]).unwrap()))
+            .collect();
+
+        let failures = find_failures(&results, &conditions);
+        assert_eq!(
+            failures,
+            vec![
+                Failure { condition: "wmc>40".to_string(), structs: vec!["crate::God".to_string()] },
+                Failure { condition: "lcom>0.8".to_string(), structs: vec!["crate::Split".to_string()] },
+            ]
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Target kinds that are not part of a package's own source
+pub(crate) const NON_SOURCE_KINDS: [&str; 4] = ["test", "bench", "example", "custom-build"];
+
+/// Source directories of the package or workspace containing `dir`, with
+/// the name of the package of each
+///
+/// Asks `cargo metadata` for the workspace members and returns the
+/// directory of each library and binary target's root file, e.g. `src/`
+/// for `src/lib.rs`. Tests, benches, examples, and build scripts are left
+/// out, as are directories nested in another one. Names are the ones code
+/// refers to the package by, `-` being `_`.
+pub fn source_dirs(dir: &Path) -> Result<Vec<(PathBuf, String)>, String> {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let output = Command::new(&cargo)
+        .args(["metadata", "--no-deps", "--format-version", "1"])
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("Failed to run cargo: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let metadata: serde_json::Value = serde_json::from_slice(&output.stdout).map_err(|e| e.to_string())?;
+    Ok(target_dirs(&metadata))
+}
+
+/// Source directories of the targets in `cargo metadata` output, with
+/// their package names
+fn target_dirs(metadata: &serde_json::Value) -> Vec<(PathBuf, String)> {
+    let mut dirs: Vec<(PathBuf, String)> = metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|package| {
+            let name = package["name"].as_str().unwrap_or_default().replace('-', "_");
+            package["targets"].as_array().into_iter().flatten().map(move |target| (target, name.clone()))
+        })
+        .filter(|(target, _)| {
+            let kinds = target["kind"].as_array().into_iter().flatten();
+            kinds.filter_map(|kind| kind.as_str()).any(|kind| !NON_SOURCE_KINDS.contains(&kind))
+        })
+        .filter_map(|(target, name)| Some((Path::new(target["src_path"].as_str()?).parent()?.to_path_buf(), name)))
+        .collect();
+
+    // Sorted, a directory comes before the directories nested in it
+    dirs.sort();
+    let mut roots: Vec<(PathBuf, String)> = Vec::new();
+    for (dir, name) in dirs {
+        if !roots.iter().any(|(root, _)| dir.starts_with(root)) {
+            roots.push((dir, name));
+        }
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_dirs_of_workspace_members() {
+        let metadata = serde_json::json!({
+            "packages": [
+                {
+                    "name": "app",
+                    "targets": [
+                        { "kind": ["bin"], "src_path": "/ws/app/src/main.rs" },
+                        { "kind": ["bin"], "src_path": "/ws/app/src/bin/tool.rs" },
+                        { "kind": ["test"], "src_path": "/ws/app/tests/cli.rs" },
+                        { "kind": ["custom-build"], "src_path": "/ws/app/build.rs" }
+                    ]
+                },
+                {
+                    "name": "app-core",
+                    "targets": [
+                        { "kind": ["lib"], "src_path": "/ws/core/src/lib.rs" },
+                        { "kind": ["example"], "src_path": "/ws/core/examples/demo.rs" }
+                    ]
+                }
+            ]
+        });
+
+        assert_eq!(
+            target_dirs(&metadata),
+            vec![
+                (PathBuf::from("/ws/app/src"), "app".to_string()),
+                (PathBuf::from("/ws/core/src"), "app_core".to_string())
+            ]
+        );
+    }
+}